@@ -1,9 +1,10 @@
 #!/bin/bash
 
-# Delete rust-seal.config.json
-rm -f rust-seal.config.json
+# Delete the project-local rust-seal home (config and keys)
+rm -rf .rust-seal
 
-# Delete keys directory and everything in it
+# Delete legacy config and keys directory
+rm -f rust-seal.config.json
 rm -rf keys
 
 # Delete everything in test directory except testfile.txt
@@ -0,0 +1,210 @@
+//! age plugin `rustseal`, which lets `age` encrypt to and decrypt with rust-seal KEM keys.
+//!
+//! Recipients carry the algorithm and public key: `age1rustseal1...`.
+//! Identities only name a key of the configuration by algorithm and fingerprint: `AGE-PLUGIN-RUSTSEAL-1...`.
+//! The secret key never leaves the rust-seal home; the plugin loads it when age asks it to unwrap a file key.
+//!
+//! The file key is wrapped in a stanza `-> rustseal <ALGORITHM>` whose body is the KEM ciphertext,
+//! followed by the file key encrypted with AES-GCM. The AES key is derived with HKDF-SHA256 from the
+//! shared secret, salted with the ciphertext and the public key of the recipient.
+mod plugin;
+mod stanza;
+
+use anyhow::{Context, Result, ensure};
+use bech32::{FromBase32, ToBase32, Variant};
+use hkdf::Hkdf;
+use oqs::kem::{Algorithm as KemAlgorithm, Kem, SecretKey as KemSecretKey};
+use sha2::Sha256;
+
+use crate::{
+    cryptography::{
+        AES_KEY_SIZE,
+        secret::SecretBytes,
+        symmetric::{symmetric_decrypt, symmetric_encrypt},
+    },
+    oqs::convert_str_to_kem_alg,
+};
+
+pub use plugin::{run_identity_plugin, run_recipient_plugin};
+
+pub const PLUGIN_NAME: &str = "rustseal";
+const RECIPIENT_HRP: &str = "age1rustseal";
+const IDENTITY_HRP: &str = "age-plugin-rustseal-";
+const STANZA_TAG: &str = "rustseal";
+const WRAP_KEY_INFO: &str = "rust-seal age plugin v1";
+
+/// Returns the age recipient of a KEM public key
+pub fn encode_recipient(algorithm: KemAlgorithm, public_key: &[u8]) -> Result<String> {
+    encode(RECIPIENT_HRP, algorithm, public_key)
+}
+
+/// Returns the algorithm and public key of an age recipient
+pub fn decode_recipient(recipient: &str) -> Result<(KemAlgorithm, Vec<u8>)> {
+    let (algorithm, public_key) = decode(RECIPIENT_HRP, recipient)?;
+    let kem = Kem::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+    ensure!(
+        public_key.len() == kem.length_public_key(),
+        "Recipient is not a valid {algorithm} public key"
+    );
+    Ok((algorithm, public_key))
+}
+
+/// Returns the age identity that refers to the configured key of `algorithm` with `fingerprint`
+pub fn encode_identity(algorithm: KemAlgorithm, fingerprint: &str) -> Result<String> {
+    let fingerprint = hex::decode(fingerprint).context("Fingerprint is not hex encoded")?;
+    Ok(encode(IDENTITY_HRP, algorithm, &fingerprint)?.to_uppercase())
+}
+
+/// Returns the algorithm and the hex encoded key fingerprint an age identity refers to
+pub fn decode_identity(identity: &str) -> Result<(KemAlgorithm, String)> {
+    let (algorithm, fingerprint) = decode(IDENTITY_HRP, identity)?;
+    Ok((algorithm, hex::encode(fingerprint)))
+}
+
+/// Bech32 data: <algorithm name length ; 1 byte><algorithm name><key material>
+fn encode(hrp: &str, algorithm: KemAlgorithm, key: &[u8]) -> Result<String> {
+    let name = algorithm.name().as_bytes();
+    let mut data = vec![u8::try_from(name.len()).context("Algorithm name is too long")?];
+    data.extend_from_slice(name);
+    data.extend_from_slice(key);
+
+    bech32::encode(hrp, data.to_base32(), Variant::Bech32).context("Failed to encode bech32")
+}
+
+fn decode(expected_hrp: &str, value: &str) -> Result<(KemAlgorithm, Vec<u8>)> {
+    let (hrp, data, variant) = bech32::decode(value).context("Not a valid bech32 string")?;
+    ensure!(
+        hrp == expected_hrp && variant == Variant::Bech32,
+        "Expected a bech32 string starting with {expected_hrp}1"
+    );
+    let data = Vec::<u8>::from_base32(&data).context("Not a valid bech32 string")?;
+
+    let (&name_length, rest) = data.split_first().context("Missing algorithm name")?;
+    ensure!(
+        rest.len() >= name_length as usize,
+        "Algorithm name is truncated"
+    );
+    let (name, key) = rest.split_at(name_length as usize);
+    let algorithm = convert_str_to_kem_alg(
+        std::str::from_utf8(name).context("Algorithm name is not valid UTF-8")?,
+    )?;
+    Ok((algorithm, key.to_vec()))
+}
+
+/// Encapsulates to `public_key` and returns the stanza body that wraps `file_key`
+fn wrap_file_key(kem: &Kem, public_key: &[u8], file_key: &[u8]) -> Result<Vec<u8>> {
+    let (ciphertext, shared_secret) = kem
+        .encapsulate(
+            kem.public_key_from_bytes(public_key)
+                .context("Invalid public key")?,
+        )
+        .context("Failed to encapsulate using KEM algorithm")?;
+
+    let wrap_key = derive_wrap_key(kem, ciphertext.as_ref(), public_key, shared_secret.as_ref())?;
+    let mut body = ciphertext.into_vec();
+    body.extend(symmetric_encrypt(file_key, &wrap_key)?);
+    Ok(body)
+}
+
+/// Decapsulates the ciphertext of a stanza body and returns the file key it wraps
+fn unwrap_file_key(
+    kem: &Kem,
+    public_key: &[u8],
+    secret_key: &KemSecretKey,
+    body: &[u8],
+) -> Result<SecretBytes> {
+    ensure!(
+        body.len() > kem.length_ciphertext(),
+        "Stanza body is too short for a {} ciphertext",
+        kem.algorithm()
+    );
+    let (ciphertext, wrapped_file_key) = body.split_at(kem.length_ciphertext());
+    let shared_secret = kem
+        .decapsulate(
+            secret_key,
+            kem.ciphertext_from_bytes(ciphertext)
+                .context("Invalid ciphertext")?,
+        )
+        .context("Failed to decapsulate shared secret")?;
+
+    let wrap_key = derive_wrap_key(kem, ciphertext, public_key, shared_secret.as_ref())?;
+    symmetric_decrypt(wrapped_file_key, &wrap_key)
+}
+
+fn derive_wrap_key(
+    kem: &Kem,
+    ciphertext: &[u8],
+    public_key: &[u8],
+    shared_secret: &[u8],
+) -> Result<[u8; AES_KEY_SIZE]> {
+    let salt = [ciphertext, public_key].concat();
+    let info = format!("{WRAP_KEY_INFO} {}", kem.algorithm().name());
+
+    let mut wrap_key = [0; AES_KEY_SIZE];
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(info.as_bytes(), &mut wrap_key)
+        .map_err(|_| anyhow::anyhow!("Failed to derive the wrap key"))?;
+    Ok(wrap_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptography::fingerprint::fingerprint;
+
+    #[test]
+    fn test_recipient_and_identity_encoding() {
+        let kem = Kem::new(KemAlgorithm::MlKem768).unwrap();
+        let (public_key, _) = kem.keypair().unwrap();
+
+        let recipient = encode_recipient(KemAlgorithm::MlKem768, public_key.as_ref()).unwrap();
+        assert!(recipient.starts_with("age1rustseal1"));
+        assert_eq!(
+            decode_recipient(&recipient).unwrap(),
+            (KemAlgorithm::MlKem768, public_key.as_ref().to_vec())
+        );
+
+        let key_fingerprint = fingerprint("ML-KEM-768", public_key.as_ref());
+        let identity = encode_identity(KemAlgorithm::MlKem768, &key_fingerprint).unwrap();
+        assert!(identity.starts_with("AGE-PLUGIN-RUSTSEAL-1"));
+        assert_eq!(
+            decode_identity(&identity).unwrap(),
+            (KemAlgorithm::MlKem768, key_fingerprint)
+        );
+
+        assert!(decode_recipient(&identity).is_err());
+        assert!(decode_identity(&recipient).is_err());
+        let truncated =
+            encode_recipient(KemAlgorithm::MlKem768, &public_key.as_ref()[1..]).unwrap();
+        assert!(decode_recipient(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_wrap_file_key() {
+        let kem = Kem::new(KemAlgorithm::MlKem512).unwrap();
+        let (public_key, secret_key) = kem.keypair().unwrap();
+        let file_key = [42; 16];
+
+        let body = wrap_file_key(&kem, public_key.as_ref(), &file_key).unwrap();
+        assert_eq!(
+            unwrap_file_key(&kem, public_key.as_ref(), &secret_key, &body)
+                .unwrap()
+                .as_ref(),
+            file_key
+        );
+
+        let (other_public_key, other_secret_key) = kem.keypair().unwrap();
+        assert!(
+            unwrap_file_key(&kem, other_public_key.as_ref(), &other_secret_key, &body).is_err()
+        );
+        assert!(
+            unwrap_file_key(
+                &kem,
+                public_key.as_ref(),
+                &secret_key,
+                &body[..kem.length_ciphertext()]
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! The two state machines of the age plugin protocol (https://c2sp.org/age-plugin).
+//!
+//! Both start with a phase in which age sends commands until `done`; unknown commands, such as
+//! the grease age mixes in, are ignored. In the second phase the plugin sends its results,
+//! each answered by age with `ok`, and finishes with `done`.
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use oqs::kem::{Kem, PublicKey as KemPublicKey, SecretKey as KemSecretKey};
+
+use super::{
+    PLUGIN_NAME, STANZA_TAG, decode_identity, decode_recipient, stanza::Stanza, unwrap_file_key,
+    wrap_file_key,
+};
+use crate::{
+    Config,
+    cli::validate_kem_algorithm,
+    cryptography::{
+        fingerprint::{fingerprint, fingerprints_match},
+        secret::Secret,
+    },
+    oqs::convert_str_to_kem_alg,
+};
+
+const ADD_RECIPIENT: &str = "add-recipient";
+const ADD_IDENTITY: &str = "add-identity";
+const WRAP_FILE_KEY: &str = "wrap-file-key";
+const RECIPIENT_STANZA: &str = "recipient-stanza";
+const FILE_KEY: &str = "file-key";
+const ERROR: &str = "error";
+const DONE: &str = "done";
+const OK: &str = "ok";
+
+/// Runs the `recipient-v1` state machine: wraps every file key for every recipient and identity
+pub fn run_recipient_plugin(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    config: &Config,
+) -> Result<()> {
+    let mut recipients = Vec::new();
+    let mut identities = Vec::new();
+    let mut file_keys = Vec::new();
+    for stanza in read_phase(input)? {
+        match stanza.tag.as_str() {
+            ADD_RECIPIENT => recipients.push(single_argument(&stanza)?),
+            ADD_IDENTITY => identities.push(single_argument(&stanza)?),
+            WRAP_FILE_KEY => file_keys.push(stanza.body),
+            _ => {}
+        }
+    }
+
+    let mut public_keys = Vec::new();
+    let mut failed = false;
+    for (index, recipient) in recipients.iter().enumerate() {
+        match resolve_recipient(recipient, config) {
+            Ok(public_key) => public_keys.push(public_key),
+            Err(err) => {
+                send_error(input, output, &["recipient", &index.to_string()], &err)?;
+                failed = true;
+            }
+        }
+    }
+    for (index, identity) in identities.iter().enumerate() {
+        match resolve_identity(identity, config).and_then(|(kem, public_key, _)| {
+            config.ensure_kem_key_valid(&kem.algorithm())?;
+            Ok((kem, public_key.into_vec()))
+        }) {
+            Ok(public_key) => public_keys.push(public_key),
+            Err(err) => {
+                send_error(input, output, &["identity", &index.to_string()], &err)?;
+                failed = true;
+            }
+        }
+    }
+
+    if !failed {
+        for (file_index, file_key) in file_keys.iter().enumerate() {
+            for (kem, public_key) in &public_keys {
+                let stanza = match wrap_file_key(kem, public_key, file_key) {
+                    Ok(body) => Stanza::new(
+                        RECIPIENT_STANZA,
+                        &[&file_index.to_string(), STANZA_TAG, kem.algorithm().name()],
+                        &body,
+                    ),
+                    Err(err) => return finish_with_internal_error(input, output, &err),
+                };
+                send_command(input, output, &stanza)?;
+            }
+        }
+    }
+
+    Stanza::new(DONE, &[], &[]).write(output)
+}
+
+/// Runs the `identity-v1` state machine: unwraps the file key of every file with one of the identities
+pub fn run_identity_plugin(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    config: &Config,
+) -> Result<()> {
+    let mut identities = Vec::new();
+    // Stanzas by file, in the order age sent them, which is how age counts the stanza index
+    let mut files: BTreeMap<usize, Vec<Stanza>> = BTreeMap::new();
+    for stanza in read_phase(input)? {
+        match stanza.tag.as_str() {
+            ADD_IDENTITY => identities.push(single_argument(&stanza)?),
+            RECIPIENT_STANZA => {
+                let [file_index, tag, args @ ..] = stanza.args.as_slice() else {
+                    bail!("{RECIPIENT_STANZA} must name the file and the stanza tag");
+                };
+                let file_index = file_index
+                    .parse()
+                    .context(format!("Invalid file index: {file_index}"))?;
+                files.entry(file_index).or_default().push(Stanza {
+                    tag: tag.clone(),
+                    args: args.to_vec(),
+                    body: stanza.body,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut keys = Vec::new();
+    for (index, identity) in identities.iter().enumerate() {
+        match resolve_identity(identity, config) {
+            Ok(key) => keys.push(key),
+            Err(err) => send_error(input, output, &["identity", &index.to_string()], &err)?,
+        }
+    }
+
+    for (file_index, stanzas) in &files {
+        for (stanza_index, stanza) in stanzas.iter().enumerate() {
+            if stanza.tag != STANZA_TAG {
+                continue;
+            }
+
+            let algorithm = match stanza.args.as_slice() {
+                [algorithm] => convert_str_to_kem_alg(algorithm),
+                _ => Err(anyhow::anyhow!(
+                    "{PLUGIN_NAME} stanza must have exactly one argument, the KEM algorithm"
+                )),
+            };
+            let algorithm = match algorithm {
+                Ok(algorithm) => algorithm,
+                Err(err) => {
+                    send_error(
+                        input,
+                        output,
+                        &["stanza", &file_index.to_string(), &stanza_index.to_string()],
+                        &err,
+                    )?;
+                    continue;
+                }
+            };
+
+            // A stanza that does not unwrap was made for another recipient
+            let file_key = keys
+                .iter()
+                .filter(|(kem, _, _)| kem.algorithm() == algorithm)
+                .find_map(|(kem, public_key, secret_key)| {
+                    unwrap_file_key(kem, public_key.as_ref(), secret_key, &stanza.body).ok()
+                });
+            if let Some(file_key) = file_key {
+                send_command(
+                    input,
+                    output,
+                    &Stanza::new(FILE_KEY, &[&file_index.to_string()], &file_key),
+                )?;
+                break;
+            }
+        }
+    }
+
+    Stanza::new(DONE, &[], &[]).write(output)
+}
+
+/// Returns the KEM and public key of a recipient whose algorithm is allowed by the algorithm policy
+fn resolve_recipient(recipient: &str, config: &Config) -> Result<(Kem, Vec<u8>)> {
+    let (algorithm, public_key) = decode_recipient(recipient)?;
+    let kem = Kem::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+    validate_kem_algorithm(&kem, config.policy(), false)?;
+    Ok((kem, public_key))
+}
+
+/// Returns the configured key an identity refers to
+fn resolve_identity(
+    identity: &str,
+    config: &Config,
+) -> Result<(Kem, KemPublicKey, Secret<KemSecretKey>)> {
+    let (algorithm, identity_fingerprint) = decode_identity(identity)?;
+    let kem = Kem::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+    let (public_key, secret_key) = config.get_kem_keys(&algorithm).context(format!(
+        "The rust-seal home {} has no {algorithm} key",
+        config.home().root().display()
+    ))?;
+    ensure!(
+        fingerprints_match(
+            &fingerprint(algorithm.name(), public_key.as_ref()),
+            &identity_fingerprint
+        ),
+        "The {algorithm} key of the rust-seal home {} is not the key of this identity. Was it replaced?",
+        config.home().root().display()
+    );
+    Ok((kem, public_key, secret_key))
+}
+
+/// Reads the commands of a phase up to, but not including, `done`
+fn read_phase(input: &mut impl BufRead) -> Result<Vec<Stanza>> {
+    let mut stanzas = Vec::new();
+    loop {
+        match Stanza::read(input)? {
+            Some(stanza) if stanza.tag == DONE => return Ok(stanzas),
+            Some(stanza) => stanzas.push(stanza),
+            None => bail!("age closed the connection before the end of the phase"),
+        }
+    }
+}
+
+fn single_argument(stanza: &Stanza) -> Result<String> {
+    match stanza.args.as_slice() {
+        [argument] => Ok(argument.clone()),
+        _ => bail!("{} must have exactly one argument", stanza.tag),
+    }
+}
+
+/// Sends a command and waits for age to acknowledge it
+fn send_command(input: &mut impl BufRead, output: &mut impl Write, stanza: &Stanza) -> Result<()> {
+    stanza.write(output)?;
+    let response = Stanza::read(input)?.context("age closed the connection")?;
+    ensure!(
+        response.tag == OK,
+        "age answered {} with {}",
+        stanza.tag,
+        response.tag
+    );
+    Ok(())
+}
+
+fn send_error(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    args: &[&str],
+    err: &anyhow::Error,
+) -> Result<()> {
+    send_command(
+        input,
+        output,
+        &Stanza::new(ERROR, args, format!("{err:#}").as_bytes()),
+    )
+}
+
+fn finish_with_internal_error(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    err: &anyhow::Error,
+) -> Result<()> {
+    send_error(input, output, &["internal"], err)?;
+    Stanza::new(DONE, &[], &[]).write(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Home;
+    use oqs::kem::Algorithm as KemAlgorithm;
+    use tempfile::tempdir;
+
+    use super::super::{encode_identity, encode_recipient};
+
+    fn transcript(stanzas: &[Stanza]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for stanza in stanzas {
+            stanza.write(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    fn read_all(mut bytes: &[u8]) -> Vec<Stanza> {
+        let mut stanzas = Vec::new();
+        while let Some(stanza) = Stanza::read(&mut bytes).unwrap() {
+            stanzas.push(stanza);
+        }
+        stanzas
+    }
+
+    fn ok() -> Stanza {
+        Stanza::new(OK, &[], &[])
+    }
+
+    fn done() -> Stanza {
+        Stanza::new(DONE, &[], &[])
+    }
+
+    #[test]
+    fn test_recipient_and_identity_transcripts() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::load(Home::from_root(dir.path())).unwrap();
+        let kem = Kem::new(KemAlgorithm::MlKem768).unwrap();
+        let (public_key, _) = config.add_kem_algorithm(&kem, None, None, false).unwrap();
+        let recipient = encode_recipient(KemAlgorithm::MlKem768, public_key.as_ref()).unwrap();
+        let identity = encode_identity(
+            KemAlgorithm::MlKem768,
+            &fingerprint("ML-KEM-768", public_key.as_ref()),
+        )
+        .unwrap();
+        let file_key = [7; 16];
+
+        let input = transcript(&[
+            Stanza::new(ADD_RECIPIENT, &[&recipient], &[]),
+            Stanza::new("x-grease", &["a"], b"ignored"),
+            Stanza::new(WRAP_FILE_KEY, &[], &file_key),
+            done(),
+            ok(),
+        ]);
+        let mut output = Vec::new();
+        run_recipient_plugin(&mut input.as_slice(), &mut output, &config).unwrap();
+        let [recipient_stanza, last] = read_all(&output).try_into().unwrap();
+        assert_eq!(last, done());
+        assert_eq!(recipient_stanza.tag, RECIPIENT_STANZA);
+        assert_eq!(recipient_stanza.args, ["0", STANZA_TAG, "ML-KEM-768"]);
+
+        let input = transcript(&[
+            Stanza::new(ADD_IDENTITY, &[&identity], &[]),
+            Stanza::new(
+                RECIPIENT_STANZA,
+                &["0", "X25519", "arg"],
+                b"other recipient",
+            ),
+            recipient_stanza,
+            done(),
+            ok(),
+        ]);
+        let mut output = Vec::new();
+        run_identity_plugin(&mut input.as_slice(), &mut output, &config).unwrap();
+        assert_eq!(
+            read_all(&output),
+            [Stanza::new(FILE_KEY, &["0"], &file_key), done()]
+        );
+    }
+
+    #[test]
+    fn test_errors_are_reported_to_age() {
+        let dir = tempdir().unwrap();
+        let config = Config::load(Home::from_root(dir.path())).unwrap();
+        let kem = Kem::new(KemAlgorithm::MlKem512).unwrap();
+        let (public_key, _) = kem.keypair().unwrap();
+        let identity = encode_identity(
+            KemAlgorithm::MlKem512,
+            &fingerprint("ML-KEM-512", public_key.as_ref()),
+        )
+        .unwrap();
+
+        // The identity refers to a key the empty home does not have
+        let input = transcript(&[
+            Stanza::new(ADD_IDENTITY, &[&identity], &[]),
+            Stanza::new(WRAP_FILE_KEY, &[], &[7; 16]),
+            done(),
+            ok(),
+        ]);
+        let mut output = Vec::new();
+        run_recipient_plugin(&mut input.as_slice(), &mut output, &config).unwrap();
+        let [error, last] = read_all(&output).try_into().unwrap();
+        assert_eq!(last, done());
+        assert_eq!(error.tag, ERROR);
+        assert_eq!(error.args, ["identity", "0"]);
+
+        let input = transcript(&[
+            Stanza::new(RECIPIENT_STANZA, &["0", STANZA_TAG, "unknown"], &[1; 32]),
+            done(),
+            ok(),
+        ]);
+        let mut output = Vec::new();
+        run_identity_plugin(&mut input.as_slice(), &mut output, &config).unwrap();
+        let [error, last] = read_all(&output).try_into().unwrap();
+        assert_eq!(last, done());
+        assert_eq!(error.args, ["stanza", "0", "0"]);
+
+        let truncated = transcript(&[Stanza::new(ADD_IDENTITY, &[&identity], &[])]);
+        assert!(run_identity_plugin(&mut truncated.as_slice(), &mut Vec::new(), &config).is_err());
+    }
+}
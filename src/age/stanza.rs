@@ -0,0 +1,142 @@
+//! Wire format of the stanzas exchanged between age and a plugin.
+//!
+//! A stanza is a line `-> TAG ARGS...` followed by its body in unpadded standard base64,
+//! wrapped at 64 columns. The last body line is always shorter than 64 columns, so a body
+//! whose encoding fills its last line is followed by an empty line.
+use std::io::{BufRead, Write};
+
+use anyhow::{Context, Result, bail, ensure};
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+
+const STANZA_PREFIX: &str = "-> ";
+const COLUMNS_PER_LINE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stanza {
+    pub tag: String,
+    pub args: Vec<String>,
+    pub body: Vec<u8>,
+}
+
+impl Stanza {
+    pub fn new(tag: &str, args: &[&str], body: &[u8]) -> Self {
+        Self {
+            tag: tag.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            body: body.to_vec(),
+        }
+    }
+
+    /// Reads the next stanza. Returns `None` at the end of the input
+    pub fn read(reader: &mut impl BufRead) -> Result<Option<Self>> {
+        let Some(header) = read_line(reader)? else {
+            return Ok(None);
+        };
+        let Some(header) = header.strip_prefix(STANZA_PREFIX) else {
+            bail!("Expected a stanza, but found: {header}");
+        };
+
+        let mut fields = header.split(' ');
+        let tag = fields.next().unwrap_or_default();
+        let args: Vec<String> = fields.map(str::to_string).collect();
+        ensure!(
+            is_valid_argument(tag) && args.iter().all(|arg| is_valid_argument(arg)),
+            "Invalid stanza header: {header}"
+        );
+
+        let mut encoded = String::new();
+        loop {
+            let line =
+                read_line(reader)?.context(format!("Stanza {tag} has no final body line"))?;
+            ensure!(
+                line.len() <= COLUMNS_PER_LINE,
+                "Body line of stanza {tag} is longer than {COLUMNS_PER_LINE} columns"
+            );
+            encoded.push_str(&line);
+            if line.len() < COLUMNS_PER_LINE {
+                break;
+            }
+        }
+
+        Ok(Some(Self {
+            tag: tag.to_string(),
+            args,
+            body: STANDARD_NO_PAD
+                .decode(&encoded)
+                .context(format!("Body of stanza {tag} is not valid base64"))?,
+        }))
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<()> {
+        let mut header = format!("{STANZA_PREFIX}{}", self.tag);
+        for arg in &self.args {
+            header.push(' ');
+            header.push_str(arg);
+        }
+        writeln!(writer, "{header}")?;
+
+        let encoded = STANDARD_NO_PAD.encode(&self.body);
+        for line in encoded.as_bytes().chunks(COLUMNS_PER_LINE) {
+            writer.write_all(line)?;
+            writer.write_all(b"\n")?;
+        }
+        if encoded.len().is_multiple_of(COLUMNS_PER_LINE) {
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush().context("Failed to send stanza")
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<Option<String>> {
+    let mut line = String::new();
+    if reader
+        .read_line(&mut line)
+        .context("Failed to read stanza")?
+        == 0
+    {
+        return Ok(None);
+    }
+    ensure!(line.ends_with('\n'), "Stanza ended without a newline");
+    line.pop();
+    Ok(Some(line))
+}
+
+/// Tags and arguments are non-empty strings of printable ASCII characters without spaces
+fn is_valid_argument(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stanza_roundtrip() {
+        for length in [0, 1, 47, 48, 49, 96, 200] {
+            let stanza = Stanza::new("tag", &["a", "b"], &vec![7; length]);
+            let mut encoded = Vec::new();
+            stanza.write(&mut encoded).unwrap();
+            let mut reader = encoded.as_slice();
+            assert_eq!(Stanza::read(&mut reader).unwrap(), Some(stanza));
+            assert_eq!(Stanza::read(&mut reader).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_stanza_encoding() {
+        let mut encoded = Vec::new();
+        Stanza::new("done", &[], &[]).write(&mut encoded).unwrap();
+        assert_eq!(encoded, b"-> done\n\n");
+
+        let mut encoded = Vec::new();
+        Stanza::new("ok", &[], &[0; 48])
+            .write(&mut encoded)
+            .unwrap();
+        assert_eq!(encoded, format!("-> ok\n{}\n\n", "A".repeat(64)).as_bytes());
+
+        assert!(Stanza::read(&mut b"-> tag\nAA=\n".as_slice()).is_err());
+        assert!(Stanza::read(&mut b"-> tag  arg\n\n".as_slice()).is_err());
+        assert!(Stanza::read(&mut b"tag\n\n".as_slice()).is_err());
+    }
+}
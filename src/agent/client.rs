@@ -0,0 +1,253 @@
+use std::{
+    env,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use oqs::{
+    kem::{CiphertextRef, Kem, SecretKey as KemSecretKey},
+    sig::{SecretKey as SigSecretKey, Sig, Signature},
+};
+use zeroize::Zeroizing;
+
+use super::{AGENT_SOCK_ENV_VAR, KeyAlgorithm, KeyInfo, Request, Response};
+use crate::cryptography::secret::{Secret, SecretBytes};
+
+/// Connection to a running agent. Every request opens a new connection to the socket
+#[derive(Debug, Clone)]
+pub struct AgentClient {
+    socket: PathBuf,
+}
+
+impl AgentClient {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// Returns the agent at `RUST_SEAL_AGENT_SOCK`, or `None` if it is not set
+    pub fn from_env() -> Option<Self> {
+        env::var_os(AGENT_SOCK_ENV_VAR)
+            .filter(|socket| !socket.is_empty())
+            .map(Self::new)
+    }
+
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    pub fn add_key(
+        &self,
+        algorithm: KeyAlgorithm,
+        public_key: &[u8],
+        secret_key: &[u8],
+        lifetime: Option<u64>,
+    ) -> Result<()> {
+        self.expect_ok(Request::AddKey {
+            algorithm,
+            public_key: public_key.to_vec(),
+            secret_key: SecretBytes::new(secret_key.to_vec()),
+            lifetime,
+        })
+    }
+
+    pub fn remove_key(&self, fingerprint: &str) -> Result<()> {
+        self.expect_ok(Request::RemoveKey {
+            fingerprint: fingerprint.to_string(),
+        })
+    }
+
+    pub fn remove_all(&self) -> Result<()> {
+        self.expect_ok(Request::RemoveAll)
+    }
+
+    pub fn stop(&self) -> Result<()> {
+        self.expect_ok(Request::Stop)
+    }
+
+    pub fn list(&self) -> Result<Vec<KeyInfo>> {
+        match self.request(&Request::List)? {
+            Response::Keys { keys } => Ok(keys),
+            response => bail!("Unexpected agent response: {response:?}"),
+        }
+    }
+
+    /// Returns whether the agent holds the key with `fingerprint`
+    pub fn holds(&self, fingerprint: &str) -> Result<bool> {
+        Ok(self
+            .list()?
+            .iter()
+            .any(|key| key.fingerprint == fingerprint))
+    }
+
+    pub fn sign(&self, fingerprint: &str, message: &[u8]) -> Result<Vec<u8>> {
+        let request = Request::Sign {
+            fingerprint: fingerprint.to_string(),
+            message: message.to_vec(),
+        };
+        match self.request(&request)? {
+            Response::Signature { signature } => Ok(signature),
+            response => bail!("Unexpected agent response: {response:?}"),
+        }
+    }
+
+    pub fn decapsulate(&self, fingerprint: &str, ciphertext: &[u8]) -> Result<SecretBytes> {
+        let request = Request::Decapsulate {
+            fingerprint: fingerprint.to_string(),
+            ciphertext: ciphertext.to_vec(),
+        };
+        match self.request(&request)? {
+            Response::SharedSecret { shared_secret } => Ok(shared_secret),
+            response => bail!("Unexpected agent response: {response:?}"),
+        }
+    }
+
+    fn expect_ok(&self, request: Request) -> Result<()> {
+        match self.request(&request)? {
+            Response::Ok => Ok(()),
+            response => bail!("Unexpected agent response: {response:?}"),
+        }
+    }
+
+    /// Sends `request` and returns the response. Fails with the message of an error response
+    fn request(&self, request: &Request) -> Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket).context(format!(
+            "Failed to connect to the agent at {}. Start it with `rust-seal agent start` or unset {AGENT_SOCK_ENV_VAR}",
+            self.socket.display()
+        ))?;
+
+        // Requests to add a key carry its secret key, responses to decapsulate the shared secret
+        let mut line = Zeroizing::new(
+            serde_json::to_vec(request).context("Failed to serialize agent request")?,
+        );
+        line.push(b'\n');
+        stream
+            .write_all(&line)
+            .context("Failed to send agent request")?;
+
+        let mut line = Zeroizing::new(String::new());
+        BufReader::new(stream)
+            .read_line(&mut line)
+            .context("Failed to read agent response")?;
+        match serde_json::from_str(&line).context("Invalid agent response")? {
+            Response::Error { message } => bail!("Agent: {message}"),
+            response => Ok(response),
+        }
+    }
+}
+
+/// A signature secret key, read from disk or held by the agent
+pub enum SigningKey {
+    Local(Secret<SigSecretKey>),
+    Agent {
+        agent: AgentClient,
+        fingerprint: String,
+    },
+}
+
+impl SigningKey {
+    pub fn sign(&self, signer: &Sig, message: &[u8]) -> Result<Signature> {
+        match self {
+            SigningKey::Local(secret_key) => signer
+                .sign(message, secret_key)
+                .context("Failed to sign message"),
+            SigningKey::Agent { agent, fingerprint } => {
+                let signature = agent.sign(fingerprint, message)?;
+                Ok(signer
+                    .signature_from_bytes(&signature)
+                    .context("Agent returned an invalid signature")?
+                    .to_owned())
+            }
+        }
+    }
+}
+
+/// A KEM secret key, read from disk or held by the agent
+pub enum DecapsulationKey {
+    Local(Secret<KemSecretKey>),
+    Agent {
+        agent: AgentClient,
+        fingerprint: String,
+    },
+}
+
+impl DecapsulationKey {
+    pub fn decapsulate(&self, kem: &Kem, ciphertext: CiphertextRef) -> Result<SecretBytes> {
+        match self {
+            DecapsulationKey::Local(secret_key) => Ok(Secret::new(
+                kem.decapsulate(secret_key, ciphertext)
+                    .context("Failed to decapsulate shared secret")?,
+            )
+            .into_bytes()),
+            DecapsulationKey::Agent { agent, fingerprint } => {
+                let shared_secret = agent.decapsulate(fingerprint, ciphertext.as_ref())?;
+                kem.shared_secret_from_bytes(shared_secret.as_ref())
+                    .context("Agent returned an invalid shared secret")?;
+                Ok(shared_secret)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::metadata, os::unix::fs::PermissionsExt, thread};
+
+    use super::*;
+    use crate::agent::{Agent, bind_socket, serve};
+    use oqs::sig::Algorithm as SigAlgorithmVariant;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_client_and_server_over_socket() {
+        let dir = tempdir().unwrap();
+        let socket = dir.path().join("agent").join("agent.sock");
+        let listener = bind_socket(&socket).unwrap();
+        assert_eq!(
+            metadata(&socket).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(
+            metadata(socket.parent().unwrap())
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o700
+        );
+        assert!(bind_socket(&socket).is_err());
+
+        let path = socket.clone();
+        let server = thread::spawn(move || serve(listener, path, Agent::default()));
+
+        let agent = AgentClient::new(&socket);
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, secret_key) = signer.keypair().unwrap();
+        let algorithm = KeyAlgorithm::Signature(SigAlgorithmVariant::MlDsa44);
+        let fingerprint = algorithm.fingerprint(public_key.as_ref());
+
+        assert!(!agent.holds(&fingerprint).unwrap());
+        agent
+            .add_key(algorithm, public_key.as_ref(), secret_key.as_ref(), None)
+            .unwrap();
+        assert!(agent.holds(&fingerprint).unwrap());
+
+        let signing_key = SigningKey::Agent {
+            agent: agent.clone(),
+            fingerprint: fingerprint.clone(),
+        };
+        let signature = signing_key.sign(&signer, b"message").unwrap();
+        signer.verify(b"message", &signature, &public_key).unwrap();
+
+        agent.remove_key(&fingerprint).unwrap();
+        assert!(signing_key.sign(&signer, b"message").is_err());
+        assert!(agent.remove_key(&fingerprint).is_err());
+
+        agent.stop().unwrap();
+        server.join().unwrap().unwrap();
+        assert!(!socket.exists());
+    }
+}
@@ -0,0 +1,104 @@
+//! Key agent, which holds unlocked secret keys in memory and signs and decapsulates for clients.
+//!
+//! The agent listens on a Unix socket that only its owner may connect to. Clients send one JSON
+//! request per line and receive one JSON response per line. Keys are added with their secret key,
+//! like with `ssh-add`, and are removed when their lifetime ends. No request returns a secret key.
+//!
+//! `rust-seal` uses the agent for `sign` and `decrypt-file` when `RUST_SEAL_AGENT_SOCK` is set and
+//! the agent holds the key; otherwise the secret key is read from disk.
+mod client;
+mod server;
+
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, Utc};
+use oqs::{kem::Algorithm as KemAlgorithmVariant, sig::Algorithm as SigAlgorithmVariant};
+use serde::{Deserialize, Serialize};
+
+use crate::cryptography::{fingerprint::fingerprint, secret::SecretBytes};
+
+pub use client::{AgentClient, DecapsulationKey, SigningKey};
+pub use server::{Agent, bind_socket, serve};
+
+/// Environment variable with the path of the agent socket
+pub const AGENT_SOCK_ENV_VAR: &str = "RUST_SEAL_AGENT_SOCK";
+
+/// Algorithm of a key held by the agent
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    Signature(SigAlgorithmVariant),
+    Kem(KemAlgorithmVariant),
+}
+
+impl KeyAlgorithm {
+    pub fn fingerprint(&self, public_key: &[u8]) -> String {
+        fingerprint(&self.to_string(), public_key)
+    }
+}
+
+impl Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyAlgorithm::Signature(algorithm) => write!(f, "{algorithm}"),
+            KeyAlgorithm::Kem(algorithm) => write!(f, "{algorithm}"),
+        }
+    }
+}
+
+/// A key held by the agent, without its secret key
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyInfo {
+    pub algorithm: KeyAlgorithm,
+    pub fingerprint: String,
+    pub expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    /// Holds the key until `lifetime` seconds have passed, or for the default lifetime of the agent
+    AddKey {
+        algorithm: KeyAlgorithm,
+        #[serde(with = "hex")]
+        public_key: Vec<u8>,
+        secret_key: SecretBytes,
+        lifetime: Option<u64>,
+    },
+    RemoveKey {
+        fingerprint: String,
+    },
+    RemoveAll,
+    List,
+    Sign {
+        fingerprint: String,
+        #[serde(with = "hex")]
+        message: Vec<u8>,
+    },
+    Decapsulate {
+        fingerprint: String,
+        #[serde(with = "hex")]
+        ciphertext: Vec<u8>,
+    },
+    /// Removes the socket and exits
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Keys {
+        keys: Vec<KeyInfo>,
+    },
+    Signature {
+        #[serde(with = "hex")]
+        signature: Vec<u8>,
+    },
+    SharedSecret {
+        shared_secret: SecretBytes,
+    },
+    Error {
+        message: String,
+    },
+}
@@ -0,0 +1,526 @@
+use std::{
+    fs::{DirBuilder, remove_dir, remove_file, rename, set_permissions},
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, TimeDelta, Utc};
+use oqs::{
+    kem::{Kem, SecretKey as KemSecretKey},
+    sig::{SecretKey as SigSecretKey, Sig},
+};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use super::{KeyAlgorithm, KeyInfo, Request, Response};
+use crate::{cryptography::secret::Secret, selftest};
+
+/// How often keys whose lifetime has ended are removed
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+enum SecretKey {
+    Signature(Secret<SigSecretKey>),
+    Kem(Secret<KemSecretKey>),
+}
+
+struct HeldKey {
+    info: KeyInfo,
+    secret_key: SecretKey,
+}
+
+/// The keys held by the agent
+#[derive(Default)]
+pub struct Agent {
+    keys: Vec<HeldKey>,
+    default_lifetime: Option<u64>,
+}
+
+impl Agent {
+    /// Keys added without a lifetime are held for `default_lifetime` seconds, or until removed if `None`
+    pub fn new(default_lifetime: Option<u64>) -> Self {
+        Self {
+            keys: Vec::new(),
+            default_lifetime,
+        }
+    }
+
+    /// Answers a request at `now`. Keys whose lifetime has ended are removed first
+    pub fn handle(&mut self, request: Request, now: DateTime<Utc>) -> Response {
+        self.expire(now);
+        self.answer(request, now)
+            .unwrap_or_else(|err| Response::Error {
+                message: format!("{err:#}"),
+            })
+    }
+
+    fn answer(&mut self, request: Request, now: DateTime<Utc>) -> Result<Response> {
+        match request {
+            Request::AddKey {
+                algorithm,
+                public_key,
+                secret_key,
+                lifetime,
+            } => self.add_key(algorithm, &public_key, &secret_key, lifetime, now),
+            Request::RemoveKey { fingerprint } => {
+                let count = self.keys.len();
+                self.keys.retain(|key| key.info.fingerprint != fingerprint);
+                ensure!(
+                    self.keys.len() < count,
+                    "The agent holds no key with fingerprint {fingerprint}"
+                );
+                Ok(Response::Ok)
+            }
+            Request::RemoveAll => {
+                self.keys.clear();
+                Ok(Response::Ok)
+            }
+            Request::List => Ok(Response::Keys {
+                keys: self.keys.iter().map(|key| key.info.clone()).collect(),
+            }),
+            Request::Sign {
+                fingerprint,
+                message,
+            } => self.sign(&fingerprint, &message),
+            Request::Decapsulate {
+                fingerprint,
+                ciphertext,
+            } => self.decapsulate(&fingerprint, &ciphertext),
+            Request::Stop => bail!("Stop is handled by the server"),
+        }
+    }
+
+    /// Removes the keys whose lifetime has ended at `now`
+    pub fn expire(&mut self, now: DateTime<Utc>) {
+        self.keys.retain(|key| {
+            let expired = key.info.expires.is_some_and(|expires| expires <= now);
+            if expired {
+                info!(
+                    "Lifetime of {} key {} ended",
+                    key.info.algorithm, key.info.fingerprint
+                );
+            }
+            !expired
+        });
+    }
+
+    fn add_key(
+        &mut self,
+        algorithm: KeyAlgorithm,
+        public_key: &[u8],
+        secret_key: &[u8],
+        lifetime: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Result<Response> {
+        let secret_key = match algorithm {
+            KeyAlgorithm::Signature(algorithm) => {
+                let signer = Sig::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+                let public_key = signer
+                    .public_key_from_bytes(public_key)
+                    .context(format!("Public key is not a valid {algorithm} key"))?;
+                let secret_key = signer
+                    .secret_key_from_bytes(secret_key)
+                    .context(format!("Secret key is not a valid {algorithm} key"))?;
+                selftest::check_signature_keypair(&signer, public_key, secret_key)?;
+                SecretKey::Signature(Secret::new(secret_key.to_owned()))
+            }
+            KeyAlgorithm::Kem(algorithm) => {
+                let kem = Kem::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+                let public_key = kem
+                    .public_key_from_bytes(public_key)
+                    .context(format!("Public key is not a valid {algorithm} key"))?;
+                let secret_key = kem
+                    .secret_key_from_bytes(secret_key)
+                    .context(format!("Secret key is not a valid {algorithm} key"))?;
+                selftest::check_kem_keypair(&kem, public_key, secret_key)?;
+                SecretKey::Kem(Secret::new(secret_key.to_owned()))
+            }
+        };
+
+        let expires = lifetime
+            .or(self.default_lifetime)
+            .map(|seconds| {
+                i64::try_from(seconds)
+                    .ok()
+                    .and_then(TimeDelta::try_seconds)
+                    .and_then(|lifetime| now.checked_add_signed(lifetime))
+                    .context(format!("Lifetime of {seconds} seconds is too long"))
+            })
+            .transpose()?;
+        let info = KeyInfo {
+            algorithm,
+            fingerprint: algorithm.fingerprint(public_key),
+            expires,
+        };
+
+        // Adding a key again replaces it, which renews its lifetime
+        self.keys
+            .retain(|key| key.info.fingerprint != info.fingerprint);
+        info!("Added {} key {}", info.algorithm, info.fingerprint);
+        self.keys.push(HeldKey { info, secret_key });
+        Ok(Response::Ok)
+    }
+
+    fn sign(&self, fingerprint: &str, message: &[u8]) -> Result<Response> {
+        let (KeyAlgorithm::Signature(algorithm), SecretKey::Signature(secret_key)) =
+            self.find(fingerprint)?
+        else {
+            bail!("Key {fingerprint} is not a signature key");
+        };
+        let signer = Sig::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+        let signature = signer
+            .sign(message, secret_key)
+            .context("Failed to sign message")?;
+        Ok(Response::Signature {
+            signature: signature.into_vec(),
+        })
+    }
+
+    fn decapsulate(&self, fingerprint: &str, ciphertext: &[u8]) -> Result<Response> {
+        let (KeyAlgorithm::Kem(algorithm), SecretKey::Kem(secret_key)) = self.find(fingerprint)?
+        else {
+            bail!("Key {fingerprint} is not a KEM key");
+        };
+        let kem = Kem::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+        let ciphertext = kem
+            .ciphertext_from_bytes(ciphertext)
+            .context(format!("Ciphertext is not a valid {algorithm} ciphertext"))?;
+        let shared_secret = Secret::new(
+            kem.decapsulate(secret_key, ciphertext)
+                .context("Failed to decapsulate shared secret")?,
+        );
+        Ok(Response::SharedSecret {
+            shared_secret: shared_secret.into_bytes(),
+        })
+    }
+
+    fn find(&self, fingerprint: &str) -> Result<(KeyAlgorithm, &SecretKey)> {
+        self.keys
+            .iter()
+            .find(|key| key.info.fingerprint == fingerprint)
+            .map(|key| (key.info.algorithm, &key.secret_key))
+            .context(format!(
+                "The agent holds no key with fingerprint {fingerprint}"
+            ))
+    }
+}
+
+/// Binds the agent socket at `path`, which only the owner may connect to.
+/// A missing parent directory is created accessible only by the owner; an existing one must be owned by the user
+/// and not writable by others, who could otherwise replace the socket.
+/// Fails if an agent is already listening on `path`
+pub fn bind_socket(path: &Path) -> Result<UnixListener> {
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if parent.exists() {
+            ensure_private_dir(parent)?;
+        } else {
+            DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(parent)
+                .context(format!("Failed to create {}", parent.display()))?;
+        }
+    }
+    if path.exists() {
+        ensure!(
+            UnixStream::connect(path).is_err(),
+            "An agent is already listening on {}",
+            path.display()
+        );
+        remove_file(path).context(format!("Failed to remove stale socket {}", path.display()))?;
+    }
+
+    // The socket gets the permissions left by the umask, so it is bound in a directory only the owner can enter
+    // and moved into place once it is restricted
+    let file_name = path
+        .file_name()
+        .context(format!("{} is not a socket path", path.display()))?;
+    let bind_dir = path.with_file_name(format!(
+        ".{}.{}.d",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    DirBuilder::new()
+        .mode(0o700)
+        .create(&bind_dir)
+        .context(format!("Failed to create {}", bind_dir.display()))?;
+    let bind_path = bind_dir.join(file_name);
+    let bound = UnixListener::bind(&bind_path)
+        .context(format!("Failed to bind agent socket {}", path.display()))
+        .and_then(|listener| {
+            set_permissions(&bind_path, PermissionsExt::from_mode(0o600))
+                .context("Failed to restrict the permissions of the agent socket")?;
+            rename(&bind_path, path).context(format!(
+                "Failed to move the agent socket to {}",
+                path.display()
+            ))?;
+            Ok(listener)
+        });
+    let _ = remove_file(&bind_path);
+    let _ = remove_dir(&bind_dir);
+    bound
+}
+
+/// Fails if `dir` is owned by another user or writable by the group or others
+fn ensure_private_dir(dir: &Path) -> Result<()> {
+    let metadata = dir.metadata().context(format!(
+        "Failed to read the permissions of {}",
+        dir.display()
+    ))?;
+    // SAFETY: geteuid cannot fail
+    let uid = unsafe { libc::geteuid() };
+    ensure!(
+        metadata.uid() == uid,
+        "Refusing to create the agent socket in {}, which is owned by another user",
+        dir.display()
+    );
+    ensure!(
+        metadata.mode() & 0o022 == 0,
+        "Refusing to create the agent socket in {}, which is writable by other users. Use a directory with mode 0700",
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Serves requests until a client sends [`Request::Stop`], then removes the socket at `path` and returns once the
+/// held keys are dropped
+pub fn serve(listener: UnixListener, path: PathBuf, agent: Agent) -> Result<()> {
+    let agent = Arc::new(Mutex::new(agent));
+    let (stop, stopped) = mpsc::channel();
+
+    let expiring = Arc::clone(&agent);
+    thread::spawn(move || {
+        loop {
+            thread::sleep(EXPIRY_INTERVAL);
+            if let Ok(mut agent) = expiring.lock() {
+                agent.expire(Utc::now());
+            }
+        }
+    });
+
+    let accepting = Arc::clone(&agent);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream.context("Failed to accept agent connection") {
+                Ok(stream) => stream,
+                Err(err) => {
+                    let _ = stop.send(Err(err));
+                    return;
+                }
+            };
+            let agent = Arc::clone(&accepting);
+            let path = path.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                if let Err(err) = handle_connection(stream, &agent, &path, &stop) {
+                    info!("Agent connection failed: {err:#}");
+                }
+            });
+        }
+    });
+
+    let result = stopped
+        .recv()
+        .context("The agent stopped accepting connections")?;
+    // The other threads outlive this function, so the keys are dropped here rather than with the last reference
+    if let Ok(mut agent) = agent.lock() {
+        agent.keys.clear();
+    }
+    result
+}
+
+fn handle_connection(
+    stream: UnixStream,
+    agent: &Mutex<Agent>,
+    path: &Path,
+    stop: &Sender<Result<()>>,
+) -> Result<()> {
+    let mut writer = stream.try_clone().context("Failed to clone agent stream")?;
+    for line in BufReader::new(stream).lines() {
+        // Requests to add a key carry its secret key
+        let line = Zeroizing::new(line.context("Failed to read agent request")?);
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Stop) => {
+                info!("Stopping agent");
+                agent
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("Agent state is poisoned"))?
+                    .keys
+                    .clear();
+                let _ = remove_file(path);
+                write_response(&mut writer, &Response::Ok)?;
+                let _ = stop.send(Ok(()));
+                return Ok(());
+            }
+            Ok(request) => agent
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Agent state is poisoned"))?
+                .handle(request, Utc::now()),
+            Err(err) => Response::Error {
+                message: format!("Invalid request: {err}"),
+            },
+        };
+        write_response(&mut writer, &response)?;
+    }
+    Ok(())
+}
+
+fn write_response(writer: &mut impl Write, response: &Response) -> Result<()> {
+    // Responses to decapsulate carry the shared secret
+    let mut line =
+        Zeroizing::new(serde_json::to_vec(response).context("Failed to serialize agent response")?);
+    line.push(b'\n');
+    writer
+        .write_all(&line)
+        .context("Failed to write agent response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptography::secret::SecretBytes;
+    use oqs::{kem::Algorithm as KemAlgorithmVariant, sig::Algorithm as SigAlgorithmVariant};
+
+    #[test]
+    fn test_sign_and_decapsulate_with_held_keys() {
+        let mut agent = Agent::default();
+        let now = Utc::now();
+
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (sig_public_key, sig_secret_key) = signer.keypair().unwrap();
+        let sig_algorithm = KeyAlgorithm::Signature(SigAlgorithmVariant::MlDsa44);
+        let kem = Kem::new(KemAlgorithmVariant::MlKem768).unwrap();
+        let (kem_public_key, kem_secret_key) = kem.keypair().unwrap();
+        let kem_algorithm = KeyAlgorithm::Kem(KemAlgorithmVariant::MlKem768);
+
+        for (algorithm, public_key, secret_key) in [
+            (
+                sig_algorithm,
+                sig_public_key.as_ref(),
+                sig_secret_key.as_ref(),
+            ),
+            (
+                kem_algorithm,
+                kem_public_key.as_ref(),
+                kem_secret_key.as_ref(),
+            ),
+        ] {
+            let request = Request::AddKey {
+                algorithm,
+                public_key: public_key.to_vec(),
+                secret_key: SecretBytes::new(secret_key.to_vec()),
+                lifetime: None,
+            };
+            assert!(matches!(agent.handle(request, now), Response::Ok));
+        }
+
+        let sig_fingerprint = sig_algorithm.fingerprint(sig_public_key.as_ref());
+        let request = Request::Sign {
+            fingerprint: sig_fingerprint.clone(),
+            message: b"message".to_vec(),
+        };
+        let Response::Signature { signature } = agent.handle(request, now) else {
+            panic!("Expected a signature");
+        };
+        signer
+            .verify(
+                b"message",
+                signer.signature_from_bytes(&signature).unwrap(),
+                &sig_public_key,
+            )
+            .unwrap();
+
+        let (ciphertext, shared_secret) = kem.encapsulate(&kem_public_key).unwrap();
+        let request = Request::Decapsulate {
+            fingerprint: kem_algorithm.fingerprint(kem_public_key.as_ref()),
+            ciphertext: ciphertext.into_vec(),
+        };
+        let Response::SharedSecret {
+            shared_secret: decapsulated,
+        } = agent.handle(request, now)
+        else {
+            panic!("Expected a shared secret");
+        };
+        assert_eq!(decapsulated.as_ref(), shared_secret.as_ref());
+
+        let request = Request::Decapsulate {
+            fingerprint: sig_fingerprint,
+            ciphertext: Vec::new(),
+        };
+        assert!(matches!(agent.handle(request, now), Response::Error { .. }));
+
+        // A secret key cannot be held under the fingerprint of another key
+        let (other_public_key, _) = signer.keypair().unwrap();
+        let request = Request::AddKey {
+            algorithm: sig_algorithm,
+            public_key: other_public_key.into_vec(),
+            secret_key: SecretBytes::new(sig_secret_key.into_vec()),
+            lifetime: None,
+        };
+        assert!(matches!(agent.handle(request, now), Response::Error { .. }));
+        assert_eq!(agent.keys.len(), 2);
+    }
+
+    #[test]
+    fn test_socket_directory_must_be_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = dir.path().join("shared");
+        DirBuilder::new().mode(0o777).create(&shared).unwrap();
+        set_permissions(&shared, PermissionsExt::from_mode(0o777)).unwrap();
+        assert!(bind_socket(&shared.join("agent.sock")).is_err());
+
+        let path = dir.path().join("private/agent.sock");
+        let _listener = bind_socket(&path).unwrap();
+        assert_eq!(path.metadata().unwrap().mode() & 0o777, 0o600);
+        assert_eq!(
+            path.parent().unwrap().metadata().unwrap().mode() & 0o777,
+            0o700
+        );
+        assert!(bind_socket(&path).is_err());
+    }
+
+    #[test]
+    fn test_keys_expire_after_their_lifetime() {
+        let mut agent = Agent::new(Some(60));
+        let now = Utc::now();
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let algorithm = KeyAlgorithm::Signature(SigAlgorithmVariant::MlDsa44);
+
+        for lifetime in [Some(10), None] {
+            let (public_key, secret_key) = signer.keypair().unwrap();
+            let request = Request::AddKey {
+                algorithm,
+                public_key: public_key.into_vec(),
+                secret_key: SecretBytes::new(secret_key.into_vec()),
+                lifetime,
+            };
+            agent.handle(request, now);
+        }
+
+        let count_at = |agent: &mut Agent, seconds| match agent
+            .handle(Request::List, now + TimeDelta::seconds(seconds))
+        {
+            Response::Keys { keys } => keys.len(),
+            _ => panic!("Expected the keys"),
+        };
+        assert_eq!(count_at(&mut agent, 0), 2);
+        assert_eq!(count_at(&mut agent, 10), 1);
+        assert_eq!(count_at(&mut agent, 59), 1);
+        assert_eq!(count_at(&mut agent, 60), 0);
+    }
+}
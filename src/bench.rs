@@ -0,0 +1,307 @@
+//! Latency measurements for the post-quantum algorithms.
+//!
+//! Every operation is timed individually, so the reported percentiles reflect the spread between single calls.
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use oqs::{kem::Kem, sig::Sig};
+use serde::Serialize;
+
+/// Operations measured by the benchmark
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Keygen,
+    Sign,
+    Verify,
+    Encapsulate,
+    Decapsulate,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self {
+            Operation::Keygen => "keygen",
+            Operation::Sign => "sign",
+            Operation::Verify => "verify",
+            Operation::Encapsulate => "encapsulate",
+            Operation::Decapsulate => "decapsulate",
+        };
+        f.write_str(operation)
+    }
+}
+
+/// Latency distribution of an operation in microseconds
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Latency {
+    pub min_us: f64,
+    pub median_us: f64,
+    pub p90_us: f64,
+    pub p99_us: f64,
+    pub max_us: f64,
+}
+
+impl Latency {
+    pub fn from_samples(samples: &mut [Duration]) -> Self {
+        samples.sort_unstable();
+        let micros = |duration: Duration| duration.as_secs_f64() * 1_000_000.0;
+
+        Self {
+            min_us: micros(percentile(samples, 0.0)),
+            median_us: micros(percentile(samples, 50.0)),
+            p90_us: micros(percentile(samples, 90.0)),
+            p99_us: micros(percentile(samples, 99.0)),
+            max_us: micros(percentile(samples, 100.0)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct OperationReport {
+    pub operation: Operation,
+    /// Size of the signed message in bytes. Only set for signature operations
+    pub message_size: Option<usize>,
+    pub iterations: usize,
+    #[serde(flatten)]
+    pub latency: Latency,
+}
+
+/// Measurements and artifact sizes (in bytes) of a single algorithm
+#[derive(Serialize, Debug)]
+pub struct AlgorithmReport {
+    pub algorithm: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub public_key_size: usize,
+    pub secret_key_size: usize,
+    pub ciphertext_size: Option<usize>,
+    pub signature_size: Option<usize>,
+    pub operations: Vec<OperationReport>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Number of timed calls per operation
+    pub iterations: usize,
+    /// Message sizes in bytes used for signing and verifying
+    pub message_sizes: Vec<usize>,
+}
+
+pub fn bench_signature(signature: &Sig, options: &BenchOptions) -> Result<AlgorithmReport> {
+    ensure!(options.iterations > 0, "At least one iteration is required");
+
+    let mut operations = vec![OperationReport {
+        operation: Operation::Keygen,
+        message_size: None,
+        iterations: options.iterations,
+        latency: measure(options.iterations, || signature.keypair())
+            .context("Failed to generate key pair")?,
+    }];
+
+    let (public_key, secret_key) = signature.keypair().context("Failed to generate key pair")?;
+    for &message_size in &options.message_sizes {
+        let message = vec![0xa5; message_size];
+
+        let latency = measure(options.iterations, || signature.sign(&message, &secret_key))
+            .context("Failed to sign message")?;
+        operations.push(OperationReport {
+            operation: Operation::Sign,
+            message_size: Some(message_size),
+            iterations: options.iterations,
+            latency,
+        });
+
+        let signed = signature
+            .sign(&message, &secret_key)
+            .context("Failed to sign message")?;
+        let latency = measure(options.iterations, || {
+            signature.verify(&message, &signed, &public_key)
+        })
+        .context("Failed to verify signature")?;
+        operations.push(OperationReport {
+            operation: Operation::Verify,
+            message_size: Some(message_size),
+            iterations: options.iterations,
+            latency,
+        });
+    }
+
+    Ok(AlgorithmReport {
+        algorithm: signature.algorithm().to_string(),
+        kind: "sig",
+        public_key_size: signature.length_public_key(),
+        secret_key_size: signature.length_secret_key(),
+        ciphertext_size: None,
+        signature_size: Some(signature.length_signature()),
+        operations,
+    })
+}
+
+pub fn bench_kem(kem: &Kem, options: &BenchOptions) -> Result<AlgorithmReport> {
+    ensure!(options.iterations > 0, "At least one iteration is required");
+
+    let keygen =
+        measure(options.iterations, || kem.keypair()).context("Failed to generate key pair")?;
+
+    let (public_key, secret_key) = kem.keypair().context("Failed to generate key pair")?;
+    let encapsulate = measure(options.iterations, || kem.encapsulate(&public_key))
+        .context("Failed to encapsulate")?;
+
+    let (ciphertext, _) = kem
+        .encapsulate(&public_key)
+        .context("Failed to encapsulate")?;
+    let decapsulate = measure(options.iterations, || {
+        kem.decapsulate(&secret_key, &ciphertext)
+    })
+    .context("Failed to decapsulate")?;
+
+    let operations = [
+        (Operation::Keygen, keygen),
+        (Operation::Encapsulate, encapsulate),
+        (Operation::Decapsulate, decapsulate),
+    ]
+    .into_iter()
+    .map(|(operation, latency)| OperationReport {
+        operation,
+        message_size: None,
+        iterations: options.iterations,
+        latency,
+    })
+    .collect();
+
+    Ok(AlgorithmReport {
+        algorithm: kem.algorithm().to_string(),
+        kind: "kem",
+        public_key_size: kem.length_public_key(),
+        secret_key_size: kem.length_secret_key(),
+        ciphertext_size: Some(kem.length_ciphertext()),
+        signature_size: None,
+        operations,
+    })
+}
+
+/// Parses a size in bytes with an optional `K`/`KiB` or `M`/`MiB` suffix, e.g. `64`, `1K` or `4MiB`
+pub fn parse_size(value: &str) -> Result<usize> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number: usize = number.parse().context(format!("Invalid size: {value}"))?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        _ => bail!("Invalid size unit in {value}. Expected one of: K, KiB, M, MiB"),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .context(format!("Size is too large: {value}"))
+}
+
+/// Formats a latency given in microseconds with a readable unit
+pub fn format_micros(micros: f64) -> String {
+    if micros >= 1_000_000.0 {
+        format!("{:.2} s", micros / 1_000_000.0)
+    } else if micros >= 1_000.0 {
+        format!("{:.2} ms", micros / 1_000.0)
+    } else {
+        format!("{micros:.1} µs")
+    }
+}
+
+fn measure<T, E>(iterations: usize, mut operation: impl FnMut() -> Result<T, E>) -> Result<Latency>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let output = operation()?;
+        samples.push(start.elapsed());
+        drop(output);
+    }
+
+    Ok(Latency::from_samples(&mut samples))
+}
+
+/// Nearest-rank percentile of sorted, non-empty `samples`
+fn percentile(samples: &[Duration], percentile: f64) -> Duration {
+    let rank = (percentile / 100.0 * samples.len() as f64).ceil() as usize;
+    samples[rank.clamp(1, samples.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use oqs::{kem::Algorithm as KemAlgorithm, sig::Algorithm as SigAlgorithm};
+
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut samples: Vec<Duration> = (1..=100).rev().map(Duration::from_micros).collect();
+
+        let latency = Latency::from_samples(&mut samples);
+
+        assert_eq!(latency.min_us, 1.0);
+        assert_eq!(latency.median_us, 50.0);
+        assert_eq!(latency.p90_us, 90.0);
+        assert_eq!(latency.p99_us, 99.0);
+        assert_eq!(latency.max_us, 100.0);
+    }
+
+    #[test]
+    fn test_single_sample() {
+        let latency = Latency::from_samples(&mut [Duration::from_micros(7)]);
+
+        assert_eq!(latency.median_us, 7.0);
+        assert_eq!(latency.p99_us, 7.0);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("64").unwrap(), 64);
+        assert_eq!(parse_size("1K").unwrap(), 1024);
+        assert_eq!(parse_size("4MiB").unwrap(), 4 * 1024 * 1024);
+        assert_eq!(parse_size("2 kib").unwrap(), 2048);
+        assert!(parse_size("1G").is_err());
+        assert!(parse_size("K").is_err());
+    }
+
+    #[test]
+    fn test_bench_reports_all_operations() {
+        let options = BenchOptions {
+            iterations: 2,
+            message_sizes: vec![0, 1024],
+        };
+
+        let signature = Sig::new(SigAlgorithm::MlDsa44).unwrap();
+        let report = bench_signature(&signature, &options).unwrap();
+        let operations: Vec<_> = report
+            .operations
+            .iter()
+            .map(|report| (report.operation, report.message_size))
+            .collect();
+        assert_eq!(
+            operations,
+            [
+                (Operation::Keygen, None),
+                (Operation::Sign, Some(0)),
+                (Operation::Verify, Some(0)),
+                (Operation::Sign, Some(1024)),
+                (Operation::Verify, Some(1024)),
+            ]
+        );
+        assert_eq!(report.signature_size, Some(signature.length_signature()));
+
+        let kem = Kem::new(KemAlgorithm::MlKem512).unwrap();
+        let report = bench_kem(&kem, &options).unwrap();
+        assert_eq!(report.operations.len(), 3);
+        assert_eq!(report.ciphertext_size, Some(kem.length_ciphertext()));
+    }
+}
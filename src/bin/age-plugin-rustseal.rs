@@ -0,0 +1,46 @@
+use std::io::{stdin, stdout};
+
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+
+use rust_seal::{
+    Config,
+    age::{run_identity_plugin, run_recipient_plugin},
+    config::Home,
+};
+
+const STATE_MACHINE_ID: &str = "state_machine";
+const RECIPIENT_STATE_MACHINE: &str = "recipient-v1";
+const IDENTITY_STATE_MACHINE: &str = "identity-v1";
+
+fn main() -> Result<()> {
+    // stdout carries the plugin protocol, so logs must go to stderr
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let matches = Command::new("age-plugin-rustseal")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("age plugin for rust-seal KEM keys. It is started by age; create recipients and identities with `rust-seal age`")
+        .arg(
+            Arg::new(STATE_MACHINE_ID)
+                .long("age-plugin")
+                .value_name("STATE_MACHINE")
+                .required(true)
+                .value_parser([RECIPIENT_STATE_MACHINE, IDENTITY_STATE_MACHINE]),
+        )
+        .get_matches();
+
+    let home = Home::resolve(None, None).context("Failed to determine rust-seal home")?;
+    let config = Config::load(home).context("Failed to load configuration")?;
+
+    let (mut input, mut output) = (stdin().lock(), stdout().lock());
+    match matches
+        .get_one::<String>(STATE_MACHINE_ID)
+        .map(String::as_str)
+    {
+        Some(RECIPIENT_STATE_MACHINE) => run_recipient_plugin(&mut input, &mut output, &config),
+        Some(IDENTITY_STATE_MACHINE) => run_identity_plugin(&mut input, &mut output, &config),
+        _ => unreachable!("clap only accepts the supported state machines"),
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs::{read, read_to_string},
+    io::{Read, Write, sink, stderr, stdin, stdout},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use rust_seal::{Config, config::Home, gpg};
+
+const DETACH_ID: &str = "detach_sign";
+const SIGN_ID: &str = "sign";
+const ARMOR_ID: &str = "armor";
+const LOCAL_USER_ID: &str = "local_user";
+const STATUS_FD_ID: &str = "status_fd";
+const KEYID_FORMAT_ID: &str = "keyid_format";
+const VERIFY_ID: &str = "verify";
+const FILES_ID: &str = "files";
+
+/// Reads the file, or stdin for `-`
+fn read_input(path: &str) -> Result<Vec<u8>> {
+    if path == "-" {
+        let mut content = Vec::new();
+        stdin()
+            .read_to_end(&mut content)
+            .context("Failed to read stdin")?;
+        return Ok(content);
+    }
+    read(path).context(format!("Failed to read {path}"))
+}
+
+/// Opens the status file descriptor. git only uses stdout and stderr
+fn status_writer(matches: &ArgMatches) -> Result<Box<dyn Write>> {
+    match matches.get_one::<u32>(STATUS_FD_ID) {
+        None => Ok(Box::new(sink())),
+        Some(1) => Ok(Box::new(stdout())),
+        Some(2) => Ok(Box::new(stderr())),
+        Some(fd) => bail!("Unsupported status file descriptor: {fd}. Use 1 or 2"),
+    }
+}
+
+fn run(matches: &ArgMatches, config: &Config) -> Result<ExitCode> {
+    let mut status = status_writer(matches)?;
+    let files: Vec<&String> = matches
+        .get_many::<String>(FILES_ID)
+        .unwrap_or_default()
+        .collect();
+
+    if matches.get_flag(VERIFY_ID) {
+        let [signature_path, rest @ ..] = files.as_slice() else {
+            bail!("--verify needs the signature file");
+        };
+        let payload = read_input(rest.first().map_or("-", |path| path.as_str()))?;
+        let armored = read_to_string(PathBuf::from(signature_path))
+            .context(format!("Failed to read signature {signature_path}"))?;
+
+        let valid = gpg::verify(&armored, &payload, config, &mut status, &mut stderr())?;
+        return Ok(if valid {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    if matches.get_flag(SIGN_ID) && matches.get_flag(DETACH_ID) {
+        let key = matches
+            .get_one::<String>(LOCAL_USER_ID)
+            .context("Signing needs the key to sign with: -u <KEY>")?;
+        let payload = read_input(files.first().map_or("-", |path| path.as_str()))?;
+
+        let armored = gpg::sign(&payload, key, config, &mut status)?;
+        stdout()
+            .write_all(armored.as_bytes())
+            .context("Failed to write signature")?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    bail!("Only detached signing (-bsau <KEY>) and --verify are supported")
+}
+
+fn main() -> Result<ExitCode> {
+    // stdout carries the signature or the status lines, so logs must go to stderr
+    tracing_subscriber::fmt().with_writer(stderr).init();
+
+    let flag = |id: &'static str, short: char, long: &'static str| {
+        Arg::new(id)
+            .short(short)
+            .long(long)
+            .action(ArgAction::SetTrue)
+    };
+    let matches = Command::new("rust-seal-gpg")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("gpg compatible signing with rust-seal signature keys for git. Set it as git's gpg.program")
+        .arg(flag(DETACH_ID, 'b', "detach-sign"))
+        .arg(flag(SIGN_ID, 's', "sign"))
+        .arg(flag(ARMOR_ID, 'a', "armor"))
+        .arg(
+            Arg::new(LOCAL_USER_ID)
+                .short('u')
+                .long("local-user")
+                .value_name("KEY")
+                .help("Signature algorithm, fingerprint or key ID of the own key to sign with"),
+        )
+        .arg(
+            Arg::new(STATUS_FD_ID)
+                .long("status-fd")
+                .value_name("FD")
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            Arg::new(KEYID_FORMAT_ID)
+                .long("keyid-format")
+                .value_name("FORMAT")
+                .help("Accepted for compatibility. Key IDs are always long"),
+        )
+        .arg(
+            Arg::new(VERIFY_ID)
+                .long("verify")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(Arg::new(FILES_ID).value_name("FILE").num_args(0..))
+        .get_matches();
+
+    let home = Home::resolve(None, None).context("Failed to determine rust-seal home")?;
+    let config = Config::load(home).context("Failed to load configuration")?;
+    run(&matches, &config)
+}
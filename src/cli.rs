@@ -1,15 +1,33 @@
 use crate::Config;
+use crate::bench::parse_size;
 use crate::commands::{
-    decrypt_file_command, encrypt_file_command, init_kem, init_sig, sign_file_command,
-    verify_signature_command,
+    age_identity_command, age_recipient_command, agent_add_command, agent_list_command,
+    agent_remove_command, agent_start_command, agent_stop_command, algorithms_command,
+    attest_command, bench_command, cert_issue_command, cert_request_command,
+    cert_self_sign_command, cert_verify_command, config_get_command, config_list_command,
+    config_set_command, config_unset_command, contacts_fingerprint_command,
+    contacts_import_bundle_command, contacts_import_command, contacts_list_command,
+    contacts_remove_command, decrypt_file_command, encrypt_file_command, init_kem, init_sig,
+    keys_certify_command, keys_combine_command, keys_export_command, keys_export_key_command,
+    keys_import_key_command, keys_import_revocation_command, keys_init_primary_command,
+    keys_recover_command, keys_revoke_command, keys_split_command, log_consistency_command,
+    log_head_command, log_init_command, log_serve_command, selftest_command, sign_file_command,
+    verify_attestation_command, verify_signature_command,
 };
+use crate::config::{Policy, Setting};
+use crate::cryptography::asn1::Encoding;
+use crate::cryptography::pkcs8::KeyFormat;
+use crate::cryptography::revocation::RevocationReason;
+use crate::cryptography::signature::SignatureFormat;
 use crate::oqs::{convert_str_to_kem_alg, convert_str_to_sig_alg};
+use crate::util::{parse_expiry, parse_lifetime};
 
-use anyhow::{Context, Result};
+use anyhow::{Result, ensure};
 use clap::builder::ValueParser;
-use clap::{Arg, Command, ValueHint};
-use oqs::kem::Algorithm as KEMAlgorithm;
-use oqs::sig::Algorithm as SignatureAlgorithm;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, ValueHint};
+use oqs::kem::{Algorithm as KEMAlgorithm, Kem};
+use oqs::sig::{Algorithm as SignatureAlgorithm, Sig};
+use tracing::warn;
 
 use std::path::PathBuf;
 
@@ -19,6 +37,71 @@ pub const KEM_ALGORITHM_ID: &str = "kem_algorithm";
 pub const SIGNATURE_PATH_ID: &str = "signature_path";
 pub const PUBLIC_KEY_PATH_ID: &str = "public_key_path";
 pub const CIPHER_TEXT_PATH_ID: &str = "cipher_text_path";
+pub const CONFIG_PATH_ID: &str = "config_path";
+pub const KEYS_DIR_ID: &str = "keys_dir";
+pub const IGNORE_POLICY_ID: &str = "ignore_policy";
+pub const SETTING_KEY_ID: &str = "setting_key";
+pub const SETTING_VALUE_ID: &str = "setting_value";
+pub const KEM_ONLY_ID: &str = "kem_only";
+pub const SIG_ONLY_ID: &str = "sig_only";
+pub const JSON_ID: &str = "json";
+pub const ALL_ALGORITHMS_ID: &str = "all_algorithms";
+pub const ITERATIONS_ID: &str = "iterations";
+pub const MESSAGE_SIZE_ID: &str = "message_size";
+pub const CONTACT_NAME_ID: &str = "contact_name";
+pub const FINGERPRINT_ID: &str = "fingerprint";
+pub const RECIPIENT_ID: &str = "recipient";
+pub const SIGNER_ID: &str = "signer";
+pub const SIGNERS_ID: &str = "signers";
+pub const ALLOW_UNTRUSTED_KEY_ID: &str = "allow_untrusted_key";
+pub const EXPIRES_ID: &str = "expires";
+pub const REVOCATION_REASON_ID: &str = "revocation_reason";
+pub const COMMENT_ID: &str = "comment";
+pub const SIGNED_BY_ID: &str = "signed_by";
+pub const REVOCATION_PATH_ID: &str = "revocation_path";
+pub const PRIMARY_SECRET_KEY_ID: &str = "primary_secret_key";
+pub const OUTPUT_PATH_ID: &str = "output_path";
+pub const BUNDLE_PATH_ID: &str = "bundle_path";
+pub const SUBJECT_ID: &str = "subject";
+pub const CA_ID: &str = "ca";
+pub const ENCODING_ID: &str = "encoding";
+pub const REQUEST_PATH_ID: &str = "request_path";
+pub const ISSUER_CERT_ID: &str = "issuer_cert";
+pub const CERTIFICATE_PATH_ID: &str = "certificate_path";
+pub const ROOT_CERT_ID: &str = "root_cert";
+pub const INTERMEDIATE_CERT_ID: &str = "intermediate_cert";
+pub const PRIVATE_ID: &str = "private";
+pub const PRIVATE_KEY_PATH_ID: &str = "private_key_path";
+pub const KEY_FORMAT_ID: &str = "key_format";
+pub const SIGNATURE_FORMAT_ID: &str = "signature_format";
+pub const NAMESPACE_ID: &str = "namespace";
+pub const ALLOWED_SIGNERS_ID: &str = "allowed_signers";
+pub const IDENTITY_ID: &str = "identity";
+pub const SOCKET_ID: &str = "socket";
+pub const LIFETIME_ID: &str = "lifetime";
+pub const FOREGROUND_ID: &str = "foreground";
+pub const REMOVE_ALL_ID: &str = "remove_all";
+pub const FORCE_ID: &str = "force";
+pub const THRESHOLD_ID: &str = "threshold";
+pub const SHARES_ID: &str = "shares";
+pub const ARMOR_ID: &str = "armor";
+pub const OUTPUT_DIR_ID: &str = "output_dir";
+pub const SHARE_PATH_ID: &str = "share_path";
+pub const MNEMONIC_ID: &str = "mnemonic";
+pub const APPEND_ID: &str = "append";
+pub const COUNTERSIGN_ID: &str = "countersign";
+pub const CHAIN_ID: &str = "chain";
+pub const TSA_ID: &str = "tsa";
+pub const LOG_ID: &str = "log";
+pub const REQUIRE_LOG_ID: &str = "require_log";
+pub const LOG_DIR_ID: &str = "log_dir";
+pub const OLD_HEAD_ID: &str = "old_head";
+pub const NEW_HEAD_ID: &str = "new_head";
+pub const LISTEN_ID: &str = "listen";
+pub const ARTIFACTS_ID: &str = "artifacts";
+pub const PREDICATE_ID: &str = "predicate";
+pub const PREDICATE_TYPE_ID: &str = "predicate_type";
+pub const ENVELOPE_PATH_ID: &str = "envelope_path";
 
 const SIGN_SUBCOMMAND_NAME: &str = "sign";
 const VERIFY_SUBCOMMAND_NAME: &str = "verify";
@@ -27,19 +110,108 @@ const SIG_SUBCOMMAND_NAME: &str = "sig";
 const KEM_SUBCOMMAND_NAME: &str = "kem";
 const ENCRYPT_FILE_SUBCOMMAND_NAME: &str = "encrypt-file";
 const DECRYPT_FILE_SUBCOMMAND_NAME: &str = "decrypt-file";
+const CONFIG_SUBCOMMAND_NAME: &str = "config";
+const CONFIG_LIST_SUBCOMMAND_NAME: &str = "list";
+const CONFIG_GET_SUBCOMMAND_NAME: &str = "get";
+const CONFIG_SET_SUBCOMMAND_NAME: &str = "set";
+const CONFIG_UNSET_SUBCOMMAND_NAME: &str = "unset";
+const ALGORITHMS_SUBCOMMAND_NAME: &str = "algorithms";
+const BENCH_SUBCOMMAND_NAME: &str = "bench";
+const SELFTEST_SUBCOMMAND_NAME: &str = "selftest";
+const CONTACTS_SUBCOMMAND_NAME: &str = "contacts";
+const CONTACTS_IMPORT_SUBCOMMAND_NAME: &str = "import";
+const CONTACTS_LIST_SUBCOMMAND_NAME: &str = "list";
+const CONTACTS_REMOVE_SUBCOMMAND_NAME: &str = "remove";
+const CONTACTS_FINGERPRINT_SUBCOMMAND_NAME: &str = "fingerprint";
+const KEYS_SUBCOMMAND_NAME: &str = "keys";
+const KEYS_REVOKE_SUBCOMMAND_NAME: &str = "revoke";
+const KEYS_IMPORT_REVOCATION_SUBCOMMAND_NAME: &str = "import-revocation";
+const KEYS_INIT_PRIMARY_SUBCOMMAND_NAME: &str = "init-primary";
+const KEYS_CERTIFY_SUBCOMMAND_NAME: &str = "certify";
+const KEYS_EXPORT_SUBCOMMAND_NAME: &str = "export";
+const KEYS_EXPORT_KEY_SUBCOMMAND_NAME: &str = "export-key";
+const KEYS_IMPORT_KEY_SUBCOMMAND_NAME: &str = "import-key";
+const KEYS_SPLIT_SUBCOMMAND_NAME: &str = "split";
+const KEYS_COMBINE_SUBCOMMAND_NAME: &str = "combine";
+const KEYS_RECOVER_SUBCOMMAND_NAME: &str = "recover";
+const CONTACTS_IMPORT_BUNDLE_SUBCOMMAND_NAME: &str = "import-bundle";
+const CERT_SUBCOMMAND_NAME: &str = "cert";
+const CERT_REQUEST_SUBCOMMAND_NAME: &str = "request";
+const CERT_SELF_SIGN_SUBCOMMAND_NAME: &str = "self-sign";
+const CERT_ISSUE_SUBCOMMAND_NAME: &str = "issue";
+const CERT_VERIFY_SUBCOMMAND_NAME: &str = "verify";
+const AGE_SUBCOMMAND_NAME: &str = "age";
+const AGE_RECIPIENT_SUBCOMMAND_NAME: &str = "recipient";
+const AGE_IDENTITY_SUBCOMMAND_NAME: &str = "identity";
+const AGENT_SUBCOMMAND_NAME: &str = "agent";
+const AGENT_START_SUBCOMMAND_NAME: &str = "start";
+const AGENT_ADD_SUBCOMMAND_NAME: &str = "add";
+const AGENT_LIST_SUBCOMMAND_NAME: &str = "list";
+const AGENT_REMOVE_SUBCOMMAND_NAME: &str = "remove";
+const AGENT_STOP_SUBCOMMAND_NAME: &str = "stop";
+const LOG_SUBCOMMAND_NAME: &str = "log";
+const LOG_INIT_SUBCOMMAND_NAME: &str = "init";
+const LOG_HEAD_SUBCOMMAND_NAME: &str = "head";
+const LOG_CONSISTENCY_SUBCOMMAND_NAME: &str = "consistency";
+const LOG_SERVE_SUBCOMMAND_NAME: &str = "serve";
+const ATTEST_SUBCOMMAND_NAME: &str = "attest";
+const VERIFY_ATTESTATION_SUBCOMMAND_NAME: &str = "verify-attestation";
 
-fn validate_signature_algorithm(algorithm: &str) -> Result<SignatureAlgorithm> {
-    let parsed = convert_str_to_sig_alg(algorithm)
-        .context(format!("Invalid signature algorithm: {algorithm}"))?;
+// The registry errors already name the algorithm and suggest a correction,
+// which clap only shows if they are not wrapped in another context
+fn parse_signature_algorithm(algorithm: &str) -> Result<SignatureAlgorithm> {
+    convert_str_to_sig_alg(algorithm)
+}
+
+fn parse_kem_algorithm(algorithm: &str) -> Result<KEMAlgorithm> {
+    convert_str_to_kem_alg(algorithm)
+}
+
+/// Checks `signature` against the algorithm policy.
+/// A violation is an error unless `ignore_policy` is set, in which case it is logged.
+pub fn validate_signature_algorithm(
+    signature: &Sig,
+    policy: &Policy,
+    ignore_policy: bool,
+) -> Result<()> {
+    enforce_policy(
+        policy,
+        signature.algorithm().name(),
+        signature.claimed_nist_level(),
+        ignore_policy,
+    )
+}
 
-    Ok(parsed)
+/// Checks `kem` against the algorithm policy.
+/// A violation is an error unless `ignore_policy` is set, in which case it is logged.
+pub fn validate_kem_algorithm(kem: &Kem, policy: &Policy, ignore_policy: bool) -> Result<()> {
+    enforce_policy(
+        policy,
+        kem.algorithm().name(),
+        kem.claimed_nist_level(),
+        ignore_policy,
+    )
 }
 
-fn validate_kem_algorithm(algorithm: &str) -> Result<KEMAlgorithm> {
-    let parsed =
-        convert_str_to_kem_alg(algorithm).context(format!("Invalid KEM algorithm: {algorithm}"))?;
+fn enforce_policy(
+    policy: &Policy,
+    algorithm: &str,
+    nist_level: u8,
+    ignore_policy: bool,
+) -> Result<()> {
+    if let Some(violation) = policy.violation(algorithm, nist_level) {
+        ensure!(
+            ignore_policy,
+            "{violation}. Pass --ignore-policy to use it anyway"
+        );
+        warn!("Algorithm policy overridden with --ignore-policy: {violation}");
+    }
+
+    if policy.is_deprecated(algorithm) {
+        warn!("{algorithm} is deprecated by the algorithm policy");
+    }
 
-    Ok(parsed)
+    Ok(())
 }
 
 pub fn create_cli() -> Command {
@@ -50,18 +222,16 @@ pub fn create_cli() -> Command {
     let sig_algorithm_arg: Arg = Arg::new(SIGNATURE_ALGORITHM_ID)
         .long("signature-algorithm")
         .short('s')
-        .help("Specify the signature algorithm to use")
-        .required(true)
+        .help("Specify the signature algorithm to use. Defaults to the configured defaults.signature_algorithm")
         .value_name("SIGNATURE_ALGORITHM")
-        .value_parser(ValueParser::new(validate_signature_algorithm));
+        .value_parser(ValueParser::new(parse_signature_algorithm));
 
     let kem_algorithm_arg: Arg = Arg::new(KEM_ALGORITHM_ID)
         .long("kem-algorithm")
         .short('k')
-        .help("Specify the KEM algorithm to use")
-        .required(true)
+        .help("Specify the KEM algorithm to use. Defaults to the configured defaults.kem_algorithm")
         .value_name("KEM_ALGORITHM")
-        .value_parser(ValueParser::new(validate_kem_algorithm));
+        .value_parser(ValueParser::new(parse_kem_algorithm));
 
     let file_path_arg: Arg = Arg::new(FILE_PATH_ID)
         .help("Path to the file to work with")
@@ -78,12 +248,29 @@ pub fn create_cli() -> Command {
         .value_parser(clap::value_parser!(PathBuf));
 
     let public_key_path_arg: Arg = Arg::new(PUBLIC_KEY_PATH_ID)
-        .help("Path to the .pub file containing the public key. If not provided, the same path as the FILE_PATH will be used with a .pub extension")
+        .help("Path to a .pub file containing a public key that is not in the contacts. Requires --allow-untrusted-key. If neither this nor a contact is given, the own configured key is used")
         .long("pub-path")
         .value_name("PUBLIC_KEY_PATH")
         .value_hint(ValueHint::FilePath)
         .value_parser(clap::value_parser!(PathBuf));
 
+    let allow_untrusted_key_arg: Arg = Arg::new(ALLOW_UNTRUSTED_KEY_ID)
+        .help("Allow using a public key file given with --pub-path whose fingerprint was never verified")
+        .long("allow-untrusted-key")
+        .action(ArgAction::SetTrue)
+        .requires(PUBLIC_KEY_PATH_ID);
+
+    let expires_arg: Arg = Arg::new(EXPIRES_ID)
+        .help("Date after which the key must no longer be used, e.g. 2030-01-31, or a duration from now such as 90d, 12w, 6m or 2y")
+        .long("expires")
+        .value_name("EXPIRES")
+        .value_parser(ValueParser::new(parse_expiry));
+
+    let contact_name_arg: Arg = Arg::new(CONTACT_NAME_ID)
+        .help("Name of the contact")
+        .value_name("NAME")
+        .required(true);
+
     let cipher_text_path_arg: Arg = Arg::new(CIPHER_TEXT_PATH_ID)
         .help("Path to the ciphertext file used for the key decapsulation. If not provided, the same path as the FILE_PATH will be used with a .cipher extension")
         .long("cipher-path")
@@ -91,6 +278,62 @@ pub fn create_cli() -> Command {
         .value_hint(ValueHint::FilePath)
         .value_parser(clap::value_parser!(PathBuf));
 
+    let config_path_arg: Arg = Arg::new(CONFIG_PATH_ID)
+        .help("Path to the configuration file. Its directory is used as the rust-seal home. Overrides RUST_SEAL_HOME")
+        .long("config")
+        .global(true)
+        .value_name("CONFIG_PATH")
+        .value_hint(ValueHint::FilePath)
+        .value_parser(clap::value_parser!(PathBuf));
+
+    let keys_dir_arg: Arg = Arg::new(KEYS_DIR_ID)
+        .help("Directory in which generated keys are stored")
+        .long("keys-dir")
+        .global(true)
+        .value_name("KEYS_DIR")
+        .value_hint(ValueHint::DirPath)
+        .value_parser(clap::value_parser!(PathBuf));
+
+    let ignore_policy_arg: Arg = Arg::new(IGNORE_POLICY_ID)
+        .help("Use algorithms that violate the configured algorithm policy. Every violation is logged")
+        .long("ignore-policy")
+        .global(true)
+        .action(ArgAction::SetTrue);
+
+    let setting_key_arg: Arg = Arg::new(SETTING_KEY_ID)
+        .help("Name of the setting")
+        .value_name("KEY")
+        .required(true)
+        .value_parser(Setting::ALL.map(|setting| setting.key()));
+
+    let setting_value_arg: Arg = Arg::new(SETTING_VALUE_ID)
+        .help("New value of the setting")
+        .value_name("VALUE")
+        .required(true);
+
+    let json_arg: Arg = Arg::new(JSON_ID)
+        .help("Print the output as JSON. Overrides the configured defaults.output_format")
+        .long("json")
+        .action(ArgAction::SetTrue);
+
+    let signature_format_arg: Arg = Arg::new(SIGNATURE_FORMAT_ID)
+        .help("Format of the signature: a detached raw signature, a compact JWS or COSE_Sign1 carrying the file, or a bundle of raw signatures by several signers. Only ML-DSA supports jws and cose. Defaults to raw, or bundle with --signers, --chain, --require-log, --append, --countersign, --tsa or --log")
+        .long("format")
+        .value_name("FORMAT")
+        .value_parser(ValueParser::new(|value: &str| {
+            value.parse::<SignatureFormat>()
+        }));
+
+    let namespace_arg: Arg = Arg::new(NAMESPACE_ID)
+        .help("Namespace of the signature, e.g. file, git or email, like `ssh-keygen -Y sign -n`. A signature is only valid in the namespace it was made for. Requires --format raw or bundle")
+        .long("namespace")
+        .value_name("NAMESPACE");
+
+    let force_arg: Arg = Arg::new(FORCE_ID)
+        .help("Overwrite files that already exist")
+        .long("force")
+        .action(ArgAction::SetTrue);
+
     //
     // Define CLI commands
     //
@@ -99,30 +342,207 @@ pub fn create_cli() -> Command {
         .about("Sign a file")
         .arg_required_else_help(true)
         .arg(&file_path_arg)
-        .arg(&sig_algorithm_arg);
+        .arg(&sig_algorithm_arg)
+        .arg(&signature_format_arg)
+        .arg(&namespace_arg)
+        .arg(
+            Arg::new(APPEND_ID)
+                .help("Add the signature to this existing signature bundle, keeping its earlier entries. Implies --format bundle")
+                .long("append")
+                .value_name("BUNDLE_PATH")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new(COUNTERSIGN_ID)
+                .help("Countersign the signature by the key with this fingerprint in the bundle, e.g. to approve it, instead of signing the file. Implies --format bundle")
+                .long("countersign")
+                .value_name("FINGERPRINT"),
+        )
+        .arg(
+            Arg::new(TSA_ID)
+                .help("Have the RFC 3161 time-stamping authority at this URL timestamp the signature, so it stays valid after the key expires. Implies --format bundle")
+                .long("tsa")
+                .value_name("URL")
+                .value_hint(ValueHint::Url),
+        )
+        .arg(
+            Arg::new(LOG_ID)
+                .help("Append the signature to the transparency log in this directory or at this URL and store its receipt with the signature. Implies --format bundle")
+                .long("log")
+                .value_name("LOG"),
+        )
+        .arg(&force_arg);
 
     let verify_cmd = Command::new(VERIFY_SUBCOMMAND_NAME)
         .about("Verify a file signature")
         .arg_required_else_help(true)
         .arg(&signature_path_arg)
         .arg(&public_key_path_arg)
+        .arg(
+            Arg::new(SIGNER_ID)
+                .help("Name of the contact whose key is used for the verification")
+                .long("signer")
+                .value_name("NAME")
+                .conflicts_with(PUBLIC_KEY_PATH_ID),
+        )
+        .arg(
+            Arg::new(SIGNERS_ID)
+                .help("Comma separated contacts, or identities of the --allowed-signers file, whose signatures in a bundle count towards the --threshold")
+                .long("signers")
+                .value_name("NAMES")
+                .value_delimiter(',')
+                .conflicts_with_all([SIGNER_ID, PUBLIC_KEY_PATH_ID]),
+        )
+        .arg(
+            Arg::new(CHAIN_ID)
+                .help("Comma separated approval chain of contacts, or identities of the --allowed-signers file: the first signed the file and each following countersigned the signature of the one before")
+                .long("chain")
+                .value_name("NAMES")
+                .value_delimiter(',')
+                .conflicts_with_all([SIGNER_ID, PUBLIC_KEY_PATH_ID]),
+        )
+        .arg(
+            Arg::new(THRESHOLD_ID)
+                .help("Number of distinct --signers that must have a valid signature in the bundle. Defaults to all of them")
+                .long("threshold")
+                .value_name("THRESHOLD")
+                .requires(SIGNERS_ID)
+                .value_parser(clap::value_parser!(u8).range(1..)),
+        )
+        .arg(
+            Arg::new(REQUIRE_LOG_ID)
+                .help("Only count signatures in the bundle whose receipt proves a trusted transparency log recorded them")
+                .long("require-log")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(&allow_untrusted_key_arg)
+        .arg(
+            Arg::new(ALLOWED_SIGNERS_ID)
+                .help("Path to an allowed signers file in the OpenSSH format. The --identity must be allowed to sign in the --namespace")
+                .long("allowed-signers")
+                .value_name("ALLOWED_SIGNERS")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all([SIGNER_ID, PUBLIC_KEY_PATH_ID])
+                .requires_all(["principals", NAMESPACE_ID]),
+        )
+        .arg(
+            Arg::new(IDENTITY_ID)
+                .help("Principal of the allowed signers file who made the signature, e.g. alice@example.com")
+                .long("identity")
+                .value_name("IDENTITY")
+                .requires(ALLOWED_SIGNERS_ID)
+                .conflicts_with_all([SIGNERS_ID, CHAIN_ID]),
+        )
+        .group(
+            ArgGroup::new("principals")
+                .args([IDENTITY_ID, SIGNERS_ID, CHAIN_ID])
+                .multiple(true),
+        )
         .arg(&sig_algorithm_arg)
+        .arg(&signature_format_arg)
+        .arg(&namespace_arg)
         .arg(&file_path_arg);
 
+    let attest_cmd = Command::new(ATTEST_SUBCOMMAND_NAME)
+        .about("Sign an in-toto Statement about artifacts, e.g. SLSA provenance, into a DSSE envelope")
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new(ARTIFACTS_ID)
+                .help("Artifacts the Statement is about. Their SHA-256 digests are attested")
+                .value_name("ARTIFACTS")
+                .required(true)
+                .num_args(1..)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new(PREDICATE_ID)
+                .help("JSON file with the predicate of the Statement")
+                .long("predicate")
+                .value_name("PREDICATE")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new(PREDICATE_TYPE_ID)
+                .help("URI of the predicate type, e.g. https://slsa.dev/provenance/v1")
+                .long("predicate-type")
+                .value_name("URI")
+                .required(true),
+        )
+        .arg(&sig_algorithm_arg)
+        .arg(
+            Arg::new(OUTPUT_PATH_ID)
+                .help("Path of the envelope to write. Defaults to the first artifact with an .intoto.json extension")
+                .long("output")
+                .value_name("OUTPUT_PATH")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(&force_arg);
+
+    let verify_attestation_cmd = Command::new(VERIFY_ATTESTATION_SUBCOMMAND_NAME)
+        .about("Verify a DSSE envelope with an in-toto Statement and the digests of the artifacts it is about")
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new(ENVELOPE_PATH_ID)
+                .help("Path to the DSSE envelope written by `rust-seal attest`")
+                .value_name("ENVELOPE_PATH")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new(ARTIFACTS_ID)
+                .help("Artifacts that must be subjects of the Statement. Defaults to the files named by its subjects")
+                .value_name("ARTIFACTS")
+                .num_args(1..)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new(PREDICATE_TYPE_ID)
+                .help("Require this predicate type, e.g. https://slsa.dev/provenance/v1")
+                .long("predicate-type")
+                .value_name("URI"),
+        )
+        .arg(&sig_algorithm_arg)
+        .arg(
+            Arg::new(SIGNER_ID)
+                .help("Name of the contact whose key is used for the verification")
+                .long("signer")
+                .value_name("NAME")
+                .conflicts_with(PUBLIC_KEY_PATH_ID),
+        )
+        .arg(&public_key_path_arg)
+        .arg(&allow_untrusted_key_arg);
+
     let init_cmd = Command::new(INIT_SUBCOMMAND_NAME)
         .about("Initialize Rust Seal Algorithm")
         .arg_required_else_help(true)
+        .subcommand_required(true)
         .subcommand(
             Command::new(KEM_SUBCOMMAND_NAME)
                 .about("Initialize a Key Exchange Mechanism (KEM) Algorithm")
-                .arg_required_else_help(true)
-                .arg(&kem_algorithm_arg),
+                .arg(&kem_algorithm_arg)
+                .arg(&expires_arg)
+                .arg(
+                    Arg::new(MNEMONIC_ID)
+                        .help("Generate the keypair from a new recovery seed and print it once as a mnemonic, from which `keys recover` recreates the keypair")
+                        .long("mnemonic")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(&force_arg),
         )
         .subcommand(
             Command::new(SIG_SUBCOMMAND_NAME)
                 .about("Initialize a Signature Algorithm")
-                .arg_required_else_help(true)
-                .arg(&sig_algorithm_arg),
+                .arg(&sig_algorithm_arg)
+                .arg(&expires_arg)
+                .arg(&force_arg),
         );
 
     let encrypt_file_cmd = Command::new(ENCRYPT_FILE_SUBCOMMAND_NAME)
@@ -130,34 +550,721 @@ pub fn create_cli() -> Command {
         .arg_required_else_help(true)
         .arg(&kem_algorithm_arg)
         .arg(&file_path_arg)
-        .arg(&public_key_path_arg);
+        .arg(&public_key_path_arg)
+        .arg(
+            Arg::new(RECIPIENT_ID)
+                .help("Name of the contact to encrypt the file for")
+                .long("recipient")
+                .value_name("NAME")
+                .conflicts_with(PUBLIC_KEY_PATH_ID),
+        )
+        .arg(&allow_untrusted_key_arg)
+        .arg(&force_arg);
 
     let decrypt_file_cmd = Command::new(DECRYPT_FILE_SUBCOMMAND_NAME)
         .about("Decrypt a file with AES and get the key with a KEM algorithm. The KEM algorithm must be initialized first")
         .arg_required_else_help(true)
         .arg(&kem_algorithm_arg)
         .arg(&file_path_arg)
-        .arg(&cipher_text_path_arg);
+        .arg(&cipher_text_path_arg)
+        .arg(&force_arg);
+
+    let config_cmd = Command::new(CONFIG_SUBCOMMAND_NAME)
+        .about("Read and change settings such as default algorithms")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(Command::new(CONFIG_LIST_SUBCOMMAND_NAME).about("List all settings"))
+        .subcommand(
+            Command::new(CONFIG_GET_SUBCOMMAND_NAME)
+                .about("Print the value of a setting")
+                .arg(&setting_key_arg),
+        )
+        .subcommand(
+            Command::new(CONFIG_SET_SUBCOMMAND_NAME)
+                .about("Change the value of a setting")
+                .arg(&setting_key_arg)
+                .arg(&setting_value_arg),
+        )
+        .subcommand(
+            Command::new(CONFIG_UNSET_SUBCOMMAND_NAME)
+                .about("Reset a setting to its default value")
+                .arg(&setting_key_arg),
+        );
+
+    let algorithms_cmd = Command::new(ALGORITHMS_SUBCOMMAND_NAME)
+        .about("List the supported algorithms with their security level, status and sizes")
+        .arg(
+            Arg::new(KEM_ONLY_ID)
+                .help("Only list KEM algorithms")
+                .long("kem")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(SIG_ONLY_ID),
+        )
+        .arg(
+            Arg::new(SIG_ONLY_ID)
+                .help("Only list signature algorithms")
+                .long("sig")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(&json_arg);
+
+    let bench_cmd = Command::new(BENCH_SUBCOMMAND_NAME)
+        .about("Measure key generation, signing, verification and key encapsulation latencies. Without algorithms, the standardised ones are measured")
+        .arg(
+            sig_algorithm_arg
+                .clone()
+                .help("Signature algorithms to measure. Can be repeated or comma separated")
+                .action(ArgAction::Append)
+                .value_delimiter(','),
+        )
+        .arg(
+            kem_algorithm_arg
+                .clone()
+                .help("KEM algorithms to measure. Can be repeated or comma separated")
+                .action(ArgAction::Append)
+                .value_delimiter(','),
+        )
+        .arg(
+            Arg::new(ALL_ALGORITHMS_ID)
+                .help("Measure every algorithm enabled in liboqs")
+                .long("all")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([SIGNATURE_ALGORITHM_ID, KEM_ALGORITHM_ID]),
+        )
+        .arg(
+            Arg::new(ITERATIONS_ID)
+                .help("Number of timed runs per operation")
+                .long("iterations")
+                .short('n')
+                .value_name("ITERATIONS")
+                .default_value("20")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new(MESSAGE_SIZE_ID)
+                .help("Sizes of the signed messages, e.g. 64, 1K or 1M. Can be repeated or comma separated")
+                .long("message-size")
+                .value_name("SIZE")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .default_value("32,1K,1M")
+                .value_parser(ValueParser::new(parse_size)),
+        )
+        .arg(&json_arg);
+
+    let selftest_cmd = Command::new(SELFTEST_SUBCOMMAND_NAME)
+        .about("Run known-answer tests and pairwise-consistency checks for every algorithm and AES-GCM")
+        .arg(&json_arg);
+
+    let contact_algorithm_group = ArgGroup::new("contact_algorithm")
+        .args([SIGNATURE_ALGORITHM_ID, KEM_ALGORITHM_ID])
+        .required(true);
+
+    let fingerprint_arg: Arg = Arg::new(FINGERPRINT_ID)
+        .help("Fingerprint of the key as confirmed by its owner. Without it, the fingerprint is only shown")
+        .long("fingerprint")
+        .value_name("FINGERPRINT");
+
+    let contacts_cmd = Command::new(CONTACTS_SUBCOMMAND_NAME)
+        .about("Manage the public keys of other people, identified by verified fingerprints")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(CONTACTS_IMPORT_SUBCOMMAND_NAME)
+                .about("Import a public key for a contact after checking its fingerprint")
+                .arg_required_else_help(true)
+                .arg(&contact_name_arg)
+                .arg(
+                    Arg::new(PUBLIC_KEY_PATH_ID)
+                        .help("Path to the .pub file or SubjectPublicKeyInfo in PEM or DER encoding containing the public key")
+                        .value_name("PUBLIC_KEY_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(&sig_algorithm_arg)
+                .arg(&kem_algorithm_arg)
+                .group(contact_algorithm_group.clone())
+                .arg(&fingerprint_arg)
+                .arg(
+                    expires_arg
+                        .clone()
+                        .help("Expiry date of the key as announced by its owner, e.g. 2030-01-31"),
+                ),
+        )
+        .subcommand(
+            Command::new(CONTACTS_IMPORT_BUNDLE_SUBCOMMAND_NAME)
+                .about("Import a contact's primary key and the keys it certifies from a bundle created with `keys export`")
+                .arg_required_else_help(true)
+                .arg(&contact_name_arg)
+                .arg(
+                    Arg::new(BUNDLE_PATH_ID)
+                        .help("Path to the key bundle")
+                        .value_name("BUNDLE_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    fingerprint_arg
+                        .clone()
+                        .help("Fingerprint of the primary key as confirmed by its owner. Without it, the fingerprint is only shown"),
+                ),
+        )
+        .subcommand(
+            Command::new(CONTACTS_LIST_SUBCOMMAND_NAME)
+                .about("List the contacts and the fingerprints of their keys")
+                .arg(&json_arg),
+        )
+        .subcommand(
+            Command::new(CONTACTS_REMOVE_SUBCOMMAND_NAME)
+                .about("Remove a contact with all of its keys")
+                .arg(&contact_name_arg),
+        )
+        .subcommand(
+            Command::new(CONTACTS_FINGERPRINT_SUBCOMMAND_NAME)
+                .about("Print the fingerprint of a public key file, or of the own key to share with others")
+                .arg(
+                    Arg::new(PUBLIC_KEY_PATH_ID)
+                        .help("Path to the .pub file or SubjectPublicKeyInfo. Defaults to the own configured key")
+                        .value_name("PUBLIC_KEY_PATH")
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(&sig_algorithm_arg)
+                .arg(&kem_algorithm_arg)
+                .group(contact_algorithm_group.clone()),
+        );
+
+    let keys_cmd = Command::new(KEYS_SUBCOMMAND_NAME)
+        .about("Manage the lifecycle of your own keys")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(KEYS_INIT_PRIMARY_SUBCOMMAND_NAME)
+                .about("Create an offline primary key that certifies your signature and KEM keys")
+                .arg(&sig_algorithm_arg)
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(KEYS_CERTIFY_SUBCOMMAND_NAME)
+                .about("Certify one of your keys with the primary key")
+                .arg_required_else_help(true)
+                .arg(sig_algorithm_arg.clone().help("Signature key to certify"))
+                .arg(kem_algorithm_arg.clone().help("KEM key to certify"))
+                .group(contact_algorithm_group.clone())
+                .arg(
+                    expires_arg
+                        .clone()
+                        .help("End of the certification, e.g. 2030-01-31 or 1y. Defaults to the expiry of the key"),
+                )
+                .arg(
+                    Arg::new(PRIMARY_SECRET_KEY_ID)
+                        .help("Path to the primary secret key if it was moved to offline storage")
+                        .long("primary-secret-key")
+                        .value_name("PRIMARY_SECRET_KEY")
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new(KEYS_EXPORT_SUBCOMMAND_NAME)
+                .about("Export the primary public key with every certified key to a bundle for others")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new(OUTPUT_PATH_ID)
+                        .help("Path of the bundle to write")
+                        .value_name("OUTPUT_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(KEYS_EXPORT_KEY_SUBCOMMAND_NAME)
+                .about("Export one of your keys as SubjectPublicKeyInfo or PKCS#8 for OpenSSL and other tools")
+                .arg_required_else_help(true)
+                .arg(sig_algorithm_arg.clone().help("Signature key to export"))
+                .arg(kem_algorithm_arg.clone().help("KEM key to export"))
+                .group(contact_algorithm_group.clone())
+                .arg(
+                    Arg::new(OUTPUT_PATH_ID)
+                        .help("Path of the key file to write")
+                        .value_name("OUTPUT_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new(PRIVATE_ID)
+                        .help("Export the secret key as unencrypted PKCS#8 instead of the public key. The seed is written if it is known")
+                        .long("private")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new(KEY_FORMAT_ID)
+                        .help("Format of the key file: SubjectPublicKeyInfo or PKCS#8 in pem or der encoding, or the public key as jwk or cose-key (ML-DSA only)")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .default_value("pem")
+                        .value_parser(ValueParser::new(|value: &str| value.parse::<KeyFormat>())),
+                )
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(KEYS_IMPORT_KEY_SUBCOMMAND_NAME)
+                .about("Import a PKCS#8 private key, e.g. one generated by OpenSSL, as one of your keys")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new(PRIVATE_KEY_PATH_ID)
+                        .help("Path to the PKCS#8 private key in PEM or DER encoding")
+                        .value_name("PRIVATE_KEY_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new(PUBLIC_KEY_PATH_ID)
+                        .help("Path to the SubjectPublicKeyInfo public key. Required for ML-DSA keys unless the PKCS#8 file contains it")
+                        .long("public-key")
+                        .value_name("PUBLIC_KEY_PATH")
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(&expires_arg)
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(KEYS_SPLIT_SUBCOMMAND_NAME)
+                .about("Split one of your secret keys into shares for custodians, any --threshold of which reconstruct it")
+                .arg_required_else_help(true)
+                .arg(sig_algorithm_arg.clone().help("Signature key to split"))
+                .arg(kem_algorithm_arg.clone().help("KEM key to split"))
+                .group(contact_algorithm_group.clone())
+                .arg(
+                    Arg::new(THRESHOLD_ID)
+                        .help("Number of shares needed to reconstruct the key")
+                        .long("threshold")
+                        .value_name("THRESHOLD")
+                        .required(true)
+                        .value_parser(clap::value_parser!(u8).range(2..)),
+                )
+                .arg(
+                    Arg::new(SHARES_ID)
+                        .help("Number of shares to create")
+                        .long("shares")
+                        .value_name("SHARES")
+                        .required(true)
+                        .value_parser(clap::value_parser!(u8).range(2..)),
+                )
+                .arg(
+                    Arg::new(OUTPUT_DIR_ID)
+                        .help("Directory in which the share files are written. Defaults to the working directory")
+                        .long("output-dir")
+                        .value_name("OUTPUT_DIR")
+                        .value_hint(ValueHint::DirPath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new(ARMOR_ID)
+                        .help("Print the shares as armored text, e.g. to print them on paper, instead of writing files")
+                        .long("armor")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(OUTPUT_DIR_ID),
+                )
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(KEYS_COMBINE_SUBCOMMAND_NAME)
+                .about("Reconstruct one of your secret keys from the shares of `keys split` and restore it to the keys directory")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new(SHARE_PATH_ID)
+                        .help("Paths to the share files, as JSON or armored text")
+                        .value_name("SHARE_PATH")
+                        .required(true)
+                        .num_args(1..)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(force_arg.clone().help("Overwrite the secret key file if it still exists")),
+        )
+        .subcommand(
+            Command::new(KEYS_RECOVER_SUBCOMMAND_NAME)
+                .about("Recreate one of your KEM keys from the recovery mnemonic of `init kem --mnemonic`, read from stdin")
+                .arg(kem_algorithm_arg.clone().help("KEM key to recover. Defaults to the configured defaults.kem_algorithm"))
+                .arg(expires_arg.clone().help("Expiry of the recovered key if it is not configured, as for `init kem`"))
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(KEYS_REVOKE_SUBCOMMAND_NAME)
+                .about("Revoke one of your keys and create a revocation certificate to share with others")
+                .arg_required_else_help(true)
+                .arg(sig_algorithm_arg.clone().help("Signature key to revoke. The revocation is signed by the key itself"))
+                .arg(kem_algorithm_arg.clone().help("KEM key to revoke. The revocation is signed by one of your signature keys"))
+                .group(contact_algorithm_group.clone())
+                .arg(
+                    Arg::new(REVOCATION_REASON_ID)
+                        .help("Reason for the revocation")
+                        .long("reason")
+                        .value_name("REASON")
+                        .default_value(RevocationReason::Unspecified.name())
+                        .value_parser(RevocationReason::ALL.map(RevocationReason::name)),
+                )
+                .arg(
+                    Arg::new(COMMENT_ID)
+                        .help("Free text explaining the revocation")
+                        .long("comment")
+                        .value_name("COMMENT"),
+                )
+                .arg(
+                    Arg::new(SIGNED_BY_ID)
+                        .help("Signature key that signs the revocation of a KEM key. Defaults to the configured defaults.signature_algorithm")
+                        .long("signed-by")
+                        .value_name("SIGNATURE_ALGORITHM")
+                        .requires(KEM_ALGORITHM_ID)
+                        .value_parser(ValueParser::new(parse_signature_algorithm)),
+                ),
+        )
+        .subcommand(
+            Command::new(KEYS_IMPORT_REVOCATION_SUBCOMMAND_NAME)
+                .about("Import the revocation certificate of a contact's key")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new(REVOCATION_PATH_ID)
+                        .help("Path to the .rev file containing the revocation certificate")
+                        .value_name("REVOCATION_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        );
+
+    let subject_arg: Arg = Arg::new(SUBJECT_ID)
+        .help("Distinguished name of the certificate subject, e.g. \"CN=Alice,O=Example\"")
+        .long("subject")
+        .value_name("SUBJECT")
+        .required(true);
+
+    let cert_output_arg: Arg = Arg::new(OUTPUT_PATH_ID)
+        .help("Path of the file to write")
+        .value_name("OUTPUT_PATH")
+        .required(true)
+        .value_hint(ValueHint::FilePath)
+        .value_parser(clap::value_parser!(PathBuf));
+
+    let encoding_arg: Arg = Arg::new(ENCODING_ID)
+        .help("Encoding of the written file")
+        .long("format")
+        .value_name("FORMAT")
+        .default_value("pem")
+        .value_parser(ValueParser::new(|value: &str| value.parse::<Encoding>()));
+
+    let ca_arg: Arg = Arg::new(CA_ID)
+        .help("Allow the certified key to issue certificates")
+        .long("ca")
+        .action(ArgAction::SetTrue);
+
+    let not_after_arg: Arg = expires_arg
+        .clone()
+        .help("End of the certificate validity, e.g. 2030-01-31, or a duration from now such as 90d, 12w, 6m or 2y")
+        .default_value("1y");
+
+    let cert_cmd = Command::new(CERT_SUBCOMMAND_NAME)
+        .about("Create and verify X.509 certificates for your ML-DSA keys")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(CERT_REQUEST_SUBCOMMAND_NAME)
+                .about("Create a certificate signing request (CSR) for one of your signature keys")
+                .arg_required_else_help(true)
+                .arg(&sig_algorithm_arg)
+                .arg(&subject_arg)
+                .arg(&cert_output_arg)
+                .arg(&encoding_arg)
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(CERT_SELF_SIGN_SUBCOMMAND_NAME)
+                .about("Create a self-signed certificate for one of your signature keys, e.g. for a root CA")
+                .arg_required_else_help(true)
+                .arg(&sig_algorithm_arg)
+                .arg(&subject_arg)
+                .arg(&not_after_arg)
+                .arg(&ca_arg)
+                .arg(&cert_output_arg)
+                .arg(&encoding_arg)
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(CERT_ISSUE_SUBCOMMAND_NAME)
+                .about("Issue a certificate for a CSR, signed by your CA key")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new(REQUEST_PATH_ID)
+                        .help("Path to the CSR in PEM or DER encoding")
+                        .value_name("REQUEST_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    sig_algorithm_arg
+                        .clone()
+                        .help("Signature algorithm of your CA key. Defaults to the configured defaults.signature_algorithm"),
+                )
+                .arg(
+                    Arg::new(ISSUER_CERT_ID)
+                        .help("Certificate of your CA key")
+                        .long("issuer")
+                        .value_name("ISSUER_CERT")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(&not_after_arg)
+                .arg(&ca_arg)
+                .arg(&cert_output_arg)
+                .arg(&encoding_arg)
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(CERT_VERIFY_SUBCOMMAND_NAME)
+                .about("Verify a certificate chain offline against a trusted root certificate")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new(CERTIFICATE_PATH_ID)
+                        .help("Path to the certificate in PEM or DER encoding")
+                        .value_name("CERTIFICATE_PATH")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new(ROOT_CERT_ID)
+                        .help("Trusted root certificate")
+                        .long("root")
+                        .value_name("ROOT_CERT")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new(INTERMEDIATE_CERT_ID)
+                        .help("Intermediate CA certificate. Can be repeated")
+                        .long("intermediate")
+                        .value_name("INTERMEDIATE_CERT")
+                        .action(ArgAction::Append)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        );
+
+    let age_cmd = Command::new(AGE_SUBCOMMAND_NAME)
+        .about("Use your KEM keys with age through the age-plugin-rustseal plugin")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(AGE_RECIPIENT_SUBCOMMAND_NAME)
+                .about("Print the age recipient of your KEM key or of a contact's KEM key, for `age -r`")
+                .arg(&kem_algorithm_arg)
+                .arg(
+                    Arg::new(CONTACT_NAME_ID)
+                        .help("Name of the contact whose key is printed instead of your own")
+                        .long("contact")
+                        .value_name("NAME"),
+                ),
+        )
+        .subcommand(
+            Command::new(AGE_IDENTITY_SUBCOMMAND_NAME)
+                .about("Print an age identity file for your KEM key, for `age -d -i`. The secret key stays in the rust-seal home")
+                .arg(&kem_algorithm_arg),
+        );
+
+    let lifetime_arg: Arg = Arg::new(LIFETIME_ID)
+        .long("lifetime")
+        .short('t')
+        .value_name("LIFETIME")
+        .value_parser(ValueParser::new(parse_lifetime));
+
+    let agent_algorithm_group = ArgGroup::new("agent_algorithm")
+        .args([SIGNATURE_ALGORITHM_ID, KEM_ALGORITHM_ID])
+        .required(true);
+
+    let agent_cmd = Command::new(AGENT_SUBCOMMAND_NAME)
+        .about("Hold unlocked secret keys in memory, so sign and decrypt-file use them without reading them from disk. Used when RUST_SEAL_AGENT_SOCK is set")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(AGENT_START_SUBCOMMAND_NAME)
+                .about("Start the agent in the background and print the shell commands that set RUST_SEAL_AGENT_SOCK, for `eval \"$(rust-seal agent start)\"`")
+                .arg(
+                    Arg::new(SOCKET_ID)
+                        .help("Path of the Unix socket. Defaults to agent.sock in the rust-seal home")
+                        .long("socket")
+                        .value_name("SOCKET")
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(lifetime_arg.clone().help(
+                    "Default lifetime of added keys in seconds, or with a unit such as 30s, 15m, 8h or 1d. Keys are held until removed if not set",
+                ))
+                .arg(
+                    Arg::new(FOREGROUND_ID)
+                        .help("Run the agent in the foreground instead of starting it in the background")
+                        .long("foreground")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new(AGENT_ADD_SUBCOMMAND_NAME)
+                .about("Add your signature or KEM key to the agent")
+                .arg(&sig_algorithm_arg)
+                .arg(&kem_algorithm_arg)
+                .group(agent_algorithm_group.clone())
+                .arg(lifetime_arg.help(
+                    "Lifetime of the key in seconds, or with a unit such as 30s, 15m, 8h or 1d. Defaults to the lifetime the agent was started with",
+                )),
+        )
+        .subcommand(
+            Command::new(AGENT_LIST_SUBCOMMAND_NAME).about("List the keys the agent holds"),
+        )
+        .subcommand(
+            Command::new(AGENT_REMOVE_SUBCOMMAND_NAME)
+                .about("Remove your signature or KEM key, or all keys, from the agent")
+                .arg(&sig_algorithm_arg)
+                .arg(&kem_algorithm_arg)
+                .arg(
+                    Arg::new(REMOVE_ALL_ID)
+                        .help("Remove all keys")
+                        .long("all")
+                        .action(ArgAction::SetTrue),
+                )
+                .group(
+                    agent_algorithm_group
+                        .arg(REMOVE_ALL_ID)
+                        .multiple(false),
+                ),
+        )
+        .subcommand(
+            Command::new(AGENT_STOP_SUBCOMMAND_NAME)
+                .about("Stop the agent, which forgets all keys"),
+        );
+
+    let log_arg: Arg = Arg::new(LOG_ID)
+        .help("Directory of the transparency log, or the URL it is served at")
+        .value_name("LOG")
+        .required(true);
+
+    let log_dir_arg: Arg = Arg::new(LOG_DIR_ID)
+        .help("Directory of the transparency log")
+        .value_name("LOG_DIR")
+        .required(true)
+        .value_hint(ValueHint::DirPath)
+        .value_parser(clap::value_parser!(PathBuf));
+
+    let log_cmd = Command::new(LOG_SUBCOMMAND_NAME)
+        .about("Keep a transparency log that proves which signatures were made, so signatures by a stolen key cannot go unnoticed")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(LOG_INIT_SUBCOMMAND_NAME)
+                .about("Create a transparency log with a new log key and print the fingerprint verifiers trust")
+                .arg_required_else_help(true)
+                .arg(&log_dir_arg)
+                .arg(sig_algorithm_arg.clone().help(
+                    "Signature algorithm of the log key. Defaults to the configured defaults.signature_algorithm",
+                )),
+        )
+        .subcommand(
+            Command::new(LOG_HEAD_SUBCOMMAND_NAME)
+                .about("Print the current signed tree head of a transparency log, optionally saving it for a later consistency check")
+                .arg_required_else_help(true)
+                .arg(&log_arg)
+                .arg(
+                    Arg::new(OUTPUT_PATH_ID)
+                        .help("Save the tree head to this file")
+                        .long("output")
+                        .value_name("OUTPUT_PATH")
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(&force_arg),
+        )
+        .subcommand(
+            Command::new(LOG_CONSISTENCY_SUBCOMMAND_NAME)
+                .about("Prove that a transparency log only appended entries since an earlier tree head")
+                .arg_required_else_help(true)
+                .arg(&log_arg)
+                .arg(
+                    Arg::new(OLD_HEAD_ID)
+                        .help("Tree head saved with `rust-seal log head --output`")
+                        .value_name("OLD_HEAD")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new(NEW_HEAD_ID)
+                        .help("Later tree head to compare with. Defaults to the current tree head of the log")
+                        .value_name("NEW_HEAD")
+                        .value_hint(ValueHint::FilePath)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new(LOG_SERVE_SUBCOMMAND_NAME)
+                .about("Serve a transparency log over HTTP, so signers and auditors can use it by URL")
+                .arg_required_else_help(true)
+                .arg(&log_dir_arg)
+                .arg(
+                    Arg::new(LISTEN_ID)
+                        .help("Address to listen on")
+                        .long("listen")
+                        .value_name("ADDRESS")
+                        .default_value("127.0.0.1:8319"),
+                ),
+        );
 
     Command::new("rust-seal")
         .author(env!("CARGO_PKG_AUTHORS"))
         .version(env!("CARGO_PKG_VERSION"))
         .about("Rust Seal")
         .arg_required_else_help(true)
+        .subcommand_required(true)
+        .arg(&config_path_arg)
+        .arg(&keys_dir_arg)
+        .arg(&ignore_policy_arg)
         .subcommand(&sign_cmd)
         .subcommand(&verify_cmd)
+        .subcommand(&attest_cmd)
+        .subcommand(&verify_attestation_cmd)
         .subcommand(&init_cmd)
         .subcommand(&encrypt_file_cmd)
         .subcommand(&decrypt_file_cmd)
+        .subcommand(&config_cmd)
+        .subcommand(&algorithms_cmd)
+        .subcommand(&bench_cmd)
+        .subcommand(&selftest_cmd)
+        .subcommand(&contacts_cmd)
+        .subcommand(&keys_cmd)
+        .subcommand(&cert_cmd)
+        .subcommand(&age_cmd)
+        .subcommand(&agent_cmd)
+        .subcommand(&log_cmd)
 }
 
-pub fn start(config: &mut Config) -> Result<()> {
-    let cli = create_cli();
-
-    let matches = cli.get_matches();
+pub fn start(matches: &ArgMatches, config: &mut Config) -> Result<()> {
     match matches.subcommand() {
         Some((SIGN_SUBCOMMAND_NAME, sub_matches)) => sign_file_command(sub_matches, config),
-        Some((VERIFY_SUBCOMMAND_NAME, sub_matches)) => verify_signature_command(sub_matches),
+        Some((VERIFY_SUBCOMMAND_NAME, sub_matches)) => {
+            verify_signature_command(sub_matches, config)
+        }
+        Some((ATTEST_SUBCOMMAND_NAME, sub_matches)) => attest_command(sub_matches, config),
+        Some((VERIFY_ATTESTATION_SUBCOMMAND_NAME, sub_matches)) => {
+            verify_attestation_command(sub_matches, config)
+        }
         Some((INIT_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
             Some((KEM_SUBCOMMAND_NAME, sub_matches)) => init_kem(sub_matches, config),
             Some((SIG_SUBCOMMAND_NAME, sub_matches)) => init_sig(sub_matches, config),
@@ -167,10 +1274,152 @@ pub fn start(config: &mut Config) -> Result<()> {
                 );
             }
         },
-        Some((ENCRYPT_FILE_SUBCOMMAND_NAME, sub_matches)) => encrypt_file_command(sub_matches),
+        Some((ENCRYPT_FILE_SUBCOMMAND_NAME, sub_matches)) => {
+            encrypt_file_command(sub_matches, config)
+        }
         Some((DECRYPT_FILE_SUBCOMMAND_NAME, sub_matches)) => {
             decrypt_file_command(sub_matches, config)
         }
+        Some((CONFIG_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((CONFIG_LIST_SUBCOMMAND_NAME, _)) => config_list_command(config),
+            Some((CONFIG_GET_SUBCOMMAND_NAME, sub_matches)) => {
+                config_get_command(sub_matches, config)
+            }
+            Some((CONFIG_SET_SUBCOMMAND_NAME, sub_matches)) => {
+                config_set_command(sub_matches, config)
+            }
+            Some((CONFIG_UNSET_SUBCOMMAND_NAME, sub_matches)) => {
+                config_unset_command(sub_matches, config)
+            }
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
+        Some((ALGORITHMS_SUBCOMMAND_NAME, sub_matches)) => algorithms_command(sub_matches, config),
+        Some((BENCH_SUBCOMMAND_NAME, sub_matches)) => bench_command(sub_matches, config),
+        Some((SELFTEST_SUBCOMMAND_NAME, sub_matches)) => selftest_command(sub_matches, config),
+        Some((KEYS_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((KEYS_INIT_PRIMARY_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_init_primary_command(sub_matches, config)
+            }
+            Some((KEYS_CERTIFY_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_certify_command(sub_matches, config)
+            }
+            Some((KEYS_EXPORT_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_export_command(sub_matches, config)
+            }
+            Some((KEYS_EXPORT_KEY_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_export_key_command(sub_matches, config)
+            }
+            Some((KEYS_IMPORT_KEY_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_import_key_command(sub_matches, config)
+            }
+            Some((KEYS_SPLIT_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_split_command(sub_matches, config)
+            }
+            Some((KEYS_COMBINE_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_combine_command(sub_matches, config)
+            }
+            Some((KEYS_RECOVER_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_recover_command(sub_matches, config)
+            }
+            Some((KEYS_REVOKE_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_revoke_command(sub_matches, config)
+            }
+            Some((KEYS_IMPORT_REVOCATION_SUBCOMMAND_NAME, sub_matches)) => {
+                keys_import_revocation_command(sub_matches, config)
+            }
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
+        Some((CERT_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((CERT_REQUEST_SUBCOMMAND_NAME, sub_matches)) => {
+                cert_request_command(sub_matches, config)
+            }
+            Some((CERT_SELF_SIGN_SUBCOMMAND_NAME, sub_matches)) => {
+                cert_self_sign_command(sub_matches, config)
+            }
+            Some((CERT_ISSUE_SUBCOMMAND_NAME, sub_matches)) => {
+                cert_issue_command(sub_matches, config)
+            }
+            Some((CERT_VERIFY_SUBCOMMAND_NAME, sub_matches)) => cert_verify_command(sub_matches),
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
+        Some((AGE_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((AGE_RECIPIENT_SUBCOMMAND_NAME, sub_matches)) => {
+                age_recipient_command(sub_matches, config)
+            }
+            Some((AGE_IDENTITY_SUBCOMMAND_NAME, sub_matches)) => {
+                age_identity_command(sub_matches, config)
+            }
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
+        Some((AGENT_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((AGENT_START_SUBCOMMAND_NAME, sub_matches)) => {
+                agent_start_command(sub_matches, config)
+            }
+            Some((AGENT_ADD_SUBCOMMAND_NAME, sub_matches)) => {
+                agent_add_command(sub_matches, config)
+            }
+            Some((AGENT_LIST_SUBCOMMAND_NAME, _)) => agent_list_command(),
+            Some((AGENT_REMOVE_SUBCOMMAND_NAME, sub_matches)) => {
+                agent_remove_command(sub_matches, config)
+            }
+            Some((AGENT_STOP_SUBCOMMAND_NAME, _)) => agent_stop_command(),
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
+        Some((LOG_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((LOG_INIT_SUBCOMMAND_NAME, sub_matches)) => log_init_command(sub_matches, config),
+            Some((LOG_HEAD_SUBCOMMAND_NAME, sub_matches)) => log_head_command(sub_matches, config),
+            Some((LOG_CONSISTENCY_SUBCOMMAND_NAME, sub_matches)) => {
+                log_consistency_command(sub_matches, config)
+            }
+            Some((LOG_SERVE_SUBCOMMAND_NAME, sub_matches)) => log_serve_command(sub_matches),
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
+        Some((CONTACTS_SUBCOMMAND_NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((CONTACTS_IMPORT_SUBCOMMAND_NAME, sub_matches)) => {
+                contacts_import_command(sub_matches, config)
+            }
+            Some((CONTACTS_IMPORT_BUNDLE_SUBCOMMAND_NAME, sub_matches)) => {
+                contacts_import_bundle_command(sub_matches, config)
+            }
+            Some((CONTACTS_LIST_SUBCOMMAND_NAME, sub_matches)) => {
+                contacts_list_command(sub_matches, config)
+            }
+            Some((CONTACTS_REMOVE_SUBCOMMAND_NAME, sub_matches)) => {
+                contacts_remove_command(sub_matches, config)
+            }
+            Some((CONTACTS_FINGERPRINT_SUBCOMMAND_NAME, sub_matches)) => {
+                contacts_fingerprint_command(sub_matches, config)
+            }
+            _ => {
+                unreachable!(
+                    "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand"
+                );
+            }
+        },
         _ => {
             unreachable!(
                 "Subcommand should always be present. If execution reaches here, it means Clap has a bug or the CLI has a misconfigured subcommand."
@@ -178,3 +1427,38 @@ pub fn start(config: &mut Config) -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn test_subcommand_groups_require_a_subcommand() {
+        create_cli().debug_assert();
+
+        for args in [
+            &["rust-seal", "--keys-dir", "keys"][..],
+            &["rust-seal", "init", "--config", "config.json"],
+            &["rust-seal", "config", "--ignore-policy"],
+            &["rust-seal", "contacts", "--config", "config.json"],
+            &["rust-seal", "keys", "--config", "config.json"],
+            &["rust-seal", "cert", "--ignore-policy"],
+            &["rust-seal", "age", "--keys-dir", "keys"],
+            &["rust-seal", "agent", "--config", "config.json"],
+            &["rust-seal", "log", "--ignore-policy"],
+        ] {
+            let err = create_cli().try_get_matches_from(args).unwrap_err();
+            assert!(
+                matches!(
+                    err.kind(),
+                    ErrorKind::MissingSubcommand
+                        | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+                ),
+                "{args:?} gave {:?}",
+                err.kind()
+            );
+        }
+    }
+}
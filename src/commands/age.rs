@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+
+use crate::{
+    Config,
+    age::{encode_identity, encode_recipient},
+    cli::CONTACT_NAME_ID,
+    config::Contacts,
+    cryptography::fingerprint::{fingerprint, format_fingerprint},
+    util::parse_kem_algorithm_arg,
+};
+
+/// Prints the age recipient of the own KEM key or of a contact's KEM key
+pub fn age_recipient_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let kem = parse_kem_algorithm_arg(args, config)?;
+
+    let public_key = if let Some(name) = args.get_one::<String>(CONTACT_NAME_ID) {
+        let contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+        contacts.kem_public_key(name, kem.algorithm())?.to_vec()
+    } else {
+        config.ensure_kem_key_valid(&kem.algorithm())?;
+        config.get_kem_keys(&kem.algorithm())?.0.into_vec()
+    };
+
+    println!("{}", encode_recipient(kem.algorithm(), &public_key)?);
+    Ok(())
+}
+
+/// Prints the age identity of the own KEM key in the format of an age identity file.
+/// The identity only refers to the key; the secret key stays in the rust-seal home
+pub fn age_identity_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let kem = parse_kem_algorithm_arg(args, config)?;
+    let (public_key, _) = config.get_kem_keys(&kem.algorithm())?;
+    let key_fingerprint = fingerprint(kem.algorithm().name(), public_key.as_ref());
+
+    println!(
+        "# rust-seal {} key {}",
+        kem.algorithm(),
+        format_fingerprint(&key_fingerprint)
+    );
+    println!(
+        "# recipient: {}",
+        encode_recipient(kem.algorithm(), public_key.as_ref())?
+    );
+    println!("{}", encode_identity(kem.algorithm(), &key_fingerprint)?);
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use std::{
+    env,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ArgMatches;
+use oqs::{kem::Algorithm as KemAlgorithmVariant, sig::Algorithm as SigAlgorithmVariant};
+
+use crate::{
+    Config,
+    agent::{AGENT_SOCK_ENV_VAR, Agent, AgentClient, KeyAlgorithm, bind_socket, serve},
+    cli::{
+        FOREGROUND_ID, KEM_ALGORITHM_ID, LIFETIME_ID, REMOVE_ALL_ID, SIGNATURE_ALGORITHM_ID,
+        SOCKET_ID,
+    },
+    cryptography::fingerprint::format_fingerprint,
+};
+
+const AGENT_SOCKET_NAME: &str = "agent.sock";
+
+/// How long `agent start` waits for the agent it spawned to listen
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Starts the agent and prints the shell commands that point `RUST_SEAL_AGENT_SOCK` at it.
+/// Without `--foreground`, the agent is started as a background process
+pub fn agent_start_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let socket = match args.get_one::<PathBuf>(SOCKET_ID) {
+        Some(socket) => env::current_dir()
+            .context("Failed to determine the working directory")?
+            .join(socket),
+        None => config.home().root().join(AGENT_SOCKET_NAME),
+    };
+    let lifetime = args.get_one::<u64>(LIFETIME_ID).copied();
+
+    if args.get_flag(FOREGROUND_ID) {
+        let listener = bind_socket(&socket)?;
+        print_environment(&socket, std::process::id());
+        return serve(listener, socket, Agent::new(lifetime));
+    }
+
+    let agent = AgentClient::new(&socket);
+    if agent.list().is_ok() {
+        bail!("An agent is already listening on {}", socket.display());
+    }
+
+    let mut command = Command::new(env::current_exe().context("Failed to locate rust-seal")?);
+    command
+        .args(["agent", "start", "--foreground", "--socket"])
+        .arg(&socket)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Detach from the terminal's process group, so the agent outlives the shell job
+        .process_group(0);
+    if let Some(lifetime) = lifetime {
+        command.args(["--lifetime", &lifetime.to_string()]);
+    }
+    let mut child = command.spawn().context("Failed to start the agent")?;
+
+    let started = std::time::Instant::now();
+    while agent.list().is_err() {
+        if let Some(status) = child.try_wait().context("Failed to wait for the agent")? {
+            bail!(
+                "Agent exited with {status} before listening on {}",
+                socket.display()
+            );
+        }
+        if started.elapsed() > START_TIMEOUT {
+            bail!("Agent did not listen on {} in time", socket.display());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    print_environment(&socket, child.id());
+    Ok(())
+}
+
+/// Adds an own key to the agent, which holds it until its lifetime ends
+pub fn agent_add_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let agent = agent_from_env()?;
+    let lifetime = args.get_one::<u64>(LIFETIME_ID).copied();
+
+    let (algorithm, public_key, secret_key) =
+        if let Some(algorithm) = args.get_one::<SigAlgorithmVariant>(SIGNATURE_ALGORITHM_ID) {
+            config.ensure_signature_key_valid(algorithm)?;
+            let (public_key, secret_key) = config.get_signature_keys(algorithm)?;
+            (
+                KeyAlgorithm::Signature(*algorithm),
+                public_key.into_vec(),
+                secret_key.into_bytes(),
+            )
+        } else if let Some(algorithm) = args.get_one::<KemAlgorithmVariant>(KEM_ALGORITHM_ID) {
+            config.ensure_kem_key_valid(algorithm)?;
+            let (public_key, secret_key) = config.get_kem_keys(algorithm)?;
+            (
+                KeyAlgorithm::Kem(*algorithm),
+                public_key.into_vec(),
+                secret_key.into_bytes(),
+            )
+        } else {
+            unreachable!("Clap requires a signature or KEM algorithm");
+        };
+
+    agent.add_key(algorithm, &public_key, secret_key.as_ref(), lifetime)?;
+    println!(
+        "Added {algorithm} key {} to the agent",
+        format_fingerprint(&algorithm.fingerprint(&public_key))
+    );
+    Ok(())
+}
+
+pub fn agent_list_command() -> Result<()> {
+    let keys = agent_from_env()?.list()?;
+    if keys.is_empty() {
+        println!("The agent holds no keys");
+    }
+    for key in keys {
+        let expires = key
+            .expires
+            .map(|expires| format!("until {}", expires.format("%Y-%m-%d %H:%M:%S UTC")))
+            .unwrap_or_else(|| "until removed".to_string());
+        println!(
+            "{} {} ({expires})",
+            key.algorithm,
+            format_fingerprint(&key.fingerprint)
+        );
+    }
+    Ok(())
+}
+
+/// Removes an own key, or all keys, from the agent
+pub fn agent_remove_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let agent = agent_from_env()?;
+    if args.get_flag(REMOVE_ALL_ID) {
+        agent.remove_all()?;
+        println!("Removed all keys from the agent");
+        return Ok(());
+    }
+
+    let (algorithm, public_key) =
+        if let Some(algorithm) = args.get_one::<SigAlgorithmVariant>(SIGNATURE_ALGORITHM_ID) {
+            (
+                KeyAlgorithm::Signature(*algorithm),
+                config.get_signature_public_key(algorithm)?.into_vec(),
+            )
+        } else if let Some(algorithm) = args.get_one::<KemAlgorithmVariant>(KEM_ALGORITHM_ID) {
+            (
+                KeyAlgorithm::Kem(*algorithm),
+                config.get_kem_public_key(algorithm)?.into_vec(),
+            )
+        } else {
+            unreachable!("Clap requires a signature or KEM algorithm or --all");
+        };
+
+    let key_fingerprint = algorithm.fingerprint(&public_key);
+    agent.remove_key(&key_fingerprint)?;
+    println!(
+        "Removed {algorithm} key {} from the agent",
+        format_fingerprint(&key_fingerprint)
+    );
+    Ok(())
+}
+
+/// Stops the agent; the keys it held are gone with it
+pub fn agent_stop_command() -> Result<()> {
+    let agent = agent_from_env()?;
+    agent.stop()?;
+    println!("Stopped the agent at {}", agent.socket().display());
+    Ok(())
+}
+
+fn agent_from_env() -> Result<AgentClient> {
+    AgentClient::from_env().context(format!(
+        "{AGENT_SOCK_ENV_VAR} is not set. Start the agent with `eval \"$(rust-seal agent start)\"`"
+    ))
+}
+
+/// Prints the shell commands that point `RUST_SEAL_AGENT_SOCK` at the agent, like `ssh-agent`
+fn print_environment(socket: &Path, pid: u32) {
+    println!(
+        "{AGENT_SOCK_ENV_VAR}={}; export {AGENT_SOCK_ENV_VAR};",
+        socket.display()
+    );
+    println!("echo Agent pid {pid};");
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use oqs::{kem::Kem, sig::Sig};
+use serde::Serialize;
+
+use crate::{
+    Config,
+    cli::{JSON_ID, KEM_ONLY_ID, SIG_ONLY_ID},
+    config::OutputFormat,
+    oqs::{KEM_ALGORITHMS, SIG_ALGORITHMS, Status},
+    util::print_table,
+};
+
+/// Row of the algorithm listing. Sizes are in bytes and only known if liboqs has the algorithm enabled
+#[derive(Serialize, Debug)]
+struct AlgorithmRow {
+    name: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    aliases: &'static [&'static str],
+    nist_level: u8,
+    status: Status,
+    enabled: bool,
+    public_key_size: Option<usize>,
+    secret_key_size: Option<usize>,
+    ciphertext_size: Option<usize>,
+    signature_size: Option<usize>,
+}
+
+pub fn algorithms_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let mut rows = Vec::new();
+    if !args.get_flag(SIG_ONLY_ID) {
+        rows.extend(kem_rows());
+    }
+    if !args.get_flag(KEM_ONLY_ID) {
+        rows.extend(sig_rows());
+    }
+
+    let output_format = if args.get_flag(JSON_ID) {
+        OutputFormat::Json
+    } else {
+        config.output_format()
+    };
+
+    match output_format {
+        OutputFormat::Text => print_algorithms(&rows),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).context("Failed to serialize algorithms")?
+        ),
+    }
+
+    Ok(())
+}
+
+fn kem_rows() -> impl Iterator<Item = AlgorithmRow> {
+    KEM_ALGORITHMS.iter().map(|info| {
+        let kem = Kem::new(info.algorithm).ok();
+        AlgorithmRow {
+            name: info.name,
+            kind: "kem",
+            aliases: info.aliases,
+            nist_level: info.nist_level,
+            status: info.status,
+            enabled: kem.is_some(),
+            public_key_size: kem.as_ref().map(Kem::length_public_key),
+            secret_key_size: kem.as_ref().map(Kem::length_secret_key),
+            ciphertext_size: kem.as_ref().map(Kem::length_ciphertext),
+            signature_size: None,
+        }
+    })
+}
+
+fn sig_rows() -> impl Iterator<Item = AlgorithmRow> {
+    SIG_ALGORITHMS.iter().map(|info| {
+        let signature = Sig::new(info.algorithm).ok();
+        AlgorithmRow {
+            name: info.name,
+            kind: "sig",
+            aliases: info.aliases,
+            nist_level: info.nist_level,
+            status: info.status,
+            enabled: signature.is_some(),
+            public_key_size: signature.as_ref().map(Sig::length_public_key),
+            secret_key_size: signature.as_ref().map(Sig::length_secret_key),
+            ciphertext_size: None,
+            signature_size: signature.as_ref().map(Sig::length_signature),
+        }
+    })
+}
+
+fn print_algorithms(rows: &[AlgorithmRow]) {
+    let size = |size: Option<usize>| size.map_or_else(|| "-".to_string(), |size| size.to_string());
+
+    let table: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            vec![
+                row.name.to_string(),
+                row.kind.to_string(),
+                row.nist_level.to_string(),
+                row.status.to_string(),
+                if row.enabled { "yes" } else { "no" }.to_string(),
+                size(row.public_key_size),
+                size(row.secret_key_size),
+                size(row.ciphertext_size.or(row.signature_size)),
+                row.aliases.join(", "),
+            ]
+        })
+        .collect();
+
+    print_table(
+        &[
+            "NAME",
+            "TYPE",
+            "LEVEL",
+            "STATUS",
+            "ENABLED",
+            "PUBLIC KEY",
+            "SECRET KEY",
+            "CT/SIG",
+            "ALIASES",
+        ],
+        &table,
+    );
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs::{read, read_to_string},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, ensure};
+use chrono::Utc;
+use clap::ArgMatches;
+use serde_json::Value;
+
+use super::verify_signature::signer_public_key;
+use crate::{
+    Config,
+    cli::{
+        ARTIFACTS_ID, ENVELOPE_PATH_ID, FORCE_ID, OUTPUT_PATH_ID, PREDICATE_ID, PREDICATE_TYPE_ID,
+    },
+    cryptography::{
+        dsse::Envelope,
+        fingerprint::{fingerprint, format_fingerprint},
+        in_toto::{PAYLOAD_TYPE, Statement, Subject},
+    },
+    util::{ensure_overwritable, parse_path_arg, parse_signature_algorithm_arg, write_file},
+};
+
+/// Signs an in-toto Statement about the artifacts with the predicate into a DSSE envelope
+pub fn attest_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let signature = parse_signature_algorithm_arg(args, config)?;
+    let artifacts: Vec<&PathBuf> = args
+        .get_many::<PathBuf>(ARTIFACTS_ID)
+        .context("Missing required argument: artifacts")?
+        .collect();
+    let predicate_path = parse_path_arg(args, PREDICATE_ID)?;
+    let predicate_type = args
+        .get_one::<String>(PREDICATE_TYPE_ID)
+        .context("Missing required argument: predicate type")?;
+    let output_path = args
+        .get_one::<PathBuf>(OUTPUT_PATH_ID)
+        .cloned()
+        .unwrap_or_else(|| artifacts[0].with_extension("intoto.json"));
+    let force = args.get_flag(FORCE_ID);
+    ensure_overwritable(&output_path, force)?;
+
+    let predicate: Value = serde_json::from_str(
+        &read_to_string(&predicate_path).context("Failed to read the predicate")?,
+    )
+    .context("The predicate is not valid JSON")?;
+    ensure!(predicate.is_object(), "The predicate must be a JSON object");
+    let mut subjects = Vec::new();
+    for artifact in &artifacts {
+        let content = read(artifact).context(format!("Failed to read {}", artifact.display()))?;
+        subjects.push(Subject::new(&artifact.to_string_lossy(), &content));
+    }
+    let statement = Statement::new(subjects, predicate_type, predicate);
+
+    config.ensure_signature_key_valid(&signature.algorithm())?;
+    let (public_key, secret_key) = config.signing_key(&signature.algorithm())?;
+    let payload = serde_json::to_vec(&statement).context("Failed to serialize the Statement")?;
+    let envelope = Envelope::sign(
+        PAYLOAD_TYPE,
+        &payload,
+        &signature,
+        public_key.as_ref(),
+        &secret_key,
+    )?;
+    write_file(
+        &output_path,
+        serde_json::to_string_pretty(&envelope).context("Failed to serialize the envelope")?,
+        force,
+    )
+    .context("Failed to write the attestation")?;
+
+    println!(
+        "Attestation about {} artifacts created successfully: {}",
+        statement.subject.len(),
+        output_path.display()
+    );
+    println!(
+        "Your key: {}",
+        format_fingerprint(&fingerprint(
+            &signature.algorithm().to_string(),
+            public_key.as_ref()
+        ))
+    );
+    Ok(())
+}
+
+/// Verifies a DSSE envelope with an in-toto Statement and that the artifacts on disk have the digests it attests.
+/// Without artifacts, each subject is looked up by its name and must have the digest of that subject
+pub fn verify_attestation_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let signature = parse_signature_algorithm_arg(args, config)?;
+    let envelope_path = parse_path_arg(args, ENVELOPE_PATH_ID)?;
+    let envelope: Envelope = serde_json::from_str(
+        &read_to_string(&envelope_path).context("Failed to read the attestation")?,
+    )
+    .context("The attestation is not a DSSE envelope")?;
+    ensure!(
+        envelope.payload_type == PAYLOAD_TYPE,
+        "The envelope carries {}, but an in-toto Statement ({PAYLOAD_TYPE}) was expected",
+        envelope.payload_type
+    );
+
+    let public_key = signer_public_key(args, config, &signature, Utc::now())?;
+    let payload = envelope
+        .verify(&signature, &public_key)
+        .context("\x1b[31m Attestation verification failed\x1b[0m")?;
+    let statement = Statement::parse(&payload)?;
+    if let Some(predicate_type) = args.get_one::<String>(PREDICATE_TYPE_ID) {
+        ensure!(
+            &statement.predicate_type == predicate_type,
+            "The attestation has the predicate type {}, but {predicate_type} was expected",
+            statement.predicate_type
+        );
+    }
+
+    match args.get_many::<PathBuf>(ARTIFACTS_ID) {
+        Some(artifacts) => {
+            for artifact in artifacts {
+                let content =
+                    read(artifact).context(format!("Failed to read {}", artifact.display()))?;
+                let subject = statement.subject_of(&content).context(format!(
+                    "\x1b[31m Attestation verification failed\x1b[0m: {} is not a subject of the attestation",
+                    artifact.display()
+                ))?;
+                println!(
+                    "Artifact {} matches subject {}",
+                    artifact.display(),
+                    subject.name
+                );
+            }
+        }
+        None => {
+            for subject in &statement.subject {
+                let content = read(&subject.name)
+                    .context(format!("Failed to read subject {}", subject.name))?;
+                ensure!(
+                    subject.matches_content(&content),
+                    "\x1b[31m Attestation verification failed\x1b[0m: {} does not have the digest of the subject",
+                    subject.name
+                );
+                println!("Artifact {} matches its subject", subject.name);
+            }
+        }
+    }
+    println!("Predicate type: {}", statement.predicate_type);
+    println!("Attestation \x1b[32mverification succeeded\x1b[0m");
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result, ensure};
+use clap::ArgMatches;
+use oqs::{
+    kem::{Algorithm as KemAlgorithm, Kem},
+    sig::{Algorithm as SigAlgorithm, Sig},
+};
+
+use crate::{
+    Config,
+    bench::{AlgorithmReport, BenchOptions, bench_kem, bench_signature, format_micros},
+    cli::{
+        ALL_ALGORITHMS_ID, ITERATIONS_ID, JSON_ID, KEM_ALGORITHM_ID, MESSAGE_SIZE_ID,
+        SIGNATURE_ALGORITHM_ID,
+    },
+    config::OutputFormat,
+    oqs::{KEM_ALGORITHMS, SIG_ALGORITHMS, Status},
+    util::print_table,
+};
+
+pub fn bench_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let options = BenchOptions {
+        iterations: *args
+            .get_one::<usize>(ITERATIONS_ID)
+            .context("Missing required argument: iterations")?,
+        message_sizes: args
+            .get_many::<usize>(MESSAGE_SIZE_ID)
+            .context("Missing required argument: message size")?
+            .copied()
+            .collect(),
+    };
+    ensure!(options.iterations > 0, "At least one iteration is required");
+
+    let (signatures, kems) = selected_algorithms(args)?;
+
+    let mut reports = Vec::new();
+    for signature in &signatures {
+        reports.push(
+            bench_signature(signature, &options)
+                .context(format!("Failed to benchmark {}", signature.algorithm()))?,
+        );
+    }
+    for kem in &kems {
+        reports.push(
+            bench_kem(kem, &options).context(format!("Failed to benchmark {}", kem.algorithm()))?,
+        );
+    }
+
+    let output_format = if args.get_flag(JSON_ID) {
+        OutputFormat::Json
+    } else {
+        config.output_format()
+    };
+
+    match output_format {
+        OutputFormat::Text => print_reports(&reports),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).context("Failed to serialize benchmark")?
+        ),
+    }
+
+    Ok(())
+}
+
+/// Returns the algorithms given on the command line.
+/// Without any, the standardised algorithms are measured and `--all` measures every enabled algorithm.
+fn selected_algorithms(args: &ArgMatches) -> Result<(Vec<Sig>, Vec<Kem>)> {
+    if args.get_flag(ALL_ALGORITHMS_ID) {
+        return Ok((
+            SIG_ALGORITHMS
+                .iter()
+                .filter_map(|info| Sig::new(info.algorithm).ok())
+                .collect(),
+            KEM_ALGORITHMS
+                .iter()
+                .filter_map(|info| Kem::new(info.algorithm).ok())
+                .collect(),
+        ));
+    }
+
+    let mut sig_algorithms: Vec<SigAlgorithm> = args
+        .get_many::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID)
+        .unwrap_or_default()
+        .copied()
+        .collect();
+    let mut kem_algorithms: Vec<KemAlgorithm> = args
+        .get_many::<KemAlgorithm>(KEM_ALGORITHM_ID)
+        .unwrap_or_default()
+        .copied()
+        .collect();
+
+    if sig_algorithms.is_empty() && kem_algorithms.is_empty() {
+        sig_algorithms = SIG_ALGORITHMS
+            .iter()
+            .filter(|info| info.status == Status::Standardized)
+            .map(|info| info.algorithm)
+            .collect();
+        kem_algorithms = KEM_ALGORITHMS
+            .iter()
+            .filter(|info| info.status == Status::Standardized)
+            .map(|info| info.algorithm)
+            .collect();
+    }
+
+    let signatures = sig_algorithms
+        .into_iter()
+        .map(|algorithm| {
+            Sig::new(algorithm).context(format!(
+                "{algorithm} is not enabled in the linked liboqs build"
+            ))
+        })
+        .collect::<Result<_>>()?;
+    let kems = kem_algorithms
+        .into_iter()
+        .map(|algorithm| {
+            Kem::new(algorithm).context(format!(
+                "{algorithm} is not enabled in the linked liboqs build"
+            ))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((signatures, kems))
+}
+
+fn print_reports(reports: &[AlgorithmReport]) {
+    let latencies: Vec<Vec<String>> = reports
+        .iter()
+        .flat_map(|report| {
+            report.operations.iter().map(|operation| {
+                vec![
+                    report.algorithm.clone(),
+                    operation.operation.to_string(),
+                    operation
+                        .message_size
+                        .map_or_else(|| "-".to_string(), |size| size.to_string()),
+                    format_micros(operation.latency.median_us),
+                    format_micros(operation.latency.p90_us),
+                    format_micros(operation.latency.p99_us),
+                ]
+            })
+        })
+        .collect();
+    print_table(
+        &["ALGORITHM", "OPERATION", "MESSAGE", "MEDIAN", "P90", "P99"],
+        &latencies,
+    );
+
+    println!();
+
+    let sizes: Vec<Vec<String>> = reports
+        .iter()
+        .map(|report| {
+            vec![
+                report.algorithm.clone(),
+                report.public_key_size.to_string(),
+                report.secret_key_size.to_string(),
+                report
+                    .ciphertext_size
+                    .or(report.signature_size)
+                    .map_or_else(|| "-".to_string(), |size| size.to_string()),
+            ]
+        })
+        .collect();
+    print_table(&["ALGORITHM", "PUBLIC KEY", "SECRET KEY", "CT/SIG"], &sizes);
+}
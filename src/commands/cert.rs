@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use oqs::sig::{PublicKey as SigPublicKey, SecretKey as SigSecretKey, Sig};
+use x509_cert::name::Name;
+
+use crate::{
+    Config,
+    cli::{
+        CA_ID, CERTIFICATE_PATH_ID, ENCODING_ID, EXPIRES_ID, FORCE_ID, INTERMEDIATE_CERT_ID,
+        ISSUER_CERT_ID, OUTPUT_PATH_ID, REQUEST_PATH_ID, ROOT_CERT_ID, SUBJECT_ID,
+    },
+    cryptography::{
+        asn1::{Encoding, write_encoded},
+        secret::Secret,
+        x509::{
+            CertificateOptions, X509Signer, create_request, issue_certificate, parse_name,
+            read_certificate, read_request, verify_chain, verify_request,
+        },
+    },
+    util::{parse_path_arg, parse_signature_algorithm_arg},
+};
+
+/// Creates a certificate signing request for an own signature key
+pub fn cert_request_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let (signature, public_key, secret_key) = own_signature_key(args, config)?;
+    let subject = parse_subject_arg(args)?;
+    let (output_path, encoding) = parse_output_args(args)?;
+
+    let signer = X509Signer {
+        signature: &signature,
+        public_key: &public_key,
+        secret_key: &secret_key,
+    };
+    let request = create_request(subject, &signer)?;
+    write_encoded(&output_path, &request, encoding, args.get_flag(FORCE_ID))?;
+
+    println!(
+        "Certificate signing request for {} written to: {}",
+        request.info.subject,
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// Creates a self-signed certificate for an own signature key, e.g. for a root CA
+pub fn cert_self_sign_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let (signature, public_key, secret_key) = own_signature_key(args, config)?;
+    let (output_path, encoding) = parse_output_args(args)?;
+
+    let signer = X509Signer {
+        signature: &signature,
+        public_key: &public_key,
+        secret_key: &secret_key,
+    };
+    let options = CertificateOptions {
+        subject: parse_subject_arg(args)?,
+        public_key_info: signer.public_key_info()?,
+        not_after: parse_not_after_arg(args)?,
+        ca: args.get_flag(CA_ID),
+        extended_key_usage: Vec::new(),
+    };
+    let certificate = issue_certificate(options, None, &signer)?;
+    write_encoded(
+        &output_path,
+        &certificate,
+        encoding,
+        args.get_flag(FORCE_ID),
+    )?;
+
+    println!(
+        "Self-signed certificate for {} written to: {}",
+        certificate.tbs_certificate.subject,
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// Issues a certificate for a request, signed by an own key that has a CA certificate
+pub fn cert_issue_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let (signature, public_key, secret_key) = own_signature_key(args, config)?;
+    let request = read_request(&parse_path_arg(args, REQUEST_PATH_ID)?)?;
+    let issuer = read_certificate(&parse_path_arg(args, ISSUER_CERT_ID)?)?;
+    let (output_path, encoding) = parse_output_args(args)?;
+
+    verify_request(&request)?;
+
+    let signer = X509Signer {
+        signature: &signature,
+        public_key: &public_key,
+        secret_key: &secret_key,
+    };
+    let options = CertificateOptions {
+        subject: request.info.subject,
+        public_key_info: request.info.public_key,
+        not_after: parse_not_after_arg(args)?,
+        ca: args.get_flag(CA_ID),
+        extended_key_usage: Vec::new(),
+    };
+    let certificate = issue_certificate(options, Some(&issuer), &signer)?;
+    write_encoded(
+        &output_path,
+        &certificate,
+        encoding,
+        args.get_flag(FORCE_ID),
+    )?;
+
+    println!(
+        "Certificate for {} issued by {} written to: {}",
+        certificate.tbs_certificate.subject,
+        certificate.tbs_certificate.issuer,
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// Verifies a certificate chain offline against a local root certificate
+pub fn cert_verify_command(args: &ArgMatches) -> Result<()> {
+    let certificate = read_certificate(&parse_path_arg(args, CERTIFICATE_PATH_ID)?)?;
+    let root = read_certificate(&parse_path_arg(args, ROOT_CERT_ID)?)?;
+    let intermediates = args
+        .get_many::<PathBuf>(INTERMEDIATE_CERT_ID)
+        .into_iter()
+        .flatten()
+        .map(|path| read_certificate(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    match verify_chain(&certificate, &intermediates, &root, Utc::now()) {
+        Ok(chain) => {
+            println!("\x1b[32mCertificate chain is valid\x1b[0m");
+            for (depth, certificate) in chain.iter().enumerate() {
+                let tbs = &certificate.tbs_certificate;
+                println!(
+                    "{depth}: {} (valid until {})",
+                    tbs.subject, tbs.validity.not_after
+                );
+            }
+            Ok(())
+        }
+        Err(error) => {
+            println!("\x1b[31mCertificate chain is invalid\x1b[0m");
+            Err(error)
+        }
+    }
+}
+
+fn own_signature_key(
+    args: &ArgMatches,
+    config: &Config,
+) -> Result<(Sig, SigPublicKey, Secret<SigSecretKey>)> {
+    let signature = parse_signature_algorithm_arg(args, config)?;
+    let (public_key, secret_key) =
+        config
+            .get_signature_keys(&signature.algorithm())
+            .context(format!(
+                "No {} key found. Create one with `rust-seal init sig`",
+                signature.algorithm()
+            ))?;
+    config.ensure_signature_key_valid(&signature.algorithm())?;
+    Ok((signature, public_key, secret_key))
+}
+
+fn parse_subject_arg(args: &ArgMatches) -> Result<Name> {
+    parse_name(
+        args.get_one::<String>(SUBJECT_ID)
+            .context("Missing required argument: subject")?,
+    )
+}
+
+fn parse_not_after_arg(args: &ArgMatches) -> Result<DateTime<Utc>> {
+    args.get_one::<DateTime<Utc>>(EXPIRES_ID)
+        .copied()
+        .context("Missing required argument: expires")
+}
+
+fn parse_output_args(args: &ArgMatches) -> Result<(PathBuf, Encoding)> {
+    let output_path = args
+        .get_one::<PathBuf>(OUTPUT_PATH_ID)
+        .context("Missing required argument: output path")?;
+    let encoding = args
+        .get_one::<Encoding>(ENCODING_ID)
+        .copied()
+        .unwrap_or(Encoding::Pem);
+    Ok((output_path.to_owned(), encoding))
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+
+use crate::{
+    Config,
+    cli::{SETTING_KEY_ID, SETTING_VALUE_ID},
+    config::{OutputFormat, Setting},
+};
+
+pub fn config_list_command(config: &Config) -> Result<()> {
+    match config.output_format() {
+        OutputFormat::Text => {
+            for setting in Setting::ALL {
+                println!(
+                    "{} = {}",
+                    setting.key(),
+                    config.get_setting(setting).unwrap_or_default()
+                );
+            }
+        }
+        OutputFormat::Json => {
+            let settings: Map<String, Value> = Setting::ALL
+                .into_iter()
+                .map(|setting| {
+                    (
+                        setting.key().to_string(),
+                        config.get_setting(setting).map_or(Value::Null, Value::from),
+                    )
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&settings).context("Failed to serialize settings")?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn config_get_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let setting = parse_setting_arg(args)?;
+
+    if let Some(value) = config.get_setting(setting) {
+        println!("{value}");
+    }
+
+    Ok(())
+}
+
+pub fn config_set_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let setting = parse_setting_arg(args)?;
+    let value = args
+        .get_one::<String>(SETTING_VALUE_ID)
+        .context("Missing required argument: value")?;
+
+    config
+        .set_setting(setting, value)
+        .context(format!("Failed to set {}", setting.key()))?;
+
+    println!(
+        "Set {} = {}",
+        setting.key(),
+        config.get_setting(setting).unwrap_or_default()
+    );
+    Ok(())
+}
+
+pub fn config_unset_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let setting = parse_setting_arg(args)?;
+
+    config.unset_setting(setting);
+
+    println!("Unset {}", setting.key());
+    Ok(())
+}
+
+fn parse_setting_arg(args: &ArgMatches) -> Result<Setting> {
+    args.get_one::<String>(SETTING_KEY_ID)
+        .context("Missing required argument: key")?
+        .parse()
+}
@@ -0,0 +1,254 @@
+use std::fs::{read, read_to_string};
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use oqs::{kem::Algorithm as KemAlgorithm, sig::Algorithm as SigAlgorithm};
+use serde::Serialize;
+
+use crate::{
+    Config,
+    cli::{
+        BUNDLE_PATH_ID, CONTACT_NAME_ID, EXPIRES_ID, FINGERPRINT_ID, JSON_ID, PUBLIC_KEY_PATH_ID,
+        SIGNATURE_ALGORITHM_ID,
+    },
+    config::{Contacts, OutputFormat},
+    cryptography::bundle::KeyBundle,
+    cryptography::fingerprint::{fingerprint, format_fingerprint},
+    cryptography::pkcs8::{KeyAlgorithm, parse_public_key},
+    util::{parse_kem_algorithm_arg, parse_path_arg, parse_signature_algorithm_arg, print_table},
+};
+
+/// Row of the contact listing, one per imported key
+#[derive(Serialize, Debug)]
+struct ContactRow<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    algorithm: String,
+    fingerprint: &'a str,
+    expires: Option<DateTime<Utc>>,
+    status: &'static str,
+}
+
+pub fn contacts_import_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let name = parse_contact_name_arg(args)?;
+    let public_key_path = parse_path_arg(args, PUBLIC_KEY_PATH_ID)?;
+    let public_key =
+        read(&public_key_path).context("Failed to read public key content for import")?;
+    let expires = args.get_one::<DateTime<Utc>>(EXPIRES_ID).copied();
+
+    let mut contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+    let fingerprint = if args.contains_id(SIGNATURE_ALGORITHM_ID) {
+        let signature = parse_signature_algorithm_arg(args, config)?;
+        let public_key =
+            raw_public_key(public_key, KeyAlgorithm::Signature(signature.algorithm()))?;
+        let expected =
+            expected_fingerprint_arg(args, &signature.algorithm().to_string(), &public_key)?;
+        contacts.import_signature_key(name, &signature, &public_key, expected, expires)?
+    } else {
+        let kem = parse_kem_algorithm_arg(args, config)?;
+        let public_key = raw_public_key(public_key, KeyAlgorithm::Kem(kem.algorithm()))?;
+        let expected = expected_fingerprint_arg(args, &kem.algorithm().to_string(), &public_key)?;
+        contacts.import_kem_key(name, &kem, &public_key, expected, expires)?
+    };
+    contacts.save().context("Failed to save contacts")?;
+
+    println!(
+        "Imported key \x1b[32m{}\x1b[0m for contact {name}",
+        format_fingerprint(&fingerprint)
+    );
+    Ok(())
+}
+
+/// Imports the primary key of a bundle after checking its fingerprint, together with the keys it certifies
+pub fn contacts_import_bundle_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let name = parse_contact_name_arg(args)?;
+    let bundle_path = parse_path_arg(args, BUNDLE_PATH_ID)?;
+    let bundle: KeyBundle =
+        serde_json::from_str(&read_to_string(&bundle_path).context("Failed to read key bundle")?)
+            .context("Failed to parse key bundle")?;
+
+    let expected = match args.get_one::<String>(FINGERPRINT_ID) {
+        Some(expected) => expected,
+        None => bail!(
+            "The primary key has the fingerprint\n  {}\nConfirm it with the key owner through another channel and pass it with --fingerprint",
+            format_fingerprint(&bundle.primary_fingerprint())
+        ),
+    };
+
+    let mut contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+    let fingerprint = contacts.import_bundle(name, &bundle, expected)?;
+    contacts.save().context("Failed to save contacts")?;
+
+    println!(
+        "Imported primary key \x1b[32m{}\x1b[0m and {} certified keys for contact {name}",
+        format_fingerprint(&fingerprint),
+        bundle.subkeys.len()
+    );
+    Ok(())
+}
+
+pub fn contacts_list_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+
+    let rows: Vec<ContactRow> = contacts
+        .contacts()
+        .iter()
+        .flat_map(|contact| {
+            let primary_key = contact.primary_key.iter().map(|key| ContactRow {
+                name: &contact.name,
+                kind: "primary",
+                algorithm: key.algorithm.to_string(),
+                fingerprint: &key.fingerprint,
+                expires: key.expires,
+                status: key.status(),
+            });
+            let signature_keys = contact.signature_keys.iter().map(|key| ContactRow {
+                name: &contact.name,
+                kind: "signature",
+                algorithm: key.algorithm.to_string(),
+                fingerprint: &key.fingerprint,
+                expires: key.expires,
+                status: key.status(),
+            });
+            let kem_keys = contact.kem_keys.iter().map(|key| ContactRow {
+                name: &contact.name,
+                kind: "kem",
+                algorithm: key.algorithm.to_string(),
+                fingerprint: &key.fingerprint,
+                expires: key.expires,
+                status: key.status(),
+            });
+            primary_key.chain(signature_keys).chain(kem_keys)
+        })
+        .collect();
+
+    let output_format = if args.get_flag(JSON_ID) {
+        OutputFormat::Json
+    } else {
+        config.output_format()
+    };
+
+    match output_format {
+        OutputFormat::Text => {
+            let rows: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| {
+                    vec![
+                        row.name.to_string(),
+                        row.kind.to_string(),
+                        row.algorithm.clone(),
+                        row.expires.map_or_else(
+                            || "-".to_string(),
+                            |expires| expires.format("%Y-%m-%d").to_string(),
+                        ),
+                        row.status.to_string(),
+                        format_fingerprint(row.fingerprint),
+                    ]
+                })
+                .collect();
+            print_table(
+                &[
+                    "NAME",
+                    "TYPE",
+                    "ALGORITHM",
+                    "EXPIRES",
+                    "STATUS",
+                    "FINGERPRINT",
+                ],
+                &rows,
+            );
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).context("Failed to serialize contacts")?
+        ),
+    }
+
+    Ok(())
+}
+
+pub fn contacts_remove_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let name = parse_contact_name_arg(args)?;
+
+    let mut contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+    contacts.remove(name)?;
+    contacts.save().context("Failed to save contacts")?;
+
+    println!("Removed contact {name}");
+    Ok(())
+}
+
+/// Prints the fingerprint of a public key file, or of the own configured key if no file is given
+pub fn contacts_fingerprint_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let public_key_path = args
+        .contains_id(PUBLIC_KEY_PATH_ID)
+        .then(|| parse_path_arg(args, PUBLIC_KEY_PATH_ID))
+        .transpose()?;
+
+    let (algorithm, public_key) = if args.contains_id(SIGNATURE_ALGORITHM_ID) {
+        let algorithm: SigAlgorithm = parse_signature_algorithm_arg(args, config)?.algorithm();
+        let public_key = match &public_key_path {
+            Some(path) => raw_public_key(
+                read(path).context("Failed to read public key file")?,
+                KeyAlgorithm::Signature(algorithm),
+            )?,
+            None => config.get_signature_keys(&algorithm)?.0.into_vec(),
+        };
+        (algorithm.to_string(), public_key)
+    } else {
+        let algorithm: KemAlgorithm = parse_kem_algorithm_arg(args, config)?.algorithm();
+        let public_key = match &public_key_path {
+            Some(path) => raw_public_key(
+                read(path).context("Failed to read public key file")?,
+                KeyAlgorithm::Kem(algorithm),
+            )?,
+            None => config.get_kem_keys(&algorithm)?.0.into_vec(),
+        };
+        (algorithm.to_string(), public_key)
+    };
+
+    println!(
+        "{}",
+        format_fingerprint(&fingerprint(&algorithm, &public_key))
+    );
+    Ok(())
+}
+
+/// Returns the raw key of a SubjectPublicKeyInfo file after checking its algorithm.
+/// Other file contents are taken to be a raw liboqs key.
+fn raw_public_key(content: Vec<u8>, expected: KeyAlgorithm) -> Result<Vec<u8>> {
+    match parse_public_key(&content)? {
+        Some((algorithm, public_key)) => {
+            ensure!(
+                algorithm == expected,
+                "The public key is a {algorithm} key, but {expected} was requested"
+            );
+            Ok(public_key)
+        }
+        None => Ok(content),
+    }
+}
+
+fn parse_contact_name_arg(args: &ArgMatches) -> Result<&str> {
+    args.get_one::<String>(CONTACT_NAME_ID)
+        .map(String::as_str)
+        .context("Missing required argument: name")
+}
+
+/// Returns the `--fingerprint` argument.
+/// Without it the import is refused, showing the fingerprint the user has to confirm with the key owner.
+fn expected_fingerprint_arg<'a>(
+    args: &'a ArgMatches,
+    algorithm: &str,
+    public_key: &[u8],
+) -> Result<&'a str> {
+    match args.get_one::<String>(FINGERPRINT_ID) {
+        Some(expected) => Ok(expected),
+        None => bail!(
+            "The key has the fingerprint\n  {}\nConfirm it with the key owner through another channel and pass it with --fingerprint",
+            format_fingerprint(&fingerprint(algorithm, public_key))
+        ),
+    }
+}
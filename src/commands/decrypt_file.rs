@@ -1,21 +1,19 @@
-use std::{
-    fs::{read, write},
-    path::absolute,
-};
+use std::{fs::read, path::absolute};
 
 use anyhow::{Context, Result, ensure};
 use clap::ArgMatches;
-use oqs::kem::{CiphertextRef, Kem, SecretKey as KemSecretKey};
+use oqs::kem::{CiphertextRef, Kem};
 
 use crate::{
     Config,
-    cli::{CIPHER_TEXT_PATH_ID, FILE_PATH_ID},
-    cryptography::{AES_KEY_SIZE, symmetric::symmetric_decrypt},
-    util::{parse_kem_algorithm_arg, parse_path_arg},
+    agent::DecapsulationKey,
+    cli::{CIPHER_TEXT_PATH_ID, FILE_PATH_ID, FORCE_ID},
+    cryptography::{AES_KEY_SIZE, secret::SecretBytes, symmetric::symmetric_decrypt},
+    util::{parse_kem_algorithm_arg, parse_path_arg, write_secret_file},
 };
 
 pub fn decrypt_file_command(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
-    let kem = parse_kem_algorithm_arg(sub_matches)?;
+    let kem = parse_kem_algorithm_arg(sub_matches, config)?;
     let file_path = parse_path_arg(sub_matches, FILE_PATH_ID)?;
     let ciphertext_path = parse_path_arg(sub_matches, CIPHER_TEXT_PATH_ID).unwrap_or_else(|_| {
         file_path.with_file_name(format!(
@@ -31,7 +29,7 @@ pub fn decrypt_file_command(sub_matches: &ArgMatches, config: &Config) -> Result
         ))
     });
 
-    let (_, secret_key) = config.get_kem_keys(&kem.algorithm()).context(
+    let (_, secret_key) = config.decapsulation_key(&kem.algorithm()).context(
         "Failed to retrieve KEM secret key from configuration. KEM Algorithm is not initialized.",
     )?;
 
@@ -48,22 +46,28 @@ pub fn decrypt_file_command(sub_matches: &ArgMatches, config: &Config) -> Result
         .ciphertext_from_bytes(&ciphertext_bytes)
         .context("Failed to parse ciphertext from bytes. Ensure the file is a valid ciphertext.")?;
 
-    let decrypted_data = decrypt_file(kem, ciphertext, secret_key, &file_content)
+    let decrypted_data = decrypt_file(&kem, ciphertext, &secret_key, &file_content)
         .context("Failed to decrypt file content with KEM algorithm and provided ciphertext.")?;
 
     //removes the extension from the path (file should have multiple extensions => test.txt.cipher)
+    // An existing file is kept by choosing another name, unless --force is given
+    let force = sub_matches.get_flag(FORCE_ID);
     let mut save_file_path = file_path.with_extension("");
-    while save_file_path.exists() {
-        let stem = save_file_path.file_stem().unwrap().to_string_lossy();
-        let new_stem = format!("{stem}-decrypt");
-        save_file_path.set_file_name(format!(
-            "{}.{}",
-            new_stem,
-            save_file_path.extension().unwrap().to_string_lossy()
-        ))
+    while !force && save_file_path.exists() {
+        let stem = save_file_path
+            .file_stem()
+            .context(format!("{} has no file name", save_file_path.display()))?
+            .to_string_lossy();
+        let file_name = match save_file_path.extension() {
+            Some(extension) => format!("{stem}-decrypt.{}", extension.to_string_lossy()),
+            None => format!("{stem}-decrypt"),
+        };
+        save_file_path.set_file_name(file_name);
     }
 
-    write(&save_file_path, decrypted_data).context("Failed to write decrypted data to file.")?;
+    // The decrypted data is authenticated at this point, so it only becomes visible if it is intact
+    write_secret_file(&save_file_path, decrypted_data, force)
+        .context("Failed to write decrypted data to file.")?;
 
     println!(
         "Decrypted data written to {}\n\x1b[32mDecryption successful\x1b[0m",
@@ -74,15 +78,14 @@ pub fn decrypt_file_command(sub_matches: &ArgMatches, config: &Config) -> Result
 }
 
 fn decrypt_file(
-    kem: Kem,
+    kem: &Kem,
     ciphertext: CiphertextRef,
-    secret_key: KemSecretKey,
+    secret_key: &DecapsulationKey,
     decrypted_data: &[u8],
-) -> Result<Vec<u8>> {
-    let shared_secret = kem.decapsulate(&secret_key, ciphertext)
+) -> Result<SecretBytes> {
+    let shared_secret = secret_key.decapsulate(kem, ciphertext)
         .context("Failed to decapsulate shared secret using KEM algorithm. Secret Key or Ciphertext might be incorrect or Algorithm might be disabled.")?;
 
-    println!("Shared secret length: {}", shared_secret.len());
     ensure!(
         shared_secret.len() >= AES_KEY_SIZE,
         "Shared secret is too short. Use another KEM algorithm"
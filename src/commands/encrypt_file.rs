@@ -1,35 +1,33 @@
-use std::fs::{read, write};
+use std::fs::read;
 
 use anyhow::{Context, Result, ensure};
 use clap::ArgMatches;
 use oqs::kem::{Ciphertext, Kem, PublicKeyRef as KemPublicKeyRef};
 
 use crate::{
-    cli::{FILE_PATH_ID, PUBLIC_KEY_PATH_ID},
-    cryptography::{AES_KEY_SIZE, symmetric::symmetric_encrypt},
-    util::{parse_kem_algorithm_arg, parse_path_arg},
+    Config,
+    cli::{ALLOW_UNTRUSTED_KEY_ID, FILE_PATH_ID, FORCE_ID, PUBLIC_KEY_PATH_ID, RECIPIENT_ID},
+    config::Contacts,
+    cryptography::{
+        AES_KEY_SIZE,
+        secret::{Secret, SecretBytes},
+        symmetric::symmetric_encrypt,
+    },
+    util::{ensure_overwritable, parse_kem_algorithm_arg, parse_path_arg, write_file},
 };
 
-pub fn encrypt_file_command(args: &ArgMatches) -> Result<()> {
-    let kem = parse_kem_algorithm_arg(args)?;
+pub fn encrypt_file_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let kem = parse_kem_algorithm_arg(args, config)?;
     let file_path = parse_path_arg(args, FILE_PATH_ID)?;
-    let public_key_path = parse_path_arg(args, PUBLIC_KEY_PATH_ID)
-        .unwrap_or_else(|_| file_path.with_extension("pub"));
-
-    let public_key_content =
-        read(&public_key_path).context("Failed to read public key content for encryption")?;
+    let public_key_content = recipient_public_key(args, config, &kem)?;
     let public_key = kem
         .public_key_from_bytes(&public_key_content)
         .context("Failed to read or parse public key")?;
 
-    let file_content = read(&file_path).context("Failed to read file content for encryption")?;
-
-    let (ciphertext, decrypted_data) = encrypt_file(kem, public_key, &file_content)?;
+    let file_content =
+        SecretBytes::new(read(&file_path).context("Failed to read file content for encryption")?);
 
     let ciphertext_path = file_path.with_extension("cipher");
-    write(&ciphertext_path, ciphertext.as_ref()).context("Failed to write ciphertext to file")?;
-    println!("Ciphertext written to {}", &ciphertext_path.display());
-
     let decrypted_data_path = file_path.with_extension(format!(
         "{}.{}",
         file_path
@@ -38,12 +36,22 @@ pub fn encrypt_file_command(args: &ArgMatches) -> Result<()> {
             .display(),
         "dec"
     ));
+    let force = args.get_flag(FORCE_ID);
+    ensure_overwritable(&ciphertext_path, force)?;
+    ensure_overwritable(&decrypted_data_path, force)?;
+
+    let (ciphertext, decrypted_data) = encrypt_file(kem, public_key, file_content.as_ref())?;
+
+    write_file(&ciphertext_path, ciphertext, force)
+        .context("Failed to write ciphertext to file")?;
+    println!("Ciphertext written to {}", &ciphertext_path.display());
+
     println!(
         "Decrypted data will be saved to {}",
         &decrypted_data_path.display()
     );
 
-    write(&decrypted_data_path, decrypted_data)
+    write_file(&decrypted_data_path, decrypted_data, force)
         .context("Failed to write decrypted data to file")?;
     println!(
         "Decrypted data written to {}",
@@ -53,6 +61,29 @@ pub fn encrypt_file_command(args: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Returns the public key of the `--recipient` contact.
+/// A key file given with `--pub-path` is only used with `--allow-untrusted-key`; without either, the own configured key is used.
+fn recipient_public_key(args: &ArgMatches, config: &Config, kem: &Kem) -> Result<Vec<u8>> {
+    if let Some(recipient) = args.get_one::<String>(RECIPIENT_ID) {
+        let contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+        return Ok(contacts
+            .kem_public_key(recipient, kem.algorithm())?
+            .to_vec());
+    }
+
+    if args.contains_id(PUBLIC_KEY_PATH_ID) {
+        ensure!(
+            args.get_flag(ALLOW_UNTRUSTED_KEY_ID),
+            "Refusing to encrypt to a public key file that is not in the contacts. Import it with `rust-seal contacts import` and pass --recipient, or pass --allow-untrusted-key"
+        );
+        let public_key_path = parse_path_arg(args, PUBLIC_KEY_PATH_ID)?;
+        return read(&public_key_path).context("Failed to read public key content for encryption");
+    }
+
+    config.ensure_kem_key_valid(&kem.algorithm())?;
+    Ok(config.get_kem_public_key(&kem.algorithm())?.into_vec())
+}
+
 fn encrypt_file(
     kem: Kem,
     public_key: KemPublicKeyRef,
@@ -61,8 +92,8 @@ fn encrypt_file(
     let (ciphertext, shared_secret) = kem
         .encapsulate(public_key)
         .context("Failed to encapsulate using KEM algorithm. Algorithm might be disabled.")?;
+    let shared_secret = Secret::new(shared_secret);
 
-    println!("Shared secret length: {}", shared_secret.len());
     ensure!(
         shared_secret.len() >= AES_KEY_SIZE,
         "Shared secret is too short. Use another KEM algorithm"
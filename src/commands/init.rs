@@ -1,29 +1,68 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use clap::ArgMatches;
 
 use crate::{
     Config,
+    cli::{EXPIRES_ID, FORCE_ID, MNEMONIC_ID},
+    cryptography::mnemonic::RecoverySeed,
     util::{parse_kem_algorithm_arg, parse_signature_algorithm_arg},
 };
 
+/// With `--mnemonic`, the keypair is generated from a new recovery seed, which is printed once as a mnemonic
 pub fn init_kem(args: &ArgMatches, config: &mut Config) -> Result<()> {
-    let kem = parse_kem_algorithm_arg(args)?;
+    let kem = parse_kem_algorithm_arg(args, config)?;
+    let expires = args.get_one::<DateTime<Utc>>(EXPIRES_ID).copied();
+
+    let recovery_seed = args.get_flag(MNEMONIC_ID).then(RecoverySeed::generate);
+    let seed = recovery_seed
+        .as_ref()
+        .map(|recovery_seed| recovery_seed.derive_kem_seed(&kem))
+        .transpose()?;
 
     config
-        .add_kem_algorithm(&kem)
+        .add_kem_algorithm(
+            &kem,
+            seed.as_ref().map(AsRef::as_ref),
+            expires,
+            args.get_flag(FORCE_ID),
+        )
         .context("Failed to add KEM algorithm to configuration")?;
 
     println!("Initialized KEM algorithm: {}", kem.algorithm());
+    print_expiry(expires);
+    if let Some(recovery_seed) = recovery_seed {
+        println!();
+        println!("Recovery mnemonic:");
+        println!();
+        println!("    {}", recovery_seed.to_mnemonic().as_str());
+        println!();
+        println!(
+            "Write these words down and keep them offline. They are not stored and will not be shown again."
+        );
+        println!(
+            "Anyone who has them can recreate your secret key with `rust-seal keys recover --kem-algorithm {}`",
+            kem.algorithm()
+        );
+    }
     Ok(())
 }
 
 pub fn init_sig(args: &ArgMatches, config: &mut Config) -> Result<()> {
-    let signature = parse_signature_algorithm_arg(args)?;
+    let signature = parse_signature_algorithm_arg(args, config)?;
+    let expires = args.get_one::<DateTime<Utc>>(EXPIRES_ID).copied();
 
     config
-        .add_signature_algorithm(&signature)
+        .add_signature_algorithm(&signature, expires, args.get_flag(FORCE_ID))
         .context("Failed to add signature algorithm to configuration")?;
 
     println!("Initialized signature algorithm: {}", signature.algorithm());
+    print_expiry(expires);
     Ok(())
 }
+
+fn print_expiry(expires: Option<DateTime<Utc>>) {
+    if let Some(expires) = expires {
+        println!("Key expires on {}", expires.format("%Y-%m-%d %H:%M:%S UTC"));
+    }
+}
@@ -0,0 +1,661 @@
+use std::{
+    fs::{create_dir_all, read_to_string},
+    io::{IsTerminal, Read, stdin},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use oqs::{
+    kem::{Algorithm as KemAlgorithm, Kem},
+    sig::{Algorithm as SigAlgorithm, Sig},
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    Config,
+    cli::{
+        ARMOR_ID, COMMENT_ID, EXPIRES_ID, FORCE_ID, IGNORE_POLICY_ID, KEM_ALGORITHM_ID,
+        KEY_FORMAT_ID, OUTPUT_DIR_ID, OUTPUT_PATH_ID, PRIMARY_SECRET_KEY_ID, PRIVATE_ID,
+        PRIVATE_KEY_PATH_ID, PUBLIC_KEY_PATH_ID, REVOCATION_PATH_ID, REVOCATION_REASON_ID,
+        SHARE_PATH_ID, SHARES_ID, SIGNATURE_ALGORITHM_ID, SIGNED_BY_ID, THRESHOLD_ID,
+        validate_kem_algorithm, validate_signature_algorithm,
+    },
+    config::{Contacts, Setting},
+    cryptography::{
+        asn1::Encoding,
+        binding::{BindingStatement, SubkeyBinding},
+        cose::public_cose_key,
+        fingerprint::{fingerprint, format_fingerprint},
+        jose::public_key_jwk,
+        mnemonic::RecoverySeed,
+        pkcs8::{
+            KeyAlgorithm, KeyFormat, PrivateKey, PrivateKeyForm, ml_kem_public_key,
+            read_private_key, read_public_key, write_private_key, write_public_key,
+        },
+        revocation::{KeyType, RevocationCertificate, RevocationReason, RevokedKey},
+        secret::Secret,
+        shamir::KeyShare,
+    },
+    oqs::{convert_str_to_kem_alg, convert_str_to_sig_alg},
+    util::{
+        ensure_overwritable, parse_kem_algorithm_arg, parse_path_arg,
+        parse_signature_algorithm_arg, write_file, write_secret_file,
+    },
+};
+
+pub fn keys_init_primary_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let signature = parse_signature_algorithm_arg(args, config)?;
+
+    let (sec_key_path, public_key) = config
+        .init_primary_key(&signature, args.get_flag(FORCE_ID))
+        .context("Failed to create primary key")?;
+
+    println!("Initialized primary key: {}", signature.algorithm());
+    println!(
+        "Fingerprint: \x1b[32m{}\x1b[0m",
+        format_fingerprint(&fingerprint(
+            &signature.algorithm().to_string(),
+            public_key.as_ref()
+        ))
+    );
+    println!(
+        "Secret key saved to: {}. Certify your keys with `rust-seal keys certify`, then move it to offline storage",
+        sec_key_path.display()
+    );
+    Ok(())
+}
+
+/// Signs a binding of an own key with the primary key.
+/// The certification ends with `--expires`, or with the expiry of the key if not given.
+pub fn keys_certify_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let (primary_algorithm, primary_public_key) = config.primary_public_key()?;
+    let primary = Sig::new(primary_algorithm)
+        .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+    let primary_secret_key = config.primary_secret_key(
+        args.get_one::<PathBuf>(PRIMARY_SECRET_KEY_ID)
+            .map(PathBuf::as_path),
+    )?;
+    let expires = args.get_one::<DateTime<Utc>>(EXPIRES_ID).copied();
+
+    let (description, key_type, public_key, not_after) =
+        if let Some(algorithm) = args.get_one::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID) {
+            config.ensure_signature_key_valid(algorithm)?;
+            (
+                algorithm.to_string(),
+                KeyType::Signature,
+                config.get_signature_keys(algorithm)?.0.into_vec(),
+                expires.or(config.signature_key_expiry(algorithm)?),
+            )
+        } else {
+            let algorithm = args
+                .get_one::<KemAlgorithm>(KEM_ALGORITHM_ID)
+                .context("Missing required argument: KEM algorithm")?;
+            config.ensure_kem_key_valid(algorithm)?;
+            (
+                algorithm.to_string(),
+                KeyType::Kem,
+                config.get_kem_keys(algorithm)?.0.into_vec(),
+                expires.or(config.kem_key_expiry(algorithm)?),
+            )
+        };
+
+    let statement = BindingStatement::new(
+        primary_algorithm,
+        primary_public_key.as_ref(),
+        key_type,
+        &description,
+        &public_key,
+        not_after,
+    );
+    let binding = SubkeyBinding::sign(statement, &primary, &primary_secret_key)?;
+    binding
+        .verify(primary_public_key.as_ref(), &public_key)
+        .context("The primary secret key does not belong to the primary public key")?;
+
+    let binding_path = match key_type {
+        KeyType::Signature => config.certify_signature_key(
+            args.get_one::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID)
+                .context("Missing required argument: signature algorithm")?,
+            &binding,
+        )?,
+        KeyType::Kem => config.certify_kem_key(
+            args.get_one::<KemAlgorithm>(KEM_ALGORITHM_ID)
+                .context("Missing required argument: KEM algorithm")?,
+            &binding,
+        )?,
+    };
+
+    match not_after {
+        Some(not_after) => println!(
+            "Certified {description} key with primary key {primary_algorithm} until {}",
+            not_after.format("%Y-%m-%d %H:%M:%S UTC")
+        ),
+        None => println!("Certified {description} key with primary key {primary_algorithm}"),
+    }
+    println!("Binding saved to: {}", binding_path.display());
+    Ok(())
+}
+
+/// Writes the primary public key and every valid certified key to a bundle others can import with `rust-seal contacts import-bundle`
+pub fn keys_export_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let output_path = args
+        .get_one::<PathBuf>(OUTPUT_PATH_ID)
+        .context("Missing required argument: output path")?;
+
+    let bundle = config.export_bundle()?;
+    ensure!(
+        !bundle.subkeys.is_empty(),
+        "No valid certified keys to export. Certify keys with `rust-seal keys certify`"
+    );
+
+    let json_string =
+        serde_json::to_string_pretty(&bundle).context("Failed to serialize key bundle")?;
+    write_file(output_path, json_string, args.get_flag(FORCE_ID))
+        .context("Failed to write key bundle")?;
+
+    println!(
+        "Exported primary key and {} certified keys to: {}",
+        bundle.subkeys.len(),
+        output_path.display()
+    );
+    println!(
+        "Primary key fingerprint: \x1b[32m{}\x1b[0m",
+        format_fingerprint(&bundle.primary_fingerprint())
+    );
+    Ok(())
+}
+
+/// Revokes an own key. The algorithm policy is not enforced, so keys of forbidden algorithms can still be revoked.
+pub fn keys_revoke_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let reason: RevocationReason = args
+        .get_one::<String>(REVOCATION_REASON_ID)
+        .context("Missing required argument: reason")?
+        .parse()?;
+    let comment = args.get_one::<String>(COMMENT_ID).cloned();
+
+    let (description, revocation_path) =
+        if let Some(algorithm) = args.get_one::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID) {
+            let signature = Sig::new(*algorithm)
+                .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+            let (public_key, secret_key) = config.get_signature_keys(algorithm)?;
+
+            let certificate = RevocationCertificate::sign(
+                RevokedKey {
+                    key_type: KeyType::Signature,
+                    algorithm: algorithm.to_string(),
+                    fingerprint: fingerprint(&algorithm.to_string(), public_key.as_ref()),
+                },
+                reason,
+                comment,
+                &signature,
+                public_key.as_ref(),
+                &secret_key,
+            )?;
+            (
+                format!("signature key {algorithm}"),
+                config.revoke_signature_key(algorithm, &certificate)?,
+            )
+        } else {
+            let algorithm = args
+                .get_one::<KemAlgorithm>(KEM_ALGORITHM_ID)
+                .context("Missing required argument: KEM algorithm")?;
+            Kem::new(*algorithm)
+                .context("Failed to create KEM algorithm. Algorithm might me disabled.")?;
+            let (public_key, _) = config.get_kem_keys(algorithm)?;
+
+            let signer_algorithm = parse_signed_by_arg(args, config)?;
+            let signer = Sig::new(signer_algorithm)
+                .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+            config.ensure_signature_key_valid(&signer_algorithm)?;
+            let (signer_public_key, signer_secret_key) =
+                config.get_signature_keys(&signer_algorithm)?;
+
+            let certificate = RevocationCertificate::sign(
+                RevokedKey {
+                    key_type: KeyType::Kem,
+                    algorithm: algorithm.to_string(),
+                    fingerprint: fingerprint(&algorithm.to_string(), public_key.as_ref()),
+                },
+                reason,
+                comment,
+                &signer,
+                signer_public_key.as_ref(),
+                &signer_secret_key,
+            )?;
+            (
+                format!("KEM key {algorithm}"),
+                config.revoke_kem_key(algorithm, &certificate)?,
+            )
+        };
+
+    println!("Revoked {description} for reason {reason}");
+    println!(
+        "Revocation certificate saved to: {}. Share it with everyone who has your public key",
+        revocation_path.display()
+    );
+    Ok(())
+}
+
+/// Returns the signature algorithm whose key signs a KEM key revocation
+fn parse_signed_by_arg(args: &ArgMatches, config: &Config) -> Result<SigAlgorithm> {
+    args.get_one::<SigAlgorithm>(SIGNED_BY_ID)
+        .copied()
+        .or_else(|| config.default_signature_algorithm())
+        .context(format!(
+            "A KEM key revocation is signed by one of your signature keys. Pass --signed-by or set a default with `rust-seal config set {} <ALGORITHM>`",
+            Setting::DefaultSignatureAlgorithm.key()
+        ))
+}
+
+/// Imports a revocation certificate of a contact key
+pub fn keys_import_revocation_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let revocation_path = parse_path_arg(args, REVOCATION_PATH_ID)?;
+    let certificate: RevocationCertificate = serde_json::from_str(
+        &read_to_string(&revocation_path).context("Failed to read revocation certificate")?,
+    )
+    .context("Failed to parse revocation certificate")?;
+    let algorithm = certificate.statement.algorithm.clone();
+    let reason = certificate.statement.reason;
+
+    let mut contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+    let name = contacts.import_revocation(certificate)?.to_string();
+    contacts.save().context("Failed to save contacts")?;
+
+    println!("The {algorithm} key of contact {name} is \x1b[31mrevoked\x1b[0m for reason {reason}");
+    Ok(())
+}
+
+/// Writes an own public key as SubjectPublicKeyInfo, JWK or COSE_Key or, with `--private`, the secret key as PKCS#8.
+/// The private key is written as the expanded key, since the seed of a key is not stored.
+pub fn keys_export_key_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let output_path = args
+        .get_one::<PathBuf>(OUTPUT_PATH_ID)
+        .context("Missing required argument: output path")?;
+    let format = args
+        .get_one::<KeyFormat>(KEY_FORMAT_ID)
+        .copied()
+        .unwrap_or(KeyFormat::Pem);
+
+    let (algorithm, public_key, form) =
+        if let Some(algorithm) = args.get_one::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID) {
+            let (public_key, secret_key) = config.get_signature_keys(algorithm)?;
+            (
+                KeyAlgorithm::Signature(*algorithm),
+                public_key.into_vec(),
+                PrivateKeyForm::ExpandedKey(secret_key.into_bytes()),
+            )
+        } else {
+            let algorithm = args
+                .get_one::<KemAlgorithm>(KEM_ALGORITHM_ID)
+                .context("Missing required argument: KEM algorithm")?;
+            let (public_key, secret_key) = config.get_kem_keys(algorithm)?;
+            (
+                KeyAlgorithm::Kem(*algorithm),
+                public_key.into_vec(),
+                PrivateKeyForm::ExpandedKey(secret_key.into_bytes()),
+            )
+        };
+
+    let encoding = match format {
+        KeyFormat::Pem => Encoding::Pem,
+        KeyFormat::Der => Encoding::Der,
+        KeyFormat::Jwk | KeyFormat::CoseKey => {
+            let KeyAlgorithm::Signature(signature_algorithm) = algorithm else {
+                bail!("JWK and COSE_Key are only defined for ML-DSA keys");
+            };
+            ensure!(
+                !args.get_flag(PRIVATE_ID),
+                "Private keys can only be exported as PKCS#8 with --format pem or der"
+            );
+            let content = if format == KeyFormat::Jwk {
+                serde_json::to_string_pretty(&public_key_jwk(signature_algorithm, &public_key)?)
+                    .context("Failed to serialize JWK")?
+                    .into_bytes()
+            } else {
+                public_cose_key(signature_algorithm, &public_key)?
+            };
+            write_file(output_path, content, args.get_flag(FORCE_ID))
+                .context("Failed to write public key")?;
+            println!(
+                "{algorithm} public key written to: {}",
+                output_path.display()
+            );
+            return Ok(());
+        }
+    };
+
+    if args.get_flag(PRIVATE_ID) {
+        write_private_key(
+            output_path,
+            &PrivateKey {
+                algorithm,
+                form,
+                public_key: None,
+            },
+            encoding,
+            args.get_flag(FORCE_ID),
+        )?;
+        println!(
+            "{algorithm} private key written to: {}",
+            output_path.display()
+        );
+        println!(
+            "\x1b[31mThe file is not encrypted. Keep it as safe as your keys directory\x1b[0m"
+        );
+    } else {
+        write_public_key(
+            output_path,
+            algorithm,
+            &public_key,
+            encoding,
+            args.get_flag(FORCE_ID),
+        )?;
+        println!(
+            "{algorithm} public key written to: {}",
+            output_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Imports a PKCS#8 private key as an own key.
+/// ML-KEM keys are expanded from their seed if present. ML-DSA keys need the expanded key and the public key,
+/// either from a version 2 PKCS#8 file or from `--public-key`, since liboqs cannot expand their seed.
+pub fn keys_import_key_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let key = read_private_key(&parse_path_arg(args, PRIVATE_KEY_PATH_ID)?)?;
+    let expires = args.get_one::<DateTime<Utc>>(EXPIRES_ID).copied();
+    let ignore_policy = args.get_flag(IGNORE_POLICY_ID);
+
+    let public_key = match args.get_one::<PathBuf>(PUBLIC_KEY_PATH_ID) {
+        Some(path) => {
+            let (algorithm, public_key) = read_public_key(path)?;
+            ensure!(
+                algorithm == key.algorithm,
+                "The public key is a {algorithm} key, but the private key is a {} key",
+                key.algorithm
+            );
+            Some(public_key)
+        }
+        None => key.public_key.clone(),
+    };
+
+    match key.algorithm {
+        KeyAlgorithm::Kem(algorithm) => {
+            let kem = Kem::new(algorithm)
+                .context("Failed to create KEM algorithm. Algorithm might me disabled.")?;
+            validate_kem_algorithm(&kem, config.policy(), ignore_policy)?;
+
+            let (derived_public_key, secret_key) = match key.form.seed() {
+                Some(seed) => {
+                    let (public_key, secret_key) = kem
+                        .keypair_derand(
+                            kem.keypair_seed_from_bytes(seed)
+                                .context(format!("{algorithm} does not support seeds"))?,
+                        )
+                        .context("Failed to expand the seed")?;
+                    if let Some(expanded_key) = key.form.expanded_key() {
+                        ensure!(
+                            expanded_key == secret_key.as_ref(),
+                            "The expanded key does not match the seed"
+                        );
+                    }
+                    (public_key.into_vec(), secret_key.into_vec())
+                }
+                None => {
+                    let expanded_key = key
+                        .form
+                        .expanded_key()
+                        .context("Private key has neither a seed nor an expanded key")?;
+                    (
+                        ml_kem_public_key(&kem, expanded_key)?,
+                        expanded_key.to_vec(),
+                    )
+                }
+            };
+            if let Some(public_key) = &public_key {
+                ensure!(
+                    *public_key == derived_public_key,
+                    "The public key does not belong to the private key"
+                );
+            }
+
+            config
+                .import_kem_keys(
+                    &kem,
+                    &derived_public_key,
+                    &secret_key,
+                    expires,
+                    args.get_flag(FORCE_ID),
+                )
+                .context("Failed to import KEM key")?;
+        }
+        KeyAlgorithm::Signature(algorithm) => {
+            let signature = Sig::new(algorithm)
+                .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+            validate_signature_algorithm(&signature, config.policy(), ignore_policy)?;
+
+            let secret_key = key.form.expanded_key().context(format!(
+                "{algorithm} keys can only be imported with their expanded key, since liboqs cannot expand the seed. Convert the key with `openssl pkey -provparam ml-dsa.output_formats=seed-priv`"
+            ))?;
+            let public_key = public_key.context(format!(
+                "{algorithm} public keys cannot be derived from the private key. Pass the public key with --public-key, e.g. from `openssl pkey -pubout`"
+            ))?;
+
+            config
+                .import_signature_keys(
+                    &signature,
+                    &public_key,
+                    secret_key,
+                    expires,
+                    args.get_flag(FORCE_ID),
+                )
+                .context("Failed to import signature key")?;
+        }
+    }
+
+    println!("Imported {} key", key.algorithm);
+    Ok(())
+}
+
+/// Splits an own secret key into shares, any `--threshold` of which reconstruct it with `rust-seal keys combine`.
+/// The shares are written to files in `--output-dir`, or printed as armored text with `--armor`
+pub fn keys_split_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let threshold = *args
+        .get_one::<u8>(THRESHOLD_ID)
+        .context("Missing required argument: threshold")?;
+    let count = *args
+        .get_one::<u8>(SHARES_ID)
+        .context("Missing required argument: shares")?;
+    let force = args.get_flag(FORCE_ID);
+
+    let (key_type, algorithm, public_key, secret_key) =
+        if let Some(algorithm) = args.get_one::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID) {
+            let (public_key, secret_key) = config.get_signature_keys(algorithm)?;
+            (
+                KeyType::Signature,
+                algorithm.to_string(),
+                public_key.into_vec(),
+                secret_key.into_bytes(),
+            )
+        } else {
+            let algorithm = args
+                .get_one::<KemAlgorithm>(KEM_ALGORITHM_ID)
+                .context("Missing required argument: KEM algorithm")?;
+            let (public_key, secret_key) = config.get_kem_keys(algorithm)?;
+            (
+                KeyType::Kem,
+                algorithm.to_string(),
+                public_key.into_vec(),
+                secret_key.into_bytes(),
+            )
+        };
+    let key_fingerprint = fingerprint(&algorithm, &public_key);
+
+    let shares = KeyShare::split(
+        key_type,
+        &algorithm,
+        &key_fingerprint,
+        secret_key.as_ref(),
+        threshold,
+        count,
+    )?;
+
+    if args.get_flag(ARMOR_ID) {
+        for share in &shares {
+            println!("{}", share.to_armored()?.as_str());
+        }
+    } else {
+        let output_dir = args
+            .get_one::<PathBuf>(OUTPUT_DIR_ID)
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let paths: Vec<PathBuf> = shares
+            .iter()
+            .map(|share| output_dir.join(format!("{algorithm}-share-{}.json", share.content.index)))
+            .collect();
+        for path in &paths {
+            ensure_overwritable(path, force)?;
+        }
+        create_dir_all(&output_dir).context(format!(
+            "Failed to create output directory {}",
+            output_dir.display()
+        ))?;
+        for (share, path) in shares.iter().zip(&paths) {
+            write_secret_file(path, share.to_json()?.as_bytes(), force)?;
+            println!(
+                "Share {} written to: {}",
+                share.content.index,
+                path.display()
+            );
+        }
+    }
+
+    // Armored shares are printed to stdout, so the summary goes to stderr to keep them separable
+    let summary = format!(
+        "Split the {algorithm} key {} into {count} shares, any {threshold} of which reconstruct it\n\
+         Hand each share to a different custodian and delete it from this machine",
+        format_fingerprint(&key_fingerprint)
+    );
+    if args.get_flag(ARMOR_ID) {
+        eprintln!("{summary}");
+    } else {
+        println!("{summary}");
+    }
+    Ok(())
+}
+
+/// Reconstructs an own secret key from shares written by `rust-seal keys split` and restores it to the keys directory,
+/// after checking it against the stored public key
+pub fn keys_combine_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let shares = args
+        .get_many::<PathBuf>(SHARE_PATH_ID)
+        .context("Missing required argument: shares")?
+        .map(|path| {
+            let content = Zeroizing::new(
+                read_to_string(path).context(format!("Failed to read share {}", path.display()))?,
+            );
+            KeyShare::parse(&content).context(format!("Invalid share {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let secret_key = KeyShare::combine(&shares)?;
+
+    let content = &shares[0].content;
+    let public_key = match content.key_type {
+        KeyType::Signature => config
+            .get_signature_public_key(&convert_str_to_sig_alg(&content.algorithm)?)?
+            .into_vec(),
+        KeyType::Kem => config
+            .get_kem_public_key(&convert_str_to_kem_alg(&content.algorithm)?)?
+            .into_vec(),
+    };
+    ensure!(
+        fingerprint(&content.algorithm, &public_key) == content.fingerprint,
+        "The shares belong to the {} key {}, which is not your current {} key",
+        content.algorithm,
+        format_fingerprint(&content.fingerprint),
+        content.algorithm
+    );
+
+    let force = args.get_flag(FORCE_ID);
+    let sec_key_path = match content.key_type {
+        KeyType::Signature => config.restore_signature_secret_key(
+            &convert_str_to_sig_alg(&content.algorithm)?,
+            secret_key.as_ref(),
+            force,
+        ),
+        KeyType::Kem => config.restore_kem_secret_key(
+            &convert_str_to_kem_alg(&content.algorithm)?,
+            secret_key.as_ref(),
+            force,
+        ),
+    }
+    .context("Failed to restore the secret key")?;
+
+    println!(
+        "Restored the {} key {} from {} shares to: {}",
+        content.algorithm,
+        format_fingerprint(&content.fingerprint),
+        shares.len(),
+        sec_key_path.display()
+    );
+    Ok(())
+}
+
+/// Recreates an own KEM keypair from the recovery mnemonic of `rust-seal init kem --mnemonic`, read from stdin.
+/// A configured key has its secret key restored after checking that the mnemonic recreates its public key, or both
+/// key files if they were lost; otherwise the keypair is added as after `rust-seal init kem`.
+/// ML-DSA keys cannot be recovered, since liboqs cannot expand their seed
+pub fn keys_recover_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
+    let kem = parse_kem_algorithm_arg(args, config)?;
+    let algorithm = kem.algorithm();
+    let force = args.get_flag(FORCE_ID);
+
+    let mnemonic = read_mnemonic()?;
+    let seed = RecoverySeed::from_mnemonic(&mnemonic)?.derive_kem_seed(&kem)?;
+
+    if config
+        .kem_key_algorithms()
+        .any(|configured| configured == algorithm)
+    {
+        let (public_key, secret_key) = kem
+            .keypair_derand(
+                kem.keypair_seed_from_bytes(seed.as_ref())
+                    .context("Invalid seed")?,
+            )
+            .context("Failed to generate keypair for KEM algorithm")?;
+        let secret_key = Secret::new(secret_key);
+        let sec_key_path = config
+            .restore_kem_keys(&kem, public_key.as_ref(), secret_key.as_ref(), force)
+            .context("Failed to restore the key")?;
+        println!(
+            "Restored the {algorithm} key {} to: {}",
+            format_fingerprint(&fingerprint(&algorithm.to_string(), public_key.as_ref())),
+            sec_key_path.display()
+        );
+    } else {
+        let expires = args.get_one::<DateTime<Utc>>(EXPIRES_ID).copied();
+        let (public_key, _) = config
+            .add_kem_algorithm(&kem, Some(seed.as_ref()), expires, force)
+            .context("Failed to add KEM algorithm to configuration")?;
+        println!(
+            "Recovered the {algorithm} key {}",
+            format_fingerprint(&fingerprint(&algorithm.to_string(), public_key.as_ref()))
+        );
+    }
+    Ok(())
+}
+
+/// Reads the mnemonic from stdin, prompting for it on a terminal
+fn read_mnemonic() -> Result<Zeroizing<String>> {
+    let mut mnemonic = Zeroizing::new(String::new());
+    if stdin().is_terminal() {
+        eprintln!("Enter the words of the recovery mnemonic:");
+        stdin().read_line(&mut mnemonic)
+    } else {
+        stdin().read_to_string(&mut mnemonic)
+    }
+    .context("Failed to read the recovery mnemonic")?;
+    Ok(mnemonic)
+}
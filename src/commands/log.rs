@@ -0,0 +1,140 @@
+use std::{
+    fs::read_to_string,
+    net::TcpListener,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, ensure};
+use clap::ArgMatches;
+use tracing::warn;
+
+use crate::{
+    Config,
+    cli::{FORCE_ID, LISTEN_ID, LOG_DIR_ID, LOG_ID, NEW_HEAD_ID, OLD_HEAD_ID, OUTPUT_PATH_ID},
+    config::Setting,
+    cryptography::fingerprint::format_fingerprint,
+    transparency::{LogClient, SignedTreeHead, TransparencyLog, serve},
+    util::{parse_path_arg, parse_signature_algorithm_arg, write_file},
+};
+
+/// Creates a transparency log with a new key in the given directory
+pub fn log_init_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let dir = args
+        .get_one::<PathBuf>(LOG_DIR_ID)
+        .context("Missing required argument: log directory")?;
+    let signature = parse_signature_algorithm_arg(args, config)?;
+
+    let log = TransparencyLog::init(dir, &signature)?;
+    println!("Transparency log created in: {}", dir.display());
+    println!("Log key: {}", format_fingerprint(&log.fingerprint()));
+    println!(
+        "Verifiers trust the log with `rust-seal config set {} {}`",
+        Setting::TransparencyTrustedLogs.key(),
+        log.fingerprint()
+    );
+    Ok(())
+}
+
+/// Prints the current tree head of a log and saves it with `--output`, so a later consistency check can start from it
+pub fn log_head_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let log = open_log_arg(args)?;
+    let tree_head = log.tree_head()?;
+    tree_head
+        .verify()
+        .context("The log sent an invalid tree head")?;
+    warn_if_untrusted(&tree_head, config);
+
+    println!(
+        "Log key: {}",
+        format_fingerprint(&tree_head.log_fingerprint())
+    );
+    println!("Tree size: {}", tree_head.tree_size);
+    println!("Root hash: {}", hex::encode(tree_head.root_hash));
+    println!(
+        "Signed at: {}",
+        tree_head.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    if let Some(output_path) = args.get_one::<PathBuf>(OUTPUT_PATH_ID) {
+        let json =
+            serde_json::to_string_pretty(&tree_head).context("Failed to serialize tree head")?;
+        write_file(output_path, json, args.get_flag(FORCE_ID))
+            .context("Failed to save the tree head")?;
+        println!("Tree head saved to: {}", output_path.display());
+    }
+    Ok(())
+}
+
+/// Checks that the log only appended entries between a saved tree head and a later one, or its current tree head
+pub fn log_consistency_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let log = open_log_arg(args)?;
+    let old = read_tree_head(&parse_path_arg(args, OLD_HEAD_ID)?)?;
+    let new = match args.get_one::<PathBuf>(NEW_HEAD_ID) {
+        Some(_) => read_tree_head(&parse_path_arg(args, NEW_HEAD_ID)?)?,
+        None => log.tree_head()?,
+    };
+    ensure!(
+        old.tree_size <= new.tree_size,
+        "The new tree head has {} entries, but the old one had {}. The log removed entries",
+        new.tree_size,
+        old.tree_size
+    );
+
+    log.consistency_proof(old.tree_size, new.tree_size)?
+        .verify(&old, &new)
+        .context("\x1b[31m Consistency verification failed\x1b[0m")?;
+    warn_if_untrusted(&new, config);
+    println!(
+        "The log only appended entries between the tree of {} entries at {} and the tree of {} entries at {}",
+        old.tree_size,
+        old.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+        new.tree_size,
+        new.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    println!("Consistency \x1b[32mverification succeeded\x1b[0m");
+    Ok(())
+}
+
+/// Serves a log over HTTP until the process is stopped
+pub fn log_serve_command(args: &ArgMatches) -> Result<()> {
+    let dir = args
+        .get_one::<PathBuf>(LOG_DIR_ID)
+        .context("Missing required argument: log directory")?;
+    let address = args
+        .get_one::<String>(LISTEN_ID)
+        .context("Missing required argument: listen address")?;
+
+    let log = TransparencyLog::open(dir)?;
+    let listener = TcpListener::bind(address).context(format!("Failed to listen on {address}"))?;
+    println!(
+        "Serving transparency log {} with key {} at http://{}",
+        dir.display(),
+        format_fingerprint(&log.fingerprint()),
+        listener
+            .local_addr()
+            .context("Failed to read the listen address")?
+    );
+    serve(log, listener)
+}
+
+fn open_log_arg(args: &ArgMatches) -> Result<LogClient> {
+    let location = args
+        .get_one::<String>(LOG_ID)
+        .context("Missing required argument: log")?;
+    LogClient::open(location)
+}
+
+fn read_tree_head(path: &Path) -> Result<SignedTreeHead> {
+    serde_json::from_str(
+        &read_to_string(path).context(format!("Failed to read {}", path.display()))?,
+    )
+    .context(format!("{} is not a tree head", path.display()))
+}
+
+fn warn_if_untrusted(tree_head: &SignedTreeHead, config: &Config) {
+    if let Err(err) = tree_head.verify_trusted(config.trusted_logs()) {
+        warn!(
+            "{err:#}. Trust it with `rust-seal config set {} <FINGERPRINTS>`",
+            Setting::TransparencyTrustedLogs.key()
+        );
+    }
+}
@@ -1,11 +1,47 @@
+mod age;
+mod agent;
+mod algorithms;
+mod attest;
+mod bench;
+mod cert;
+mod config;
+mod contacts;
 mod decrypt_file;
 mod encrypt_file;
 mod init;
+mod keys;
+mod log;
+mod selftest;
 mod sign_file;
 mod verify_signature;
 
+pub use age::{age_identity_command, age_recipient_command};
+pub use agent::{
+    agent_add_command, agent_list_command, agent_remove_command, agent_start_command,
+    agent_stop_command,
+};
+pub use algorithms::algorithms_command;
+pub use attest::{attest_command, verify_attestation_command};
+pub use bench::bench_command;
+pub use cert::{
+    cert_issue_command, cert_request_command, cert_self_sign_command, cert_verify_command,
+};
+pub use config::{
+    config_get_command, config_list_command, config_set_command, config_unset_command,
+};
+pub use contacts::{
+    contacts_fingerprint_command, contacts_import_bundle_command, contacts_import_command,
+    contacts_list_command, contacts_remove_command,
+};
 pub use decrypt_file::decrypt_file_command;
 pub use encrypt_file::encrypt_file_command;
 pub use init::{init_kem, init_sig};
+pub use keys::{
+    keys_certify_command, keys_combine_command, keys_export_command, keys_export_key_command,
+    keys_import_key_command, keys_import_revocation_command, keys_init_primary_command,
+    keys_recover_command, keys_revoke_command, keys_split_command,
+};
+pub use log::{log_consistency_command, log_head_command, log_init_command, log_serve_command};
+pub use selftest::selftest_command;
 pub use sign_file::sign_file_command;
 pub use verify_signature::verify_signature_command;
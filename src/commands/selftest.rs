@@ -0,0 +1,54 @@
+use anyhow::{Context, Result, ensure};
+use clap::ArgMatches;
+
+use crate::{
+    Config,
+    cli::JSON_ID,
+    config::OutputFormat,
+    selftest::{self, Outcome},
+};
+
+pub fn selftest_command(args: &ArgMatches, config: &Config) -> Result<()> {
+    let results = selftest::run_all();
+
+    let output_format = if args.get_flag(JSON_ID) {
+        OutputFormat::Json
+    } else {
+        config.output_format()
+    };
+
+    match output_format {
+        OutputFormat::Text => {
+            for result in &results {
+                match &result.outcome {
+                    Outcome::Passed => {
+                        println!("\x1b[32mPASS\x1b[0m {} {}", result.kind, result.name)
+                    }
+                    Outcome::Failed(reason) => println!(
+                        "\x1b[31mFAIL\x1b[0m {} {}: {reason}",
+                        result.kind, result.name
+                    ),
+                    Outcome::Skipped(reason) => {
+                        println!("SKIP {} {}: {reason}", result.kind, result.name)
+                    }
+                }
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&results).context("Failed to serialize self-test")?
+        ),
+    }
+
+    let failed = results.iter().filter(|result| result.failed()).count();
+    ensure!(
+        failed == 0,
+        "\x1b[31m{failed} of {} self-test checks failed\x1b[0m",
+        results.len()
+    );
+
+    if output_format == OutputFormat::Text {
+        println!("\x1b[32mAll self-test checks passed\x1b[0m");
+    }
+    Ok(())
+}
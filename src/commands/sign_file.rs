@@ -1,60 +1,365 @@
-use std::{fs::write, path::PathBuf};
+use std::{
+    fs::read,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, ensure};
 use clap::ArgMatches;
-use oqs::sig::{PublicKey as SigPublicKey, SecretKey as SigSecretKey, Sig};
+use oqs::sig::{PublicKey as SigPublicKey, Sig};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use x509_cert::Certificate;
 
 use crate::{
     Config,
-    cli::FILE_PATH_ID,
-    cryptography::signature::get_signature_from_file,
-    util::{parse_path_arg, parse_signature_algorithm_arg},
+    agent::SigningKey,
+    cli::{
+        APPEND_ID, COUNTERSIGN_ID, FILE_PATH_ID, FORCE_ID, LOG_ID, NAMESPACE_ID,
+        SIGNATURE_FORMAT_ID, TSA_ID,
+    },
+    cryptography::{
+        cose::sign_cose,
+        fingerprint::{fingerprint, fingerprints_match, format_fingerprint},
+        jose::sign_jws,
+        signature::{SignatureFormat, namespaced_message},
+        signature_bundle::{BundleSignature, SignatureBundle, describe_namespace},
+        timestamp::TimestampToken,
+    },
+    transparency::{LogClient, LogEntry, LogReceipt},
+    util::{ensure_overwritable, parse_path_arg, parse_signature_algorithm_arg, write_file},
 };
 
 pub fn sign_file_command(args: &ArgMatches, config: &mut Config) -> Result<()> {
-    let signature = parse_signature_algorithm_arg(args)?;
+    let signature = parse_signature_algorithm_arg(args, config)?;
     let file_path = parse_path_arg(args, FILE_PATH_ID)?;
+    let append = args.get_one::<PathBuf>(APPEND_ID);
+    let countersign = args.get_one::<String>(COUNTERSIGN_ID).map(String::as_str);
+    let tsa = args.get_one::<String>(TSA_ID).map(String::as_str);
+    let log = args.get_one::<String>(LOG_ID).map(String::as_str);
+    let bundle_only = append.is_some() || countersign.is_some() || tsa.is_some() || log.is_some();
+    let format = args
+        .get_one::<SignatureFormat>(SIGNATURE_FORMAT_ID)
+        .copied()
+        .unwrap_or(if bundle_only {
+            SignatureFormat::Bundle
+        } else {
+            SignatureFormat::Raw
+        });
+    format.ensure_supports(signature.algorithm())?;
+    ensure!(
+        !bundle_only || format == SignatureFormat::Bundle,
+        "--append, --countersign, --tsa and --log are only supported with --format bundle"
+    );
+    let namespace = args.get_one::<String>(NAMESPACE_ID).map(String::as_str);
+    ensure!(
+        namespace.is_none() || matches!(format, SignatureFormat::Raw | SignatureFormat::Bundle),
+        "Namespaces are only supported with --format raw or bundle"
+    );
+    let force = args.get_flag(FORCE_ID);
 
-    let (public_key, secret_key) = match config.get_signature_keys(&signature.algorithm()) {
-        Ok(keys) => keys,
+    let (public_key, secret_key) = match config.signing_key(&signature.algorithm()) {
+        Ok(keys) => {
+            config.ensure_signature_key_valid(&signature.algorithm())?;
+            keys
+        }
         Err(_) => {
             println!(
                 "No keys found for signature algorithm '{}'. Generating new keys...",
                 signature.algorithm()
             );
-            config
-                .add_signature_algorithm(&signature)
-                .context("Failed to add signature algorithm")?
+            let (public_key, secret_key) = config
+                .add_signature_algorithm(&signature, None, force)
+                .context("Failed to add signature algorithm")?;
+            (public_key, SigningKey::Local(secret_key))
         }
     };
 
-    sign_file(&file_path, &signature, &public_key, &secret_key).context("Failed to sign file")?;
+    match format {
+        SignatureFormat::Raw => sign_file(
+            &file_path,
+            namespace,
+            &signature,
+            &public_key,
+            &secret_key,
+            force,
+        ),
+        SignatureFormat::Bundle => sign_file_bundle(
+            &file_path,
+            &BundleOptions {
+                append: append.map(PathBuf::as_path),
+                countersign,
+                tsa,
+                trusted_tsas: match tsa {
+                    Some(_) => config.tsa_certificates()?,
+                    None => Vec::new(),
+                },
+                log,
+                trusted_logs: config.trusted_logs(),
+                force,
+            },
+            namespace,
+            &signature,
+            &public_key,
+            &secret_key,
+        ),
+        SignatureFormat::Jws | SignatureFormat::Cose => sign_file_enveloped(
+            &file_path,
+            format,
+            &signature,
+            &public_key,
+            &secret_key,
+            force,
+        ),
+    }
+    .context("Failed to sign file")?;
 
     Ok(())
 }
 
+/// Writes a JWS or COSE_Sign1 that carries the file content as payload
+fn sign_file_enveloped(
+    file_path: &PathBuf,
+    format: SignatureFormat,
+    signature: &Sig,
+    public_key: &SigPublicKey,
+    secret_key: &SigningKey,
+    force: bool,
+) -> Result<()> {
+    let signed_path = file_path.with_extension(format.extension());
+    ensure_overwritable(&signed_path, force)?;
+
+    let file_content = read(file_path).context("Failed to read file content for signature")?;
+    let signed = match format {
+        SignatureFormat::Jws => {
+            sign_jws(&file_content, signature, public_key.as_ref(), secret_key)?.into_bytes()
+        }
+        SignatureFormat::Cose => {
+            sign_cose(&file_content, signature, public_key.as_ref(), secret_key)?
+        }
+        SignatureFormat::Raw | SignatureFormat::Bundle => {
+            unreachable!("Raw signatures do not carry the payload")
+        }
+    };
+
+    write_file(&signed_path, signed, force).context("Failed to write signature to file")?;
+    println!(
+        "Signed {} file created successfully: {}",
+        format.to_string().to_uppercase(),
+        signed_path.display()
+    );
+
+    Ok(())
+}
+
+/// Writes a detached signature of the file, or of the file in `namespace`
 fn sign_file(
     file_path: &PathBuf,
+    namespace: Option<&str>,
     signature: &Sig,
     public_key: &SigPublicKey,
-    secret_key: &SigSecretKey,
+    secret_key: &SigningKey,
+    force: bool,
 ) -> Result<()> {
-    let file_signature = get_signature_from_file(file_path, signature, secret_key)
-        .context("Failed to create signature from file")?;
+    let signature_path = file_path.with_extension("sig");
+    let public_key_path = file_path.with_extension("pub");
+    ensure_overwritable(&signature_path, force)?;
+    ensure_overwritable(&public_key_path, force)?;
+
+    let file_content = read(file_path).context("Failed to read file content for signature")?;
+    let file_signature = match namespace {
+        Some(namespace) => {
+            secret_key.sign(signature, &namespaced_message(namespace, &file_content)?)
+        }
+        None => secret_key.sign(signature, &file_content),
+    }
+    .context("Failed to sign file content")?;
 
-    write(file_path.with_extension("sig"), file_signature.as_ref())
+    write_file(&signature_path, file_signature, force)
         .context("Failed to write signature to file")?;
     println!(
         "Signature file created successfully: {}",
-        file_path.with_extension("sig").display()
+        signature_path.display()
     );
 
-    write(file_path.with_extension("pub"), public_key.as_ref())
-        .context("Failed to save public key")?;
+    write_file(&public_key_path, public_key, force).context("Failed to save public key")?;
     println!(
         "Public key created successfully: {}",
-        file_path.with_extension("pub").display()
+        public_key_path.display()
     );
 
     Ok(())
 }
+
+/// Where and how a signature is added to a signature bundle
+struct BundleOptions<'a> {
+    /// Existing bundle to add the signature to, instead of the one next to the file
+    append: Option<&'a Path>,
+    /// Fingerprint of the key whose signature is countersigned instead of signing the file
+    countersign: Option<&'a str>,
+    /// URL of the time-stamping authority that timestamps the signature
+    tsa: Option<&'a str>,
+    /// Certificates of the time-stamping authorities the configuration trusts
+    trusted_tsas: Vec<Certificate>,
+    /// Directory or URL of the transparency log the signature is appended to
+    log: Option<&'a str>,
+    /// Fingerprints of the transparency logs the configuration trusts
+    trusted_logs: &'a [String],
+    /// Overwrite a file at the bundle path that was created while signing
+    force: bool,
+}
+
+/// Adds a signature of the file, or with `countersign` a countersignature of the signature by that key,
+/// to the signature bundle at `append`. Without `append`, the bundle next to the file is used and created if needed.
+/// An earlier signature by the same key over the same content is replaced; all other entries are kept
+fn sign_file_bundle(
+    file_path: &PathBuf,
+    options: &BundleOptions,
+    namespace: Option<&str>,
+    signature: &Sig,
+    public_key: &SigPublicKey,
+    secret_key: &SigningKey,
+) -> Result<()> {
+    let bundle_path = options
+        .append
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| file_path.with_extension(SignatureFormat::Bundle.extension()));
+    let existing = options.append.is_some() || bundle_path.exists();
+    let mut bundle = if options.append.is_some() {
+        SignatureBundle::load(&bundle_path).context(
+            "Only signature bundles can be appended to. A raw signature does not name its key; sign with --format bundle instead",
+        )?
+    } else if existing {
+        SignatureBundle::load(&bundle_path)?
+    } else {
+        SignatureBundle::new(namespace)
+    };
+    ensure!(
+        bundle.namespace.as_deref() == namespace,
+        "The signature bundle {} was made in {}, but you sign in {}",
+        bundle_path.display(),
+        describe_namespace(bundle.namespace.as_deref()),
+        describe_namespace(namespace)
+    );
+
+    let file_content = read(file_path).context("Failed to read file content for signature")?;
+    let message = bundle.message(&file_content)?;
+    let countersigned = options
+        .countersign
+        .map(|fingerprint| bundle.position_by_fingerprint(fingerprint))
+        .transpose()?;
+    let (signed, countersigns) = match countersigned {
+        Some(index) => {
+            let countersigned = &bundle.signatures[index];
+            if let Err(err) = bundle.verify_entries(&file_content)?.swap_remove(index) {
+                return Err(err.context(format!(
+                    "Refusing to countersign the invalid signature by key {}",
+                    format_fingerprint(&countersigned.fingerprint())
+                )));
+            }
+            (
+                countersigned.countersignature_message(&message),
+                Some(countersigned.digest()),
+            )
+        }
+        None => (message, None),
+    };
+    let file_signature = secret_key
+        .sign(signature, &signed)
+        .context("Failed to sign file content")?;
+    let timestamp = options
+        .tsa
+        .map(|url| timestamp_signature(url, file_signature.as_ref(), &options.trusted_tsas))
+        .transpose()?;
+    let mut entry = BundleSignature {
+        algorithm: signature.algorithm(),
+        public_key: public_key.as_ref().to_vec(),
+        countersigns,
+        signature: file_signature.into_vec(),
+        timestamp,
+        log: None,
+        signed_at: None,
+    };
+    if let Some(location) = options.log {
+        let log_entry = entry.log_entry(namespace, Sha256::digest(&file_content).into());
+        entry.log = Some(log_signature(location, &log_entry, options.trusted_logs)?);
+    }
+    let replaced = bundle.add(entry);
+
+    // A bundle that was loaded is updated in place; a new one must not replace a file created in the meantime
+    write_file(&bundle_path, bundle.to_json()?, existing || options.force)
+        .context("Failed to write signature bundle to file")?;
+    if let Some(index) = countersigned {
+        println!(
+            "Countersigned the signature by key {} in bundle: {}",
+            format_fingerprint(&bundle.signatures[index].fingerprint()),
+            bundle_path.display()
+        );
+    } else if replaced {
+        println!(
+            "Replaced your signature in bundle: {}",
+            bundle_path.display()
+        );
+    } else {
+        println!("Added your signature to bundle: {}", bundle_path.display());
+    }
+    println!(
+        "Your key: {}",
+        format_fingerprint(&fingerprint(
+            &signature.algorithm().to_string(),
+            public_key.as_ref()
+        ))
+    );
+    println!("Signatures in the bundle: {}", bundle.signatures.len());
+
+    Ok(())
+}
+
+/// Requests a timestamp of `file_signature` from the time-stamping authority at `url`.
+/// The token is checked against the trusted authorities, if any are configured, since verifiers ignore untrusted ones
+fn timestamp_signature(
+    url: &str,
+    file_signature: &[u8],
+    trusted_tsas: &[Certificate],
+) -> Result<TimestampToken> {
+    let (token, time) = TimestampToken::request(url, file_signature)
+        .context(format!("Failed to timestamp the signature with {url}"))?;
+    if trusted_tsas.is_empty() {
+        warn!(
+            "No time-stamping authority is trusted, so the timestamp by {url} is not checked. Trust its certificate with `rust-seal config set timestamping.tsa_certificates <PATHS>`"
+        );
+    } else if let Err(err) = token.verify(file_signature, trusted_tsas) {
+        warn!(
+            "The timestamp by {url} is not trusted and will be ignored by verifiers that trust the same authorities: {err:#}"
+        );
+    }
+    println!(
+        "Signature timestamped by {url} at {}",
+        time.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    Ok(token)
+}
+
+/// Appends the signature to the transparency log at `location` and returns the receipt.
+/// Warns if the log is not trusted, since `verify --require-log` ignores its receipts
+fn log_signature(location: &str, entry: &LogEntry, trusted_logs: &[String]) -> Result<LogReceipt> {
+    let receipt = LogClient::open(location)
+        .and_then(|log| log.append(entry))
+        .context(format!(
+            "Failed to append the signature to the transparency log {location}"
+        ))?;
+    let log_fingerprint = receipt.tree_head.log_fingerprint();
+    if !trusted_logs
+        .iter()
+        .any(|trusted| fingerprints_match(trusted, &log_fingerprint))
+    {
+        warn!(
+            "The transparency log {location} with key {} is not trusted, so verifiers that trust the same logs will ignore its receipt. Trust it with `rust-seal config set transparency.trusted_logs <FINGERPRINTS>`",
+            format_fingerprint(&log_fingerprint)
+        );
+    }
+    println!(
+        "Signature appended to transparency log {location} as entry {} of {}",
+        receipt.index, receipt.tree_head.tree_size
+    );
+    Ok(receipt)
+}
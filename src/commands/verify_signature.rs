@@ -1,35 +1,397 @@
-use anyhow::{Context, Result};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use chrono::{DateTime, Utc};
 use clap::ArgMatches;
+use oqs::sig::Sig;
+use tracing::warn;
 
 use crate::{
-    cli::{FILE_PATH_ID, PUBLIC_KEY_PATH_ID, SIGNATURE_PATH_ID},
-    cryptography::signature::verify_file_with_signature,
+    Config,
+    cli::{
+        ALLOW_UNTRUSTED_KEY_ID, ALLOWED_SIGNERS_ID, CHAIN_ID, FILE_PATH_ID, IDENTITY_ID,
+        IGNORE_POLICY_ID, NAMESPACE_ID, PUBLIC_KEY_PATH_ID, REQUIRE_LOG_ID, SIGNATURE_FORMAT_ID,
+        SIGNATURE_PATH_ID, SIGNER_ID, SIGNERS_ID, THRESHOLD_ID, validate_signature_algorithm,
+    },
+    config::{AllowedSigners, Contacts, Setting},
+    cryptography::{
+        cose::verify_cose,
+        fingerprint::format_fingerprint,
+        jose::verify_jws,
+        signature::{SignatureFormat, namespaced_message, verify_file_with_signature},
+        signature_bundle::{SignatureBundle, TrustedSigner, describe_namespace},
+    },
     util::{parse_path_arg, parse_signature_algorithm_arg},
 };
 
-pub fn verify_signature_command(args: &ArgMatches) -> Result<()> {
-    let signature = parse_signature_algorithm_arg(args)?;
+pub fn verify_signature_command(args: &ArgMatches, config: &Config) -> Result<()> {
     let file_path = parse_path_arg(args, FILE_PATH_ID)?;
-    let signature_path =
-        parse_path_arg(args, SIGNATURE_PATH_ID).unwrap_or_else(|_| file_path.with_extension("sig"));
-    let public_key_path = parse_path_arg(args, PUBLIC_KEY_PATH_ID)
-        .unwrap_or_else(|_| file_path.with_extension("pub"));
+    let require_log = args.get_flag(REQUIRE_LOG_ID);
+    let bundle_only = args.contains_id(SIGNERS_ID) || args.contains_id(CHAIN_ID) || require_log;
+    let format = args
+        .get_one::<SignatureFormat>(SIGNATURE_FORMAT_ID)
+        .copied()
+        .unwrap_or(if bundle_only {
+            SignatureFormat::Bundle
+        } else {
+            SignatureFormat::Raw
+        });
+    let namespace = args.get_one::<String>(NAMESPACE_ID);
+    ensure!(
+        namespace.is_none() || matches!(format, SignatureFormat::Raw | SignatureFormat::Bundle),
+        "Namespaces are only supported with --format raw or bundle"
+    );
+    ensure!(
+        !bundle_only || format == SignatureFormat::Bundle,
+        "--signers, --chain and --require-log are only supported with --format bundle"
+    );
+    ensure!(
+        !require_log || !config.trusted_logs().is_empty(),
+        "--require-log needs a trusted transparency log. Trust one with `rust-seal config set {} <FINGERPRINTS>`",
+        Setting::TransparencyTrustedLogs.key()
+    );
+    let signature_path = parse_path_arg(args, SIGNATURE_PATH_ID)
+        .unwrap_or_else(|_| file_path.with_extension(format.extension()));
 
     let file_content =
         std::fs::read(file_path).context("Failed to read file content for verification")?;
+    if format == SignatureFormat::Bundle {
+        let mut bundle = SignatureBundle::load(&signature_path)?;
+        bundle.retain_allowed_algorithms(|sig| {
+            validate_signature_algorithm(sig, config.policy(), args.get_flag(IGNORE_POLICY_ID))
+        });
+        bundle.verify_timestamps(&config.tsa_certificates()?);
+        if require_log {
+            bundle.require_log_receipts(&file_content, config.trusted_logs());
+        }
+        for entry in &bundle.signatures {
+            if let Some(signed_at) = entry.signed_at {
+                println!(
+                    "Signature by key {} was timestamped at {}",
+                    format_fingerprint(&entry.fingerprint()),
+                    signed_at.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+            }
+            if require_log && let Some(receipt) = &entry.log {
+                println!(
+                    "Signature by key {} is entry {} of the transparency log with key {}",
+                    format_fingerprint(&entry.fingerprint()),
+                    receipt.index,
+                    format_fingerprint(&receipt.tree_head.log_fingerprint())
+                );
+            }
+        }
+        return verify_bundle(args, config, &file_content, &bundle);
+    }
+
+    let signature = parse_signature_algorithm_arg(args, config)?;
     let sig_content = std::fs::read(signature_path)
         .context("Failed to read signature content for verification")?;
-    let pub_key_content = std::fs::read(public_key_path)
-        .context("Failed to read public key content for verification")?;
+    let signed_content = match namespace {
+        Some(namespace) => namespaced_message(namespace, &file_content)?,
+        None => file_content,
+    };
+
+    let public_keys = match args.get_one::<PathBuf>(ALLOWED_SIGNERS_ID) {
+        Some(path) => allowed_signer_public_keys(args, path, &signature, Utc::now())?,
+        None => vec![signer_public_key(args, config, &signature, Utc::now())?],
+    };
 
+    // An identity may have several keys of the algorithm in the allowed signers file
+    let mut result = Err(anyhow!("No public key to verify with"));
+    for pub_key_content in &public_keys {
+        result = verify_content(
+            format,
+            &signed_content,
+            &sig_content,
+            &signature,
+            pub_key_content,
+        );
+        if result.is_ok() {
+            break;
+        }
+    }
+    result.context("\x1b[31m Signature verification failed\x1b[0m")?;
+
+    if let (Some(identity), Some(namespace)) = (args.get_one::<String>(IDENTITY_ID), namespace) {
+        println!("Good signature for {identity} in namespace {namespace}");
+    }
+    println!("Signature \x1b[32mverification succeeded\x1b[0m");
+
+    Ok(())
+}
+
+fn verify_content(
+    format: SignatureFormat,
+    file_content: &[u8],
+    sig_content: &[u8],
+    signature: &Sig,
+    pub_key_content: &[u8],
+) -> Result<()> {
     let public_key = signature
-        .public_key_from_bytes(&pub_key_content)
+        .public_key_from_bytes(pub_key_content)
         .context("Provided public key is not valid")?;
 
-    verify_file_with_signature(&file_content, &sig_content, &signature, public_key)
-        .context("\x1b[31m Signature verification failed\x1b[0m")?;
+    match format {
+        SignatureFormat::Raw => {
+            verify_file_with_signature(file_content, sig_content, signature, public_key)
+        }
+        SignatureFormat::Jws => std::str::from_utf8(sig_content)
+            .context("JWS is not valid UTF-8")
+            .and_then(|token| verify_jws(token, signature, public_key))
+            .and_then(|payload| ensure_payload_is_file(&payload, file_content)),
+        SignatureFormat::Cose => verify_cose(sig_content, signature, public_key)
+            .and_then(|payload| ensure_payload_is_file(&payload, file_content)),
+        SignatureFormat::Bundle => unreachable!("Signature bundles are verified by verify_bundle"),
+    }
+}
 
-    println!("Signature \x1b[32mverification succeeded\x1b[0m");
+/// Verifies a signature bundle, which passes if at least `--threshold` of the `--signers` have a valid signature in it
+/// and the `--chain` of approvals is complete. Without `--threshold`, all signers must have signed.
+/// Without `--signers` and `--chain`, the key is chosen as for a single signature.
+/// Keys must have been valid when the entries were signed, see [`SignatureBundle::verify_timestamps`]
+fn verify_bundle(
+    args: &ArgMatches,
+    config: &Config,
+    file_content: &[u8],
+    bundle: &SignatureBundle,
+) -> Result<()> {
+    let namespace = args.get_one::<String>(NAMESPACE_ID).map(String::as_str);
+    ensure!(
+        bundle.namespace.as_deref() == namespace,
+        "The signature bundle was made in {}, but {} was expected",
+        describe_namespace(bundle.namespace.as_deref()),
+        describe_namespace(namespace)
+    );
+
+    let signers: Option<BTreeSet<&str>> = args
+        .get_many::<String>(SIGNERS_ID)
+        .map(|names| names.map(String::as_str).collect());
+    let chain: Option<Vec<&str>> = args
+        .get_many::<String>(CHAIN_ID)
+        .map(|names| names.map(String::as_str).collect());
+    if signers.is_none() && chain.is_none() {
+        let signature = parse_signature_algorithm_arg(args, config)?;
+        let name = args
+            .get_one::<String>(IDENTITY_ID)
+            .or(args.get_one::<String>(SIGNER_ID))
+            .cloned()
+            .unwrap_or_else(|| "the verifying key".to_string());
+        let now = Utc::now();
+        let mut trusted = Vec::new();
+        let mut reason = None;
+        for signed_at in bundle.signing_times() {
+            let time = signed_at.unwrap_or(now);
+            let public_keys = match args.get_one::<PathBuf>(ALLOWED_SIGNERS_ID) {
+                Some(path) => allowed_signer_public_keys(args, path, &signature, time),
+                None => signer_public_key(args, config, &signature, time)
+                    .map(|public_key| vec![public_key]),
+            };
+            match public_keys {
+                Ok(public_keys) => {
+                    trusted.extend(public_keys.into_iter().map(|public_key| TrustedSigner {
+                        name: name.clone(),
+                        algorithm: signature.algorithm(),
+                        public_key,
+                        signed_at,
+                    }))
+                }
+                Err(err) => reason = Some(err),
+            }
+        }
+        if let (Some(reason), true) = (reason, trusted.is_empty()) {
+            return Err(reason);
+        }
+        return verify_threshold(bundle, file_content, &trusted, 1, 1);
+    }
+
+    let names: BTreeSet<&str> = signers
+        .iter()
+        .flatten()
+        .chain(chain.iter().flatten())
+        .copied()
+        .collect();
+    let trusted = trusted_signers(args, config, &names, bundle)?;
+
+    if let Some(chain) = &chain {
+        bundle
+            .verify_chain(file_content, chain, &trusted)
+            .context("\x1b[31m Signature verification failed\x1b[0m")?;
+        println!("Approval chain verified: {}", chain.join(" -> "));
+    }
+    match &signers {
+        Some(signers) => {
+            let required = args
+                .get_one::<u8>(THRESHOLD_ID)
+                .map_or(signers.len(), |threshold| usize::from(*threshold));
+            ensure!(
+                required <= signers.len(),
+                "The threshold of {required} is larger than the {} signers",
+                signers.len()
+            );
+            let trusted: Vec<TrustedSigner> = trusted
+                .into_iter()
+                .filter(|signer| signers.contains(signer.name.as_str()))
+                .collect();
+            verify_threshold(bundle, file_content, &trusted, required, signers.len())
+        }
+        None => {
+            println!("Signature \x1b[32mverification succeeded\x1b[0m");
+            Ok(())
+        }
+    }
+}
+
+/// Fails unless at least `required` of the `candidates` trusted signers have a valid signature in the bundle
+fn verify_threshold(
+    bundle: &SignatureBundle,
+    file_content: &[u8],
+    trusted: &[TrustedSigner],
+    required: usize,
+    candidates: usize,
+) -> Result<()> {
+    let signers = bundle.valid_signers(file_content, trusted)?;
+    if !signers.is_empty() {
+        println!(
+            "Valid signatures from: {}",
+            signers.iter().copied().collect::<Vec<_>>().join(", ")
+        );
+    }
+    ensure!(
+        signers.len() >= required,
+        "\x1b[31m Signature verification failed\x1b[0m: {} of {candidates} signers signed, but {required} are required",
+        signers.len()
+    );
+    println!(
+        "Signature \x1b[32mverification succeeded\x1b[0m: {} of {candidates} signers signed, {required} required",
+        signers.len()
+    );
+    Ok(())
+}
+
+/// Returns the keys of the `names`, from the allowed signers file if given and from the contacts otherwise.
+/// Only keys of the algorithms used in the bundle are looked up, once for every time its entries were signed at
+fn trusted_signers(
+    args: &ArgMatches,
+    config: &Config,
+    names: &BTreeSet<&str>,
+    bundle: &SignatureBundle,
+) -> Result<Vec<TrustedSigner>> {
+    let mut lookups = Vec::new();
+    for entry in &bundle.signatures {
+        if !lookups.contains(&(entry.algorithm, entry.signed_at)) {
+            lookups.push((entry.algorithm, entry.signed_at));
+        }
+    }
+
+    let allowed_signers = args
+        .get_one::<PathBuf>(ALLOWED_SIGNERS_ID)
+        .map(|path| AllowedSigners::load(path))
+        .transpose()?;
+    let contacts = match allowed_signers {
+        Some(_) => None,
+        None => Some(Contacts::load(config.home()).context("Failed to load contacts")?),
+    };
+
+    let now = Utc::now();
+    let mut trusted = Vec::new();
+    for name in names {
+        let mut reason = None;
+        let before = trusted.len();
+        for (algorithm, signed_at) in &lookups {
+            let time = signed_at.unwrap_or(now);
+            let public_keys = match (&allowed_signers, &contacts) {
+                (Some(allowed_signers), _) => {
+                    let namespace = args
+                        .get_one::<String>(NAMESPACE_ID)
+                        .context("Missing required argument: namespace")?;
+                    allowed_signers.public_keys(name, namespace, *algorithm, time)
+                }
+                (None, Some(contacts)) => contacts
+                    .signature_public_key_at(name, *algorithm, time)
+                    .map(|public_key| vec![public_key]),
+                (None, None) => unreachable!("Contacts are loaded without an allowed signers file"),
+            };
+            match public_keys {
+                Ok(public_keys) => {
+                    trusted.extend(public_keys.into_iter().map(|public_key| TrustedSigner {
+                        name: name.to_string(),
+                        algorithm: *algorithm,
+                        public_key: public_key.to_vec(),
+                        signed_at: *signed_at,
+                    }))
+                }
+                Err(err) => reason = Some(err),
+            }
+        }
+        if let (Some(reason), true) = (reason, trusted.len() == before) {
+            warn!("{name} has no trusted key of the algorithms in the bundle: {reason:#}");
+        }
+    }
+    Ok(trusted)
+}
 
+/// A valid JWS or COSE_Sign1 only vouches for the file if it carries the file content
+fn ensure_payload_is_file(payload: &[u8], file_content: &[u8]) -> Result<()> {
+    ensure!(
+        payload == file_content,
+        "The signed payload differs from the file content"
+    );
     Ok(())
 }
+
+/// Returns the public key of the `--signer` contact, if it was valid at `time`.
+/// A key file given with `--pub-path` is only used with `--allow-untrusted-key`; without either, the own configured key is used.
+pub(super) fn signer_public_key(
+    args: &ArgMatches,
+    config: &Config,
+    signature: &Sig,
+    time: DateTime<Utc>,
+) -> Result<Vec<u8>> {
+    if let Some(signer) = args.get_one::<String>(SIGNER_ID) {
+        let contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+        return Ok(contacts
+            .signature_public_key_at(signer, signature.algorithm(), time)?
+            .to_vec());
+    }
+
+    if args.contains_id(PUBLIC_KEY_PATH_ID) {
+        ensure!(
+            args.get_flag(ALLOW_UNTRUSTED_KEY_ID),
+            "Refusing to verify with a public key file that is not in the contacts. Import it with `rust-seal contacts import` and pass --signer, or pass --allow-untrusted-key"
+        );
+        let public_key_path = parse_path_arg(args, PUBLIC_KEY_PATH_ID)?;
+        return std::fs::read(public_key_path)
+            .context("Failed to read public key content for verification");
+    }
+
+    config.ensure_signature_key_valid_at(&signature.algorithm(), time)?;
+    Ok(config
+        .get_signature_public_key(&signature.algorithm())?
+        .into_vec())
+}
+
+/// Returns the keys the `--identity` could sign with in the `--namespace` at `time`, according to the allowed signers file
+fn allowed_signer_public_keys(
+    args: &ArgMatches,
+    path: &Path,
+    signature: &Sig,
+    time: DateTime<Utc>,
+) -> Result<Vec<Vec<u8>>> {
+    let identity = args
+        .get_one::<String>(IDENTITY_ID)
+        .context("Missing required argument: identity")?;
+    let namespace = args
+        .get_one::<String>(NAMESPACE_ID)
+        .context("Missing required argument: namespace")?;
+
+    let allowed_signers = AllowedSigners::load(path)?;
+    Ok(allowed_signers
+        .public_keys(identity, namespace, signature.algorithm(), time)?
+        .into_iter()
+        .map(<[u8]>::to_vec)
+        .collect())
+}
@@ -0,0 +1,353 @@
+use std::{fs::read_to_string, path::Path};
+
+use anyhow::{Context, Result, bail, ensure};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, Sig};
+
+use super::policy::matches_pattern;
+use crate::oqs::convert_str_to_sig_alg;
+
+/// Keys allowed to sign for a principal, in the format of the OpenSSH `allowed_signers` file.
+///
+/// Each line is `<principals> [options] <algorithm> <base64 public key>`, for example
+/// `alice@example.com,alice@example.org namespaces="file,git" ML-DSA-65 AAAA...`.
+/// Principals and namespaces are comma separated patterns with the wildcards `*` and `?`; a pattern starting with `!` excludes.
+/// The options `namespaces`, `valid-after` and `valid-before` are supported. Times are `YYYYMMDD[HHMM[SS]]` in UTC.
+#[derive(Debug, Default)]
+pub struct AllowedSigners {
+    signers: Vec<AllowedSigner>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AllowedSigner {
+    principals: Vec<String>,
+    namespaces: Option<Vec<String>>,
+    valid_after: Option<DateTime<Utc>>,
+    valid_before: Option<DateTime<Utc>>,
+    algorithm: SigAlgorithmVariant,
+    public_key: Vec<u8>,
+}
+
+impl AllowedSigners {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = read_to_string(path).context(format!(
+            "Failed to read allowed signers file: {}",
+            path.display()
+        ))?;
+        content.parse().context(format!(
+            "Failed to parse allowed signers file: {}",
+            path.display()
+        ))
+    }
+
+    /// Returns the public keys `identity` may sign with in `namespace` at `time`.
+    /// Fails with the reason if there are none
+    pub fn public_keys(
+        &self,
+        identity: &str,
+        namespace: &str,
+        algorithm: SigAlgorithmVariant,
+        time: DateTime<Utc>,
+    ) -> Result<Vec<&[u8]>> {
+        let candidates: Vec<&AllowedSigner> = self
+            .signers
+            .iter()
+            .filter(|signer| {
+                signer.algorithm == algorithm && matches_list(&signer.principals, identity)
+            })
+            .collect();
+        ensure!(
+            !candidates.is_empty(),
+            "{identity} is not an allowed signer with an {algorithm} key"
+        );
+
+        let mut reason = None;
+        let mut keys = Vec::new();
+        for signer in candidates {
+            match signer.check(namespace, time) {
+                Ok(()) => keys.push(signer.public_key.as_slice()),
+                Err(err) => {
+                    reason.get_or_insert(err);
+                }
+            }
+        }
+
+        match reason {
+            Some(reason) if keys.is_empty() => Err(reason.context(format!(
+                "No {algorithm} key of {identity} may sign in namespace {namespace}"
+            ))),
+            _ => Ok(keys),
+        }
+    }
+}
+
+impl std::str::FromStr for AllowedSigners {
+    type Err = anyhow::Error;
+
+    fn from_str(content: &str) -> Result<Self> {
+        let signers = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(index, line)| {
+                AllowedSigner::parse(line).context(format!("Invalid entry in line {}", index + 1))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { signers })
+    }
+}
+
+impl AllowedSigner {
+    fn parse(line: &str) -> Result<Self> {
+        let tokens = split_outside_quotes(line.trim(), |c| c.is_ascii_whitespace());
+        let (principals, options, algorithm, public_key) = match tokens.as_slice() {
+            [principals, algorithm, public_key] => (principals, None, algorithm, public_key),
+            [principals, options, algorithm, public_key] => {
+                (principals, Some(options), algorithm, public_key)
+            }
+            _ => bail!("Expected <principals> [options] <algorithm> <public key>"),
+        };
+
+        let algorithm = convert_str_to_sig_alg(algorithm)?;
+        let public_key = STANDARD
+            .decode(public_key)
+            .context("Public key is not base64")?;
+        let signature = Sig::new(algorithm).context(format!("{algorithm} is not enabled"))?;
+        ensure!(
+            signature.public_key_from_bytes(&public_key).is_some(),
+            "Public key is not a valid {algorithm} key"
+        );
+
+        let mut signer = Self {
+            principals: split_list(&unquote(principals)),
+            namespaces: None,
+            valid_after: None,
+            valid_before: None,
+            algorithm,
+            public_key,
+        };
+        for option in options
+            .map(|options| split_outside_quotes(options, |c| c == ','))
+            .unwrap_or_default()
+        {
+            let (name, value) = option.split_once('=').unwrap_or((&option, ""));
+            let value = unquote(value);
+            match name.to_ascii_lowercase().as_str() {
+                "namespaces" => signer.namespaces = Some(split_list(&value)),
+                "valid-after" => signer.valid_after = Some(parse_time(&value)?),
+                "valid-before" => signer.valid_before = Some(parse_time(&value)?),
+                "cert-authority" => bail!("Certificate authorities are not supported"),
+                _ => bail!("Unsupported option: {name}"),
+            }
+        }
+
+        Ok(signer)
+    }
+
+    fn check(&self, namespace: &str, time: DateTime<Utc>) -> Result<()> {
+        if let Some(namespaces) = &self.namespaces {
+            ensure!(
+                matches_list(namespaces, namespace),
+                "The key may only sign in the namespaces {}",
+                namespaces.join(",")
+            );
+        }
+        if let Some(valid_after) = self.valid_after {
+            ensure!(
+                time >= valid_after,
+                "The key is only valid after {valid_after}"
+            );
+        }
+        if let Some(valid_before) = self.valid_before {
+            ensure!(
+                time < valid_before,
+                "The key was only valid before {valid_before}"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A name matches a pattern list if it matches one of its patterns and none of its `!` patterns
+fn matches_list(patterns: &[String], name: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if matches_pattern(negated, name) => return false,
+            Some(_) => {}
+            None => matched |= matches_pattern(pattern, name),
+        }
+    }
+    matched
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Splits at separators that are not enclosed in double quotes
+fn split_outside_quotes(value: &str, is_separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if !quoted && is_separator(c) {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.strip_suffix(['Z', 'z']).unwrap_or(value);
+    let time = match value.len() {
+        8 => NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default()),
+        12 => NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M"),
+        14 => NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M%S"),
+        _ => bail!("Invalid time: {value}. Expected YYYYMMDD[HHMM[SS]]"),
+    }
+    .context(format!(
+        "Invalid time: {value}. Expected YYYYMMDD[HHMM[SS]]"
+    ))?;
+    Ok(time.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed_signers(public_key: &[u8]) -> AllowedSigners {
+        let key = STANDARD.encode(public_key);
+        format!(
+            "# Release signers\n\
+             alice@example.com,*@release.example,!mallory@release.example namespaces=\"file,git\" ML-DSA-44 {key}\n\
+             \n\
+             bob@example.com valid-after=\"20200101\",valid-before=\"20210101Z\" ML-DSA-44 {key}\n"
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_principals_and_namespaces() {
+        let signature = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+        let signers = allowed_signers(public_key.as_ref());
+        let now = Utc::now();
+
+        assert_eq!(
+            signers
+                .public_keys(
+                    "alice@example.com",
+                    "file",
+                    SigAlgorithmVariant::MlDsa44,
+                    now
+                )
+                .unwrap(),
+            [public_key.as_ref()]
+        );
+        assert!(
+            signers
+                .public_keys(
+                    "ci@release.example",
+                    "git",
+                    SigAlgorithmVariant::MlDsa44,
+                    now
+                )
+                .is_ok()
+        );
+        assert!(
+            signers
+                .public_keys(
+                    "mallory@release.example",
+                    "git",
+                    SigAlgorithmVariant::MlDsa44,
+                    now
+                )
+                .is_err()
+        );
+        assert!(
+            signers
+                .public_keys(
+                    "alice@example.com",
+                    "email",
+                    SigAlgorithmVariant::MlDsa44,
+                    now
+                )
+                .is_err()
+        );
+        assert!(
+            signers
+                .public_keys(
+                    "alice@example.com",
+                    "file",
+                    SigAlgorithmVariant::MlDsa65,
+                    now
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validity_window() {
+        let signature = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+        let signers = allowed_signers(public_key.as_ref());
+        let time = |value: &str| parse_time(value).unwrap();
+
+        for (at, valid) in [
+            ("20191231235959", false),
+            ("20200101", true),
+            ("202012312359", true),
+            ("20210101", false),
+        ] {
+            assert_eq!(
+                signers
+                    .public_keys(
+                        "bob@example.com",
+                        "file",
+                        SigAlgorithmVariant::MlDsa44,
+                        time(at)
+                    )
+                    .is_ok(),
+                valid,
+                "{at}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_entries_are_rejected() {
+        assert!("alice ML-DSA-44".parse::<AllowedSigners>().is_err());
+        assert!("alice ML-DSA-44 AAAA".parse::<AllowedSigners>().is_err());
+        assert!(
+            "alice cert-authority ML-DSA-44 AAAA"
+                .parse::<AllowedSigners>()
+                .is_err()
+        );
+        assert!("# only a comment\n".parse::<AllowedSigners>().is_ok());
+    }
+}
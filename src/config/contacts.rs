@@ -0,0 +1,810 @@
+use std::{
+    fmt::Display,
+    fs::{create_dir_all, read_to_string},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, Utc};
+use oqs::{
+    kem::{Algorithm as KemAlgorithmVariant, Kem},
+    sig::{Algorithm as SigAlgorithmVariant, Sig},
+};
+use serde::{Deserialize, Serialize};
+
+use super::Home;
+use crate::{
+    cryptography::{
+        binding::SubkeyBinding,
+        bundle::KeyBundle,
+        fingerprint::{fingerprint, fingerprints_match, format_fingerprint},
+        revocation::{KeyType, RevocationCertificate, ensure_key_valid_at},
+    },
+    oqs::{convert_str_to_kem_alg, convert_str_to_sig_alg},
+    util::write_file,
+};
+
+const CONTACTS_VERSION: u64 = 1;
+
+/// Keyring of other people's public keys, stored as `contacts.json` in the rust-seal home.
+///
+/// A key is only imported if its fingerprint matches one obtained from the owner through another channel,
+/// so commands resolving keys by contact name can trust them.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Contacts {
+    #[serde(skip)]
+    path: PathBuf,
+    version: u64,
+    contacts: Vec<Contact>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Contact {
+    pub name: String,
+    /// Key certifying the other keys of the contact. If present, keys without a valid binding to it are rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<ContactKey<SigAlgorithmVariant>>,
+    #[serde(default)]
+    pub kem_keys: Vec<ContactKey<KemAlgorithmVariant>>,
+    #[serde(default)]
+    pub signature_keys: Vec<ContactKey<SigAlgorithmVariant>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ContactKey<A> {
+    pub algorithm: A,
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+    pub fingerprint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<RevocationCertificate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binding: Option<SubkeyBinding>,
+}
+
+impl<A: Display> ContactKey<A> {
+    /// Returns the public key if it still matches its fingerprint, is not revoked and had not expired at `time`.
+    /// If the contact has a `primary` key, the key must also carry a binding signed by it that was valid at `time`.
+    fn verified_public_key(
+        &self,
+        contact: &str,
+        primary: Option<&ContactKey<SigAlgorithmVariant>>,
+        time: DateTime<Utc>,
+    ) -> Result<&[u8]> {
+        let description = format!("The {} key of contact {contact}", self.algorithm);
+        ensure!(
+            fingerprint(&self.algorithm.to_string(), &self.public_key) == self.fingerprint,
+            "{description} does not match its fingerprint. The contacts file might have been tampered with"
+        );
+        ensure_key_valid_at(&description, self.expires, self.revocation.as_ref(), time)?;
+
+        if let Some(primary) = primary {
+            let primary_public_key = primary
+                .verified_public_key(contact, None, time)
+                .context(format!("The primary key of contact {contact} is not valid"))?;
+            let binding = self.binding.as_ref().context(format!(
+                "{description} is not certified by their primary key"
+            ))?;
+            binding
+                .verify(primary_public_key, &self.public_key)
+                .context(format!(
+                    "{description} has no valid binding to their primary key"
+                ))?;
+            binding.ensure_valid_at(&description, time)?;
+        }
+
+        Ok(&self.public_key)
+    }
+
+    /// Returns "valid", "expired" or "revoked"
+    pub fn status(&self) -> &'static str {
+        if self.revocation.is_some() {
+            "revoked"
+        } else if self.expires.is_some_and(|expires| expires <= Utc::now()) {
+            "expired"
+        } else {
+            "valid"
+        }
+    }
+}
+
+impl<A> ContactKey<A> {
+    /// Creates a key certified by `binding`. It expires with the certification
+    fn certified(algorithm: A, public_key: Vec<u8>, binding: &SubkeyBinding) -> Self {
+        Self {
+            algorithm,
+            public_key,
+            fingerprint: binding.statement.fingerprint.clone(),
+            expires: binding.statement.not_after,
+            revocation: None,
+            binding: Some(binding.clone()),
+        }
+    }
+}
+
+impl Contacts {
+    /// Loads the keyring of `home`. A missing file results in an empty keyring
+    pub fn load(home: &Home) -> Result<Self> {
+        let path = home.contacts_file();
+        let contents = match read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path,
+                    version: CONTACTS_VERSION,
+                    contacts: Vec::new(),
+                });
+            }
+            Err(err) => return Err(err).context("Failed to read contacts file"),
+        };
+
+        let mut contacts: Contacts = serde_json::from_str(&contents)
+            .context(format!("Failed to parse contacts file: {}", path.display()))?;
+        ensure!(
+            contacts.version <= CONTACTS_VERSION,
+            "Contacts file has version {}, but this rust-seal only supports versions up to {CONTACTS_VERSION}",
+            contacts.version
+        );
+        contacts.path = path;
+
+        Ok(contacts)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            create_dir_all(parent).context("Failed to create contacts directory")?;
+        }
+
+        let json_string =
+            serde_json::to_string_pretty(&self).context("Failed to serialize contacts")?;
+        write_file(&self.path, json_string, true).context("Failed to write contacts file")
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Imports a KEM public key for `name` if its fingerprint matches `expected_fingerprint`
+    pub fn import_kem_key(
+        &mut self,
+        name: &str,
+        kem: &Kem,
+        public_key: &[u8],
+        expected_fingerprint: &str,
+        expires: Option<DateTime<Utc>>,
+    ) -> Result<String> {
+        kem.public_key_from_bytes(public_key).context(format!(
+            "Public key is not a valid key for {}",
+            kem.algorithm()
+        ))?;
+        let fingerprint = verify_fingerprint(
+            &kem.algorithm().to_string(),
+            public_key,
+            expected_fingerprint,
+        )?;
+
+        let contact = self.contact_entry(name)?;
+        ensure!(
+            contact.primary_key.is_none(),
+            "Contact {name} has a primary key. Import their key bundle with `rust-seal contacts import-bundle` instead"
+        );
+        ensure!(
+            !contact
+                .kem_keys
+                .iter()
+                .any(|key| key.algorithm == kem.algorithm()),
+            "Contact {name} already has a {} key. Remove the contact first to replace it",
+            kem.algorithm()
+        );
+        contact.kem_keys.push(ContactKey {
+            algorithm: kem.algorithm(),
+            public_key: public_key.to_vec(),
+            fingerprint: fingerprint.clone(),
+            expires,
+            revocation: None,
+            binding: None,
+        });
+
+        Ok(fingerprint)
+    }
+
+    /// Imports a signature public key for `name` if its fingerprint matches `expected_fingerprint`
+    pub fn import_signature_key(
+        &mut self,
+        name: &str,
+        signature: &Sig,
+        public_key: &[u8],
+        expected_fingerprint: &str,
+        expires: Option<DateTime<Utc>>,
+    ) -> Result<String> {
+        signature
+            .public_key_from_bytes(public_key)
+            .context(format!(
+                "Public key is not a valid key for {}",
+                signature.algorithm()
+            ))?;
+        let fingerprint = verify_fingerprint(
+            &signature.algorithm().to_string(),
+            public_key,
+            expected_fingerprint,
+        )?;
+
+        let contact = self.contact_entry(name)?;
+        ensure!(
+            contact.primary_key.is_none(),
+            "Contact {name} has a primary key. Import their key bundle with `rust-seal contacts import-bundle` instead"
+        );
+        ensure!(
+            !contact
+                .signature_keys
+                .iter()
+                .any(|key| key.algorithm == signature.algorithm()),
+            "Contact {name} already has a {} key. Remove the contact first to replace it",
+            signature.algorithm()
+        );
+        contact.signature_keys.push(ContactKey {
+            algorithm: signature.algorithm(),
+            public_key: public_key.to_vec(),
+            fingerprint: fingerprint.clone(),
+            expires,
+            revocation: None,
+            binding: None,
+        });
+
+        Ok(fingerprint)
+    }
+
+    /// Attaches a revocation to the contact key it revokes, after checking its signature.
+    ///
+    /// Signature keys must have signed their own revocation. KEM key revocations must be signed by a signature key or the primary key of the same contact.
+    /// Returns the name of the contact.
+    pub fn import_revocation(&mut self, certificate: RevocationCertificate) -> Result<&str> {
+        let statement = &certificate.statement;
+        let contact = self
+            .contacts
+            .iter_mut()
+            .find(|contact| match statement.key_type {
+                KeyType::Signature => contact
+                    .signature_keys
+                    .iter()
+                    .any(|key| key.fingerprint == statement.fingerprint),
+                KeyType::Kem => contact
+                    .kem_keys
+                    .iter()
+                    .any(|key| key.fingerprint == statement.fingerprint),
+            })
+            .context(format!(
+                "The revoked {} key is not in the contacts",
+                statement.algorithm
+            ))?;
+
+        if statement.key_type == KeyType::Signature {
+            ensure!(
+                statement.signer_fingerprint == statement.fingerprint,
+                "A signature key revocation must be signed by the revoked key itself"
+            );
+        }
+        let signer = contact
+            .signature_keys
+            .iter()
+            .chain(&contact.primary_key)
+            .find(|key| key.fingerprint == statement.signer_fingerprint)
+            .context(format!(
+                "The revocation was not signed by a key of contact {}",
+                contact.name
+            ))?;
+        certificate.verify(&signer.public_key)?;
+
+        let revocation = match statement.key_type {
+            KeyType::Signature => contact
+                .signature_keys
+                .iter_mut()
+                .find(|key| key.fingerprint == statement.fingerprint)
+                .map(|key| &mut key.revocation),
+            KeyType::Kem => contact
+                .kem_keys
+                .iter_mut()
+                .find(|key| key.fingerprint == statement.fingerprint)
+                .map(|key| &mut key.revocation),
+        }
+        .context("Revoked key was found before")?;
+        ensure!(
+            revocation.is_none(),
+            "The {} key of contact {} is already revoked",
+            certificate.statement.algorithm,
+            contact.name
+        );
+        *revocation = Some(certificate);
+
+        Ok(&contact.name)
+    }
+
+    /// Imports the primary key of `bundle` for `name` if its fingerprint matches `expected_fingerprint`,
+    /// together with every subkey it certifies. The subkeys replace the contact's previous keys; known revocations are kept.
+    pub fn import_bundle(
+        &mut self,
+        name: &str,
+        bundle: &KeyBundle,
+        expected_fingerprint: &str,
+    ) -> Result<String> {
+        let primary = Sig::new(bundle.primary.algorithm)
+            .context("Signature algorithm of the primary key is not enabled")?;
+        primary
+            .public_key_from_bytes(&bundle.primary.public_key)
+            .context(format!(
+                "Primary key is not a valid key for {}",
+                bundle.primary.algorithm
+            ))?;
+        let fingerprint = verify_fingerprint(
+            &bundle.primary.algorithm.to_string(),
+            &bundle.primary.public_key,
+            expected_fingerprint,
+        )?;
+        bundle.verify()?;
+
+        let mut kem_keys: Vec<ContactKey<KemAlgorithmVariant>> = Vec::new();
+        let mut signature_keys: Vec<ContactKey<SigAlgorithmVariant>> = Vec::new();
+        for subkey in &bundle.subkeys {
+            let statement = &subkey.binding.statement;
+            match statement.key_type {
+                KeyType::Kem => {
+                    let algorithm = convert_str_to_kem_alg(&statement.algorithm)?;
+                    Kem::new(algorithm)
+                        .context(format!("{algorithm} is not enabled"))?
+                        .public_key_from_bytes(&subkey.public_key)
+                        .context(format!("Subkey is not a valid key for {algorithm}"))?;
+                    ensure!(
+                        !kem_keys.iter().any(|key| key.algorithm == algorithm),
+                        "Key bundle contains more than one {algorithm} key"
+                    );
+                    kem_keys.push(ContactKey::certified(
+                        algorithm,
+                        subkey.public_key.clone(),
+                        &subkey.binding,
+                    ));
+                }
+                KeyType::Signature => {
+                    let algorithm = convert_str_to_sig_alg(&statement.algorithm)?;
+                    Sig::new(algorithm)
+                        .context(format!("{algorithm} is not enabled"))?
+                        .public_key_from_bytes(&subkey.public_key)
+                        .context(format!("Subkey is not a valid key for {algorithm}"))?;
+                    ensure!(
+                        !signature_keys.iter().any(|key| key.algorithm == algorithm),
+                        "Key bundle contains more than one {algorithm} key"
+                    );
+                    signature_keys.push(ContactKey::certified(
+                        algorithm,
+                        subkey.public_key.clone(),
+                        &subkey.binding,
+                    ));
+                }
+            }
+        }
+
+        let contact = self.contact_entry(name)?;
+        match &contact.primary_key {
+            Some(primary_key) => ensure!(
+                primary_key.fingerprint == fingerprint,
+                "Contact {name} has another primary key. Remove the contact first to replace it"
+            ),
+            None => ensure!(
+                contact.kem_keys.is_empty() && contact.signature_keys.is_empty(),
+                "Contact {name} has keys that are not certified by a primary key. Remove the contact first"
+            ),
+        }
+
+        for key in &mut kem_keys {
+            key.revocation = contact
+                .kem_keys
+                .iter_mut()
+                .find(|old| old.fingerprint == key.fingerprint)
+                .and_then(|old| old.revocation.take());
+        }
+        for key in &mut signature_keys {
+            key.revocation = contact
+                .signature_keys
+                .iter_mut()
+                .find(|old| old.fingerprint == key.fingerprint)
+                .and_then(|old| old.revocation.take());
+        }
+
+        contact.primary_key = Some(ContactKey {
+            algorithm: bundle.primary.algorithm,
+            public_key: bundle.primary.public_key.clone(),
+            fingerprint: fingerprint.clone(),
+            expires: None,
+            revocation: None,
+            binding: None,
+        });
+        contact.kem_keys = kem_keys;
+        contact.signature_keys = signature_keys;
+
+        Ok(fingerprint)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        let count = self.contacts.len();
+        self.contacts.retain(|contact| contact.name != name);
+        ensure!(self.contacts.len() < count, "Unknown contact: {name}");
+        Ok(())
+    }
+
+    pub fn kem_public_key(&self, name: &str, algorithm: KemAlgorithmVariant) -> Result<&[u8]> {
+        let contact = self.find(name)?;
+        match contact
+            .kem_keys
+            .iter()
+            .find(|key| key.algorithm == algorithm)
+        {
+            Some(key) => key.verified_public_key(name, contact.primary_key.as_ref(), Utc::now()),
+            None => bail!(
+                "Contact {name} has no {algorithm} key. Available KEM keys: {}",
+                list_algorithms(contact.kem_keys.iter().map(|key| key.algorithm))
+            ),
+        }
+    }
+
+    pub fn signature_public_key(
+        &self,
+        name: &str,
+        algorithm: SigAlgorithmVariant,
+    ) -> Result<&[u8]> {
+        self.signature_public_key_at(name, algorithm, Utc::now())
+    }
+
+    /// Returns the signature key of `name` if it was valid at `time`, e.g. when a timestamped signature was made
+    pub fn signature_public_key_at(
+        &self,
+        name: &str,
+        algorithm: SigAlgorithmVariant,
+        time: DateTime<Utc>,
+    ) -> Result<&[u8]> {
+        let contact = self.find(name)?;
+        match contact
+            .signature_keys
+            .iter()
+            .find(|key| key.algorithm == algorithm)
+        {
+            Some(key) => key.verified_public_key(name, contact.primary_key.as_ref(), time),
+            None => bail!(
+                "Contact {name} has no {algorithm} key. Available signature keys: {}",
+                list_algorithms(contact.signature_keys.iter().map(|key| key.algorithm))
+            ),
+        }
+    }
+
+    /// Returns the name of the contact owning the signature key with `fingerprint`.
+    /// The key is not checked; resolve it with [`Contacts::signature_public_key`]
+    pub fn signature_key_owner(
+        &self,
+        algorithm: SigAlgorithmVariant,
+        fingerprint: &str,
+    ) -> Option<&str> {
+        self.contacts
+            .iter()
+            .find(|contact| {
+                contact.signature_keys.iter().any(|key| {
+                    key.algorithm == algorithm && fingerprints_match(&key.fingerprint, fingerprint)
+                })
+            })
+            .map(|contact| contact.name.as_str())
+    }
+
+    fn find(&self, name: &str) -> Result<&Contact> {
+        self.contacts
+            .iter()
+            .find(|contact| contact.name == name)
+            .context(format!(
+                "Unknown contact: {name}. Import a key with `rust-seal contacts import`"
+            ))
+    }
+
+    fn contact_entry(&mut self, name: &str) -> Result<&mut Contact> {
+        validate_name(name)?;
+
+        match self
+            .contacts
+            .iter()
+            .position(|contact| contact.name == name)
+        {
+            Some(index) => Ok(&mut self.contacts[index]),
+            None => {
+                self.contacts.push(Contact {
+                    name: name.to_string(),
+                    primary_key: None,
+                    kem_keys: Vec::new(),
+                    signature_keys: Vec::new(),
+                });
+                Ok(self.contacts.last_mut().expect("Contact was just added"))
+            }
+        }
+    }
+}
+
+fn verify_fingerprint(algorithm: &str, public_key: &[u8], expected: &str) -> Result<String> {
+    let fingerprint = fingerprint(algorithm, public_key);
+    ensure!(
+        fingerprints_match(&fingerprint, expected),
+        "Fingerprint mismatch. The key has the fingerprint\n  {}\nbut\n  {expected}\nwas expected. Do not import this key",
+        format_fingerprint(&fingerprint)
+    );
+    Ok(fingerprint)
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    ensure!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@')),
+        "Invalid contact name: {name:?}. Use letters, digits and '-', '_', '.', '@'"
+    );
+    Ok(())
+}
+
+fn list_algorithms<A: Display>(algorithms: impl Iterator<Item = A>) -> String {
+    let algorithms: Vec<String> = algorithms.map(|algorithm| algorithm.to_string()).collect();
+    if algorithms.is_empty() {
+        "none".to_string()
+    } else {
+        algorithms.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::cryptography::{
+        binding::BindingStatement,
+        bundle::{BundlePrimaryKey, BundleSubkey},
+        revocation::{RevocationReason, RevokedKey},
+    };
+
+    #[test]
+    fn test_import_and_resolve_keys() {
+        let dir = tempdir().unwrap();
+        let home = Home::from_root(dir.path());
+        let signature = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let kem = Kem::new(KemAlgorithmVariant::MlKem768).unwrap();
+        let (sig_public_key, _) = signature.keypair().unwrap();
+        let (kem_public_key, _) = kem.keypair().unwrap();
+
+        let mut contacts = Contacts::load(&home).unwrap();
+        let sig_fingerprint = fingerprint("ML-DSA-44", sig_public_key.as_ref());
+        contacts
+            .import_signature_key(
+                "release-bot",
+                &signature,
+                sig_public_key.as_ref(),
+                &format_fingerprint(&sig_fingerprint),
+                None,
+            )
+            .unwrap();
+        contacts
+            .import_kem_key(
+                "release-bot",
+                &kem,
+                kem_public_key.as_ref(),
+                &fingerprint("ML-KEM-768", kem_public_key.as_ref()),
+                None,
+            )
+            .unwrap();
+        contacts.save().unwrap();
+
+        let contacts = Contacts::load(&home).unwrap();
+        assert_eq!(contacts.contacts().len(), 1);
+        assert_eq!(
+            contacts
+                .signature_public_key("release-bot", SigAlgorithmVariant::MlDsa44)
+                .unwrap(),
+            sig_public_key.as_ref()
+        );
+        assert_eq!(
+            contacts
+                .kem_public_key("release-bot", KemAlgorithmVariant::MlKem768)
+                .unwrap(),
+            kem_public_key.as_ref()
+        );
+        assert!(
+            contacts
+                .signature_public_key("release-bot", SigAlgorithmVariant::MlDsa65)
+                .is_err()
+        );
+        assert!(
+            contacts
+                .signature_public_key("mallory", SigAlgorithmVariant::MlDsa44)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_mismatch_is_rejected() {
+        let dir = tempdir().unwrap();
+        let signature = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+        let (other_public_key, _) = signature.keypair().unwrap();
+
+        let mut contacts = Contacts::load(&Home::from_root(dir.path())).unwrap();
+        let result = contacts.import_signature_key(
+            "alice",
+            &signature,
+            public_key.as_ref(),
+            &fingerprint("ML-DSA-44", other_public_key.as_ref()),
+            None,
+        );
+
+        assert!(result.is_err());
+        assert!(contacts.contacts().is_empty());
+    }
+
+    #[test]
+    fn test_tampered_key_is_rejected() {
+        let dir = tempdir().unwrap();
+        let signature = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+
+        let mut contacts = Contacts::load(&Home::from_root(dir.path())).unwrap();
+        contacts
+            .import_signature_key(
+                "alice",
+                &signature,
+                public_key.as_ref(),
+                &fingerprint("ML-DSA-44", public_key.as_ref()),
+                None,
+            )
+            .unwrap();
+        contacts.contacts[0].signature_keys[0].public_key[0] ^= 1;
+
+        assert!(
+            contacts
+                .signature_public_key("alice", SigAlgorithmVariant::MlDsa44)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_revoked_and_expired_keys_are_rejected() {
+        let dir = tempdir().unwrap();
+        let signature = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let kem = Kem::new(KemAlgorithmVariant::MlKem768).unwrap();
+        let (sig_public_key, sig_secret_key) = signature.keypair().unwrap();
+        let (kem_public_key, _) = kem.keypair().unwrap();
+        let sig_fingerprint = fingerprint("ML-DSA-44", sig_public_key.as_ref());
+
+        let mut contacts = Contacts::load(&Home::from_root(dir.path())).unwrap();
+        contacts
+            .import_signature_key(
+                "alice",
+                &signature,
+                sig_public_key.as_ref(),
+                &sig_fingerprint,
+                None,
+            )
+            .unwrap();
+        contacts
+            .import_kem_key(
+                "alice",
+                &kem,
+                kem_public_key.as_ref(),
+                &fingerprint("ML-KEM-768", kem_public_key.as_ref()),
+                Some(Utc::now() - chrono::Duration::days(1)),
+            )
+            .unwrap();
+
+        assert!(
+            contacts
+                .kem_public_key("alice", KemAlgorithmVariant::MlKem768)
+                .is_err()
+        );
+
+        let certificate = RevocationCertificate::sign(
+            RevokedKey {
+                key_type: KeyType::Signature,
+                algorithm: "ML-DSA-44".to_string(),
+                fingerprint: sig_fingerprint,
+            },
+            RevocationReason::KeyCompromise,
+            None,
+            &signature,
+            sig_public_key.as_ref(),
+            &sig_secret_key,
+        )
+        .unwrap();
+        let mut forged = certificate.clone();
+        forged.statement.reason = RevocationReason::Retired;
+
+        assert!(contacts.import_revocation(forged).is_err());
+        assert!(
+            contacts
+                .signature_public_key("alice", SigAlgorithmVariant::MlDsa44)
+                .is_ok()
+        );
+
+        assert_eq!(contacts.import_revocation(certificate).unwrap(), "alice");
+        assert!(
+            contacts
+                .signature_public_key("alice", SigAlgorithmVariant::MlDsa44)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bundle_subkeys_require_binding() {
+        let dir = tempdir().unwrap();
+        let primary = Sig::new(SigAlgorithmVariant::MlDsa87).unwrap();
+        let kem = Kem::new(KemAlgorithmVariant::MlKem768).unwrap();
+        let (primary_public_key, primary_secret_key) = primary.keypair().unwrap();
+        let (kem_public_key, _) = kem.keypair().unwrap();
+
+        let statement = BindingStatement::new(
+            SigAlgorithmVariant::MlDsa87,
+            primary_public_key.as_ref(),
+            KeyType::Kem,
+            "ML-KEM-768",
+            kem_public_key.as_ref(),
+            None,
+        );
+        let bundle = KeyBundle::new(
+            BundlePrimaryKey {
+                algorithm: SigAlgorithmVariant::MlDsa87,
+                public_key: primary_public_key.as_ref().to_vec(),
+            },
+            vec![BundleSubkey {
+                public_key: kem_public_key.as_ref().to_vec(),
+                binding: SubkeyBinding::sign(statement, &primary, &primary_secret_key).unwrap(),
+            }],
+        );
+
+        let mut contacts = Contacts::load(&Home::from_root(dir.path())).unwrap();
+        assert!(
+            contacts
+                .import_bundle("alice", &bundle, &fingerprint("ML-KEM-768", b"wrong"))
+                .is_err()
+        );
+        contacts
+            .import_bundle("alice", &bundle, &bundle.primary_fingerprint())
+            .unwrap();
+        assert_eq!(
+            contacts
+                .kem_public_key("alice", KemAlgorithmVariant::MlKem768)
+                .unwrap(),
+            kem_public_key.as_ref()
+        );
+
+        contacts.contacts[0].kem_keys[0]
+            .binding
+            .as_mut()
+            .unwrap()
+            .statement
+            .not_after = Some(Utc::now() + chrono::Duration::days(1));
+        assert!(
+            contacts
+                .kem_public_key("alice", KemAlgorithmVariant::MlKem768)
+                .is_err()
+        );
+
+        contacts.contacts[0].kem_keys[0].binding = None;
+        assert!(
+            contacts
+                .kem_public_key("alice", KemAlgorithmVariant::MlKem768)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_names_are_rejected() {
+        assert!(validate_name("release-bot").is_ok());
+        assert!(validate_name("alice@example.com").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../alice").is_err());
+        assert!(validate_name("alice smith").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, ensure};
+
+/// Environment variable pointing to a directory that holds the configuration file and keys
+pub const HOME_ENV_VAR: &str = "RUST_SEAL_HOME";
+
+const APP_DIR_NAME: &str = "rust-seal";
+const PROJECT_DIR_NAME: &str = ".rust-seal";
+const CONFIG_FILE_NAME: &str = "config.json";
+const CONTACTS_FILE_NAME: &str = "contacts.json";
+const KEYS_DIR_NAME: &str = "keys";
+
+/// Configuration file that versions before the home kept in the working directory, next to `keys/`
+const LEGACY_CONFIG_FILE_NAME: &str = "rust-seal.config.json";
+
+/// Locations of the configuration file and the key directories.
+///
+/// The home is resolved in the following order:
+/// 1. `--config` (the directory of the given file becomes the home)
+/// 2. `RUST_SEAL_HOME`
+/// 3. a project-local `.rust-seal/` directory found by walking up from the working directory
+/// 4. XDG defaults (`$XDG_CONFIG_HOME/rust-seal/config.json` and `$XDG_DATA_HOME/rust-seal/keys`)
+///
+/// `--keys-dir` overrides the key directory independently of the home.
+/// Key paths stored in the configuration are relative to [`Home::root`] whenever possible.
+///
+/// A legacy `rust-seal.config.json` in the working directory is not used implicitly. Resolving a home without a
+/// configuration file fails while one exists, so its keys are not silently replaced by new ones.
+#[derive(Debug, Clone, Default)]
+pub struct Home {
+    root: PathBuf,
+    config_file: PathBuf,
+    keys_dir: PathBuf,
+}
+
+impl Home {
+    pub fn resolve(config_file: Option<&Path>, keys_dir: Option<&Path>) -> Result<Self> {
+        let cwd = env::current_dir().context("Failed to determine the working directory")?;
+        Self::resolve_with(config_file, keys_dir, &cwd, |key| env::var_os(key))
+    }
+
+    /// Uses `root` for both the configuration file and the keys
+    pub fn from_root(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self {
+            config_file: root.join(CONFIG_FILE_NAME),
+            keys_dir: root.join(KEYS_DIR_NAME),
+            root,
+        }
+    }
+
+    fn resolve_with(
+        config_file: Option<&Path>,
+        keys_dir: Option<&Path>,
+        cwd: &Path,
+        env_var: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Self> {
+        let mut home = if let Some(config_file) = config_file {
+            let config_file = cwd.join(config_file);
+            let root = config_file
+                .parent()
+                .context("Config path has no parent directory")?
+                .to_path_buf();
+            Self {
+                keys_dir: root.join(KEYS_DIR_NAME),
+                root,
+                config_file,
+            }
+        } else if let Some(root) = env_var(HOME_ENV_VAR).filter(|value| !value.is_empty()) {
+            Self::from_root(cwd.join(root))
+        } else {
+            let home = match find_project_dir(cwd) {
+                Some(root) => Self::from_root(root),
+                None => Self::xdg(&env_var)?,
+            };
+            ensure_no_legacy_config(&home, cwd)?;
+            home
+        };
+
+        if let Some(keys_dir) = keys_dir {
+            home.keys_dir = cwd.join(keys_dir);
+        }
+
+        Ok(home)
+    }
+
+    fn xdg(env_var: &impl Fn(&str) -> Option<OsString>) -> Result<Self> {
+        let user_home = || {
+            env_var("HOME")
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+                .context(
+                    "Could not determine the home directory. Set RUST_SEAL_HOME or pass --config",
+                )
+        };
+        let xdg_dir = |key: &str, fallback: &str| -> Result<PathBuf> {
+            match env_var(key).map(PathBuf::from) {
+                // The XDG spec says relative paths are invalid and should be ignored
+                Some(path) if path.is_absolute() => Ok(path),
+                _ => Ok(user_home()?.join(fallback)),
+            }
+        };
+
+        let config_dir = xdg_dir("XDG_CONFIG_HOME", ".config")?.join(APP_DIR_NAME);
+        let root = xdg_dir("XDG_DATA_HOME", ".local/share")?.join(APP_DIR_NAME);
+
+        Ok(Self {
+            config_file: config_dir.join(CONFIG_FILE_NAME),
+            keys_dir: root.join(KEYS_DIR_NAME),
+            root,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn config_file(&self) -> &Path {
+        &self.config_file
+    }
+
+    pub fn keys_dir(&self) -> &Path {
+        &self.keys_dir
+    }
+
+    /// Keyring of other people's public keys
+    pub fn contacts_file(&self) -> PathBuf {
+        self.root.join(CONTACTS_FILE_NAME)
+    }
+
+    pub fn sig_keys_dir(&self) -> PathBuf {
+        self.keys_dir.join("sig")
+    }
+
+    pub fn kem_keys_dir(&self) -> PathBuf {
+        self.keys_dir.join("kem")
+    }
+
+    /// Directory of the primary key certifying the other keys
+    pub fn primary_keys_dir(&self) -> PathBuf {
+        self.keys_dir.join("primary")
+    }
+
+    /// Converts `path` into the form stored in the configuration file
+    pub fn relativize(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Converts a path stored in the configuration file into a usable path
+    pub fn resolve_path(&self, stored: &Path) -> PathBuf {
+        self.root.join(stored)
+    }
+}
+
+/// Fails if the working directory has the configuration of a version before the home and `home` has none
+fn ensure_no_legacy_config(home: &Home, cwd: &Path) -> Result<()> {
+    ensure!(
+        home.config_file.exists() || !cwd.join(LEGACY_CONFIG_FILE_NAME).is_file(),
+        "Found {LEGACY_CONFIG_FILE_NAME} of an older rust-seal in the working directory, but no configuration in {}. \
+        Move it into a project home with `mkdir {PROJECT_DIR_NAME} && mv {LEGACY_CONFIG_FILE_NAME} {PROJECT_DIR_NAME}/{CONFIG_FILE_NAME} && mv {KEYS_DIR_NAME} {PROJECT_DIR_NAME}/`, \
+        or keep using it with `--config {LEGACY_CONFIG_FILE_NAME}`",
+        home.config_file.display()
+    );
+    Ok(())
+}
+
+fn find_project_dir(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_DIR_NAME))
+        .find(|candidate| candidate.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        ffi::OsString,
+        fs::{create_dir_all, write},
+        path::Path,
+    };
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn env_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), OsString::from(value)))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_xdg_defaults() {
+        let home = Home::resolve_with(
+            None,
+            None,
+            Path::new("/nonexistent/work"),
+            env_from(&[("HOME", "/home/user")]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            home.config_file(),
+            Path::new("/home/user/.config/rust-seal/config.json")
+        );
+        assert_eq!(
+            home.keys_dir(),
+            Path::new("/home/user/.local/share/rust-seal/keys")
+        );
+    }
+
+    #[test]
+    fn test_xdg_variables_are_respected() {
+        let home = Home::resolve_with(
+            None,
+            None,
+            Path::new("/nonexistent/work"),
+            env_from(&[
+                ("HOME", "/home/user"),
+                ("XDG_CONFIG_HOME", "/cfg"),
+                ("XDG_DATA_HOME", "relative/is/ignored"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(home.config_file(), Path::new("/cfg/rust-seal/config.json"));
+        assert_eq!(
+            home.keys_dir(),
+            Path::new("/home/user/.local/share/rust-seal/keys")
+        );
+    }
+
+    #[test]
+    fn test_env_home_takes_precedence_over_project_dir() {
+        let dir = tempdir().unwrap();
+        create_dir_all(dir.path().join(PROJECT_DIR_NAME)).unwrap();
+
+        let home = Home::resolve_with(
+            None,
+            None,
+            dir.path(),
+            env_from(&[(HOME_ENV_VAR, "/seal-home")]),
+        )
+        .unwrap();
+
+        assert_eq!(home.config_file(), Path::new("/seal-home/config.json"));
+        assert_eq!(home.keys_dir(), Path::new("/seal-home/keys"));
+    }
+
+    #[test]
+    fn test_project_dir_is_discovered_from_subdirectory() {
+        let dir = tempdir().unwrap();
+        let project_dir = dir.path().join(PROJECT_DIR_NAME);
+        let nested = dir.path().join("a/b/c");
+        create_dir_all(&project_dir).unwrap();
+        create_dir_all(&nested).unwrap();
+
+        let home = Home::resolve_with(None, None, &nested, env_from(&[])).unwrap();
+
+        assert_eq!(home.root(), project_dir);
+        assert_eq!(home.config_file(), project_dir.join(CONFIG_FILE_NAME));
+    }
+
+    #[test]
+    fn test_legacy_config_is_not_silently_ignored() {
+        let dir = tempdir().unwrap();
+        write(dir.path().join(LEGACY_CONFIG_FILE_NAME), "{}").unwrap();
+        let xdg_env = [("HOME", "/nonexistent/user")];
+
+        let err = Home::resolve_with(None, None, dir.path(), env_from(&xdg_env)).unwrap_err();
+        assert!(err.to_string().contains("mv rust-seal.config.json"));
+
+        let home = Home::resolve_with(
+            Some(Path::new(LEGACY_CONFIG_FILE_NAME)),
+            None,
+            dir.path(),
+            env_from(&xdg_env),
+        )
+        .unwrap();
+        assert_eq!(home.keys_dir(), dir.path().join(KEYS_DIR_NAME));
+
+        let project_dir = dir.path().join(PROJECT_DIR_NAME);
+        create_dir_all(&project_dir).unwrap();
+        write(project_dir.join(CONFIG_FILE_NAME), "{}").unwrap();
+        let home = Home::resolve_with(None, None, dir.path(), env_from(&xdg_env)).unwrap();
+        assert_eq!(home.root(), project_dir);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let home = Home::resolve_with(
+            Some(Path::new("other/seal.json")),
+            Some(Path::new("/keys")),
+            Path::new("/work"),
+            env_from(&[(HOME_ENV_VAR, "/seal-home")]),
+        )
+        .unwrap();
+
+        assert_eq!(home.root(), Path::new("/work/other"));
+        assert_eq!(home.config_file(), Path::new("/work/other/seal.json"));
+        assert_eq!(home.keys_dir(), Path::new("/keys"));
+    }
+
+    #[test]
+    fn test_paths_are_stored_relative_to_root() {
+        let home = Home::from_root("/seal-home");
+
+        let stored = home.relativize(&home.sig_keys_dir().join("ML-DSA-44.pub"));
+        assert_eq!(stored, Path::new("keys/sig/ML-DSA-44.pub"));
+        assert_eq!(
+            home.resolve_path(&stored),
+            Path::new("/seal-home/keys/sig/ML-DSA-44.pub")
+        );
+
+        let outside = Path::new("/elsewhere/key.pub");
+        assert_eq!(home.relativize(outside), outside);
+        assert_eq!(home.resolve_path(outside), outside);
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{Context, Result, ensure};
+use serde_json::{Map, Value};
+
+/// Version of the configuration schema written by this build
+pub const CONFIG_VERSION: u64 = 1;
+
+pub const VERSION_KEY: &str = "version";
+
+/// Migrates a raw configuration from version `n` to `n + 1`, where `n` is the index in [`MIGRATIONS`]
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Brings a raw configuration up to [`CONFIG_VERSION`].
+///
+/// Returns the version the configuration was migrated from, or `None` if it already was up to date.
+/// Configurations written by a newer rust-seal are rejected instead of silently dropping their fields.
+pub fn migrate(config: &mut Value) -> Result<Option<u64>> {
+    let object = config
+        .as_object_mut()
+        .context("Config file must contain a JSON object")?;
+
+    let version = match object.get(VERSION_KEY) {
+        Some(version) => version
+            .as_u64()
+            .context("Config version must be a non-negative integer")?,
+        None => 0,
+    };
+    ensure!(
+        version <= CONFIG_VERSION,
+        "Config file has version {version}, but this rust-seal only supports versions up to {CONFIG_VERSION}. Update rust-seal to use this configuration"
+    );
+
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(object)?;
+    }
+    object.insert(VERSION_KEY.to_string(), Value::from(CONFIG_VERSION));
+
+    Ok(Some(version))
+}
+
+/// Unversioned configurations stored key paths as they were created, e.g. `./keys/sig/Dilithium2.pub`.
+/// Version 1 stores them relative to the rust-seal home without the leading `./`.
+fn migrate_v0_to_v1(config: &mut Map<String, Value>) -> Result<()> {
+    for section in ["kem_algorithms", "signature_algorithms"] {
+        let Some(entries) = config.get_mut(section) else {
+            continue;
+        };
+        let entries = entries
+            .as_array_mut()
+            .context(format!("Config field {section} must be a list"))?;
+
+        for entry in entries {
+            for key in ["pub_key_path", "sec_key_path"] {
+                if let Some(Value::String(path)) = entry.get_mut(key)
+                    && let Some(stripped) = path.strip_prefix("./")
+                {
+                    *path = stripped.to_string();
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_unversioned_config_is_migrated() {
+        let mut config = json!({
+            "kem_algorithms": [],
+            "signature_algorithms": [{
+                "algorithm": "Dilithium2",
+                "pub_key_path": "./keys/sig/Dilithium2.pub",
+                "sec_key_path": "./keys/sig/Dilithium2.sec"
+            }]
+        });
+
+        let migrated_from = migrate(&mut config).expect("Migration failed");
+
+        assert_eq!(migrated_from, Some(0));
+        assert_eq!(config[VERSION_KEY], json!(CONFIG_VERSION));
+        assert_eq!(
+            config["signature_algorithms"][0]["pub_key_path"],
+            json!("keys/sig/Dilithium2.pub")
+        );
+    }
+
+    #[test]
+    fn test_current_config_is_untouched() {
+        let mut config = json!({ "version": CONFIG_VERSION, "kem_algorithms": [] });
+        let expected = config.clone();
+
+        assert_eq!(migrate(&mut config).unwrap(), None);
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn test_newer_config_is_rejected() {
+        let mut config = json!({ "version": CONFIG_VERSION + 1 });
+
+        assert!(migrate(&mut config).is_err());
+    }
+}
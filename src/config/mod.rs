@@ -0,0 +1,1065 @@
+mod allowed_signers;
+mod contacts;
+mod home;
+mod migrations;
+mod policy;
+mod settings;
+
+use std::{
+    fs::{copy, create_dir_all, read, read_to_string},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, Utc};
+use oqs::{
+    kem::{
+        Algorithm as KemAlgorithmVariant, Kem, PublicKey as KemPublicKey, SecretKey as KemSecretKey,
+    },
+    sig::{
+        Algorithm as SigAlgorithmVariant, PublicKey as SigPublicKey, SecretKey as SigSecretKey, Sig,
+    },
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{
+    agent::{AgentClient, DecapsulationKey, KeyAlgorithm, SigningKey},
+    cryptography::{
+        binding::SubkeyBinding,
+        bundle::{BundlePrimaryKey, BundleSubkey, KeyBundle},
+        revocation::{RevocationCertificate, ensure_key_valid, ensure_key_valid_at},
+        secret::Secret,
+    },
+    selftest,
+    util::{ensure_overwritable, write_file, write_secret_file},
+};
+
+pub use allowed_signers::AllowedSigners;
+pub use contacts::{Contact, ContactKey, Contacts};
+pub use home::{HOME_ENV_VAR, Home};
+pub use migrations::CONFIG_VERSION;
+pub use policy::Policy;
+pub use settings::{Defaults, OutputFormat, Setting, Timestamping, Transparency};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip)]
+    home: Home,
+    #[serde(skip)]
+    modified: bool,
+    version: u64,
+    defaults: Defaults,
+    policy: Policy,
+    timestamping: Timestamping,
+    transparency: Transparency,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    primary_key: Option<PrimaryKey>,
+    kem_algorithms: Vec<KemAlgorithm>,
+    signature_algorithms: Vec<SigAlgorithm>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            home: Home::default(),
+            modified: false,
+            version: CONFIG_VERSION,
+            defaults: Defaults::default(),
+            policy: Policy::default(),
+            timestamping: Timestamping::default(),
+            transparency: Transparency::default(),
+            primary_key: None,
+            kem_algorithms: Vec::new(),
+            signature_algorithms: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct KemAlgorithm {
+    algorithm: KemAlgorithmVariant,
+    pub_key_path: PathBuf,
+    sec_key_path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revocation_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    binding_path: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SigAlgorithm {
+    algorithm: SigAlgorithmVariant,
+    pub_key_path: PathBuf,
+    sec_key_path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revocation_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    binding_path: Option<PathBuf>,
+}
+
+/// Signature key that only certifies the other keys. Its secret key is meant to be kept offline
+#[derive(Serialize, Deserialize, Debug)]
+struct PrimaryKey {
+    algorithm: SigAlgorithmVariant,
+    pub_key_path: PathBuf,
+    sec_key_path: PathBuf,
+}
+
+impl Config {
+    /// Loads the configuration of `home`.
+    /// A missing configuration file results in an empty configuration; nothing is written until [`Config::save`] is called.
+    ///
+    /// Configurations of an older schema version are migrated and saved right away.
+    /// The original file is kept as `<config file>.v<old version>.bak`.
+    pub fn load(home: Home) -> Result<Self> {
+        let contents = match read_to_string(home.config_file()) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Config {
+                    home,
+                    ..Config::default()
+                });
+            }
+            Err(err) => return Err(err).context("Failed to read config file"),
+        };
+
+        let mut raw_config: serde_json::Value =
+            serde_json::from_str(&contents).context(format!(
+                "Failed to parse config file: {}",
+                home.config_file().display()
+            ))?;
+        let migrated_from = migrations::migrate(&mut raw_config).context(format!(
+            "Failed to migrate config file: {}",
+            home.config_file().display()
+        ))?;
+
+        let mut config: Config = serde_json::from_value(raw_config).context(format!(
+            "Failed to parse config file: {}",
+            home.config_file().display()
+        ))?;
+        config.home = home;
+
+        if let Some(old_version) = migrated_from {
+            let config_file = config.home.config_file();
+            let backup_path = config_file.with_file_name(format!(
+                "{}.v{old_version}.bak",
+                config_file
+                    .file_name()
+                    .context("Config path has no file name")?
+                    .to_string_lossy()
+            ));
+            copy(config_file, &backup_path).context("Failed to back up config file")?;
+            config
+                .save()
+                .context("Failed to save migrated config file")?;
+
+            println!(
+                "Migrated config file from version {old_version} to {CONFIG_VERSION}. Backup saved to: {}",
+                backup_path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        let config_file = self.home.config_file();
+        if let Some(parent) = config_file.parent() {
+            create_dir_all(parent).context("Failed to create config directory")?;
+        }
+
+        let json_string =
+            serde_json::to_string_pretty(&self).context("Failed to serialize config")?;
+        write_file(config_file, json_string, true).context("Failed to write config file")?;
+        self.modified = false;
+        Ok(())
+    }
+
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Returns true if the configuration changed since it was loaded or last saved
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Generates a keypair for `kem`. Keys without `expires` never expire.
+    /// Algorithms with deterministic key generation, such as ML-KEM, expand the keypair from `seed` if given,
+    /// e.g. one derived from a recovery mnemonic. The seed is not stored.
+    /// Key files left in the keys directory are only overwritten if `force` is set
+    pub fn add_kem_algorithm(
+        &mut self,
+        kem: &Kem,
+        seed: Option<&[u8]>,
+        expires: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Result<(KemPublicKey, Secret<KemSecretKey>)> {
+        self.ensure_kem_algorithm_new(kem.algorithm())?;
+        ensure!(
+            seed.is_none() || kem.length_keypair_seed() > 0,
+            "{} does not support key generation from a seed",
+            kem.algorithm()
+        );
+
+        selftest::ensure_known_answer_tests_passed()?;
+        let (public_key, secret_key) = match seed {
+            Some(seed) => {
+                kem.keypair_derand(kem.keypair_seed_from_bytes(seed).context("Invalid seed")?)
+            }
+            None => kem.keypair(),
+        }
+        .context("Failed to generate keypair for KEM algorithm")?;
+        selftest::check_kem_keypair(kem, &public_key, &secret_key)?;
+
+        self.store_kem_keys(
+            kem,
+            public_key.as_ref(),
+            secret_key.as_ref(),
+            expires,
+            force,
+        )?;
+        Ok((public_key, Secret::new(secret_key)))
+    }
+
+    /// Adds an existing keypair for `kem`, e.g. one imported from PKCS#8, after checking that its keys belong together
+    pub fn import_kem_keys(
+        &mut self,
+        kem: &Kem,
+        public_key: &[u8],
+        secret_key: &[u8],
+        expires: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Result<()> {
+        self.ensure_kem_algorithm_new(kem.algorithm())?;
+
+        let algorithm = kem.algorithm();
+        let public_key = kem
+            .public_key_from_bytes(public_key)
+            .context(format!("Public key is not a valid key for {algorithm}"))?;
+        let secret_key = kem
+            .secret_key_from_bytes(secret_key)
+            .context(format!("Secret key is not a valid key for {algorithm}"))?;
+
+        selftest::ensure_known_answer_tests_passed()?;
+        selftest::check_kem_keypair(kem, public_key, secret_key)?;
+
+        self.store_kem_keys(
+            kem,
+            public_key.as_ref(),
+            secret_key.as_ref(),
+            expires,
+            force,
+        )
+    }
+
+    fn ensure_kem_algorithm_new(&self, algorithm: KemAlgorithmVariant) -> Result<()> {
+        ensure!(
+            !self
+                .kem_algorithms
+                .iter()
+                .any(|element| element.algorithm == algorithm),
+            "KEM algorithm {algorithm} already initialized"
+        );
+        Ok(())
+    }
+
+    fn store_kem_keys(
+        &mut self,
+        kem: &Kem,
+        public_key: &[u8],
+        secret_key: &[u8],
+        expires: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Result<()> {
+        let keys_dir = self.home.kem_keys_dir();
+        create_dir_all(&keys_dir).context("Failed to create kem-keys directory")?;
+
+        let pub_key_path = keys_dir.join(format!("{}.pub", kem.algorithm()));
+        let sec_key_path = keys_dir.join(format!("{}.sec", kem.algorithm()));
+        for path in [&pub_key_path, &sec_key_path] {
+            ensure_overwritable(path, force)?;
+        }
+
+        write_file(&pub_key_path, public_key, force)
+            .context("Failed to save public key to file")?;
+        println!("Public key saved to: {}", pub_key_path.display());
+
+        write_secret_file(&sec_key_path, secret_key, force)
+            .context("Failed to save secret key to file")?;
+        println!("Secret key saved to: {}", sec_key_path.display());
+
+        self.kem_algorithms.push(KemAlgorithm {
+            algorithm: kem.algorithm(),
+            pub_key_path: self.home.relativize(&pub_key_path),
+            sec_key_path: self.home.relativize(&sec_key_path),
+            expires,
+            revocation_path: None,
+            binding_path: None,
+        });
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Generates a keypair for `sig`. Keys without `expires` never expire.
+    /// Key files left in the keys directory are only overwritten if `force` is set
+    pub fn add_signature_algorithm(
+        &mut self,
+        sig: &Sig,
+        expires: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Result<(SigPublicKey, Secret<SigSecretKey>)> {
+        self.ensure_signature_algorithm_new(sig.algorithm())?;
+
+        selftest::ensure_known_answer_tests_passed()?;
+        let (public_key, secret_key) = sig
+            .keypair()
+            .context("Failed to generate keypair for signature algorithm")?;
+        selftest::check_signature_keypair(sig, &public_key, &secret_key)?;
+
+        self.store_signature_keys(
+            sig,
+            public_key.as_ref(),
+            secret_key.as_ref(),
+            expires,
+            force,
+        )?;
+        Ok((public_key, Secret::new(secret_key)))
+    }
+
+    /// Adds an existing keypair for `sig`, e.g. one imported from PKCS#8, after checking that its keys belong together
+    pub fn import_signature_keys(
+        &mut self,
+        sig: &Sig,
+        public_key: &[u8],
+        secret_key: &[u8],
+        expires: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Result<()> {
+        self.ensure_signature_algorithm_new(sig.algorithm())?;
+
+        let algorithm = sig.algorithm();
+        let public_key = sig
+            .public_key_from_bytes(public_key)
+            .context(format!("Public key is not a valid key for {algorithm}"))?;
+        let secret_key = sig
+            .secret_key_from_bytes(secret_key)
+            .context(format!("Secret key is not a valid key for {algorithm}"))?;
+
+        selftest::ensure_known_answer_tests_passed()?;
+        selftest::check_signature_keypair(sig, public_key, secret_key)?;
+
+        self.store_signature_keys(
+            sig,
+            public_key.as_ref(),
+            secret_key.as_ref(),
+            expires,
+            force,
+        )
+    }
+
+    fn ensure_signature_algorithm_new(&self, algorithm: SigAlgorithmVariant) -> Result<()> {
+        ensure!(
+            !self
+                .signature_algorithms
+                .iter()
+                .any(|element| element.algorithm == algorithm),
+            "Signature algorithm {algorithm} already initialized"
+        );
+        Ok(())
+    }
+
+    fn store_signature_keys(
+        &mut self,
+        sig: &Sig,
+        public_key: &[u8],
+        secret_key: &[u8],
+        expires: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Result<()> {
+        let keys_dir = self.home.sig_keys_dir();
+        create_dir_all(&keys_dir).context("Failed to create sig-keys directory")?;
+
+        let pub_key_path = keys_dir.join(format!("{}.pub", sig.algorithm()));
+        let sec_key_path = keys_dir.join(format!("{}.sec", sig.algorithm()));
+        for path in [&pub_key_path, &sec_key_path] {
+            ensure_overwritable(path, force)?;
+        }
+
+        write_file(&pub_key_path, public_key, force)
+            .context("Failed to save public key to file")?;
+        println!("Public key saved to: {}", pub_key_path.display());
+
+        write_secret_file(&sec_key_path, secret_key, force)
+            .context("Failed to save secret key to file")?;
+        println!("Secret key saved to: {}", sec_key_path.display());
+
+        self.signature_algorithms.push(SigAlgorithm {
+            algorithm: sig.algorithm(),
+            pub_key_path: self.home.relativize(&pub_key_path),
+            sec_key_path: self.home.relativize(&sec_key_path),
+            expires,
+            revocation_path: None,
+            binding_path: None,
+        });
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Returns the algorithms of the own signature keys
+    pub fn signature_key_algorithms(&self) -> impl Iterator<Item = SigAlgorithmVariant> + '_ {
+        self.signature_algorithms.iter().map(|alg| alg.algorithm)
+    }
+
+    /// Returns the algorithms of the own KEM keys
+    pub fn kem_key_algorithms(&self) -> impl Iterator<Item = KemAlgorithmVariant> + '_ {
+        self.kem_algorithms.iter().map(|alg| alg.algorithm)
+    }
+
+    pub fn get_signature_keys(
+        &self,
+        algorithm: &SigAlgorithmVariant,
+    ) -> Result<(SigPublicKey, Secret<SigSecretKey>)> {
+        self.signature_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .map(|alg| {
+                let public_key = read(self.home.resolve_path(&alg.pub_key_path))
+                    .context("Failed to read public key file")?;
+                let secret_key = Zeroizing::new(
+                    read(self.home.resolve_path(&alg.sec_key_path))
+                        .context("Failed to read secret key file")?,
+                );
+
+                let sig = Sig::new(*algorithm).context(
+                    "Failed to create signature algorithm. Algorithm might me disabled.",
+                )?;
+
+                Ok((
+                    sig.public_key_from_bytes(&public_key)
+                        .context(format!("Public key is not a valid key for {algorithm}"))?
+                        .to_owned(),
+                    Secret::new(
+                        sig.secret_key_from_bytes(&secret_key)
+                            .context(format!("Secret key is not a valid key for {algorithm}"))?
+                            .to_owned(),
+                    ),
+                ))
+            })
+            .ok_or(anyhow::anyhow!(
+                "Signature algorithm {} not found",
+                algorithm
+            ))?
+    }
+
+    pub fn get_kem_keys(
+        &self,
+        algorithm: &KemAlgorithmVariant,
+    ) -> Result<(KemPublicKey, Secret<KemSecretKey>)> {
+        self.kem_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .map(|alg| {
+                let public_key = read(self.home.resolve_path(&alg.pub_key_path))
+                    .context("Failed to read public key file")?;
+                let secret_key = Zeroizing::new(
+                    read(self.home.resolve_path(&alg.sec_key_path))
+                        .context("Failed to read secret key file")?,
+                );
+
+                let kem = Kem::new(*algorithm)
+                    .context("Failed to create KEM algorithm. Algorithm might me disabled.")?;
+
+                Ok((
+                    kem.public_key_from_bytes(&public_key)
+                        .context(format!("Public key is not a valid key for {algorithm}"))?
+                        .to_owned(),
+                    Secret::new(
+                        kem.secret_key_from_bytes(&secret_key)
+                            .context(format!("Secret key is not a valid key for {algorithm}"))?
+                            .to_owned(),
+                    ),
+                ))
+            })
+            .ok_or(anyhow::anyhow!("KEM algorithm {} not found", algorithm))?
+    }
+
+    /// Returns the own signature public key and the secret key to sign with.
+    /// The secret key stays in the agent at `RUST_SEAL_AGENT_SOCK` if it holds the key; otherwise it is read from disk
+    pub fn signing_key(
+        &self,
+        algorithm: &SigAlgorithmVariant,
+    ) -> Result<(SigPublicKey, SigningKey)> {
+        if let Some(agent) = AgentClient::from_env() {
+            let public_key = self.get_signature_public_key(algorithm)?;
+            let fingerprint = KeyAlgorithm::Signature(*algorithm).fingerprint(public_key.as_ref());
+            if agent.holds(&fingerprint)? {
+                return Ok((public_key, SigningKey::Agent { agent, fingerprint }));
+            }
+        }
+
+        let (public_key, secret_key) = self.get_signature_keys(algorithm)?;
+        Ok((public_key, SigningKey::Local(secret_key)))
+    }
+
+    /// Returns the own KEM public key and the secret key to decapsulate with.
+    /// The secret key stays in the agent at `RUST_SEAL_AGENT_SOCK` if it holds the key; otherwise it is read from disk
+    pub fn decapsulation_key(
+        &self,
+        algorithm: &KemAlgorithmVariant,
+    ) -> Result<(KemPublicKey, DecapsulationKey)> {
+        if let Some(agent) = AgentClient::from_env() {
+            let public_key = self.get_kem_public_key(algorithm)?;
+            let fingerprint = KeyAlgorithm::Kem(*algorithm).fingerprint(public_key.as_ref());
+            if agent.holds(&fingerprint)? {
+                return Ok((public_key, DecapsulationKey::Agent { agent, fingerprint }));
+            }
+        }
+
+        let (public_key, secret_key) = self.get_kem_keys(algorithm)?;
+        Ok((public_key, DecapsulationKey::Local(secret_key)))
+    }
+
+    pub fn get_signature_public_key(
+        &self,
+        algorithm: &SigAlgorithmVariant,
+    ) -> Result<SigPublicKey> {
+        let alg = self
+            .signature_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("Signature algorithm {algorithm} not found"))?;
+        let public_key = read(self.home.resolve_path(&alg.pub_key_path))
+            .context("Failed to read public key file")?;
+        let sig = Sig::new(*algorithm)
+            .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+        Ok(sig
+            .public_key_from_bytes(&public_key)
+            .context(format!("Public key is not a valid key for {algorithm}"))?
+            .to_owned())
+    }
+
+    pub fn get_kem_public_key(&self, algorithm: &KemAlgorithmVariant) -> Result<KemPublicKey> {
+        let alg = self
+            .kem_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("KEM algorithm {algorithm} not found"))?;
+        let public_key = read(self.home.resolve_path(&alg.pub_key_path))
+            .context("Failed to read public key file")?;
+        let kem = Kem::new(*algorithm)
+            .context("Failed to create KEM algorithm. Algorithm might me disabled.")?;
+        Ok(kem
+            .public_key_from_bytes(&public_key)
+            .context(format!("Public key is not a valid key for {algorithm}"))?
+            .to_owned())
+    }
+
+    /// Writes `secret_key` as the secret key of the own signature key of `algorithm`, e.g. after it was reconstructed
+    /// from a backup, after checking that it belongs to the stored public key. Returns the path of the secret key file.
+    /// An existing secret key file is only overwritten if `force` is set
+    pub fn restore_signature_secret_key(
+        &self,
+        algorithm: &SigAlgorithmVariant,
+        secret_key: &[u8],
+        force: bool,
+    ) -> Result<PathBuf> {
+        let entry = self
+            .signature_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("Signature algorithm {algorithm} not found"))?;
+        let public_key = self.get_signature_public_key(algorithm)?;
+        let sig = Sig::new(*algorithm)
+            .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+        let parsed_secret_key = sig
+            .secret_key_from_bytes(secret_key)
+            .context(format!("Secret key is not a valid key for {algorithm}"))?;
+
+        selftest::ensure_known_answer_tests_passed()?;
+        selftest::check_signature_keypair(&sig, &public_key, parsed_secret_key)
+            .context("The secret key does not belong to the stored public key")?;
+
+        let sec_key_path = self.home.resolve_path(&entry.sec_key_path);
+        write_secret_file(&sec_key_path, secret_key, force)
+            .context("Failed to save secret key to file")?;
+        Ok(sec_key_path)
+    }
+
+    /// Like [`Config::restore_signature_secret_key`], for the own KEM key of `algorithm`.
+    /// The seed of the key is not restored
+    pub fn restore_kem_secret_key(
+        &self,
+        algorithm: &KemAlgorithmVariant,
+        secret_key: &[u8],
+        force: bool,
+    ) -> Result<PathBuf> {
+        let entry = self
+            .kem_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("KEM algorithm {algorithm} not found"))?;
+        let public_key = self.get_kem_public_key(algorithm)?;
+        let kem = Kem::new(*algorithm)
+            .context("Failed to create KEM algorithm. Algorithm might me disabled.")?;
+        let parsed_secret_key = kem
+            .secret_key_from_bytes(secret_key)
+            .context(format!("Secret key is not a valid key for {algorithm}"))?;
+
+        selftest::ensure_known_answer_tests_passed()?;
+        selftest::check_kem_keypair(&kem, &public_key, parsed_secret_key)
+            .context("The secret key does not belong to the stored public key")?;
+
+        let sec_key_path = self.home.resolve_path(&entry.sec_key_path);
+        write_secret_file(&sec_key_path, secret_key, force)
+            .context("Failed to save secret key to file")?;
+        Ok(sec_key_path)
+    }
+
+    /// Writes the keypair of the own KEM key of `kem`, e.g. after it was recreated from a recovery mnemonic.
+    /// If the public key file is still present, the keypair must match it and only the secret key is restored;
+    /// otherwise both key files are written again. Returns the path of the secret key file
+    pub fn restore_kem_keys(
+        &self,
+        kem: &Kem,
+        public_key: &[u8],
+        secret_key: &[u8],
+        force: bool,
+    ) -> Result<PathBuf> {
+        let algorithm = kem.algorithm();
+        let entry = self
+            .kem_algorithms
+            .iter()
+            .find(|alg| alg.algorithm == algorithm)
+            .context(format!("KEM algorithm {algorithm} not found"))?;
+        let pub_key_path = self.home.resolve_path(&entry.pub_key_path);
+        if pub_key_path.exists() {
+            ensure!(
+                self.get_kem_public_key(&algorithm)?.as_ref() == public_key,
+                "The keypair does not match your current {algorithm} public key"
+            );
+            return self.restore_kem_secret_key(&algorithm, secret_key, force);
+        }
+
+        let parsed_public_key = kem
+            .public_key_from_bytes(public_key)
+            .context(format!("Public key is not a valid key for {algorithm}"))?;
+        let parsed_secret_key = kem
+            .secret_key_from_bytes(secret_key)
+            .context(format!("Secret key is not a valid key for {algorithm}"))?;
+        selftest::ensure_known_answer_tests_passed()?;
+        selftest::check_kem_keypair(kem, parsed_public_key, parsed_secret_key)?;
+
+        let sec_key_path = self.home.resolve_path(&entry.sec_key_path);
+        for path in [&pub_key_path, &sec_key_path] {
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent).context("Failed to create kem-keys directory")?;
+            }
+        }
+        write_file(&pub_key_path, public_key, false)
+            .context("Failed to save public key to file")?;
+        write_secret_file(&sec_key_path, secret_key, force)
+            .context("Failed to save secret key to file")?;
+        Ok(sec_key_path)
+    }
+
+    /// Fails if the own signature key of `algorithm` is revoked or expired
+    pub fn ensure_signature_key_valid(&self, algorithm: &SigAlgorithmVariant) -> Result<()> {
+        self.ensure_signature_key_valid_at(algorithm, Utc::now())
+    }
+
+    /// Fails if the own signature key of `algorithm` is revoked or had expired at `time`
+    pub fn ensure_signature_key_valid_at(
+        &self,
+        algorithm: &SigAlgorithmVariant,
+        time: DateTime<Utc>,
+    ) -> Result<()> {
+        let entry = self
+            .signature_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("Signature algorithm {algorithm} not found"))?;
+
+        ensure_key_valid_at(
+            &format!("Your {algorithm} key"),
+            entry.expires,
+            self.read_revocation(entry.revocation_path.as_deref())?
+                .as_ref(),
+            time,
+        )
+    }
+
+    /// Fails if the own KEM key of `algorithm` is revoked or expired
+    pub fn ensure_kem_key_valid(&self, algorithm: &KemAlgorithmVariant) -> Result<()> {
+        let entry = self
+            .kem_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("KEM algorithm {algorithm} not found"))?;
+
+        ensure_key_valid(
+            &format!("Your {algorithm} key"),
+            entry.expires,
+            self.read_revocation(entry.revocation_path.as_deref())?
+                .as_ref(),
+        )
+    }
+
+    /// Saves `certificate` next to the revoked signature key and marks the key as revoked
+    pub fn revoke_signature_key(
+        &mut self,
+        algorithm: &SigAlgorithmVariant,
+        certificate: &RevocationCertificate,
+    ) -> Result<PathBuf> {
+        let revocation_path = self.home.sig_keys_dir().join(format!("{algorithm}.rev"));
+        let entry = self
+            .signature_algorithms
+            .iter_mut()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("Signature algorithm {algorithm} not found"))?;
+        ensure!(
+            entry.revocation_path.is_none(),
+            "Your {algorithm} key is already revoked"
+        );
+
+        write_revocation(&revocation_path, certificate)?;
+        entry.revocation_path = Some(self.home.relativize(&revocation_path));
+        self.modified = true;
+
+        Ok(revocation_path)
+    }
+
+    /// Saves `certificate` next to the revoked KEM key and marks the key as revoked
+    pub fn revoke_kem_key(
+        &mut self,
+        algorithm: &KemAlgorithmVariant,
+        certificate: &RevocationCertificate,
+    ) -> Result<PathBuf> {
+        let revocation_path = self.home.kem_keys_dir().join(format!("{algorithm}.rev"));
+        let entry = self
+            .kem_algorithms
+            .iter_mut()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("KEM algorithm {algorithm} not found"))?;
+        ensure!(
+            entry.revocation_path.is_none(),
+            "Your {algorithm} key is already revoked"
+        );
+
+        write_revocation(&revocation_path, certificate)?;
+        entry.revocation_path = Some(self.home.relativize(&revocation_path));
+        self.modified = true;
+
+        Ok(revocation_path)
+    }
+
+    fn read_revocation(&self, path: Option<&Path>) -> Result<Option<RevocationCertificate>> {
+        path.map(|path| {
+            let contents = read_to_string(self.home.resolve_path(path))
+                .context("Failed to read revocation certificate")?;
+            serde_json::from_str(&contents).context("Failed to parse revocation certificate")
+        })
+        .transpose()
+    }
+
+    /// Generates the primary key. Its secret key is only needed to certify keys with [`Config::certify_signature_key`] and [`Config::certify_kem_key`].
+    /// Key files left in the keys directory are only overwritten if `force` is set
+    pub fn init_primary_key(&mut self, sig: &Sig, force: bool) -> Result<(PathBuf, SigPublicKey)> {
+        if let Some(primary_key) = &self.primary_key {
+            bail!("Primary key {} already initialized", primary_key.algorithm);
+        }
+
+        let keys_dir = self.home.primary_keys_dir();
+        create_dir_all(&keys_dir).context("Failed to create primary key directory")?;
+
+        selftest::ensure_known_answer_tests_passed()?;
+        let (public_key, secret_key) = sig
+            .keypair()
+            .context("Failed to generate keypair for primary key")?;
+        selftest::check_signature_keypair(sig, &public_key, &secret_key)?;
+
+        let pub_key_path = keys_dir.join(format!("{}.pub", sig.algorithm()));
+        let sec_key_path = keys_dir.join(format!("{}.sec", sig.algorithm()));
+        for path in [&pub_key_path, &sec_key_path] {
+            ensure_overwritable(path, force)?;
+        }
+
+        write_file(&pub_key_path, &public_key, force)
+            .context("Failed to save public key to file")?;
+        println!("Public key saved to: {}", pub_key_path.display());
+
+        write_secret_file(&sec_key_path, &secret_key, force)
+            .context("Failed to save secret key to file")?;
+
+        self.primary_key = Some(PrimaryKey {
+            algorithm: sig.algorithm(),
+            pub_key_path: self.home.relativize(&pub_key_path),
+            sec_key_path: self.home.relativize(&sec_key_path),
+        });
+        self.modified = true;
+
+        Ok((sec_key_path, public_key))
+    }
+
+    pub fn primary_public_key(&self) -> Result<(SigAlgorithmVariant, SigPublicKey)> {
+        let primary_key = self
+            .primary_key
+            .as_ref()
+            .context("No primary key initialized. Create one with `rust-seal keys init-primary`")?;
+        let public_key = read(self.home.resolve_path(&primary_key.pub_key_path))
+            .context("Failed to read primary public key file")?;
+        let sig = Sig::new(primary_key.algorithm)
+            .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+
+        Ok((
+            primary_key.algorithm,
+            sig.public_key_from_bytes(&public_key)
+                .context(format!(
+                    "Public key is not a valid key for {}",
+                    primary_key.algorithm
+                ))?
+                .to_owned(),
+        ))
+    }
+
+    /// Reads the primary secret key from `path`, or from where it was generated if `path` is `None`
+    pub fn primary_secret_key(&self, path: Option<&Path>) -> Result<Secret<SigSecretKey>> {
+        let primary_key = self
+            .primary_key
+            .as_ref()
+            .context("No primary key initialized. Create one with `rust-seal keys init-primary`")?;
+        let path = path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.home.resolve_path(&primary_key.sec_key_path));
+        ensure!(
+            path.is_file(),
+            "Primary secret key not found at {}. Pass the path of the offline copy with --primary-secret-key",
+            path.display()
+        );
+
+        let secret_key =
+            Zeroizing::new(read(&path).context("Failed to read primary secret key file")?);
+        let sig = Sig::new(primary_key.algorithm)
+            .context("Failed to create signature algorithm. Algorithm might me disabled.")?;
+
+        Ok(Secret::new(
+            sig.secret_key_from_bytes(&secret_key)
+                .context(format!(
+                    "Secret key is not a valid key for {}",
+                    primary_key.algorithm
+                ))?
+                .to_owned(),
+        ))
+    }
+
+    /// Saves the binding of the own signature key next to it, replacing an earlier one
+    pub fn certify_signature_key(
+        &mut self,
+        algorithm: &SigAlgorithmVariant,
+        binding: &SubkeyBinding,
+    ) -> Result<PathBuf> {
+        let binding_path = self
+            .home
+            .sig_keys_dir()
+            .join(format!("{algorithm}.binding"));
+        let entry = self
+            .signature_algorithms
+            .iter_mut()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("Signature algorithm {algorithm} not found"))?;
+
+        write_binding(&binding_path, binding)?;
+        entry.binding_path = Some(self.home.relativize(&binding_path));
+        self.modified = true;
+
+        Ok(binding_path)
+    }
+
+    /// Saves the binding of the own KEM key next to it, replacing an earlier one
+    pub fn certify_kem_key(
+        &mut self,
+        algorithm: &KemAlgorithmVariant,
+        binding: &SubkeyBinding,
+    ) -> Result<PathBuf> {
+        let binding_path = self
+            .home
+            .kem_keys_dir()
+            .join(format!("{algorithm}.binding"));
+        let entry = self
+            .kem_algorithms
+            .iter_mut()
+            .find(|alg| &alg.algorithm == algorithm)
+            .context(format!("KEM algorithm {algorithm} not found"))?;
+
+        write_binding(&binding_path, binding)?;
+        entry.binding_path = Some(self.home.relativize(&binding_path));
+        self.modified = true;
+
+        Ok(binding_path)
+    }
+
+    /// Returns the expiry date of the own signature key
+    pub fn signature_key_expiry(
+        &self,
+        algorithm: &SigAlgorithmVariant,
+    ) -> Result<Option<DateTime<Utc>>> {
+        self.signature_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .map(|alg| alg.expires)
+            .context(format!("Signature algorithm {algorithm} not found"))
+    }
+
+    /// Returns the expiry date of the own KEM key
+    pub fn kem_key_expiry(&self, algorithm: &KemAlgorithmVariant) -> Result<Option<DateTime<Utc>>> {
+        self.kem_algorithms
+            .iter()
+            .find(|alg| &alg.algorithm == algorithm)
+            .map(|alg| alg.expires)
+            .context(format!("KEM algorithm {algorithm} not found"))
+    }
+
+    /// Bundles the primary public key with every certified key that is neither revoked nor expired
+    pub fn export_bundle(&self) -> Result<KeyBundle> {
+        let (algorithm, public_key) = self.primary_public_key()?;
+
+        let mut subkeys = Vec::new();
+        let entries = self
+            .signature_algorithms
+            .iter()
+            .map(|alg| {
+                (
+                    alg.algorithm.to_string(),
+                    &alg.pub_key_path,
+                    alg.expires,
+                    &alg.revocation_path,
+                    &alg.binding_path,
+                )
+            })
+            .chain(self.kem_algorithms.iter().map(|alg| {
+                (
+                    alg.algorithm.to_string(),
+                    &alg.pub_key_path,
+                    alg.expires,
+                    &alg.revocation_path,
+                    &alg.binding_path,
+                )
+            }));
+        for (name, pub_key_path, expires, revocation_path, binding_path) in entries {
+            let Some(binding_path) = binding_path else {
+                continue;
+            };
+            if revocation_path.is_some() || expires.is_some_and(|expires| expires <= Utc::now()) {
+                continue;
+            }
+
+            let binding: SubkeyBinding = serde_json::from_str(
+                &read_to_string(self.home.resolve_path(binding_path))
+                    .context("Failed to read subkey binding")?,
+            )
+            .context("Failed to parse subkey binding")?;
+            if binding.ensure_valid_now(&name).is_err() {
+                continue;
+            }
+
+            subkeys.push(BundleSubkey {
+                public_key: read(self.home.resolve_path(pub_key_path))
+                    .context("Failed to read public key file")?,
+                binding,
+            });
+        }
+
+        let bundle = KeyBundle::new(
+            BundlePrimaryKey {
+                algorithm,
+                public_key: public_key.into_vec(),
+            },
+            subkeys,
+        );
+        bundle
+            .verify()
+            .context("Keys were certified by another primary key")?;
+
+        Ok(bundle)
+    }
+}
+
+fn write_revocation(path: &Path, certificate: &RevocationCertificate) -> Result<()> {
+    ensure!(
+        !path.exists(),
+        "Revocation certificate already exists: {}",
+        path.display()
+    );
+    let json_string = serde_json::to_string_pretty(certificate)
+        .context("Failed to serialize revocation certificate")?;
+    write_file(path, json_string, false).context("Failed to write revocation certificate")
+}
+fn write_binding(path: &Path, binding: &SubkeyBinding) -> Result<()> {
+    let json_string =
+        serde_json::to_string_pretty(binding).context("Failed to serialize subkey binding")?;
+    write_file(path, json_string, true).context("Failed to write subkey binding")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_lost_kem_keys_are_restored() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::load(Home::from_root(dir.path())).unwrap();
+        let kem = Kem::new(KemAlgorithmVariant::MlKem768).unwrap();
+        let (public_key, secret_key) = config.add_kem_algorithm(&kem, None, None, false).unwrap();
+
+        let (other_public_key, other_secret_key) = kem.keypair().unwrap();
+        assert!(
+            config
+                .restore_kem_keys(
+                    &kem,
+                    other_public_key.as_ref(),
+                    other_secret_key.as_ref(),
+                    true
+                )
+                .is_err()
+        );
+
+        remove_dir_all(config.home().kem_keys_dir()).unwrap();
+        assert!(config.get_kem_public_key(&kem.algorithm()).is_err());
+        config
+            .restore_kem_keys(
+                &kem,
+                public_key.as_ref(),
+                secret_key.as_ref().as_ref(),
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            config.get_kem_public_key(&kem.algorithm()).unwrap(),
+            public_key
+        );
+        config
+            .restore_kem_keys(
+                &kem,
+                public_key.as_ref(),
+                secret_key.as_ref().as_ref(),
+                true,
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+/// Restrictions on the algorithms rust-seal may use.
+///
+/// Algorithms are matched by their liboqs name against case-insensitive patterns,
+/// where `*` matches any sequence of characters and `?` matches a single character.
+/// An empty policy allows every algorithm.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// If not empty, only algorithms matching one of these patterns may be used
+    pub allow: Vec<String>,
+    /// Algorithms matching one of these patterns may not be used, even if they are allowed
+    pub deny: Vec<String>,
+    /// Minimum claimed NIST security level (1-5)
+    pub min_security_level: Option<u8>,
+    /// Algorithms matching one of these patterns may be used, but print a warning
+    pub deprecated: Vec<String>,
+}
+
+impl Policy {
+    /// Returns the reason why `algorithm` violates the policy, if it does
+    pub fn violation(&self, algorithm: &str, nist_level: u8) -> Option<String> {
+        if let Some(pattern) = find_match(&self.deny, algorithm) {
+            return Some(format!(
+                "{algorithm} is denied by the policy pattern \"{pattern}\""
+            ));
+        }
+
+        if !self.allow.is_empty() && find_match(&self.allow, algorithm).is_none() {
+            return Some(format!(
+                "{algorithm} is not in the policy allow list ({})",
+                self.allow.join(", ")
+            ));
+        }
+
+        match self.min_security_level {
+            Some(min_level) if nist_level < min_level => Some(format!(
+                "{algorithm} has NIST security level {nist_level}, but the policy requires at least level {min_level}"
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn is_deprecated(&self, algorithm: &str) -> bool {
+        find_match(&self.deprecated, algorithm).is_some()
+    }
+}
+
+fn find_match<'a>(patterns: &'a [String], algorithm: &str) -> Option<&'a str> {
+    patterns
+        .iter()
+        .map(String::as_str)
+        .find(|pattern| matches_pattern(pattern, algorithm))
+}
+
+pub(super) fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    // Iterative wildcard matching with backtracking to the last `*`
+    let (mut p, mut n) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = last_star {
+            p = star_p + 1;
+            n = star_n + 1;
+            last_star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Policy {
+        Policy {
+            allow: vec![],
+            deny: vec![
+                "SPHINCS+-*f-simple".to_string(),
+                "Kyber*".to_string(),
+                "Dilithium?".to_string(),
+            ],
+            min_security_level: Some(3),
+            deprecated: vec!["HQC-*".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(matches_pattern(
+            "SPHINCS+-*f-simple",
+            "SPHINCS+-SHA2-192f-simple"
+        ));
+        assert!(matches_pattern(
+            "sphincs+-*F-SIMPLE",
+            "SPHINCS+-SHAKE-256f-simple"
+        ));
+        assert!(!matches_pattern(
+            "SPHINCS+-*f-simple",
+            "SPHINCS+-SHA2-192s-simple"
+        ));
+        assert!(matches_pattern("ML-DSA-??", "ML-DSA-65"));
+        assert!(!matches_pattern("ML-DSA-?", "ML-DSA-65"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("*-*-*", "ML-KEM-768"));
+    }
+
+    #[test]
+    fn test_denied_algorithms() {
+        let policy = policy();
+
+        assert!(policy.violation("SPHINCS+-SHA2-256f-simple", 5).is_some());
+        assert!(policy.violation("Kyber1024", 5).is_some());
+        assert!(policy.violation("Dilithium5", 5).is_some());
+        assert!(policy.violation("SPHINCS+-SHA2-256s-simple", 5).is_none());
+        assert!(policy.violation("ML-KEM-1024", 5).is_none());
+    }
+
+    #[test]
+    fn test_min_security_level() {
+        let policy = policy();
+
+        assert!(policy.violation("ML-DSA-44", 2).is_some());
+        assert!(policy.violation("ML-DSA-65", 3).is_none());
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let policy = Policy {
+            allow: vec!["ML-*".to_string()],
+            ..policy()
+        };
+
+        assert!(policy.violation("ML-KEM-768", 3).is_none());
+        assert!(policy.violation("Falcon-1024", 5).is_some());
+    }
+
+    #[test]
+    fn test_deprecated() {
+        let policy = policy();
+
+        assert!(policy.is_deprecated("HQC-256"));
+        assert!(!policy.is_deprecated("ML-KEM-768"));
+    }
+
+    #[test]
+    fn test_empty_policy_allows_everything() {
+        assert!(Policy::default().violation("Kyber512", 1).is_none());
+    }
+}
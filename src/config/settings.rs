@@ -0,0 +1,268 @@
+use std::{fmt, fs::canonicalize, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result, bail, ensure};
+use oqs::{kem::Algorithm as KemAlgorithmVariant, sig::Algorithm as SigAlgorithmVariant};
+use serde::{Deserialize, Serialize};
+use x509_cert::Certificate;
+
+use super::Config;
+use crate::{
+    cryptography::{fingerprint::normalize_fingerprint, x509::read_certificate},
+    oqs::{convert_str_to_kem_alg, convert_str_to_sig_alg},
+};
+
+/// Values used when the corresponding command line argument is omitted
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    signature_algorithm: Option<SigAlgorithmVariant>,
+    kem_algorithm: Option<KemAlgorithmVariant>,
+    output_format: OutputFormat,
+}
+
+/// Time-stamping authorities (TSAs) whose RFC 3161 timestamps `verify` trusts
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timestamping {
+    /// Certificates of the TSAs or of the CAs that issued them, in PEM or DER encoding
+    tsa_certificates: Vec<PathBuf>,
+}
+
+/// Transparency logs whose receipts `verify --require-log` accepts
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Transparency {
+    /// Fingerprints of the log keys, as printed by `rust-seal log init`
+    trusted_logs: Vec<String>,
+}
+
+/// Format of reports printed to stdout
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => bail!("Unsupported output format: {value}. Expected one of: text, json"),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Settings that can be read and changed with `rust-seal config`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    DefaultSignatureAlgorithm,
+    DefaultKemAlgorithm,
+    DefaultOutputFormat,
+    PolicyAllow,
+    PolicyDeny,
+    PolicyMinSecurityLevel,
+    PolicyDeprecated,
+    TimestampingTsaCertificates,
+    TransparencyTrustedLogs,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 9] = [
+        Setting::DefaultSignatureAlgorithm,
+        Setting::DefaultKemAlgorithm,
+        Setting::DefaultOutputFormat,
+        Setting::PolicyAllow,
+        Setting::PolicyDeny,
+        Setting::PolicyMinSecurityLevel,
+        Setting::PolicyDeprecated,
+        Setting::TimestampingTsaCertificates,
+        Setting::TransparencyTrustedLogs,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Setting::DefaultSignatureAlgorithm => "defaults.signature_algorithm",
+            Setting::DefaultKemAlgorithm => "defaults.kem_algorithm",
+            Setting::DefaultOutputFormat => "defaults.output_format",
+            Setting::PolicyAllow => "policy.allow",
+            Setting::PolicyDeny => "policy.deny",
+            Setting::PolicyMinSecurityLevel => "policy.min_security_level",
+            Setting::PolicyDeprecated => "policy.deprecated",
+            Setting::TimestampingTsaCertificates => "timestamping.tsa_certificates",
+            Setting::TransparencyTrustedLogs => "transparency.trusted_logs",
+        }
+    }
+}
+
+impl FromStr for Setting {
+    type Err = anyhow::Error;
+
+    fn from_str(key: &str) -> Result<Self> {
+        match Setting::ALL
+            .into_iter()
+            .find(|setting| setting.key() == key)
+        {
+            Some(setting) => Ok(setting),
+            None => bail!(
+                "Unknown setting: {key}. Available settings: {}",
+                Setting::ALL.map(|setting| setting.key()).join(", ")
+            ),
+        }
+    }
+}
+
+impl Config {
+    pub fn default_signature_algorithm(&self) -> Option<SigAlgorithmVariant> {
+        self.defaults.signature_algorithm
+    }
+
+    pub fn default_kem_algorithm(&self) -> Option<KemAlgorithmVariant> {
+        self.defaults.kem_algorithm
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.defaults.output_format
+    }
+
+    /// Reads the certificates of the trusted time-stamping authorities
+    pub fn tsa_certificates(&self) -> Result<Vec<Certificate>> {
+        self.timestamping
+            .tsa_certificates
+            .iter()
+            .map(|path| read_certificate(path).context("Failed to read a TSA certificate"))
+            .collect()
+    }
+
+    /// Fingerprints of the transparency logs whose receipts are trusted
+    pub fn trusted_logs(&self) -> &[String] {
+        &self.transparency.trusted_logs
+    }
+
+    /// Returns the current value of `setting` or `None` if it is not set
+    pub fn get_setting(&self, setting: Setting) -> Option<String> {
+        match setting {
+            Setting::DefaultSignatureAlgorithm => self
+                .defaults
+                .signature_algorithm
+                .map(|algorithm| algorithm.to_string()),
+            Setting::DefaultKemAlgorithm => self
+                .defaults
+                .kem_algorithm
+                .map(|algorithm| algorithm.to_string()),
+            Setting::DefaultOutputFormat => Some(self.defaults.output_format.to_string()),
+            Setting::PolicyAllow => join_patterns(&self.policy.allow),
+            Setting::PolicyDeny => join_patterns(&self.policy.deny),
+            Setting::PolicyMinSecurityLevel => self
+                .policy
+                .min_security_level
+                .map(|level| level.to_string()),
+            Setting::PolicyDeprecated => join_patterns(&self.policy.deprecated),
+            Setting::TimestampingTsaCertificates => {
+                let paths: Vec<String> = self
+                    .timestamping
+                    .tsa_certificates
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                join_patterns(&paths)
+            }
+            Setting::TransparencyTrustedLogs => join_patterns(&self.transparency.trusted_logs),
+        }
+    }
+
+    /// Validates `value` and stores it for `setting`.
+    /// List settings such as `policy.deny` take a comma separated list of patterns.
+    /// TSA certificates are stored with their absolute path, once they have been read successfully.
+    /// Log fingerprints are stored in lowercase without separators.
+    pub fn set_setting(&mut self, setting: Setting, value: &str) -> Result<()> {
+        match setting {
+            Setting::DefaultSignatureAlgorithm => {
+                self.defaults.signature_algorithm = Some(convert_str_to_sig_alg(value)?)
+            }
+            Setting::DefaultKemAlgorithm => {
+                self.defaults.kem_algorithm = Some(convert_str_to_kem_alg(value)?)
+            }
+            Setting::DefaultOutputFormat => self.defaults.output_format = value.parse()?,
+            Setting::PolicyAllow => self.policy.allow = split_patterns(value),
+            Setting::PolicyDeny => self.policy.deny = split_patterns(value),
+            Setting::PolicyMinSecurityLevel => {
+                let level: u8 = value
+                    .parse()
+                    .context(format!("Invalid security level: {value}"))?;
+                ensure!(
+                    (1..=5).contains(&level),
+                    "Security level must be between 1 and 5"
+                );
+                self.policy.min_security_level = Some(level);
+            }
+            Setting::PolicyDeprecated => self.policy.deprecated = split_patterns(value),
+            Setting::TimestampingTsaCertificates => {
+                let mut paths = Vec::new();
+                for path in split_patterns(value) {
+                    let path = canonicalize(&path)
+                        .context(format!("TSA certificate not found: {path}"))?;
+                    read_certificate(&path)?;
+                    paths.push(path);
+                }
+                self.timestamping.tsa_certificates = paths;
+            }
+            Setting::TransparencyTrustedLogs => {
+                let mut fingerprints = Vec::new();
+                for fingerprint in split_patterns(value) {
+                    let normalized = normalize_fingerprint(&fingerprint);
+                    ensure!(
+                        normalized.len() == 64 && normalized.chars().all(|c| c.is_ascii_hexdigit()),
+                        "Invalid log fingerprint: {fingerprint}. Expected 64 hex characters"
+                    );
+                    fingerprints.push(normalized);
+                }
+                self.transparency.trusted_logs = fingerprints;
+            }
+        }
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Resets `setting` to its default value
+    pub fn unset_setting(&mut self, setting: Setting) {
+        match setting {
+            Setting::DefaultSignatureAlgorithm => self.defaults.signature_algorithm = None,
+            Setting::DefaultKemAlgorithm => self.defaults.kem_algorithm = None,
+            Setting::DefaultOutputFormat => self.defaults.output_format = OutputFormat::default(),
+            Setting::PolicyAllow => self.policy.allow.clear(),
+            Setting::PolicyDeny => self.policy.deny.clear(),
+            Setting::PolicyMinSecurityLevel => self.policy.min_security_level = None,
+            Setting::PolicyDeprecated => self.policy.deprecated.clear(),
+            Setting::TimestampingTsaCertificates => self.timestamping.tsa_certificates.clear(),
+            Setting::TransparencyTrustedLogs => self.transparency.trusted_logs.clear(),
+        }
+        self.modified = true;
+    }
+}
+
+fn split_patterns(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn join_patterns(patterns: &[String]) -> Option<String> {
+    (!patterns.is_empty()).then(|| patterns.join(","))
+}
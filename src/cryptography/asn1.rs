@@ -0,0 +1,213 @@
+//! Algorithm identifiers and file encodings shared by the X.509, PKCS#8 and SPKI formats.
+//!
+//! ML-KEM uses the identifiers of draft-ietf-lamps-kyber-certificates, ML-DSA those of RFC 9881.
+//! Both are defined without parameters.
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use oqs::{kem::Algorithm as KemAlgorithm, sig::Algorithm as SigAlgorithm};
+use x509_cert::{
+    der::{
+        Encode, EncodePem,
+        asn1::ObjectIdentifier,
+        pem::{self, LineEnding},
+    },
+    spki::AlgorithmIdentifierOwned,
+};
+
+use crate::util::write_file;
+
+const SIGNATURE_OIDS: &[(SigAlgorithm, ObjectIdentifier)] = &[
+    (
+        SigAlgorithm::MlDsa44,
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.17"),
+    ),
+    (
+        SigAlgorithm::MlDsa65,
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.18"),
+    ),
+    (
+        SigAlgorithm::MlDsa87,
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.19"),
+    ),
+];
+
+const KEM_OIDS: &[(KemAlgorithm, ObjectIdentifier)] = &[
+    (
+        KemAlgorithm::MlKem512,
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.1"),
+    ),
+    (
+        KemAlgorithm::MlKem768,
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.2"),
+    ),
+    (
+        KemAlgorithm::MlKem1024,
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.3"),
+    ),
+];
+
+/// File encoding of certificates, requests and keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Pem,
+    Der,
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "pem" => Ok(Encoding::Pem),
+            "der" => Ok(Encoding::Der),
+            _ => bail!("Unsupported encoding: {value}. Expected one of: pem, der"),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Pem => f.write_str("pem"),
+            Encoding::Der => f.write_str("der"),
+        }
+    }
+}
+
+/// Returns the algorithm identifier of a signature algorithm
+pub fn signature_algorithm_identifier(algorithm: SigAlgorithm) -> Result<AlgorithmIdentifierOwned> {
+    let Some((_, oid)) = SIGNATURE_OIDS.iter().find(|(known, _)| *known == algorithm) else {
+        if algorithm.to_string().starts_with("SPHINCS+") {
+            bail!(
+                "{algorithm} is the pre-standard version of SLH-DSA and has no standardised identifier. The SLH-DSA identifiers (RFC 9909) require FIPS 205 SLH-DSA, which the linked liboqs does not provide"
+            );
+        }
+        bail!(
+            "{algorithm} has no standardised identifier. Supported signature algorithms: {}",
+            SIGNATURE_OIDS
+                .iter()
+                .map(|(algorithm, _)| algorithm.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+
+    Ok(AlgorithmIdentifierOwned {
+        oid: *oid,
+        parameters: None,
+    })
+}
+
+/// Returns the algorithm identifier of a KEM algorithm
+pub fn kem_algorithm_identifier(algorithm: KemAlgorithm) -> Result<AlgorithmIdentifierOwned> {
+    let (_, oid) = KEM_OIDS
+        .iter()
+        .find(|(known, _)| *known == algorithm)
+        .context(format!(
+            "{algorithm} has no standardised identifier. Supported KEM algorithms: {}",
+            KEM_OIDS
+                .iter()
+                .map(|(algorithm, _)| algorithm.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))?;
+
+    Ok(AlgorithmIdentifierOwned {
+        oid: *oid,
+        parameters: None,
+    })
+}
+
+/// Returns the signature algorithm named by an algorithm identifier
+pub fn signature_algorithm(identifier: &AlgorithmIdentifierOwned) -> Result<SigAlgorithm> {
+    ensure_no_parameters(identifier)?;
+    SIGNATURE_OIDS
+        .iter()
+        .find(|(_, oid)| *oid == identifier.oid)
+        .map(|(algorithm, _)| *algorithm)
+        .context(format!(
+            "Unsupported signature algorithm: {}",
+            identifier.oid
+        ))
+}
+
+/// Returns the KEM algorithm named by an algorithm identifier, or `None` if it names another kind of algorithm
+pub fn kem_algorithm(identifier: &AlgorithmIdentifierOwned) -> Result<Option<KemAlgorithm>> {
+    ensure_no_parameters(identifier)?;
+    Ok(KEM_OIDS
+        .iter()
+        .find(|(_, oid)| *oid == identifier.oid)
+        .map(|(algorithm, _)| *algorithm))
+}
+
+fn ensure_no_parameters(identifier: &AlgorithmIdentifierOwned) -> Result<()> {
+    ensure!(
+        identifier.parameters.is_none(),
+        "Algorithm identifier {} must not have parameters",
+        identifier.oid
+    );
+    Ok(())
+}
+
+/// Reads the DER bytes of a file in PEM or DER encoding. PEM files must have the given label, e.g. `CERTIFICATE`
+pub fn read_der(path: &Path, label: &str) -> Result<Vec<u8>> {
+    let bytes = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    if !bytes.starts_with(b"-----BEGIN") {
+        return Ok(bytes);
+    }
+
+    let (found_label, der) = pem::decode_vec(&bytes)
+        .map_err(|err| anyhow!("Failed to decode PEM file {}: {err}", path.display()))?;
+    ensure!(
+        found_label == label,
+        "Expected a PEM file with label {label}, but {} has label {found_label}",
+        path.display()
+    );
+    Ok(der)
+}
+
+/// Encodes a value in the given encoding
+pub fn encode<T: EncodePem + Encode>(value: &T, encoding: Encoding) -> Result<Vec<u8>> {
+    Ok(match encoding {
+        Encoding::Pem => value.to_pem(LineEnding::LF)?.into_bytes(),
+        Encoding::Der => value.to_der()?,
+    })
+}
+
+/// Writes a value in the given encoding. An existing file is only overwritten if `force` is set
+pub fn write_encoded<T: EncodePem + Encode>(
+    path: &Path,
+    value: &T,
+    encoding: Encoding,
+    force: bool,
+) -> Result<()> {
+    write_file(path, encode(value, encoding)?, force)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithm_identifiers() {
+        let identifier = signature_algorithm_identifier(SigAlgorithm::MlDsa65).unwrap();
+        assert_eq!(identifier.oid.to_string(), "2.16.840.1.101.3.4.3.18");
+        assert_eq!(
+            signature_algorithm(&identifier).unwrap(),
+            SigAlgorithm::MlDsa65
+        );
+        assert_eq!(kem_algorithm(&identifier).unwrap(), None);
+
+        let identifier = kem_algorithm_identifier(KemAlgorithm::MlKem768).unwrap();
+        assert_eq!(identifier.oid.to_string(), "2.16.840.1.101.3.4.4.2");
+        assert_eq!(
+            kem_algorithm(&identifier).unwrap(),
+            Some(KemAlgorithm::MlKem768)
+        );
+
+        assert!(signature_algorithm_identifier(SigAlgorithm::Falcon512).is_err());
+        assert!(signature_algorithm_identifier(SigAlgorithm::SphincsSha2128sSimple).is_err());
+        assert!(kem_algorithm_identifier(KemAlgorithm::Kyber768).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::{Context, Result, ensure};
+use chrono::{DateTime, SubsecRound, Utc};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, SecretKey as SigSecretKey, Sig};
+use serde::{Deserialize, Serialize};
+
+use super::{fingerprint::fingerprint, revocation::KeyType};
+
+const BINDING_VERSION: u64 = 1;
+
+/// Prefix of the signed binding statement, so the signature cannot be mistaken for one over a file
+const BINDING_CONTEXT: &[u8] = b"rust-seal subkey binding v1\0";
+
+/// The signed part of a [`SubkeyBinding`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BindingStatement {
+    pub primary_algorithm: SigAlgorithmVariant,
+    pub primary_fingerprint: String,
+    pub key_type: KeyType,
+    /// liboqs name of the subkey's algorithm
+    pub algorithm: String,
+    pub fingerprint: String,
+    pub not_before: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+impl BindingStatement {
+    /// Describes a subkey that is valid from now until `not_after`
+    pub fn new(
+        primary_algorithm: SigAlgorithmVariant,
+        primary_public_key: &[u8],
+        key_type: KeyType,
+        algorithm: &str,
+        public_key: &[u8],
+        not_after: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            primary_algorithm,
+            primary_fingerprint: fingerprint(&primary_algorithm.to_string(), primary_public_key),
+            key_type,
+            algorithm: algorithm.to_string(),
+            fingerprint: fingerprint(algorithm, public_key),
+            not_before: Utc::now().trunc_subsecs(0),
+            not_after,
+        }
+    }
+}
+
+/// Certifies with the signature of a primary key that a signing or KEM subkey belongs to the primary key's owner
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubkeyBinding {
+    pub version: u64,
+    #[serde(flatten)]
+    pub statement: BindingStatement,
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+}
+
+impl SubkeyBinding {
+    pub fn sign(
+        statement: BindingStatement,
+        primary: &Sig,
+        primary_secret_key: &SigSecretKey,
+    ) -> Result<Self> {
+        ensure!(
+            primary.algorithm() == statement.primary_algorithm,
+            "Binding must be signed with a {} key",
+            statement.primary_algorithm
+        );
+
+        let signature = primary
+            .sign(&signed_message(&statement)?, primary_secret_key)
+            .context("Failed to sign subkey binding")?;
+
+        Ok(Self {
+            version: BINDING_VERSION,
+            statement,
+            signature: signature.into_vec(),
+        })
+    }
+
+    /// Checks that the binding was signed by `primary_public_key` and is about `public_key`
+    pub fn verify(&self, primary_public_key: &[u8], public_key: &[u8]) -> Result<()> {
+        let statement = &self.statement;
+        ensure!(
+            self.version <= BINDING_VERSION,
+            "Subkey binding has version {}, but this rust-seal only supports versions up to {BINDING_VERSION}",
+            self.version
+        );
+        ensure!(
+            fingerprint(&statement.primary_algorithm.to_string(), primary_public_key)
+                == statement.primary_fingerprint,
+            "Subkey binding was not signed by the given primary key"
+        );
+        ensure!(
+            fingerprint(&statement.algorithm, public_key) == statement.fingerprint,
+            "Subkey binding certifies another {} key",
+            statement.algorithm
+        );
+
+        let primary = Sig::new(statement.primary_algorithm)
+            .context("Signature algorithm of the primary key is not enabled")?;
+        let primary_public_key = primary
+            .public_key_from_bytes(primary_public_key)
+            .context("Primary public key is not valid")?;
+        let signature = primary
+            .signature_from_bytes(&self.signature)
+            .context("Subkey binding signature is not valid")?;
+
+        primary
+            .verify(&signed_message(statement)?, signature, primary_public_key)
+            .context("Subkey binding signature verification failed")
+    }
+
+    /// Fails if the current time is outside of the certified validity period
+    pub fn ensure_valid_now(&self, description: &str) -> Result<()> {
+        self.ensure_valid_at(description, Utc::now())
+    }
+
+    /// Fails if `time` is outside of the certified validity period
+    pub fn ensure_valid_at(&self, description: &str, time: DateTime<Utc>) -> Result<()> {
+        ensure!(
+            self.statement.not_before <= time,
+            "{description} is not valid before {}",
+            self.statement.not_before.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        if let Some(not_after) = self.statement.not_after {
+            ensure!(
+                time < not_after,
+                "The certification of {} expired on {}",
+                description.to_lowercase(),
+                not_after.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+        }
+        Ok(())
+    }
+}
+
+fn signed_message(statement: &BindingStatement) -> Result<Vec<u8>> {
+    let mut message = BINDING_CONTEXT.to_vec();
+    message.extend(serde_json::to_vec(statement).context("Failed to serialize subkey binding")?);
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use oqs::{kem::Kem, sig::Algorithm};
+
+    use super::*;
+
+    #[test]
+    fn test_binding_roundtrip() {
+        let primary = Sig::new(Algorithm::MlDsa87).unwrap();
+        let (primary_public_key, primary_secret_key) = primary.keypair().unwrap();
+        let kem = Kem::new(oqs::kem::Algorithm::MlKem768).unwrap();
+        let (public_key, _) = kem.keypair().unwrap();
+        let (other_public_key, _) = kem.keypair().unwrap();
+
+        let statement = BindingStatement::new(
+            Algorithm::MlDsa87,
+            primary_public_key.as_ref(),
+            KeyType::Kem,
+            "ML-KEM-768",
+            public_key.as_ref(),
+            Some(Utc::now() + Duration::days(30)),
+        );
+        let binding = SubkeyBinding::sign(statement, &primary, &primary_secret_key).unwrap();
+        let parsed: SubkeyBinding =
+            serde_json::from_str(&serde_json::to_string(&binding).unwrap()).unwrap();
+
+        assert_eq!(parsed, binding);
+        parsed
+            .verify(primary_public_key.as_ref(), public_key.as_ref())
+            .unwrap();
+        parsed.ensure_valid_now("Key").unwrap();
+        assert!(
+            parsed
+                .verify(primary_public_key.as_ref(), other_public_key.as_ref())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_modified_or_expired_binding_is_rejected() {
+        let primary = Sig::new(Algorithm::MlDsa44).unwrap();
+        let (primary_public_key, primary_secret_key) = primary.keypair().unwrap();
+        let (public_key, _) = primary.keypair().unwrap();
+
+        let statement = BindingStatement::new(
+            Algorithm::MlDsa44,
+            primary_public_key.as_ref(),
+            KeyType::Signature,
+            "ML-DSA-44",
+            public_key.as_ref(),
+            Some(Utc::now() - Duration::days(1)),
+        );
+        let mut binding = SubkeyBinding::sign(statement, &primary, &primary_secret_key).unwrap();
+
+        assert!(binding.ensure_valid_now("Key").is_err());
+
+        binding.statement.not_after = None;
+        assert!(
+            binding
+                .verify(primary_public_key.as_ref(), public_key.as_ref())
+                .is_err()
+        );
+    }
+}
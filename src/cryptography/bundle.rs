@@ -0,0 +1,71 @@
+use anyhow::{Context, Result, ensure};
+use oqs::sig::Algorithm as SigAlgorithmVariant;
+use serde::{Deserialize, Serialize};
+
+use super::{binding::SubkeyBinding, fingerprint::fingerprint};
+
+const BUNDLE_VERSION: u64 = 1;
+
+/// Public part of a primary key together with its certified subkeys, as exported with `rust-seal keys export`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBundle {
+    pub version: u64,
+    pub primary: BundlePrimaryKey,
+    pub subkeys: Vec<BundleSubkey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BundlePrimaryKey {
+    pub algorithm: SigAlgorithmVariant,
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+}
+
+/// A subkey is described by its binding, which names its type and algorithm
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BundleSubkey {
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+    pub binding: SubkeyBinding,
+}
+
+impl KeyBundle {
+    pub fn new(primary: BundlePrimaryKey, subkeys: Vec<BundleSubkey>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            primary,
+            subkeys,
+        }
+    }
+
+    pub fn primary_fingerprint(&self) -> String {
+        fingerprint(
+            &self.primary.algorithm.to_string(),
+            &self.primary.public_key,
+        )
+    }
+
+    /// Checks that every subkey is certified by the primary key
+    pub fn verify(&self) -> Result<()> {
+        ensure!(
+            self.version <= BUNDLE_VERSION,
+            "Key bundle has version {}, but this rust-seal only supports versions up to {BUNDLE_VERSION}",
+            self.version
+        );
+
+        for subkey in &self.subkeys {
+            subkey
+                .binding
+                .verify(&self.primary.public_key, &subkey.public_key)
+                .context(format!(
+                    "Invalid binding of the {} subkey",
+                    subkey.binding.statement.algorithm
+                ))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Verification of RSA and ECDSA signatures, which time-stamping authorities and their CAs still use.
+//!
+//! rust-seal never creates such signatures. RSA is verified with PKCS#1 v1.5 padding,
+//! ECDSA on the curves P-256 and P-384. The digest is SHA-256, SHA-384 or SHA-512.
+use anyhow::{Context, Result, bail, ensure};
+use p256::ecdsa::signature::{Verifier, hazmat::PrehashVerifier};
+use rsa::{RsaPublicKey, pkcs1v15, pkcs8::DecodePublicKey};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_cert::{
+    der::{Encode, asn1::ObjectIdentifier, oid::db::rfc5912},
+    spki::SubjectPublicKeyInfoOwned,
+};
+
+/// Returns true if `algorithm` is an RSA or ECDSA signature algorithm that can be verified
+pub fn is_classic_signature_algorithm(algorithm: &ObjectIdentifier) -> bool {
+    digest_of(algorithm).is_some()
+}
+
+/// Verifies an RSA or ECDSA `signature` over `message`. The `algorithm` names the digest as well,
+/// e.g. sha256WithRSAEncryption or ecdsa-with-SHA384
+pub fn verify_classic_signature(
+    message: &[u8],
+    algorithm: &ObjectIdentifier,
+    signature: &[u8],
+    public_key_info: &SubjectPublicKeyInfoOwned,
+) -> Result<()> {
+    let digest =
+        digest_of(algorithm).context(format!("Unsupported signature algorithm {algorithm}"))?;
+    let public_key_der = public_key_info
+        .to_der()
+        .context("Failed to encode public key")?;
+
+    match public_key_info.algorithm.oid {
+        rfc5912::RSA_ENCRYPTION => {
+            ensure_algorithm_family(algorithm, &RSA_ALGORITHMS)?;
+            let public_key = RsaPublicKey::from_public_key_der(&public_key_der)
+                .context("RSA public key is not valid")?;
+            let signature =
+                pkcs1v15::Signature::try_from(signature).context("RSA signature is not valid")?;
+            match digest {
+                HashAlgorithm::Sha256 => {
+                    pkcs1v15::VerifyingKey::<Sha256>::new(public_key).verify(message, &signature)
+                }
+                HashAlgorithm::Sha384 => {
+                    pkcs1v15::VerifyingKey::<Sha384>::new(public_key).verify(message, &signature)
+                }
+                HashAlgorithm::Sha512 => {
+                    pkcs1v15::VerifyingKey::<Sha512>::new(public_key).verify(message, &signature)
+                }
+            }
+            .context("Signature verification failed")
+        }
+        rfc5912::ID_EC_PUBLIC_KEY => {
+            ensure_algorithm_family(algorithm, &ECDSA_ALGORITHMS)?;
+            let prehash = digest.hash(message);
+            if let Ok(public_key) = p256::ecdsa::VerifyingKey::from_public_key_der(&public_key_der)
+            {
+                let signature = p256::ecdsa::Signature::from_der(signature)
+                    .context("ECDSA signature is not valid")?;
+                public_key.verify_prehash(&prehash, &signature)
+            } else if let Ok(public_key) =
+                p384::ecdsa::VerifyingKey::from_public_key_der(&public_key_der)
+            {
+                let signature = p384::ecdsa::Signature::from_der(signature)
+                    .context("ECDSA signature is not valid")?;
+                public_key.verify_prehash(&prehash, &signature)
+            } else {
+                bail!("Unsupported elliptic curve. Only P-256 and P-384 are supported")
+            }
+            .context("Signature verification failed")
+        }
+        other => bail!("Unsupported public key algorithm {other}"),
+    }
+}
+
+const RSA_ALGORITHMS: [ObjectIdentifier; 3] = [
+    rfc5912::SHA_256_WITH_RSA_ENCRYPTION,
+    rfc5912::SHA_384_WITH_RSA_ENCRYPTION,
+    rfc5912::SHA_512_WITH_RSA_ENCRYPTION,
+];
+
+const ECDSA_ALGORITHMS: [ObjectIdentifier; 3] = [
+    rfc5912::ECDSA_WITH_SHA_256,
+    rfc5912::ECDSA_WITH_SHA_384,
+    rfc5912::ECDSA_WITH_SHA_512,
+];
+
+/// Digest algorithms of the SHA-2 family, as used in signatures and timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Returns the digest algorithm with the identifier `oid`, e.g. id-sha256
+    pub fn from_oid(oid: &ObjectIdentifier) -> Result<Self> {
+        match *oid {
+            rfc5912::ID_SHA_256 => Ok(HashAlgorithm::Sha256),
+            rfc5912::ID_SHA_384 => Ok(HashAlgorithm::Sha384),
+            rfc5912::ID_SHA_512 => Ok(HashAlgorithm::Sha512),
+            _ => bail!("Unsupported digest algorithm {oid}. Expected SHA-256, SHA-384 or SHA-512"),
+        }
+    }
+
+    pub fn oid(self) -> ObjectIdentifier {
+        match self {
+            HashAlgorithm::Sha256 => rfc5912::ID_SHA_256,
+            HashAlgorithm::Sha384 => rfc5912::ID_SHA_384,
+            HashAlgorithm::Sha512 => rfc5912::ID_SHA_512,
+        }
+    }
+
+    /// Returns the RSA PKCS#1 v1.5 signature algorithm with this digest
+    pub fn with_rsa_encryption(self) -> ObjectIdentifier {
+        match self {
+            HashAlgorithm::Sha256 => rfc5912::SHA_256_WITH_RSA_ENCRYPTION,
+            HashAlgorithm::Sha384 => rfc5912::SHA_384_WITH_RSA_ENCRYPTION,
+            HashAlgorithm::Sha512 => rfc5912::SHA_512_WITH_RSA_ENCRYPTION,
+        }
+    }
+
+    pub fn hash(self, message: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(message).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(message).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(message).to_vec(),
+        }
+    }
+}
+
+fn digest_of(algorithm: &ObjectIdentifier) -> Option<HashAlgorithm> {
+    match *algorithm {
+        rfc5912::SHA_256_WITH_RSA_ENCRYPTION | rfc5912::ECDSA_WITH_SHA_256 => {
+            Some(HashAlgorithm::Sha256)
+        }
+        rfc5912::SHA_384_WITH_RSA_ENCRYPTION | rfc5912::ECDSA_WITH_SHA_384 => {
+            Some(HashAlgorithm::Sha384)
+        }
+        rfc5912::SHA_512_WITH_RSA_ENCRYPTION | rfc5912::ECDSA_WITH_SHA_512 => {
+            Some(HashAlgorithm::Sha512)
+        }
+        _ => None,
+    }
+}
+
+fn ensure_algorithm_family(
+    algorithm: &ObjectIdentifier,
+    family: &[ObjectIdentifier],
+) -> Result<()> {
+    ensure!(
+        family.contains(algorithm),
+        "Signature algorithm {algorithm} does not match the key algorithm"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::aead::OsRng;
+    use p256::{
+        ecdsa::{DerSignature, SigningKey, signature::Signer},
+        pkcs8::EncodePublicKey,
+    };
+    use x509_cert::der::Decode;
+
+    #[test]
+    fn test_ecdsa_signature_is_verified() {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let public_key_info = SubjectPublicKeyInfoOwned::from_der(
+            signing_key
+                .verifying_key()
+                .to_public_key_der()
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+        let signature: DerSignature = signing_key.sign(b"message");
+
+        let verify = |message: &[u8], algorithm| {
+            verify_classic_signature(message, &algorithm, signature.as_bytes(), &public_key_info)
+        };
+        verify(b"message", rfc5912::ECDSA_WITH_SHA_256).unwrap();
+        assert!(verify(b"other", rfc5912::ECDSA_WITH_SHA_256).is_err());
+        assert!(verify(b"message", rfc5912::ECDSA_WITH_SHA_384).is_err());
+        assert!(verify(b"message", rfc5912::SHA_256_WITH_RSA_ENCRYPTION).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! COSE_Key and COSE_Sign1 (RFC 9052) with ML-DSA, as specified by draft-ietf-cose-dilithium.
+//!
+//! ML-DSA keys have the key type AKP (7) with the public key in parameter -1.
+//! The algorithms ML-DSA-44, ML-DSA-65 and ML-DSA-87 have the values -48, -49 and -50.
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use ciborium::Value;
+use oqs::sig::{Algorithm as SigAlgorithm, PublicKeyRef, Sig};
+use sha2::{Digest, Sha256};
+
+use super::signature::verify_file_with_signature;
+use crate::agent::SigningKey;
+
+const ALGORITHMS: &[(SigAlgorithm, i64)] = &[
+    (SigAlgorithm::MlDsa44, -48),
+    (SigAlgorithm::MlDsa65, -49),
+    (SigAlgorithm::MlDsa87, -50),
+];
+
+const COSE_SIGN1_TAG: u64 = 18;
+const SIGNATURE1_CONTEXT: &str = "Signature1";
+
+const KEY_TYPE_LABEL: i64 = 1;
+const KEY_ID_LABEL: i64 = 2;
+const KEY_ALGORITHM_LABEL: i64 = 3;
+const AKP_KEY_TYPE: i64 = 7;
+const AKP_PUBLIC_KEY_LABEL: i64 = -1;
+
+const HEADER_ALGORITHM_LABEL: i64 = 1;
+const HEADER_CRITICAL_LABEL: i64 = 2;
+const HEADER_KEY_ID_LABEL: i64 = 4;
+
+fn algorithm_value(algorithm: SigAlgorithm) -> Result<i64> {
+    ALGORITHMS
+        .iter()
+        .find(|(known, _)| *known == algorithm)
+        .map(|(_, value)| *value)
+        .context(format!(
+            "{algorithm} has no COSE algorithm. Supported: ML-DSA-44, ML-DSA-65, ML-DSA-87"
+        ))
+}
+
+/// Returns the CBOR encoded COSE_Key of an ML-DSA public key, identified by its thumbprint
+pub fn public_cose_key(algorithm: SigAlgorithm, public_key: &[u8]) -> Result<Vec<u8>> {
+    encode(&Value::Map(vec![
+        (KEY_TYPE_LABEL.into(), AKP_KEY_TYPE.into()),
+        (
+            KEY_ID_LABEL.into(),
+            Value::Bytes(cose_key_thumbprint(algorithm, public_key)?),
+        ),
+        (
+            KEY_ALGORITHM_LABEL.into(),
+            algorithm_value(algorithm)?.into(),
+        ),
+        (
+            AKP_PUBLIC_KEY_LABEL.into(),
+            Value::Bytes(public_key.to_vec()),
+        ),
+    ]))
+}
+
+/// Returns the COSE Key thumbprint (RFC 9679) of an ML-DSA public key,
+/// the hash of the deterministically encoded map of its required parameters
+pub fn cose_key_thumbprint(algorithm: SigAlgorithm, public_key: &[u8]) -> Result<Vec<u8>> {
+    let required = encode(&Value::Map(vec![
+        (KEY_TYPE_LABEL.into(), AKP_KEY_TYPE.into()),
+        (
+            KEY_ALGORITHM_LABEL.into(),
+            algorithm_value(algorithm)?.into(),
+        ),
+        (
+            AKP_PUBLIC_KEY_LABEL.into(),
+            Value::Bytes(public_key.to_vec()),
+        ),
+    ]))?;
+    Ok(Sha256::digest(required).to_vec())
+}
+
+/// Signs `payload` as tagged COSE_Sign1 with the payload attached.
+/// The unprotected header names the key by its COSE Key thumbprint
+pub fn sign_cose(
+    payload: &[u8],
+    signature: &Sig,
+    public_key: &[u8],
+    secret_key: &SigningKey,
+) -> Result<Vec<u8>> {
+    let protected = encode(&Value::Map(vec![(
+        HEADER_ALGORITHM_LABEL.into(),
+        algorithm_value(signature.algorithm())?.into(),
+    )]))?;
+    let to_be_signed = signature_structure(&protected, payload)?;
+    let cose_signature = secret_key
+        .sign(signature, &to_be_signed)
+        .context("Failed to sign COSE_Sign1")?;
+
+    encode(&Value::Tag(
+        COSE_SIGN1_TAG,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(vec![(
+                HEADER_KEY_ID_LABEL.into(),
+                Value::Bytes(cose_key_thumbprint(signature.algorithm(), public_key)?),
+            )]),
+            Value::Bytes(payload.to_vec()),
+            Value::Bytes(cose_signature.into_vec()),
+        ])),
+    ))
+}
+
+/// Verifies a tagged or untagged COSE_Sign1 with an attached payload and returns the payload
+pub fn verify_cose(message: &[u8], signature: &Sig, public_key: PublicKeyRef) -> Result<Vec<u8>> {
+    let value = match decode(message).context("COSE_Sign1 is not valid CBOR")? {
+        Value::Tag(COSE_SIGN1_TAG, value) => *value,
+        Value::Tag(tag, _) => bail!("Expected a COSE_Sign1 message, but found CBOR tag {tag}"),
+        value => value,
+    };
+    let [protected, unprotected, payload, cose_signature]: [Value; 4] = value
+        .into_array()
+        .ok()
+        .and_then(|array| array.try_into().ok())
+        .context("COSE_Sign1 must be an array of four elements")?;
+
+    let protected = protected
+        .into_bytes()
+        .map_err(|_| anyhow!("COSE_Sign1 protected header must be a byte string"))?;
+    let protected_header = decode(&protected)
+        .context("COSE_Sign1 protected header is not valid CBOR")?
+        .into_map()
+        .map_err(|_| anyhow!("COSE_Sign1 protected header must be a map"))?;
+    ensure!(
+        unprotected.is_map(),
+        "COSE_Sign1 unprotected header must be a map"
+    );
+    ensure!(
+        header_value(&protected_header, HEADER_CRITICAL_LABEL).is_none(),
+        "COSE_Sign1 has critical header parameters, which are not supported"
+    );
+
+    let algorithm = header_value(&protected_header, HEADER_ALGORITHM_LABEL)
+        .and_then(Value::as_integer)
+        .and_then(|value| i64::try_from(value).ok())
+        .context("COSE_Sign1 protected header has no algorithm")?;
+    let expected = algorithm_value(signature.algorithm())?;
+    ensure!(
+        algorithm == expected,
+        "COSE_Sign1 is signed with algorithm {algorithm}, but {} ({expected}) was requested",
+        signature.algorithm()
+    );
+
+    let payload = payload
+        .into_bytes()
+        .map_err(|_| anyhow!("COSE_Sign1 has no attached payload"))?;
+    let cose_signature = cose_signature
+        .into_bytes()
+        .map_err(|_| anyhow!("COSE_Sign1 signature must be a byte string"))?;
+    verify_file_with_signature(
+        &signature_structure(&protected, &payload)?,
+        &cose_signature,
+        signature,
+        public_key,
+    )?;
+
+    Ok(payload)
+}
+
+/// The `Sig_structure` of RFC 9052, section 4.4, without external additional data
+fn signature_structure(protected: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
+    encode(&Value::Array(vec![
+        Value::Text(SIGNATURE1_CONTEXT.to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+fn header_value(header: &[(Value, Value)], label: i64) -> Option<&Value> {
+    header
+        .iter()
+        .find(|(key, _)| key.as_integer() == Some(label.into()))
+        .map(|(_, value)| value)
+}
+
+fn encode(value: &Value) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).context("Failed to encode CBOR")?;
+    Ok(bytes)
+}
+
+/// Decodes a single CBOR item that must span all of `bytes`
+fn decode(mut bytes: &[u8]) -> Result<Value> {
+    let value = ciborium::from_reader(&mut bytes).map_err(|err| anyhow!("{err}"))?;
+    ensure!(bytes.is_empty(), "Unexpected data after the CBOR item");
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptography::secret::Secret;
+
+    #[test]
+    fn test_cose_sign1_roundtrip() {
+        let signature = Sig::new(SigAlgorithm::MlDsa65).unwrap();
+        let (public_key, secret_key) = signature.keypair().unwrap();
+
+        let message = sign_cose(
+            b"payload",
+            &signature,
+            public_key.as_ref(),
+            &SigningKey::Local(Secret::new(secret_key)),
+        )
+        .unwrap();
+        assert_eq!(
+            verify_cose(&message, &signature, PublicKeyRef::from(&public_key)).unwrap(),
+            b"payload"
+        );
+
+        let (other_public_key, _) = signature.keypair().unwrap();
+        assert!(verify_cose(&message, &signature, PublicKeyRef::from(&other_public_key)).is_err());
+
+        let other_algorithm = Sig::new(SigAlgorithm::MlDsa44).unwrap();
+        assert!(verify_cose(&message, &other_algorithm, PublicKeyRef::from(&public_key)).is_err());
+    }
+
+    #[test]
+    fn test_cose_key() {
+        let signature = Sig::new(SigAlgorithm::MlDsa44).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+
+        let key = decode(&public_cose_key(SigAlgorithm::MlDsa44, public_key.as_ref()).unwrap())
+            .unwrap()
+            .into_map()
+            .unwrap();
+        assert_eq!(
+            header_value(&key, KEY_TYPE_LABEL),
+            Some(&Value::from(AKP_KEY_TYPE))
+        );
+        assert_eq!(
+            header_value(&key, KEY_ALGORITHM_LABEL),
+            Some(&Value::from(-48))
+        );
+        assert_eq!(
+            header_value(&key, AKP_PUBLIC_KEY_LABEL),
+            Some(&Value::Bytes(public_key.into_vec()))
+        );
+    }
+}
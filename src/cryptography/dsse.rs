@@ -0,0 +1,153 @@
+//! Dead Simple Signing Envelopes (DSSE) v1, which sign a payload together with its type.
+//!
+//! Signatures are made over the pre-authentication encoding (PAE) of the payload type and payload, so a payload
+//! cannot be passed off as one of another type. The `keyid` of a signature is the rust-seal fingerprint of the key.
+use anyhow::{Context, Result, bail};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE},
+};
+use oqs::sig::Sig;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    fingerprint::{fingerprint, fingerprints_match, format_fingerprint},
+    signature::verify_file_with_signature,
+};
+use crate::agent::SigningKey;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    /// Base64 encoded payload
+    pub payload: String,
+    pub payload_type: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnvelopeSignature {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyid: String,
+    /// Base64 encoded signature over the PAE
+    pub sig: String,
+}
+
+/// Returns the pre-authentication encoding `DSSEv1 <len> <type> <len> <payload>` the signatures are made over
+pub fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut encoded = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+    encoded.extend_from_slice(payload);
+    encoded
+}
+
+/// DSSE allows both base64 alphabets
+fn decode_base64(value: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .or_else(|_| URL_SAFE.decode(value))
+        .context("Invalid base64")
+}
+
+impl Envelope {
+    /// Signs `payload` of `payload_type` into a new envelope
+    pub fn sign(
+        payload_type: &str,
+        payload: &[u8],
+        signature: &Sig,
+        public_key: &[u8],
+        secret_key: &SigningKey,
+    ) -> Result<Self> {
+        let sig = secret_key
+            .sign(signature, &pae(payload_type, payload))
+            .context("Failed to sign the envelope")?;
+        Ok(Self {
+            payload: STANDARD.encode(payload),
+            payload_type: payload_type.to_string(),
+            signatures: vec![EnvelopeSignature {
+                keyid: fingerprint(&signature.algorithm().to_string(), public_key),
+                sig: STANDARD.encode(sig),
+            }],
+        })
+    }
+
+    /// Verifies the signatures of the key and returns the payload if one of them is valid.
+    /// Signatures naming another key are skipped; those without a key ID are tried
+    pub fn verify(&self, signature: &Sig, public_key: &[u8]) -> Result<Vec<u8>> {
+        let payload = decode_base64(&self.payload).context("The envelope payload is invalid")?;
+        let key = signature
+            .public_key_from_bytes(public_key)
+            .context("Provided public key is not valid")?;
+        let key_fingerprint = fingerprint(&signature.algorithm().to_string(), public_key);
+        let message = pae(&self.payload_type, &payload);
+
+        let mut reason = None;
+        for entry in &self.signatures {
+            if !entry.keyid.is_empty() && !fingerprints_match(&entry.keyid, &key_fingerprint) {
+                continue;
+            }
+            let result = decode_base64(&entry.sig)
+                .context("The envelope signature is invalid")
+                .and_then(|sig| verify_file_with_signature(&message, &sig, signature, key));
+            match result {
+                Ok(()) => return Ok(payload),
+                Err(err) => reason = Some(err),
+            }
+        }
+        match reason {
+            Some(reason) => Err(reason),
+            None => bail!(
+                "The envelope has no signature by key {}",
+                format_fingerprint(&key_fingerprint)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptography::secret::Secret;
+    use oqs::sig::Algorithm;
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        // Test vector of the DSSE specification
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+
+        let signature = Sig::new(Algorithm::MlDsa44).unwrap();
+        let (public_key, secret_key) = signature.keypair().unwrap();
+        let envelope = Envelope::sign(
+            "text/plain",
+            b"payload",
+            &signature,
+            public_key.as_ref(),
+            &SigningKey::Local(Secret::new(secret_key)),
+        )
+        .unwrap();
+        let envelope: Envelope =
+            serde_json::from_str(&serde_json::to_string(&envelope).unwrap()).unwrap();
+        assert_eq!(
+            envelope.verify(&signature, public_key.as_ref()).unwrap(),
+            b"payload"
+        );
+
+        let (other_public_key, _) = signature.keypair().unwrap();
+        assert!(
+            envelope
+                .verify(&signature, other_public_key.as_ref())
+                .is_err()
+        );
+
+        let mut retyped = envelope.clone();
+        retyped.payload_type = "text/html".to_string();
+        assert!(retyped.verify(&signature, public_key.as_ref()).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use sha2::{Digest, Sha256};
+
+/// Computes the fingerprint of a public key: the hex encoded SHA-256 digest of
+/// the liboqs algorithm name, a zero byte and the public key bytes.
+///
+/// Binding the algorithm name prevents the same bytes from being trusted as a key of another algorithm.
+pub fn fingerprint(algorithm: &str, public_key: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(algorithm.as_bytes());
+    hasher.update([0]);
+    hasher.update(public_key);
+    hex::encode(hasher.finalize())
+}
+
+/// Formats a fingerprint in groups of four characters to make comparing it by eye easier
+pub fn format_fingerprint(fingerprint: &str) -> String {
+    fingerprint
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).to_uppercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Removes whitespace and `:` separators from a fingerprint and converts it to lowercase
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Compares fingerprints ignoring case, whitespace and `:` separators
+pub fn fingerprints_match(a: &str, b: &str) -> bool {
+    normalize_fingerprint(a) == normalize_fingerprint(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_binds_algorithm() {
+        let key = [7u8; 32];
+
+        assert_eq!(fingerprint("ML-DSA-44", &key).len(), 64);
+        assert_eq!(
+            fingerprint("ML-DSA-44", &key),
+            fingerprint("ML-DSA-44", &key)
+        );
+        assert_ne!(
+            fingerprint("ML-DSA-44", &key),
+            fingerprint("ML-DSA-65", &key)
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_formatting() {
+        let fingerprint = fingerprint("ML-KEM-768", b"key");
+        let formatted = format_fingerprint(&fingerprint);
+
+        assert_eq!(formatted.len(), 64 + 15);
+        assert!(fingerprints_match(&fingerprint, &formatted));
+        assert!(fingerprints_match("AB:CD", "abcd"));
+        assert!(!fingerprints_match("abcd", "abce"));
+    }
+}
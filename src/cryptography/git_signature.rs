@@ -0,0 +1,147 @@
+use anyhow::{Context, Result, anyhow, ensure};
+use chrono::{DateTime, SubsecRound, Utc};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, SecretKey as SigSecretKey, Sig};
+use serde::{Deserialize, Serialize};
+use x509_cert::der::pem::{self, LineEnding};
+
+use super::fingerprint::fingerprint;
+
+const GIT_SIGNATURE_VERSION: u64 = 1;
+
+/// Prefix of the signed message, so the signature cannot be mistaken for one over a file
+const GIT_SIGNATURE_CONTEXT: &[u8] = b"rust-seal git signature v1\0";
+
+/// git recognises OpenPGP signatures by this armor label, so it stores and hands back the signature unchanged
+const ARMOR_LABEL: &str = "PGP SIGNATURE";
+
+/// The signed part of a [`GitSignature`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GitSignatureStatement {
+    pub algorithm: SigAlgorithmVariant,
+    pub fingerprint: String,
+    pub created: DateTime<Utc>,
+}
+
+/// Detached signature over a commit or tag, armored like an OpenPGP signature.
+/// The armor contains the JSON encoded signature, not OpenPGP packets
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GitSignature {
+    pub version: u64,
+    #[serde(flatten)]
+    pub statement: GitSignatureStatement,
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+}
+
+impl GitSignature {
+    /// Signs `payload`, the commit or tag without its signature.
+    /// The creation time is truncated to seconds.
+    pub fn sign(
+        payload: &[u8],
+        signer: &Sig,
+        public_key: &[u8],
+        secret_key: &SigSecretKey,
+    ) -> Result<Self> {
+        let statement = GitSignatureStatement {
+            algorithm: signer.algorithm(),
+            fingerprint: fingerprint(&signer.algorithm().to_string(), public_key),
+            created: Utc::now().trunc_subsecs(0),
+        };
+
+        let signature = signer
+            .sign(&signed_message(&statement, payload)?, secret_key)
+            .context("Failed to sign git payload")?;
+
+        Ok(Self {
+            version: GIT_SIGNATURE_VERSION,
+            statement,
+            signature: signature.into_vec(),
+        })
+    }
+
+    /// Checks that `payload` was signed by `public_key`
+    pub fn verify(&self, payload: &[u8], public_key: &[u8]) -> Result<()> {
+        ensure!(
+            fingerprint(&self.statement.algorithm.to_string(), public_key)
+                == self.statement.fingerprint,
+            "Git signature was not made by the given key"
+        );
+
+        let signer = Sig::new(self.statement.algorithm)
+            .context("Signature algorithm of the git signature is not enabled")?;
+        let public_key = signer
+            .public_key_from_bytes(public_key)
+            .context("Signer public key is not valid")?;
+        let signature = signer
+            .signature_from_bytes(&self.signature)
+            .context("Git signature is not valid")?;
+
+        signer
+            .verify(
+                &signed_message(&self.statement, payload)?,
+                signature,
+                public_key,
+            )
+            .context("Git signature verification failed")
+    }
+
+    pub fn to_armored(&self) -> Result<String> {
+        let json = serde_json::to_vec(self).context("Failed to serialize git signature")?;
+        pem::encode_string(ARMOR_LABEL, LineEnding::LF, &json)
+            .map_err(|err| anyhow!("Failed to armor git signature: {err}"))
+    }
+
+    pub fn from_armored(armored: &str) -> Result<Self> {
+        let (label, json) = pem::decode_vec(armored.trim().as_bytes())
+            .map_err(|err| anyhow!("Git signature is not armored: {err}"))?;
+        ensure!(
+            label == ARMOR_LABEL,
+            "Expected a {ARMOR_LABEL}, but found {label}"
+        );
+
+        let signature: Self = serde_json::from_slice(&json).context(
+            "Not a rust-seal git signature. OpenPGP signatures must be verified with gpg",
+        )?;
+        ensure!(
+            signature.version <= GIT_SIGNATURE_VERSION,
+            "Git signature has version {}, but this rust-seal only supports versions up to {GIT_SIGNATURE_VERSION}",
+            signature.version
+        );
+        Ok(signature)
+    }
+}
+
+fn signed_message(statement: &GitSignatureStatement, payload: &[u8]) -> Result<Vec<u8>> {
+    let mut message = GIT_SIGNATURE_CONTEXT.to_vec();
+    message.extend(serde_json::to_vec(statement).context("Failed to serialize git signature")?);
+    message.extend_from_slice(payload);
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_signature_roundtrip() {
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa65).unwrap();
+        let (public_key, secret_key) = signer.keypair().unwrap();
+
+        let signature =
+            GitSignature::sign(b"tree 1234\n", &signer, public_key.as_ref(), &secret_key).unwrap();
+        let armored = signature.to_armored().unwrap();
+        assert!(armored.starts_with("-----BEGIN PGP SIGNATURE-----\n"));
+
+        let parsed = GitSignature::from_armored(&armored).unwrap();
+        assert_eq!(parsed, signature);
+        parsed.verify(b"tree 1234\n", public_key.as_ref()).unwrap();
+        assert!(parsed.verify(b"tree 5678\n", public_key.as_ref()).is_err());
+
+        let (other_public_key, _) = signer.keypair().unwrap();
+        assert!(
+            parsed
+                .verify(b"tree 1234\n", other_public_key.as_ref())
+                .is_err()
+        );
+    }
+}
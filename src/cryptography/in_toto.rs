@@ -0,0 +1,165 @@
+//! in-toto attestation Statements, which bind a predicate such as SLSA provenance to the digests of artifacts.
+//!
+//! Statements are carried as payload of a DSSE envelope with the payload type [`PAYLOAD_TYPE`].
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, ensure};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+pub const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Type of the Statements written by older in-toto and SLSA tooling, which only differ in name
+const STATEMENT_TYPE_V0_1: &str = "https://in-toto.io/Statement/v0.1";
+
+const SHA256: &str = "sha256";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    pub subject: Vec<Subject>,
+    #[serde(rename = "predicateType")]
+    pub predicate_type: String,
+    #[serde(default)]
+    pub predicate: Value,
+}
+
+/// An artifact the Statement is about, identified by its digests
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Subject {
+    #[serde(default)]
+    pub name: String,
+    /// Hex encoded digests by algorithm, e.g. `sha256`
+    pub digest: BTreeMap<String, String>,
+}
+
+impl Subject {
+    /// Describes the artifact `name` with the SHA-256 digest of its `content`
+    pub fn new(name: &str, content: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            digest: BTreeMap::from([(SHA256.to_string(), hex::encode(Sha256::digest(content)))]),
+        }
+    }
+
+    /// Returns true if `content` has the SHA-256 digest of the subject
+    pub fn matches_content(&self, content: &[u8]) -> bool {
+        self.matches(&hex::encode(Sha256::digest(content)))
+    }
+
+    fn matches(&self, sha256: &str) -> bool {
+        self.digest
+            .get(SHA256)
+            .is_some_and(|digest| digest.eq_ignore_ascii_case(sha256))
+    }
+}
+
+impl Statement {
+    pub fn new(subject: Vec<Subject>, predicate_type: &str, predicate: Value) -> Self {
+        Self {
+            statement_type: STATEMENT_TYPE.to_string(),
+            subject,
+            predicate_type: predicate_type.to_string(),
+            predicate,
+        }
+    }
+
+    /// Parses the payload of an envelope and checks that it is a Statement about at least one artifact
+    pub fn parse(payload: &[u8]) -> Result<Self> {
+        let statement: Self =
+            serde_json::from_slice(payload).context("The payload is not an in-toto Statement")?;
+        ensure!(
+            [STATEMENT_TYPE, STATEMENT_TYPE_V0_1].contains(&statement.statement_type.as_str()),
+            "Unsupported Statement type {}. Expected {STATEMENT_TYPE}",
+            statement.statement_type
+        );
+        ensure!(
+            !statement.subject.is_empty(),
+            "The Statement is not about any artifact"
+        );
+        Ok(statement)
+    }
+
+    /// Returns the subject with the SHA-256 digest of `content`, or None if the Statement is not about it
+    pub fn subject_of(&self, content: &[u8]) -> Option<&Subject> {
+        let sha256 = hex::encode(Sha256::digest(content));
+        self.subject.iter().find(|subject| subject.matches(&sha256))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use oqs::sig::{Algorithm, Sig};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::SigningKey,
+        cryptography::{dsse::Envelope, secret::Secret},
+    };
+
+    #[test]
+    fn test_statement_matches_subject_digests() {
+        let statement = Statement::new(
+            vec![Subject::new("app.tar", b"app")],
+            "https://slsa.dev/provenance/v1",
+            json!({ "buildDefinition": {} }),
+        );
+        let payload = serde_json::to_vec(&statement).unwrap();
+        let statement = Statement::parse(&payload).unwrap();
+
+        assert_eq!(statement.subject_of(b"app").unwrap().name, "app.tar");
+        assert!(statement.subject_of(b"other").is_none());
+        assert!(statement.subject[0].matches_content(b"app"));
+        assert!(!statement.subject[0].matches_content(b"other"));
+
+        let mut value: Value = serde_json::from_slice(&payload).unwrap();
+        value["_type"] = json!("https://example.com/Statement");
+        assert!(Statement::parse(&serde_json::to_vec(&value).unwrap()).is_err());
+        value["_type"] = json!(STATEMENT_TYPE_V0_1);
+        value["subject"] = json!([]);
+        assert!(Statement::parse(&serde_json::to_vec(&value).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_attestation_roundtrip() {
+        let statement = Statement::new(
+            vec![
+                Subject::new("app.tar", b"app"),
+                Subject::new("lib.tar", b"lib"),
+            ],
+            "https://slsa.dev/provenance/v1",
+            json!({ "buildDefinition": {} }),
+        );
+        let signature = Sig::new(Algorithm::MlDsa44).unwrap();
+        let (public_key, secret_key) = signature.keypair().unwrap();
+        let envelope = Envelope::sign(
+            PAYLOAD_TYPE,
+            &serde_json::to_vec(&statement).unwrap(),
+            &signature,
+            public_key.as_ref(),
+            &SigningKey::Local(Secret::new(secret_key)),
+        )
+        .unwrap();
+
+        let payload = envelope.verify(&signature, public_key.as_ref()).unwrap();
+        let statement = Statement::parse(&payload).unwrap();
+        let app = &statement.subject[0];
+        assert_eq!(app.name, "app.tar");
+        assert!(app.matches_content(b"app"));
+        // The content of another subject is attested, but not under this name
+        assert!(statement.subject_of(b"lib").is_some());
+        assert!(!app.matches_content(b"lib"));
+
+        let mut forged = statement.clone();
+        forged.subject[0] = Subject::new("app.tar", b"backdoored app");
+        let mut tampered = envelope.clone();
+        tampered.payload = STANDARD.encode(serde_json::to_vec(&forged).unwrap());
+        assert!(tampered.verify(&signature, public_key.as_ref()).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! JSON Web Keys and compact JWS (RFC 7515) with ML-DSA, as specified by draft-ietf-cose-dilithium.
+//!
+//! ML-DSA keys have the key type `AKP` (algorithm key pair) and the algorithm names `ML-DSA-44`, `ML-DSA-65` and `ML-DSA-87`.
+//! Signatures are pure ML-DSA with an empty context over the JWS signing input.
+use anyhow::{Context, Result, bail, ensure};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use oqs::sig::{Algorithm as SigAlgorithm, PublicKeyRef, Sig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::signature::verify_file_with_signature;
+use crate::agent::SigningKey;
+
+const KEY_TYPE: &str = "AKP";
+
+/// Public ML-DSA key as JSON Web Key
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Jwk {
+    pub kty: String,
+    pub alg: String,
+    #[serde(rename = "pub")]
+    pub public_key: String,
+    /// JWK thumbprint (RFC 7638) of the key
+    pub kid: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct JwsHeader {
+    alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+    /// Extensions the verifier must understand. None are supported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crit: Option<serde_json::Value>,
+}
+
+/// Returns the JOSE algorithm name of an ML-DSA algorithm
+fn algorithm_name(algorithm: SigAlgorithm) -> Result<String> {
+    match algorithm {
+        SigAlgorithm::MlDsa44 | SigAlgorithm::MlDsa65 | SigAlgorithm::MlDsa87 => {
+            Ok(algorithm.to_string())
+        }
+        _ => bail!("{algorithm} has no JOSE algorithm. Supported: ML-DSA-44, ML-DSA-65, ML-DSA-87"),
+    }
+}
+
+/// Returns the JWK of an ML-DSA public key
+pub fn public_key_jwk(algorithm: SigAlgorithm, public_key: &[u8]) -> Result<Jwk> {
+    Ok(Jwk {
+        kty: KEY_TYPE.to_string(),
+        alg: algorithm_name(algorithm)?,
+        public_key: URL_SAFE_NO_PAD.encode(public_key),
+        kid: jwk_thumbprint(algorithm, public_key)?,
+    })
+}
+
+/// Returns the JWK thumbprint (RFC 7638) of an ML-DSA public key, the hash of its required members in lexicographic order
+pub fn jwk_thumbprint(algorithm: SigAlgorithm, public_key: &[u8]) -> Result<String> {
+    let members = format!(
+        r#"{{"alg":"{}","kty":"{KEY_TYPE}","pub":"{}"}}"#,
+        algorithm_name(algorithm)?,
+        URL_SAFE_NO_PAD.encode(public_key)
+    );
+    let digest = Sha256::digest(members.as_bytes());
+    Ok(URL_SAFE_NO_PAD.encode(digest))
+}
+
+/// Signs `payload` as compact JWS. The header names the key by its JWK thumbprint
+pub fn sign_jws(
+    payload: &[u8],
+    signature: &Sig,
+    public_key: &[u8],
+    secret_key: &SigningKey,
+) -> Result<String> {
+    let header = JwsHeader {
+        alg: algorithm_name(signature.algorithm())?,
+        kid: Some(jwk_thumbprint(signature.algorithm(), public_key)?),
+        crit: None,
+    };
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+        URL_SAFE_NO_PAD.encode(payload)
+    );
+
+    let jws_signature = secret_key
+        .sign(signature, signing_input.as_bytes())
+        .context("Failed to sign JWS")?;
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(jws_signature)
+    ))
+}
+
+/// Verifies a compact JWS and returns its payload
+pub fn verify_jws(token: &str, signature: &Sig, public_key: PublicKeyRef) -> Result<Vec<u8>> {
+    let [header, payload, jws_signature]: [&str; 3] = token
+        .trim()
+        .split('.')
+        .collect::<Vec<_>>()
+        .try_into()
+        .ok()
+        .context("JWS must consist of header, payload and signature separated by dots")?;
+
+    let decoded_header: JwsHeader = serde_json::from_slice(
+        &URL_SAFE_NO_PAD
+            .decode(header)
+            .context("JWS header is not base64url")?,
+    )
+    .context("JWS header is not valid JSON")?;
+    ensure!(
+        decoded_header.crit.is_none(),
+        "JWS header has critical extensions, which are not supported"
+    );
+    let expected = algorithm_name(signature.algorithm())?;
+    ensure!(
+        decoded_header.alg == expected,
+        "JWS is signed with {}, but {expected} was requested",
+        decoded_header.alg
+    );
+
+    let jws_signature = URL_SAFE_NO_PAD
+        .decode(jws_signature)
+        .context("JWS signature is not base64url")?;
+    verify_file_with_signature(
+        format!("{header}.{payload}").as_bytes(),
+        &jws_signature,
+        signature,
+        public_key,
+    )?;
+
+    URL_SAFE_NO_PAD
+        .decode(payload)
+        .context("JWS payload is not base64url")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptography::secret::Secret;
+
+    #[test]
+    fn test_jws_roundtrip() {
+        let signature = Sig::new(SigAlgorithm::MlDsa44).unwrap();
+        let (public_key, secret_key) = signature.keypair().unwrap();
+
+        let token = sign_jws(
+            b"payload",
+            &signature,
+            public_key.as_ref(),
+            &SigningKey::Local(Secret::new(secret_key)),
+        )
+        .unwrap();
+        assert_eq!(
+            verify_jws(&token, &signature, PublicKeyRef::from(&public_key)).unwrap(),
+            b"payload"
+        );
+
+        let (other_public_key, _) = signature.keypair().unwrap();
+        assert!(verify_jws(&token, &signature, PublicKeyRef::from(&other_public_key)).is_err());
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let tampered = format!(
+            "{}.{}.{}",
+            parts[0],
+            URL_SAFE_NO_PAD.encode(b"other payload"),
+            parts[2]
+        );
+        assert!(verify_jws(&tampered, &signature, PublicKeyRef::from(&public_key)).is_err());
+    }
+
+    #[test]
+    fn test_jwk_thumbprint_is_kid() {
+        let signature = Sig::new(SigAlgorithm::MlDsa65).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+
+        let jwk = public_key_jwk(SigAlgorithm::MlDsa65, public_key.as_ref()).unwrap();
+        assert_eq!(jwk.kty, "AKP");
+        assert_eq!(jwk.alg, "ML-DSA-65");
+        assert_eq!(
+            URL_SAFE_NO_PAD.decode(&jwk.public_key).unwrap(),
+            public_key.as_ref()
+        );
+        assert_eq!(
+            jwk.kid,
+            jwk_thumbprint(SigAlgorithm::MlDsa65, public_key.as_ref()).unwrap()
+        );
+        assert!(public_key_jwk(SigAlgorithm::Falcon512, public_key.as_ref()).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Recovery seeds, from which KEM keypairs are generated deterministically, written as a word mnemonic.
+//!
+//! The mnemonic encodes the 32-byte seed and an 8-bit checksum with the BIP-39 English word list, giving 24 words.
+//! The seed of a keypair is derived from the recovery seed with HKDF-SHA256 and the algorithm name, so the recovery
+//! seed is never used as key material itself. Every `init kem --mnemonic` draws a new recovery seed, whose mnemonic
+//! recreates the key of that one algorithm.
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use anyhow::{Result, anyhow, ensure};
+use hkdf::Hkdf;
+use oqs::kem::Kem;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use super::secret::SecretBytes;
+
+pub const RECOVERY_SEED_LEN: usize = 32;
+
+/// 256 bits of seed and 8 bits of checksum, 11 bits per word
+const MNEMONIC_WORDS: usize = 24;
+
+const BITS_PER_WORD: usize = 11;
+
+/// The 2048 words of the BIP-39 English word list, sorted
+const WORDLIST: &str = include_str!("bip39-english.txt");
+
+/// Salt of the key derivation, so the derived seeds cannot be mistaken for anything else
+const KEYGEN_SALT: &[u8] = b"rust-seal keygen v1";
+
+/// Seed from which a keypair of an algorithm that supports deterministic key generation is derived
+pub struct RecoverySeed {
+    seed: SecretBytes,
+}
+
+impl RecoverySeed {
+    pub fn generate() -> Self {
+        let mut seed = vec![0; RECOVERY_SEED_LEN];
+        OsRng.fill_bytes(&mut seed);
+        Self {
+            seed: SecretBytes::new(seed),
+        }
+    }
+
+    /// Parses a mnemonic written by [`RecoverySeed::to_mnemonic`]. Case and whitespace between the words are ignored
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self> {
+        let wordlist: Vec<&str> = WORDLIST.lines().collect();
+        let words: Vec<&str> = mnemonic.split_whitespace().collect();
+        ensure!(
+            words.len() == MNEMONIC_WORDS,
+            "The mnemonic must have {MNEMONIC_WORDS} words, but has {}",
+            words.len()
+        );
+
+        let mut bits = Zeroizing::new(vec![0u8; RECOVERY_SEED_LEN + 1]);
+        for (i, word) in words.iter().enumerate() {
+            let word = Zeroizing::new(word.to_lowercase());
+            let index = wordlist
+                .binary_search(&word.as_str())
+                .map_err(|_| anyhow!("Word {} of the mnemonic is not in the word list", i + 1))?;
+            for bit in 0..BITS_PER_WORD {
+                if (index >> (BITS_PER_WORD - 1 - bit)) & 1 == 1 {
+                    let position = i * BITS_PER_WORD + bit;
+                    bits[position / 8] |= 1 << (7 - position % 8);
+                }
+            }
+        }
+
+        let (seed, checksum) = bits.split_at(RECOVERY_SEED_LEN);
+        ensure!(
+            Sha256::digest(seed)[0] == checksum[0],
+            "The checksum of the mnemonic does not match. Check the words and their order"
+        );
+        Ok(Self {
+            seed: SecretBytes::new(seed.to_vec()),
+        })
+    }
+
+    /// Returns the 24 words encoding the seed, separated by spaces
+    pub fn to_mnemonic(&self) -> Zeroizing<String> {
+        let wordlist: Vec<&str> = WORDLIST.lines().collect();
+        let mut bits = Zeroizing::new(self.seed.to_vec());
+        bits.push(Sha256::digest(self.seed.as_ref())[0]);
+
+        let words: Vec<&str> = (0..MNEMONIC_WORDS)
+            .map(|i| {
+                let index =
+                    (i * BITS_PER_WORD..(i + 1) * BITS_PER_WORD).fold(0, |index, position| {
+                        (index << 1) | usize::from((bits[position / 8] >> (7 - position % 8)) & 1)
+                    });
+                wordlist[index]
+            })
+            .collect();
+        Zeroizing::new(words.join(" "))
+    }
+
+    /// Derives the seed from which the keypair of `kem` is expanded with `Kem::keypair_derand`
+    pub fn derive_kem_seed(&self, kem: &Kem) -> Result<SecretBytes> {
+        let algorithm = kem.algorithm();
+        ensure!(
+            kem.length_keypair_seed() > 0,
+            "{algorithm} does not support key generation from a seed"
+        );
+
+        let mut seed = vec![0; kem.length_keypair_seed()];
+        Hkdf::<Sha256>::new(Some(KEYGEN_SALT), self.seed.as_ref())
+            .expand(algorithm.name().as_bytes(), &mut seed)
+            .map_err(|_| anyhow!("Failed to derive the seed of {algorithm}"))?;
+        Ok(SecretBytes::new(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oqs::kem::Algorithm as KemAlgorithm;
+
+    #[test]
+    fn test_mnemonic_matches_bip39_test_vectors() {
+        let seed = RecoverySeed {
+            seed: SecretBytes::new(vec![0x7f; RECOVERY_SEED_LEN]),
+        };
+        let mnemonic = seed.to_mnemonic();
+        assert_eq!(
+            mnemonic.as_str(),
+            "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth title"
+        );
+
+        let parsed =
+            RecoverySeed::from_mnemonic(&format!("  {}\n", mnemonic.to_uppercase())).unwrap();
+        assert_eq!(parsed.seed, seed.seed);
+
+        let zero = RecoverySeed::from_mnemonic(&format!("{}art", "abandon ".repeat(23))).unwrap();
+        assert_eq!(zero.seed.as_ref(), [0; RECOVERY_SEED_LEN]);
+    }
+
+    #[test]
+    fn test_mnemonic_detects_mistakes() {
+        let mnemonic = RecoverySeed::generate().to_mnemonic();
+        let mut words: Vec<&str> = mnemonic.split(' ').collect();
+
+        assert!(RecoverySeed::from_mnemonic(&words[1..].join(" ")).is_err());
+        words[3] = "rustseal";
+        assert!(RecoverySeed::from_mnemonic(&words.join(" ")).is_err());
+
+        assert!(RecoverySeed::from_mnemonic(&format!("{}zoo", "abandon ".repeat(23))).is_err());
+    }
+
+    #[test]
+    fn test_derived_keypairs_are_deterministic() {
+        let recovery_seed = RecoverySeed::generate();
+        let recovered = RecoverySeed::from_mnemonic(&recovery_seed.to_mnemonic()).unwrap();
+
+        let kem = Kem::new(KemAlgorithm::MlKem768).unwrap();
+        let keypair = |seed: &RecoverySeed| {
+            let seed = seed.derive_kem_seed(&kem).unwrap();
+            let (public_key, _) = kem
+                .keypair_derand(kem.keypair_seed_from_bytes(seed.as_ref()).unwrap())
+                .unwrap();
+            public_key
+        };
+        assert_eq!(keypair(&recovery_seed), keypair(&recovered));
+
+        let other = Kem::new(KemAlgorithm::MlKem512).unwrap();
+        assert_ne!(
+            recovery_seed.derive_kem_seed(&kem).unwrap().as_ref()[..32],
+            recovery_seed.derive_kem_seed(&other).unwrap().as_ref()[..32]
+        );
+    }
+}
@@ -1,4 +1,22 @@
+pub mod asn1;
+pub mod binding;
+pub mod bundle;
+pub mod classic;
+pub mod cose;
+pub mod dsse;
+pub mod fingerprint;
+pub mod git_signature;
+pub mod in_toto;
+pub mod jose;
+pub mod mnemonic;
+pub mod pkcs8;
+pub mod revocation;
+pub mod secret;
+pub mod shamir;
 pub mod signature;
+pub mod signature_bundle;
 pub mod symmetric;
+pub mod timestamp;
+pub mod x509;
 
 pub use symmetric::AES_KEY_SIZE;
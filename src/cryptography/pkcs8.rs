@@ -0,0 +1,371 @@
+//! PKCS#8 private keys and SubjectPublicKeyInfo (SPKI) public keys, as read and written by OpenSSL 3.5 and the oqs-provider.
+//!
+//! The private key has one of the forms of draft-ietf-lamps-kyber-certificates and RFC 9881:
+//! the seed that key generation expands, the expanded key, or both.
+use std::{fmt, path::Path, str::FromStr};
+
+use anyhow::{Context, Result, bail, ensure};
+use oqs::{
+    kem::{Algorithm as KemAlgorithm, Kem},
+    sig::Algorithm as SigAlgorithm,
+};
+use pkcs8::PrivateKeyInfo;
+use x509_cert::{
+    der::{
+        Any, Decode, Encode, Tag, TagNumber, Tagged,
+        asn1::{BitString, OctetString},
+    },
+    spki::{AlgorithmIdentifierOwned, AlgorithmIdentifierRef, SubjectPublicKeyInfoOwned},
+};
+
+use super::asn1::{
+    Encoding, encode, kem_algorithm, kem_algorithm_identifier, read_der, signature_algorithm,
+    signature_algorithm_identifier, write_encoded,
+};
+use super::secret::SecretBytes;
+use crate::util::write_secret_file;
+
+/// Length of the ML-KEM seed `d || z` (FIPS 203, algorithm 19)
+const ML_KEM_SEED_LEN: usize = 64;
+/// Length of the ML-DSA seed `ξ` (FIPS 204, algorithm 1)
+const ML_DSA_SEED_LEN: usize = 32;
+
+/// Format of an exported key file. JWK and COSE_Key are only defined for ML-DSA public keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    Pem,
+    Der,
+    Jwk,
+    CoseKey,
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "pem" => Ok(KeyFormat::Pem),
+            "der" => Ok(KeyFormat::Der),
+            "jwk" => Ok(KeyFormat::Jwk),
+            "cose-key" => Ok(KeyFormat::CoseKey),
+            _ => bail!("Unsupported key format: {value}. Expected one of: pem, der, jwk, cose-key"),
+        }
+    }
+}
+
+/// Algorithm of a key in a PKCS#8 or SPKI file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    Kem(KemAlgorithm),
+    Signature(SigAlgorithm),
+}
+
+impl KeyAlgorithm {
+    fn identifier(self) -> Result<AlgorithmIdentifierOwned> {
+        match self {
+            KeyAlgorithm::Kem(algorithm) => kem_algorithm_identifier(algorithm),
+            KeyAlgorithm::Signature(algorithm) => signature_algorithm_identifier(algorithm),
+        }
+    }
+
+    fn from_identifier(identifier: &AlgorithmIdentifierOwned) -> Result<Self> {
+        match kem_algorithm(identifier)? {
+            Some(algorithm) => Ok(KeyAlgorithm::Kem(algorithm)),
+            None => signature_algorithm(identifier).map(KeyAlgorithm::Signature),
+        }
+    }
+
+    fn seed_len(self) -> usize {
+        match self {
+            KeyAlgorithm::Kem(_) => ML_KEM_SEED_LEN,
+            KeyAlgorithm::Signature(_) => ML_DSA_SEED_LEN,
+        }
+    }
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyAlgorithm::Kem(algorithm) => write!(f, "{algorithm}"),
+            KeyAlgorithm::Signature(algorithm) => write!(f, "{algorithm}"),
+        }
+    }
+}
+
+/// Content of the private key field of a PKCS#8 file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivateKeyForm {
+    Seed(SecretBytes),
+    ExpandedKey(SecretBytes),
+    Both {
+        seed: SecretBytes,
+        expanded_key: SecretBytes,
+    },
+}
+
+impl PrivateKeyForm {
+    pub fn seed(&self) -> Option<&[u8]> {
+        match self {
+            PrivateKeyForm::Seed(seed) | PrivateKeyForm::Both { seed, .. } => Some(seed.as_ref()),
+            PrivateKeyForm::ExpandedKey(_) => None,
+        }
+    }
+
+    pub fn expanded_key(&self) -> Option<&[u8]> {
+        match self {
+            PrivateKeyForm::ExpandedKey(expanded_key)
+            | PrivateKeyForm::Both { expanded_key, .. } => Some(expanded_key.as_ref()),
+            PrivateKeyForm::Seed(_) => None,
+        }
+    }
+
+    fn to_der(&self) -> Result<SecretBytes> {
+        let der = match self {
+            PrivateKeyForm::Seed(seed) => Any::new(seed_tag(), seed.as_slice())?.to_der()?,
+            PrivateKeyForm::ExpandedKey(expanded_key) => {
+                OctetString::new(expanded_key.as_slice())?.to_der()?
+            }
+            PrivateKeyForm::Both { seed, expanded_key } => vec![
+                OctetString::new(seed.as_slice())?,
+                OctetString::new(expanded_key.as_slice())?,
+            ]
+            .to_der()?,
+        };
+        Ok(SecretBytes::new(der))
+    }
+
+    fn from_der(der: &[u8]) -> Result<Self> {
+        let any = Any::from_der(der).context("Private key is not valid DER")?;
+        let form = match any.tag() {
+            Tag::OctetString => PrivateKeyForm::ExpandedKey(any.value().to_vec().into()),
+            Tag::Sequence => {
+                let [seed, expanded_key]: [OctetString; 2] = Vec::<OctetString>::from_der(der)
+                    .ok()
+                    .and_then(|both| both.try_into().ok())
+                    .context("Private key is not a valid sequence of seed and expanded key")?;
+                PrivateKeyForm::Both {
+                    seed: seed.into_bytes().into(),
+                    expanded_key: expanded_key.into_bytes().into(),
+                }
+            }
+            tag if tag == seed_tag() => PrivateKeyForm::Seed(any.value().to_vec().into()),
+            tag => bail!("Unsupported private key form with tag {tag}"),
+        };
+        Ok(form)
+    }
+}
+
+/// The seed form is `[0] IMPLICIT OCTET STRING`
+fn seed_tag() -> Tag {
+    Tag::ContextSpecific {
+        constructed: false,
+        number: TagNumber::N0,
+    }
+}
+
+/// A private key read from or written to a PKCS#8 file
+#[derive(Debug)]
+pub struct PrivateKey {
+    pub algorithm: KeyAlgorithm,
+    pub form: PrivateKeyForm,
+    /// Public key of a version 2 PKCS#8 file (RFC 5958), if present
+    pub public_key: Option<Vec<u8>>,
+}
+
+/// Writes a private key as PKCS#8, readable only by its owner.
+/// The public key is left out, since OpenSSL does not read version 2 files
+pub fn write_private_key(
+    path: &Path,
+    key: &PrivateKey,
+    encoding: Encoding,
+    force: bool,
+) -> Result<()> {
+    let identifier = key.algorithm.identifier()?;
+    let private_key = key.form.to_der()?;
+    let info = PrivateKeyInfo::new(
+        AlgorithmIdentifierRef {
+            oid: identifier.oid,
+            parameters: None,
+        },
+        &private_key,
+    );
+    write_secret_file(path, SecretBytes::new(encode(&info, encoding)?), force)
+}
+
+/// Reads a PKCS#8 private key in PEM or DER encoding
+pub fn read_private_key(path: &Path) -> Result<PrivateKey> {
+    let der = SecretBytes::new(read_der(path, "PRIVATE KEY")?);
+    let info = PrivateKeyInfo::try_from(der.as_slice()).context(format!(
+        "Failed to parse PKCS#8 private key {}",
+        path.display()
+    ))?;
+    ensure!(
+        info.algorithm.parameters.is_none(),
+        "Algorithm identifier {} must not have parameters",
+        info.algorithm.oid
+    );
+    let algorithm = KeyAlgorithm::from_identifier(&AlgorithmIdentifierOwned {
+        oid: info.algorithm.oid,
+        parameters: None,
+    })?;
+
+    let form = PrivateKeyForm::from_der(info.private_key)?;
+    if let Some(seed) = form.seed() {
+        ensure!(
+            seed.len() == algorithm.seed_len(),
+            "{algorithm} seed must be {} bytes long, but has {} bytes",
+            algorithm.seed_len(),
+            seed.len()
+        );
+    }
+
+    Ok(PrivateKey {
+        algorithm,
+        form,
+        public_key: info.public_key.map(<[u8]>::to_vec),
+    })
+}
+
+/// Writes a public key as SubjectPublicKeyInfo
+pub fn write_public_key(
+    path: &Path,
+    algorithm: KeyAlgorithm,
+    public_key: &[u8],
+    encoding: Encoding,
+    force: bool,
+) -> Result<()> {
+    let info = SubjectPublicKeyInfoOwned {
+        algorithm: algorithm.identifier()?,
+        subject_public_key: BitString::from_bytes(public_key)
+            .context("Failed to encode public key")?,
+    };
+    write_encoded(path, &info, encoding, force)
+}
+
+/// Reads a SubjectPublicKeyInfo public key in PEM or DER encoding
+pub fn read_public_key(path: &Path) -> Result<(KeyAlgorithm, Vec<u8>)> {
+    let der = read_der(path, "PUBLIC KEY")?;
+    parse_public_key(&der)?.context(format!(
+        "Failed to parse SubjectPublicKeyInfo public key {}",
+        path.display()
+    ))
+}
+
+/// Parses a SubjectPublicKeyInfo in PEM or DER encoding.
+/// Returns `None` if `content` is not a SubjectPublicKeyInfo, e.g. a raw liboqs public key.
+pub fn parse_public_key(content: &[u8]) -> Result<Option<(KeyAlgorithm, Vec<u8>)>> {
+    let der = if content.starts_with(b"-----BEGIN PUBLIC KEY-----") {
+        let (_, der) = x509_cert::der::pem::decode_vec(content)
+            .map_err(|err| anyhow::anyhow!("Failed to decode PEM public key: {err}"))?;
+        der
+    } else {
+        content.to_vec()
+    };
+    let Ok(info) = SubjectPublicKeyInfoOwned::from_der(&der) else {
+        return Ok(None);
+    };
+
+    let algorithm = KeyAlgorithm::from_identifier(&info.algorithm)?;
+    let public_key = info
+        .subject_public_key
+        .as_bytes()
+        .context("Public key is not a whole number of bytes")?
+        .to_vec();
+    Ok(Some((algorithm, public_key)))
+}
+
+/// Returns the encapsulation key contained in an expanded ML-KEM decapsulation key (FIPS 203, algorithm 16):
+/// `dk = dk_PKE || ek || H(ek) || z`, where `dk_PKE` is 32 bytes shorter than `ek`
+pub fn ml_kem_public_key(kem: &Kem, expanded_key: &[u8]) -> Result<Vec<u8>> {
+    let public_key_len = kem.length_public_key();
+    ensure!(
+        expanded_key.len() == kem.length_secret_key(),
+        "{} expanded key must be {} bytes long, but has {} bytes",
+        kem.algorithm(),
+        kem.length_secret_key(),
+        expanded_key.len()
+    );
+    let start = public_key_len - 32;
+    Ok(expanded_key[start..start + public_key_len].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use oqs::sig::Sig;
+
+    use super::*;
+
+    #[test]
+    fn test_private_key_forms_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let forms = [
+            PrivateKeyForm::Seed(vec![7; ML_KEM_SEED_LEN].into()),
+            PrivateKeyForm::ExpandedKey(vec![1, 2, 3].into()),
+            PrivateKeyForm::Both {
+                seed: vec![9; ML_KEM_SEED_LEN].into(),
+                expanded_key: vec![4, 5, 6].into(),
+            },
+        ];
+
+        for (index, form) in forms.into_iter().enumerate() {
+            let encoding = if index % 2 == 0 {
+                Encoding::Pem
+            } else {
+                Encoding::Der
+            };
+            let path = dir.path().join(format!("{index}.key"));
+            let key = PrivateKey {
+                algorithm: KeyAlgorithm::Kem(KemAlgorithm::MlKem768),
+                form,
+                public_key: None,
+            };
+            write_private_key(&path, &key, encoding, false).unwrap();
+
+            let read = read_private_key(&path).unwrap();
+            assert_eq!(read.algorithm, key.algorithm);
+            assert_eq!(read.form, key.form);
+        }
+    }
+
+    #[test]
+    fn test_seed_length_is_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("short.key");
+        let key = PrivateKey {
+            algorithm: KeyAlgorithm::Signature(SigAlgorithm::MlDsa44),
+            form: PrivateKeyForm::Seed(vec![0; ML_KEM_SEED_LEN].into()),
+            public_key: None,
+        };
+        write_private_key(&path, &key, Encoding::Der, false).unwrap();
+
+        assert!(read_private_key(&path).is_err());
+    }
+
+    #[test]
+    fn test_public_key_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.pem");
+        let signature = Sig::new(SigAlgorithm::MlDsa65).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+        let algorithm = KeyAlgorithm::Signature(SigAlgorithm::MlDsa65);
+
+        write_public_key(&path, algorithm, public_key.as_ref(), Encoding::Pem, false).unwrap();
+
+        assert_eq!(
+            read_public_key(&path).unwrap(),
+            (algorithm, public_key.into_vec())
+        );
+        assert_eq!(parse_public_key(b"raw liboqs key").unwrap(), None);
+    }
+
+    #[test]
+    fn test_ml_kem_public_key_from_expanded_key() {
+        let kem = Kem::new(KemAlgorithm::MlKem512).unwrap();
+        let (public_key, secret_key) = kem.keypair().unwrap();
+
+        assert_eq!(
+            ml_kem_public_key(&kem, secret_key.as_ref()).unwrap(),
+            public_key.into_vec()
+        );
+    }
+}
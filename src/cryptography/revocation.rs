@@ -0,0 +1,307 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, SubsecRound, Utc};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, SecretKey as SigSecretKey, Sig};
+use serde::{Deserialize, Serialize};
+
+use super::fingerprint::fingerprint;
+
+const REVOCATION_VERSION: u64 = 1;
+
+/// Prefix of the signed revocation statement, so the signature cannot be mistaken for one over a file
+const REVOCATION_CONTEXT: &[u8] = b"rust-seal revocation v1\0";
+
+/// Reason for revoking a key. The codes are the ones of OpenPGP revocation signatures (RFC 4880, section 5.2.3.23)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RevocationReason {
+    Unspecified,
+    Superseded,
+    KeyCompromise,
+    Retired,
+}
+
+impl RevocationReason {
+    pub const ALL: [RevocationReason; 4] = [
+        RevocationReason::Unspecified,
+        RevocationReason::Superseded,
+        RevocationReason::KeyCompromise,
+        RevocationReason::Retired,
+    ];
+
+    pub fn code(self) -> u8 {
+        match self {
+            RevocationReason::Unspecified => 0,
+            RevocationReason::Superseded => 1,
+            RevocationReason::KeyCompromise => 2,
+            RevocationReason::Retired => 3,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RevocationReason::Unspecified => "unspecified",
+            RevocationReason::Superseded => "superseded",
+            RevocationReason::KeyCompromise => "key-compromise",
+            RevocationReason::Retired => "retired",
+        }
+    }
+}
+
+impl FromStr for RevocationReason {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        RevocationReason::ALL
+            .into_iter()
+            .find(|reason| reason.name() == value || reason.code().to_string() == value)
+            .context(format!(
+                "Unsupported revocation reason: {value}. Expected one of: {}",
+                RevocationReason::ALL.map(RevocationReason::name).join(", ")
+            ))
+    }
+}
+
+impl fmt::Display for RevocationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.code())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    Signature,
+    Kem,
+}
+
+/// Key to be revoked
+#[derive(Debug, Clone)]
+pub struct RevokedKey {
+    pub key_type: KeyType,
+    /// liboqs name of the key's algorithm
+    pub algorithm: String,
+    pub fingerprint: String,
+}
+
+/// The signed part of a [`RevocationCertificate`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RevocationStatement {
+    pub key_type: KeyType,
+    /// liboqs name of the revoked key's algorithm
+    pub algorithm: String,
+    pub fingerprint: String,
+    pub reason: RevocationReason,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub revoked_at: DateTime<Utc>,
+    pub signer_algorithm: SigAlgorithmVariant,
+    pub signer_fingerprint: String,
+}
+
+/// Announces that a key must no longer be used.
+///
+/// Signature keys sign their own revocation. KEM keys cannot sign, so their revocation is signed by a signature key of the same owner.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RevocationCertificate {
+    pub version: u64,
+    #[serde(flatten)]
+    pub statement: RevocationStatement,
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+}
+
+impl RevocationCertificate {
+    /// Creates a revocation of `key`, signed by `signer`.
+    /// The revocation time is truncated to seconds.
+    pub fn sign(
+        key: RevokedKey,
+        reason: RevocationReason,
+        comment: Option<String>,
+        signer: &Sig,
+        signer_public_key: &[u8],
+        signer_secret_key: &SigSecretKey,
+    ) -> Result<Self> {
+        let statement = RevocationStatement {
+            key_type: key.key_type,
+            algorithm: key.algorithm,
+            fingerprint: key.fingerprint,
+            reason,
+            comment,
+            revoked_at: Utc::now().trunc_subsecs(0),
+            signer_algorithm: signer.algorithm(),
+            signer_fingerprint: fingerprint(&signer.algorithm().to_string(), signer_public_key),
+        };
+
+        let signature = signer
+            .sign(&signed_message(&statement)?, signer_secret_key)
+            .context("Failed to sign revocation")?;
+
+        Ok(Self {
+            version: REVOCATION_VERSION,
+            statement,
+            signature: signature.into_vec(),
+        })
+    }
+
+    /// Checks that the certificate was signed by `signer_public_key`
+    pub fn verify(&self, signer_public_key: &[u8]) -> Result<()> {
+        ensure!(
+            self.version <= REVOCATION_VERSION,
+            "Revocation certificate has version {}, but this rust-seal only supports versions up to {REVOCATION_VERSION}",
+            self.version
+        );
+        ensure!(
+            fingerprint(
+                &self.statement.signer_algorithm.to_string(),
+                signer_public_key
+            ) == self.statement.signer_fingerprint,
+            "Revocation certificate was not signed by the given key"
+        );
+
+        let signer = Sig::new(self.statement.signer_algorithm)
+            .context("Signature algorithm of the revocation is not enabled")?;
+        let public_key = signer
+            .public_key_from_bytes(signer_public_key)
+            .context("Signer public key is not valid")?;
+        let signature = signer
+            .signature_from_bytes(&self.signature)
+            .context("Revocation signature is not valid")?;
+
+        signer
+            .verify(&signed_message(&self.statement)?, signature, public_key)
+            .context("Revocation signature verification failed")
+    }
+}
+
+fn signed_message(statement: &RevocationStatement) -> Result<Vec<u8>> {
+    let mut message = REVOCATION_CONTEXT.to_vec();
+    message.extend(serde_json::to_vec(statement).context("Failed to serialize revocation")?);
+    Ok(message)
+}
+
+/// Fails if the key described by `description` is revoked or expired
+pub fn ensure_key_valid(
+    description: &str,
+    expires: Option<DateTime<Utc>>,
+    revocation: Option<&RevocationCertificate>,
+) -> Result<()> {
+    ensure_key_valid_at(description, expires, revocation, Utc::now())
+}
+
+/// Fails if the key described by `description` is revoked or had expired at `time`.
+/// A revoked key is never valid, since it might have been revoked because it was compromised
+pub fn ensure_key_valid_at(
+    description: &str,
+    expires: Option<DateTime<Utc>>,
+    revocation: Option<&RevocationCertificate>,
+    time: DateTime<Utc>,
+) -> Result<()> {
+    if let Some(revocation) = revocation {
+        let statement = &revocation.statement;
+        match &statement.comment {
+            Some(comment) => bail!(
+                "{description} was revoked on {} for reason {}: {comment}",
+                statement.revoked_at.format("%Y-%m-%d"),
+                statement.reason
+            ),
+            None => bail!(
+                "{description} was revoked on {} for reason {}",
+                statement.revoked_at.format("%Y-%m-%d"),
+                statement.reason
+            ),
+        }
+    }
+
+    if let Some(expires) = expires {
+        ensure!(
+            time < expires,
+            "{description} expired on {}",
+            expires.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use oqs::sig::Algorithm;
+
+    use super::*;
+
+    fn revoke(signer: &Sig) -> (RevocationCertificate, Vec<u8>) {
+        let (public_key, secret_key) = signer.keypair().unwrap();
+        let certificate = RevocationCertificate::sign(
+            RevokedKey {
+                key_type: KeyType::Signature,
+                algorithm: "ML-DSA-44".to_string(),
+                fingerprint: fingerprint("ML-DSA-44", public_key.as_ref()),
+            },
+            RevocationReason::KeyCompromise,
+            Some("Laptop stolen".to_string()),
+            signer,
+            public_key.as_ref(),
+            &secret_key,
+        )
+        .unwrap();
+        (certificate, public_key.into_vec())
+    }
+
+    #[test]
+    fn test_revocation_roundtrip() {
+        let signer = Sig::new(Algorithm::MlDsa44).unwrap();
+        let (certificate, public_key) = revoke(&signer);
+
+        let json = serde_json::to_string(&certificate).unwrap();
+        let parsed: RevocationCertificate = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, certificate);
+        parsed.verify(&public_key).unwrap();
+    }
+
+    #[test]
+    fn test_modified_revocation_is_rejected() {
+        let signer = Sig::new(Algorithm::MlDsa44).unwrap();
+        let (mut certificate, public_key) = revoke(&signer);
+        let (other_public_key, _) = signer.keypair().unwrap();
+
+        assert!(certificate.verify(other_public_key.as_ref()).is_err());
+
+        certificate.statement.reason = RevocationReason::Superseded;
+        assert!(certificate.verify(&public_key).is_err());
+    }
+
+    #[test]
+    fn test_ensure_key_valid() {
+        let signer = Sig::new(Algorithm::MlDsa44).unwrap();
+        let (certificate, _) = revoke(&signer);
+        let tomorrow = Utc::now() + Duration::days(1);
+        let yesterday = Utc::now() - Duration::days(1);
+
+        assert!(ensure_key_valid("Key", None, None).is_ok());
+        assert!(ensure_key_valid("Key", Some(tomorrow), None).is_ok());
+        assert!(ensure_key_valid("Key", Some(yesterday), None).is_err());
+        assert!(ensure_key_valid("Key", Some(tomorrow), Some(&certificate)).is_err());
+
+        // A key that expired is valid for signatures timestamped before, a revoked key never is
+        let last_week = Utc::now() - Duration::days(7);
+        assert!(ensure_key_valid_at("Key", Some(yesterday), None, last_week).is_ok());
+        assert!(ensure_key_valid_at("Key", None, Some(&certificate), last_week).is_err());
+    }
+
+    #[test]
+    fn test_reason_parsing() {
+        assert_eq!(
+            "key-compromise".parse::<RevocationReason>().unwrap(),
+            RevocationReason::KeyCompromise
+        );
+        assert_eq!(
+            "1".parse::<RevocationReason>().unwrap(),
+            RevocationReason::Superseded
+        );
+        assert!("lost".parse::<RevocationReason>().is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! Owned secret material: secret keys, shared secrets, seeds and decrypted plaintext.
+//!
+//! A [`Secret`] is overwritten with zeros when it is dropped, is locked into memory with `mlock`
+//! where the operating system allows it, so it is not written to swap, and is redacted in `Debug` output.
+use std::{
+    fmt::{self, Debug},
+    ops::Deref,
+};
+
+use oqs::{
+    kem::{
+        KeypairSeed, SecretKey as KemSecretKey, SecretKeyRef as KemSecretKeyRef, SharedSecret,
+        SharedSecretRef,
+    },
+    sig::{SecretKey as SigSecretKey, SecretKeyRef as SigSecretKeyRef},
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Buffer whose bytes can be taken out without copying, so they can be wiped
+pub trait SecretBuffer: AsRef<[u8]> {
+    fn into_vec(self) -> Vec<u8>;
+}
+
+impl SecretBuffer for Vec<u8> {
+    fn into_vec(self) -> Vec<u8> {
+        self
+    }
+}
+
+macro_rules! impl_secret_buffer {
+    ($($buffer:ty),*) => {
+        $(impl SecretBuffer for $buffer {
+            fn into_vec(self) -> Vec<u8> {
+                <$buffer>::into_vec(self)
+            }
+        })*
+    };
+}
+
+impl_secret_buffer!(SigSecretKey, KemSecretKey, SharedSecret, KeypairSeed);
+
+/// Secret material that is wiped from memory when dropped
+pub struct Secret<T: SecretBuffer> {
+    // Only `None` while dropping
+    buffer: Option<T>,
+    locked: bool,
+}
+
+/// Secret bytes, such as decrypted plaintext
+pub type SecretBytes = Secret<Vec<u8>>;
+
+impl<T: SecretBuffer> Secret<T> {
+    /// Takes ownership of `buffer` and locks it into memory if possible.
+    /// The buffer must not have been copied before, or the copy is not wiped
+    pub fn new(buffer: T) -> Self {
+        let locked = lock(buffer.as_ref());
+        Self {
+            buffer: Some(buffer),
+            locked,
+        }
+    }
+
+    /// Moves the bytes into a [`SecretBytes`] without copying them
+    pub fn into_bytes(mut self) -> SecretBytes {
+        let buffer = self
+            .buffer
+            .take()
+            .expect("A secret only loses its buffer when it is dropped");
+        SecretBytes::new(buffer.into_vec())
+    }
+}
+
+impl<T: SecretBuffer> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.buffer
+            .as_ref()
+            .expect("A secret only loses its buffer when it is dropped")
+    }
+}
+
+impl<T: SecretBuffer> AsRef<[u8]> for Secret<T> {
+    fn as_ref(&self) -> &[u8] {
+        (**self).as_ref()
+    }
+}
+
+impl<T: SecretBuffer> Drop for Secret<T> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            let mut bytes = buffer.into_vec();
+            // Zeroizing clears the vector, so the locked range is taken before
+            let (pointer, len) = (bytes.as_ptr(), bytes.len());
+            bytes.zeroize();
+            if self.locked {
+                unlock(pointer, len);
+            }
+        }
+    }
+}
+
+impl<T: SecretBuffer> Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret([REDACTED; {} bytes])", self.as_ref().len())
+    }
+}
+
+impl Clone for SecretBytes {
+    fn clone(&self) -> Self {
+        Self::new(self.as_ref().to_vec())
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl<'a> From<&'a Secret<SigSecretKey>> for SigSecretKeyRef<'a> {
+    fn from(secret: &'a Secret<SigSecretKey>) -> Self {
+        (&**secret).into()
+    }
+}
+
+impl<'a> From<&'a Secret<KemSecretKey>> for KemSecretKeyRef<'a> {
+    fn from(secret: &'a Secret<KemSecretKey>) -> Self {
+        (&**secret).into()
+    }
+}
+
+impl<'a> From<&'a Secret<SharedSecret>> for SharedSecretRef<'a> {
+    fn from(secret: &'a Secret<SharedSecret>) -> Self {
+        (&**secret).into()
+    }
+}
+
+/// Serialized as hex, like the other binary fields. The intermediate strings are wiped as well
+impl Serialize for SecretBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&Zeroizing::new(hex::encode(self.as_ref())))
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = Zeroizing::new(String::deserialize(deserializer)?);
+        hex::decode(encoded.as_bytes())
+            .map(Self::new)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Locks the pages of `bytes` into memory. Returns false if the operating system refuses,
+/// e.g. because `RLIMIT_MEMLOCK` is exceeded; the secret is still wiped on drop
+fn lock(bytes: &[u8]) -> bool {
+    if bytes.is_empty() {
+        return false;
+    }
+    // SAFETY: The range is a live allocation of `bytes.len()` bytes
+    unsafe { libc::mlock(bytes.as_ptr().cast(), bytes.len()) == 0 }
+}
+
+fn unlock(pointer: *const u8, len: usize) {
+    // SAFETY: The range was locked by `lock` and is still allocated
+    unsafe {
+        libc::munlock(pointer.cast(), len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oqs::sig::{Algorithm as SigAlgorithmVariant, Sig};
+
+    #[test]
+    fn test_secret_is_redacted_and_usable() {
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, secret_key) = signer.keypair().unwrap();
+        let secret_key = Secret::new(secret_key);
+
+        let debug = format!("{secret_key:?}");
+        assert_eq!(
+            debug,
+            format!("Secret([REDACTED; {} bytes])", secret_key.len())
+        );
+
+        let signature = signer.sign(b"message", &secret_key).unwrap();
+        signer.verify(b"message", &signature, &public_key).unwrap();
+
+        let bytes = SecretBytes::new(b"plaintext".to_vec());
+        let json = serde_json::to_string(&bytes).unwrap();
+        assert_eq!(json, "\"706c61696e74657874\"");
+        let parsed: SecretBytes = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_ref(), b"plaintext");
+        assert!(!format!("{parsed:?}").contains("plain"));
+    }
+}
@@ -0,0 +1,326 @@
+//! Shamir secret sharing of secret keys, so a key can be backed up without any single custodian holding it.
+//!
+//! Every byte of the secret is the constant term of a random polynomial of degree `threshold - 1` over GF(2^8).
+//! Share `x` holds the evaluations of these polynomials at `x`; any `threshold` shares reconstruct the secret
+//! by Lagrange interpolation at zero, while fewer shares reveal nothing about it.
+use std::collections::BTreeSet;
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x509_cert::der::pem::{self, LineEnding};
+use zeroize::Zeroizing;
+
+use super::{revocation::KeyType, secret::SecretBytes};
+
+const KEY_SHARE_VERSION: u64 = 1;
+
+/// Prefix of the checksummed share, so the checksum cannot be mistaken for a digest of anything else
+const KEY_SHARE_CONTEXT: &[u8] = b"rust-seal key share v1\0";
+
+/// Armor label. Hyphens are not allowed in PEM labels
+const ARMOR_LABEL: &str = "RUST SEAL KEY SHARE";
+
+/// Length of the checksum, a truncated SHA-256 digest
+const CHECKSUM_LEN: usize = 8;
+
+/// Splits `secret` into `count` shares, any `threshold` of which reconstruct it.
+/// The share at index `i` is evaluated at `x = i + 1`
+pub fn split(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<SecretBytes>> {
+    ensure!(threshold >= 2, "The threshold must be at least 2");
+    ensure!(
+        threshold <= count,
+        "The threshold of {threshold} is larger than the {count} shares"
+    );
+    ensure!(!secret.is_empty(), "The secret must not be empty");
+
+    let mut shares: Vec<Vec<u8>> = (0..count)
+        .map(|_| Vec::with_capacity(secret.len()))
+        .collect();
+    let mut coefficients = Zeroizing::new(vec![0u8; usize::from(threshold)]);
+    for &byte in secret {
+        coefficients[0] = byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+        for (x, share) in (1..=count).zip(&mut shares) {
+            share.push(evaluate(&coefficients, x));
+        }
+    }
+    Ok(shares.into_iter().map(SecretBytes::new).collect())
+}
+
+/// Reconstructs the secret from shares given as `(x, share)`. Fails if two shares have the same `x`.
+/// Too few shares reconstruct a wrong secret, which the caller has to detect
+pub fn combine(shares: &[(u8, &[u8])]) -> Result<SecretBytes> {
+    let Some((_, first)) = shares.first() else {
+        bail!("No shares given");
+    };
+    ensure!(
+        shares.iter().all(|(_, share)| share.len() == first.len()),
+        "The shares have different lengths"
+    );
+    let xs: BTreeSet<u8> = shares.iter().map(|(x, _)| *x).collect();
+    ensure!(xs.len() == shares.len(), "A share was given more than once");
+    ensure!(!xs.contains(&0), "Share index 0 is invalid");
+
+    // Allocated once and moved into the result, so no copy of the secret is left behind
+    let mut secret = vec![0; first.len()];
+    for (i, (x_i, share)) in shares.iter().enumerate() {
+        // Lagrange basis polynomial of x_i at zero. Subtraction is XOR in GF(2^8)
+        let mut basis = 1;
+        for (j, (x_j, _)) in shares.iter().enumerate() {
+            if i != j {
+                basis = mul(basis, mul(*x_j, inverse(x_j ^ x_i)));
+            }
+        }
+        for (byte, y) in secret.iter_mut().zip(share.iter()) {
+            *byte ^= mul(*y, basis);
+        }
+    }
+    Ok(SecretBytes::new(secret))
+}
+
+/// Evaluates the polynomial with `coefficients`, lowest degree first, at `x` with Horner's method
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |result, coefficient| mul(result, x) ^ coefficient)
+}
+
+/// Multiplies in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1, without branching on the operands
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = a >> 7;
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(carry));
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverts a non-zero element as `a^254`, since `a^255 = 1`
+fn inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul(result, power);
+        }
+        power = mul(power, power);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// The checksummed part of a [`KeyShare`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyShareContent {
+    pub key_type: KeyType,
+    /// liboqs name of the key's algorithm
+    pub algorithm: String,
+    /// Fingerprint of the public key, against which the combined secret key is checked
+    pub fingerprint: String,
+    /// Number of shares needed to reconstruct the secret key
+    pub threshold: u8,
+    /// Point at which the share was evaluated, from 1 to the number of shares
+    pub index: u8,
+    pub share: SecretBytes,
+}
+
+/// One share of a secret key, stored as JSON or armored text
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyShare {
+    pub version: u64,
+    #[serde(flatten)]
+    pub content: KeyShareContent,
+    /// Detects shares that were damaged, e.g. while typing them in from paper
+    #[serde(with = "hex")]
+    pub checksum: Vec<u8>,
+}
+
+impl KeyShare {
+    /// Splits `secret_key` into `count` shares, any `threshold` of which reconstruct it
+    pub fn split(
+        key_type: KeyType,
+        algorithm: &str,
+        fingerprint: &str,
+        secret_key: &[u8],
+        threshold: u8,
+        count: u8,
+    ) -> Result<Vec<Self>> {
+        split(secret_key, threshold, count)?
+            .into_iter()
+            .zip(1..=count)
+            .map(|(share, index)| {
+                Self::new(KeyShareContent {
+                    key_type,
+                    algorithm: algorithm.to_string(),
+                    fingerprint: fingerprint.to_string(),
+                    threshold,
+                    index,
+                    share,
+                })
+            })
+            .collect()
+    }
+
+    fn new(content: KeyShareContent) -> Result<Self> {
+        let checksum = checksum(&content)?;
+        Ok(Self {
+            version: KEY_SHARE_VERSION,
+            content,
+            checksum,
+        })
+    }
+
+    /// Reconstructs the secret key from at least `threshold` shares of the same key.
+    /// The result must still be checked against the public key with the fingerprint of the shares
+    pub fn combine(shares: &[KeyShare]) -> Result<SecretBytes> {
+        let Some(first) = shares.first() else {
+            bail!("No shares given");
+        };
+        for share in shares {
+            share.verify_checksum()?;
+            ensure!(
+                share.content.key_type == first.content.key_type
+                    && share.content.algorithm == first.content.algorithm
+                    && share.content.fingerprint == first.content.fingerprint,
+                "Share {} belongs to another key than share {}",
+                share.content.index,
+                first.content.index
+            );
+            ensure!(
+                share.content.threshold == first.content.threshold,
+                "Share {} has a threshold of {}, but share {} of {}",
+                share.content.index,
+                share.content.threshold,
+                first.content.index,
+                first.content.threshold
+            );
+        }
+        ensure!(
+            shares.len() >= usize::from(first.content.threshold),
+            "{} shares are needed to reconstruct the key, but only {} were given",
+            first.content.threshold,
+            shares.len()
+        );
+
+        let shares: Vec<(u8, &[u8])> = shares
+            .iter()
+            .map(|share| (share.content.index, share.content.share.as_ref()))
+            .collect();
+        combine(&shares)
+    }
+
+    /// Fails if the share was damaged or has an unsupported version
+    pub fn verify_checksum(&self) -> Result<()> {
+        ensure!(
+            self.version <= KEY_SHARE_VERSION,
+            "Key share has version {}, but this rust-seal only supports versions up to {KEY_SHARE_VERSION}",
+            self.version
+        );
+        ensure!(
+            checksum(&self.content)? == self.checksum,
+            "Checksum of share {} does not match. The share is damaged",
+            self.content.index
+        );
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<Zeroizing<String>> {
+        serde_json::to_string_pretty(self)
+            .map(Zeroizing::new)
+            .context("Failed to serialize key share")
+    }
+
+    pub fn to_armored(&self) -> Result<Zeroizing<String>> {
+        let json =
+            Zeroizing::new(serde_json::to_vec(self).context("Failed to serialize key share")?);
+        pem::encode_string(ARMOR_LABEL, LineEnding::LF, &json)
+            .map(Zeroizing::new)
+            .map_err(|err| anyhow!("Failed to armor key share: {err}"))
+    }
+
+    /// Parses a share written as JSON or as armored text
+    pub fn parse(content: &str) -> Result<Self> {
+        let content = content.trim();
+        let share: Self = if content.starts_with("-----BEGIN") {
+            let (label, json) = pem::decode_vec(content.as_bytes())
+                .map_err(|err| anyhow!("Key share is not armored correctly: {err}"))?;
+            let json = Zeroizing::new(json);
+            ensure!(
+                label == ARMOR_LABEL,
+                "Expected a {ARMOR_LABEL}, but found {label}"
+            );
+            serde_json::from_slice(&json).context("Failed to parse key share")?
+        } else {
+            serde_json::from_str(content).context("Failed to parse key share")?
+        };
+        share.verify_checksum()?;
+        Ok(share)
+    }
+}
+
+fn checksum(content: &KeyShareContent) -> Result<Vec<u8>> {
+    let json =
+        Zeroizing::new(serde_json::to_vec(content).context("Failed to serialize key share")?);
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_SHARE_CONTEXT);
+    hasher.update(&*json);
+    Ok(hasher.finalize()[..CHECKSUM_LEN].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_arithmetic() {
+        assert_eq!(mul(0x57, 0x83), 0xc1);
+        for a in 1..=255 {
+            assert_eq!(mul(a, inverse(a)), 1);
+        }
+    }
+
+    #[test]
+    fn test_any_threshold_shares_reconstruct_the_secret() {
+        let secret = b"release signing key".to_vec();
+        let shares = split(&secret, 3, 5).unwrap();
+
+        for subset in [[0, 1, 2], [0, 2, 4], [4, 3, 1]] {
+            let chosen: Vec<(u8, &[u8])> = subset
+                .iter()
+                .map(|&i| (i as u8 + 1, shares[i].as_ref()))
+                .collect();
+            assert_eq!(combine(&chosen).unwrap().as_ref(), secret);
+        }
+
+        let too_few: Vec<(u8, &[u8])> = vec![(1, shares[0].as_ref()), (2, shares[1].as_ref())];
+        assert_ne!(combine(&too_few).unwrap().as_ref(), secret);
+        assert!(split(&secret, 1, 5).is_err());
+        assert!(split(&secret, 6, 5).is_err());
+    }
+
+    #[test]
+    fn test_key_shares_roundtrip_and_detect_damage() {
+        let shares =
+            KeyShare::split(KeyType::Signature, "ML-DSA-44", "abcd", b"secret key", 2, 3).unwrap();
+
+        let armored = shares[0].to_armored().unwrap();
+        assert!(armored.starts_with("-----BEGIN RUST SEAL KEY SHARE-----\n"));
+        let parsed = vec![
+            KeyShare::parse(&armored).unwrap(),
+            KeyShare::parse(&shares[2].to_json().unwrap()).unwrap(),
+        ];
+        assert_eq!(parsed[0], shares[0]);
+        assert_eq!(KeyShare::combine(&parsed).unwrap().as_ref(), b"secret key");
+        assert!(KeyShare::combine(&parsed[..1]).is_err());
+
+        let mut damaged = shares[1].clone();
+        damaged.content.share = SecretBytes::new(vec![0; 10]);
+        assert!(KeyShare::combine(&[parsed[0].clone(), damaged]).is_err());
+    }
+}
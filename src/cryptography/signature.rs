@@ -1,8 +1,82 @@
-use std::{fs::write, path::PathBuf};
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result, bail, ensure};
+use oqs::sig::{
+    Algorithm as SigAlgorithm, PublicKeyRef, SecretKey as SigSecretKey, Sig, Signature,
+};
+use sha2::{Digest, Sha512};
+
+use crate::util::write_file;
+
+/// Prefix of namespaced signatures, so they cannot be mistaken for a signature over a file
+const NAMESPACE_CONTEXT: &[u8] = b"rust-seal namespaced signature v1\0";
+
+/// Format of a file signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// Detached liboqs signature bytes
+    Raw,
+    /// Compact JWS with the file as payload
+    Jws,
+    /// COSE_Sign1 with the file as payload
+    Cose,
+    /// Raw signatures of several signers in a JSON signature bundle
+    Bundle,
+}
+
+impl SignatureFormat {
+    /// Extension of the signature file written next to the signed file
+    pub fn extension(self) -> &'static str {
+        match self {
+            SignatureFormat::Raw => "sig",
+            SignatureFormat::Jws => "jws",
+            SignatureFormat::Cose => "cose",
+            SignatureFormat::Bundle => "sigs",
+        }
+    }
+
+    /// Fails if signatures of `algorithm` cannot be written in this format. JWS and COSE are only defined for ML-DSA
+    pub fn ensure_supports(self, algorithm: SigAlgorithm) -> Result<()> {
+        let ml_dsa = matches!(
+            algorithm,
+            SigAlgorithm::MlDsa44 | SigAlgorithm::MlDsa65 | SigAlgorithm::MlDsa87
+        );
+        ensure!(
+            matches!(self, SignatureFormat::Raw | SignatureFormat::Bundle) || ml_dsa,
+            "{algorithm} signatures cannot be written as {self}. Use ML-DSA-44, ML-DSA-65 or ML-DSA-87"
+        );
+        Ok(())
+    }
+}
+
+impl FromStr for SignatureFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "raw" => Ok(SignatureFormat::Raw),
+            "jws" => Ok(SignatureFormat::Jws),
+            "cose" => Ok(SignatureFormat::Cose),
+            "bundle" => Ok(SignatureFormat::Bundle),
+            _ => bail!(
+                "Unsupported signature format: {value}. Expected one of: raw, jws, cose, bundle"
+            ),
+        }
+    }
+}
 
-use anyhow::{Context, Result};
-use oqs::sig::{PublicKeyRef, SecretKey as SigSecretKey, Sig, Signature};
+impl fmt::Display for SignatureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureFormat::Raw => f.write_str("raw"),
+            SignatureFormat::Jws => f.write_str("jws"),
+            SignatureFormat::Cose => f.write_str("cose"),
+            SignatureFormat::Bundle => f.write_str("bundle"),
+        }
+    }
+}
 
+/// Signs the file and writes the signature next to it. An existing signature file is not overwritten
 pub fn sign_and_save_file_signature(
     file_path: &PathBuf,
     signature: &Sig,
@@ -14,7 +88,7 @@ pub fn sign_and_save_file_signature(
     // save file
     let signature_file_path = file_path.with_extension("sig");
 
-    write(&signature_file_path, signature.as_ref())
+    write_file(&signature_file_path, signature, false)
         .context("Failed to create signature file with content")?;
 
     Ok(signature_file_path)
@@ -33,6 +107,23 @@ pub fn get_signature_from_file(
         .context("Failed to sign file content")
 }
 
+/// Returns the message signed for `file_content` in `namespace`, modelled on OpenSSH's SSHSIG:
+/// the context, the length prefixed namespace and the SHA-512 digest of the file.
+/// A signature made in one namespace, e.g. `file`, is not valid in another, e.g. `git`
+pub fn namespaced_message(namespace: &str, file_content: &[u8]) -> Result<Vec<u8>> {
+    ensure!(!namespace.is_empty(), "Namespace must not be empty");
+
+    let mut message = NAMESPACE_CONTEXT.to_vec();
+    message.extend_from_slice(
+        &u32::try_from(namespace.len())
+            .context("Namespace is too long")?
+            .to_be_bytes(),
+    );
+    message.extend_from_slice(namespace.as_bytes());
+    message.extend_from_slice(&Sha512::digest(file_content));
+    Ok(message)
+}
+
 pub fn verify_file_with_signature(
     file_content: &[u8],
     sig_content: &[u8],
@@ -61,7 +152,7 @@ mod tests {
     use oqs::sig::{PublicKeyRef, Sig};
 
     use crate::cryptography::signature::{
-        sign_and_save_file_signature, verify_file_with_signature,
+        namespaced_message, sign_and_save_file_signature, verify_file_with_signature,
     };
 
     fn prep_test() -> (TempDir, File, PathBuf, Sig, SigPublicKey, SigSecretKey) {
@@ -107,4 +198,34 @@ mod tests {
         )
         .expect("Signature verification failed");
     }
+
+    #[test]
+    fn test_namespaced_signature() {
+        let (_dir, _, _, sig, public_key, secret_key) = prep_test();
+        let message = namespaced_message("file", b"Hello World!").unwrap();
+        let signature = sig.sign(&message, &secret_key).unwrap();
+
+        verify_file_with_signature(
+            &message,
+            signature.as_ref(),
+            &sig,
+            PublicKeyRef::from(&public_key),
+        )
+        .expect("Signature verification failed");
+        for other in [
+            namespaced_message("git", b"Hello World!").unwrap(),
+            b"Hello World!".to_vec(),
+        ] {
+            assert!(
+                verify_file_with_signature(
+                    &other,
+                    signature.as_ref(),
+                    &sig,
+                    PublicKeyRef::from(&public_key)
+                )
+                .is_err()
+            );
+        }
+        assert!(namespaced_message("", b"Hello World!").is_err());
+    }
 }
@@ -0,0 +1,597 @@
+//! Signatures of several signers over the same content, for releases that need M of N signers.
+//!
+//! Besides signatures over the file, a bundle holds countersignatures, which sign the signature of another entry,
+//! so approval chains such as build bot, reviewer and notary can be verified.
+//! An entry may carry an RFC 3161 timestamp over its signature, which proves it was made before its key expired,
+//! and the receipt of a transparency log that recorded it.
+use std::{collections::BTreeSet, fs::read, path::Path};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use chrono::{DateTime, Utc};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, Sig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use tracing::warn;
+use x509_cert::Certificate;
+
+use super::{
+    fingerprint::{fingerprint, fingerprints_match, format_fingerprint},
+    signature::{namespaced_message, verify_file_with_signature},
+    timestamp::TimestampToken,
+};
+use crate::transparency::{Hash, LogEntry, LogReceipt};
+
+const SIGNATURE_BUNDLE_VERSION: u64 = 1;
+
+/// Prefix of countersignatures, so they cannot be mistaken for a signature over a file
+const COUNTERSIGNATURE_CONTEXT: &[u8] = b"rust-seal countersignature v1\0";
+
+/// Signatures over a file, or over the file in a namespace, written with `rust-seal sign --format bundle`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureBundle {
+    pub version: u64,
+    /// Namespace every signature was made in, or none for signatures over the file content itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub signatures: Vec<BundleSignature>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BundleSignature {
+    pub algorithm: SigAlgorithmVariant,
+    /// Identifies the signer. It is only trusted if it matches a key given to [`SignatureBundle::valid_signers`]
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+    /// Digest of the signature this entry countersigns, see [`BundleSignature::digest`]. None for a signature over the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub countersigns: Option<String>,
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+    /// Timestamp token of a time-stamping authority over the signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<TimestampToken>,
+    /// Receipt of the transparency log the signature was appended to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<LogReceipt>,
+    /// Time of the timestamp once [`SignatureBundle::verify_timestamps`] verified it. None if the signature must be
+    /// treated as made now
+    #[serde(skip)]
+    pub signed_at: Option<DateTime<Utc>>,
+}
+
+impl BundleSignature {
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.algorithm.to_string(), &self.public_key)
+    }
+
+    /// Hex encoded SHA-256 digest of the signature, by which countersignatures refer to it
+    pub fn digest(&self) -> String {
+        hex::encode(Sha256::digest(&self.signature))
+    }
+
+    /// Returns the message a countersignature of this signature signs: the context, the length prefixed signature
+    /// and the SHA-512 digest of `message`, the message the bundle signs
+    pub fn countersignature_message(&self, message: &[u8]) -> Vec<u8> {
+        let mut countersigned = COUNTERSIGNATURE_CONTEXT.to_vec();
+        countersigned.extend_from_slice(&(self.signature.len() as u64).to_be_bytes());
+        countersigned.extend_from_slice(&self.signature);
+        countersigned.extend_from_slice(&Sha512::digest(message));
+        countersigned
+    }
+
+    /// Returns the entry a transparency log records for this signature over the file with the SHA-256 `file_digest`
+    pub fn log_entry(&self, namespace: Option<&str>, file_digest: Hash) -> LogEntry {
+        LogEntry {
+            algorithm: self.algorithm,
+            public_key: self.public_key.clone(),
+            namespace: namespace.map(str::to_string),
+            file_digest,
+            signature: self.signature.clone(),
+        }
+    }
+
+    fn verify(&self, message: &[u8]) -> Result<()> {
+        let sig = Sig::new(self.algorithm).context(format!(
+            "Failed to create signature algorithm {}. Algorithm might be disabled",
+            self.algorithm
+        ))?;
+        let public_key = sig
+            .public_key_from_bytes(&self.public_key)
+            .context("Provided public key is not valid")?;
+        verify_file_with_signature(message, &self.signature, &sig, public_key)
+    }
+
+    fn is_by(&self, signer: &TrustedSigner) -> bool {
+        self.algorithm == signer.algorithm
+            && self.public_key == signer.public_key
+            && self.signed_at == signer.signed_at
+    }
+}
+
+/// A key whose signature counts towards the threshold, under the name of its owner
+#[derive(Debug, Clone)]
+pub struct TrustedSigner {
+    pub name: String,
+    pub algorithm: SigAlgorithmVariant,
+    pub public_key: Vec<u8>,
+    /// The key is only trusted for entries signed at this time, see [`BundleSignature::signed_at`].
+    /// None if it is trusted for entries without a verified timestamp
+    pub signed_at: Option<DateTime<Utc>>,
+}
+
+impl SignatureBundle {
+    pub fn new(namespace: Option<&str>) -> Self {
+        Self {
+            version: SIGNATURE_BUNDLE_VERSION,
+            namespace: namespace.map(str::to_string),
+            signatures: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = read(path).context(format!(
+            "Failed to read signature bundle: {}",
+            path.display()
+        ))?;
+        Self::parse(&content).context(format!(
+            "Failed to parse signature bundle: {}",
+            path.display()
+        ))
+    }
+
+    pub fn parse(content: &[u8]) -> Result<Self> {
+        let bundle: Self = serde_json::from_slice(content)?;
+        ensure!(
+            bundle.version <= SIGNATURE_BUNDLE_VERSION,
+            "Signature bundle has version {}, but this rust-seal only supports versions up to {SIGNATURE_BUNDLE_VERSION}",
+            bundle.version
+        );
+        Ok(bundle)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize signature bundle")
+    }
+
+    /// Returns the message every signature of the bundle is made over
+    pub fn message(&self, file_content: &[u8]) -> Result<Vec<u8>> {
+        match &self.namespace {
+            Some(namespace) => namespaced_message(namespace, file_content),
+            None => Ok(file_content.to_vec()),
+        }
+    }
+
+    /// Adds a signature, replacing an earlier one by the same key over the same content, i.e. the file
+    /// or the same countersigned signature. Other entries are never changed. Returns true if one was replaced
+    pub fn add(&mut self, signature: BundleSignature) -> bool {
+        match self.signatures.iter_mut().find(|existing| {
+            existing.algorithm == signature.algorithm
+                && existing.public_key == signature.public_key
+                && existing.countersigns == signature.countersigns
+        }) {
+            Some(existing) => {
+                *existing = signature;
+                true
+            }
+            None => {
+                self.signatures.push(signature);
+                false
+            }
+        }
+    }
+
+    /// Verifies the timestamps against the certificates of the trusted time-stamping authorities and records
+    /// when the entries were signed. Entries with an invalid or untrusted timestamp count as signed now
+    pub fn verify_timestamps(&mut self, trusted_tsas: &[Certificate]) {
+        for entry in &mut self.signatures {
+            entry.signed_at = None;
+        }
+        if trusted_tsas.is_empty() {
+            if self
+                .signatures
+                .iter()
+                .any(|entry| entry.timestamp.is_some())
+            {
+                warn!(
+                    "Ignoring the timestamps in the signature bundle, since no time-stamping authority is trusted. Trust one with `rust-seal config set timestamping.tsa_certificates <PATHS>`"
+                );
+            }
+            return;
+        }
+
+        for entry in &mut self.signatures {
+            let Some(timestamp) = &entry.timestamp else {
+                continue;
+            };
+            match timestamp.verify(&entry.signature, trusted_tsas) {
+                Ok(time) => entry.signed_at = Some(time),
+                Err(err) => warn!(
+                    "Ignoring the timestamp of the signature by key {}: {err:#}",
+                    format_fingerprint(&entry.fingerprint())
+                ),
+            }
+        }
+    }
+
+    /// Drops the entries without a receipt that proves one of the `trusted_logs` recorded them,
+    /// so a signature made with a stolen key cannot count unless its owner could have noticed it in the log
+    pub fn require_log_receipts(&mut self, file_content: &[u8], trusted_logs: &[String]) {
+        let file_digest: Hash = Sha256::digest(file_content).into();
+        let namespace = self.namespace.clone();
+        self.signatures.retain(|entry| {
+            let result = match &entry.log {
+                Some(receipt) => receipt.verify(
+                    &entry.log_entry(namespace.as_deref(), file_digest),
+                    trusted_logs,
+                ),
+                None => Err(anyhow!("It has no transparency log receipt")),
+            };
+            if let Err(err) = &result {
+                warn!(
+                    "Ignoring the signature by key {}: {err:#}",
+                    format_fingerprint(&entry.fingerprint())
+                );
+            }
+            result.is_ok()
+        });
+    }
+
+    /// Drops the entries whose algorithm fails `check`, e.g. the algorithm policy. The bundle, not the verifier,
+    /// chooses the algorithms, so a signature with a denied algorithm must not count towards a threshold or chain
+    pub fn retain_allowed_algorithms(&mut self, check: impl Fn(&Sig) -> Result<()>) {
+        self.signatures.retain(|entry| {
+            let result = Sig::new(entry.algorithm)
+                .context(format!(
+                    "Failed to create signature algorithm {}. Algorithm might be disabled",
+                    entry.algorithm
+                ))
+                .and_then(|sig| check(&sig));
+            if let Err(err) = &result {
+                warn!(
+                    "Ignoring the signature by key {}: {err:#}",
+                    format_fingerprint(&entry.fingerprint())
+                );
+            }
+            result.is_ok()
+        });
+    }
+
+    /// Returns the distinct times the entries were signed at, see [`BundleSignature::signed_at`]
+    pub fn signing_times(&self) -> Vec<Option<DateTime<Utc>>> {
+        let mut times = Vec::new();
+        for entry in &self.signatures {
+            if !times.contains(&entry.signed_at) {
+                times.push(entry.signed_at);
+            }
+        }
+        times
+    }
+
+    /// Returns the index of the only signature by the key with `fingerprint`, e.g. to countersign it
+    pub fn position_by_fingerprint(&self, fingerprint: &str) -> Result<usize> {
+        let mut matches = self
+            .signatures
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| fingerprints_match(&entry.fingerprint(), fingerprint));
+        match (matches.next(), matches.next()) {
+            (Some((index, _)), None) => Ok(index),
+            (None, _) => bail!(
+                "The bundle has no signature by key {fingerprint}. It has signatures by the keys: {}",
+                self.signatures
+                    .iter()
+                    .map(|entry| format_fingerprint(&entry.fingerprint()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            (Some(_), Some(_)) => {
+                bail!("The bundle has several signatures by key {fingerprint}")
+            }
+        }
+    }
+
+    /// Returns the names of the trusted signers with a valid signature over `file_content`, or a valid
+    /// countersignature, which approves the file as well. Signatures by unknown keys are ignored;
+    /// invalid signatures by trusted keys are logged and not counted
+    pub fn valid_signers<'a>(
+        &self,
+        file_content: &[u8],
+        trusted: &'a [TrustedSigner],
+    ) -> Result<BTreeSet<&'a str>> {
+        let results = self.verify_entries(file_content)?;
+
+        let mut signers = BTreeSet::new();
+        for (entry, result) in self.signatures.iter().zip(&results) {
+            let Some(signer) = trusted.iter().find(|signer| entry.is_by(signer)) else {
+                continue;
+            };
+            match result {
+                Ok(()) => {
+                    signers.insert(signer.name.as_str());
+                }
+                Err(err) => warn!(
+                    "Ignoring the invalid {} signature of {} by key {}: {err:#}",
+                    entry.algorithm,
+                    signer.name,
+                    format_fingerprint(&entry.fingerprint())
+                ),
+            }
+        }
+        Ok(signers)
+    }
+
+    /// Checks the approval chain: the first signer of `chain` signed the file,
+    /// and every following signer countersigned the signature of the signer before
+    pub fn verify_chain(
+        &self,
+        file_content: &[u8],
+        chain: &[&str],
+        trusted: &[TrustedSigner],
+    ) -> Result<()> {
+        let Some((first, rest)) = chain.split_first() else {
+            bail!("The approval chain is empty");
+        };
+        let results = self.verify_entries(file_content)?;
+        let valid_by = |name: &str, countersigns: &dyn Fn(Option<&String>) -> bool| {
+            self.signatures
+                .iter()
+                .zip(&results)
+                .filter(|(entry, result)| {
+                    result.is_ok()
+                        && countersigns(entry.countersigns.as_ref())
+                        && trusted
+                            .iter()
+                            .any(|signer| signer.name == name && entry.is_by(signer))
+                })
+                .map(|(entry, _)| entry.digest())
+                .collect::<Vec<_>>()
+        };
+
+        // Digests of the valid signatures that end the chain so far
+        let mut ends = valid_by(first, &|countersigns| countersigns.is_none());
+        ensure!(
+            !ends.is_empty(),
+            "{first} has no valid signature of the file"
+        );
+        for (previous, name) in chain.iter().zip(rest) {
+            ends = valid_by(name, &|countersigns| {
+                countersigns.is_some_and(|digest| ends.contains(digest))
+            });
+            ensure!(
+                !ends.is_empty(),
+                "{name} has no valid countersignature of the signature of {previous}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Verifies every entry, whether its key is trusted or not. A countersignature is only valid
+    /// if the signature it countersigns is, so entries are verified once the signature they countersign has been
+    pub fn verify_entries(&self, file_content: &[u8]) -> Result<Vec<Result<()>>> {
+        let message = self.message(file_content)?;
+
+        let mut results: Vec<Option<Result<()>>> = self.signatures.iter().map(|_| None).collect();
+        loop {
+            let mut progress = false;
+            for (index, entry) in self.signatures.iter().enumerate() {
+                if results[index].is_some() {
+                    continue;
+                }
+                let signed = match &entry.countersigns {
+                    None => message.clone(),
+                    Some(digest) => {
+                        let countersigned =
+                            self.signatures
+                                .iter()
+                                .zip(&results)
+                                .find(|(other, result)| {
+                                    matches!(result, Some(Ok(()))) && &other.digest() == digest
+                                });
+                        match countersigned {
+                            Some((countersigned, _)) => {
+                                countersigned.countersignature_message(&message)
+                            }
+                            None => continue,
+                        }
+                    }
+                };
+                results[index] = Some(entry.verify(&signed));
+                progress = true;
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(anyhow!(
+                        "The signature it countersigns is missing or invalid"
+                    ))
+                })
+            })
+            .collect())
+    }
+}
+
+/// Describes the namespace of a bundle for error messages
+pub fn describe_namespace(namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) => format!("namespace {namespace}"),
+        None => "no namespace".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::validate_signature_algorithm, config::Policy};
+
+    #[test]
+    fn test_threshold_counts_distinct_trusted_signers() {
+        let sig = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let keypairs: Vec<_> = (0..4).map(|_| sig.keypair().unwrap()).collect();
+        let trusted: Vec<TrustedSigner> = ["alice", "bob", "carol"]
+            .iter()
+            .zip(&keypairs)
+            .map(|(name, (public_key, _))| TrustedSigner {
+                name: name.to_string(),
+                algorithm: SigAlgorithmVariant::MlDsa44,
+                public_key: public_key.as_ref().to_vec(),
+                signed_at: None,
+            })
+            .collect();
+
+        let mut bundle = SignatureBundle::new(Some("release"));
+        let message = bundle.message(b"artifact").unwrap();
+        // alice signs twice, the fourth key is not trusted and bob's signature is over other content
+        for index in [0, 0, 3] {
+            let (public_key, secret_key) = &keypairs[index];
+            bundle.add(BundleSignature {
+                algorithm: SigAlgorithmVariant::MlDsa44,
+                public_key: public_key.as_ref().to_vec(),
+                countersigns: None,
+                signature: sig.sign(&message, secret_key).unwrap().into_vec(),
+                timestamp: None,
+                log: None,
+                signed_at: None,
+            });
+        }
+        bundle.add(BundleSignature {
+            algorithm: SigAlgorithmVariant::MlDsa44,
+            public_key: keypairs[1].0.as_ref().to_vec(),
+            countersigns: None,
+            signature: sig.sign(b"other", &keypairs[1].1).unwrap().into_vec(),
+            timestamp: None,
+            log: None,
+            signed_at: None,
+        });
+        assert_eq!(bundle.signatures.len(), 3);
+
+        let bundle = SignatureBundle::parse(bundle.to_json().unwrap().as_bytes()).unwrap();
+        let signers = bundle.valid_signers(b"artifact", &trusted).unwrap();
+        assert_eq!(signers, BTreeSet::from(["alice"]));
+        assert!(
+            bundle
+                .valid_signers(b"tampered", &trusted)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_chain_requires_countersignatures_in_order() {
+        let sig = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let keypairs: Vec<_> = (0..3).map(|_| sig.keypair().unwrap()).collect();
+        let trusted: Vec<TrustedSigner> = ["bot", "reviewer", "notary"]
+            .iter()
+            .zip(&keypairs)
+            .map(|(name, (public_key, _))| TrustedSigner {
+                name: name.to_string(),
+                algorithm: SigAlgorithmVariant::MlDsa44,
+                public_key: public_key.as_ref().to_vec(),
+                signed_at: None,
+            })
+            .collect();
+
+        let mut bundle = SignatureBundle::new(None);
+        let message = bundle.message(b"artifact").unwrap();
+        let mut countersigns = None;
+        for (public_key, secret_key) in &keypairs {
+            let signed = match &countersigns {
+                None => message.clone(),
+                Some(_) => bundle
+                    .signatures
+                    .last()
+                    .unwrap()
+                    .countersignature_message(&message),
+            };
+            bundle.add(BundleSignature {
+                algorithm: SigAlgorithmVariant::MlDsa44,
+                public_key: public_key.as_ref().to_vec(),
+                countersigns: countersigns.clone(),
+                signature: sig.sign(&signed, secret_key).unwrap().into_vec(),
+                timestamp: None,
+                log: None,
+                signed_at: None,
+            });
+            countersigns = Some(bundle.signatures.last().unwrap().digest());
+        }
+
+        let bundle = SignatureBundle::parse(bundle.to_json().unwrap().as_bytes()).unwrap();
+        let chain = ["bot", "reviewer", "notary"];
+        bundle.verify_chain(b"artifact", &chain, &trusted).unwrap();
+        assert_eq!(
+            bundle.valid_signers(b"artifact", &trusted).unwrap().len(),
+            3
+        );
+        assert!(
+            bundle
+                .verify_chain(b"artifact", &["bot", "notary"], &trusted)
+                .is_err()
+        );
+        assert!(
+            bundle
+                .verify_chain(b"artifact", &["reviewer", "bot"], &trusted)
+                .is_err()
+        );
+        assert!(bundle.verify_chain(b"tampered", &chain, &trusted).is_err());
+        assert_eq!(
+            bundle
+                .position_by_fingerprint(&bundle.signatures[1].fingerprint())
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_denied_algorithms_do_not_count() {
+        let mut bundle = SignatureBundle::new(None);
+        let message = bundle.message(b"artifact").unwrap();
+        let mut trusted = Vec::new();
+        for (name, algorithm) in [
+            ("alice", SigAlgorithmVariant::MlDsa44),
+            ("bob", SigAlgorithmVariant::MlDsa65),
+        ] {
+            let sig = Sig::new(algorithm).unwrap();
+            let (public_key, secret_key) = sig.keypair().unwrap();
+            trusted.push(TrustedSigner {
+                name: name.to_string(),
+                algorithm,
+                public_key: public_key.as_ref().to_vec(),
+                signed_at: None,
+            });
+            bundle.add(BundleSignature {
+                algorithm,
+                public_key: public_key.into_vec(),
+                countersigns: None,
+                signature: sig.sign(&message, &secret_key).unwrap().into_vec(),
+                timestamp: None,
+                log: None,
+                signed_at: None,
+            });
+        }
+        let policy = Policy {
+            deny: vec!["ML-DSA-44".to_string()],
+            ..Policy::default()
+        };
+
+        let mut overridden = bundle.clone();
+        overridden
+            .retain_allowed_algorithms(|sig| validate_signature_algorithm(sig, &policy, true));
+        assert_eq!(
+            overridden.valid_signers(b"artifact", &trusted).unwrap(),
+            BTreeSet::from(["alice", "bob"])
+        );
+
+        bundle.retain_allowed_algorithms(|sig| validate_signature_algorithm(sig, &policy, false));
+        assert_eq!(
+            bundle.valid_signers(b"artifact", &trusted).unwrap(),
+            BTreeSet::from(["bob"])
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use aes_gcm::{
-    AeadCore, Aes256Gcm, Key, KeyInit,
+    AeadCore, Aes256Gcm, Key, KeyInit, Nonce,
     aead::{Aead, OsRng},
 };
-use anyhow::{Result, bail};
-use tracing::debug;
+use anyhow::{Result, bail, ensure};
+
+use super::secret::SecretBytes;
 
 pub const AES_KEY_SIZE: usize = 32; // 256 bits
+const NONCE_SIZE: usize = 12; // 96 bits
 
 /// Encrypts data using AES-GCM with the provided key.
 /// The key must be of size 32 bytes (256 bits).
@@ -17,15 +19,26 @@ pub const AES_KEY_SIZE: usize = 32; // 256 bits
 ///
 /// Data format: <nonce-length in bytes ; 1 byte><nonce><encrypted_data>
 pub fn symmetric_encrypt(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    symmetric_encrypt_with_nonce(data, key, &nonce)
+}
+
+/// Same as [`symmetric_encrypt`], but with a caller provided 12 byte nonce.
+/// Only meant for known-answer tests, a nonce must never be reused with the same key.
+pub(crate) fn symmetric_encrypt_with_nonce(
+    data: &[u8],
+    key: &[u8],
+    nonce: &Nonce<<Aes256Gcm as AeadCore>::NonceSize>,
+) -> Result<Vec<u8>> {
     let aes_key: &Key<Aes256Gcm> = key.into();
     let cipher = Aes256Gcm::new(aes_key);
-    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
 
     let encrypted_data = cipher
-        .encrypt(&nonce, data)
+        .encrypt(nonce, data)
         .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
 
-    Ok(combine_data_and_nonce(&encrypted_data, &nonce))
+    Ok(combine_data_and_nonce(&encrypted_data, nonce))
 }
 
 /// Decrypts data using AES-GCM with the provided key.
@@ -34,20 +47,24 @@ pub fn symmetric_encrypt(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
 /// ---
 ///
 ///
-/// Return the decrypted data.
+/// Return the decrypted data, which is wiped from memory when dropped.
 ///
 /// Data format: <nonce-length in bytes ; 1 byte><nonce><encrypted_data>
 ///
-pub fn symmetric_decrypt(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+pub fn symmetric_decrypt(data: &[u8], key: &[u8]) -> Result<SecretBytes> {
     let aes_key: &Key<Aes256Gcm> = key.into();
     let cipher = Aes256Gcm::new(aes_key);
-    let (nonce, data) = extract_data_and_nonce(data).unwrap();
+    let (nonce, data) = extract_data_and_nonce(data)?;
+    ensure!(
+        nonce.len() == NONCE_SIZE,
+        "Invalid nonce length in combined data"
+    );
 
     let decrypted_data = cipher
         .decrypt(nonce.into(), data)
         .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))?;
 
-    Ok(decrypted_data)
+    Ok(SecretBytes::new(decrypted_data))
 }
 
 fn combine_data_and_nonce(data: &[u8], nonce: &[u8]) -> Vec<u8> {
@@ -55,11 +72,6 @@ fn combine_data_and_nonce(data: &[u8], nonce: &[u8]) -> Vec<u8> {
     combined.extend_from_slice(nonce);
     combined.extend_from_slice(data);
 
-    print_byte_array("Data", data);
-    print_byte_array("Nonce", nonce);
-    print_byte_array("Nonce Length", &nonce.len().to_le_bytes()[..1]);
-    print_byte_array("Combined", &combined);
-
     combined
 }
 
@@ -76,20 +88,9 @@ fn extract_data_and_nonce(combined: &[u8]) -> Result<(&[u8], &[u8])> {
     let nonce = &combined[1..1 + nonce_length];
     let data = &combined[1 + nonce_length..];
 
-    print_byte_array("Extracted Nonce", nonce);
-    print_byte_array("Extracted Data", data);
-
     Ok((nonce, data))
 }
 
-fn print_byte_array(pre: &str, data: &[u8]) {
-    let bytes = data
-        .iter()
-        .map(|&b| format!("{b:#04x} "))
-        .collect::<String>();
-    debug!("{}:\n{}", pre, bytes);
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,664 @@
+//! RFC 3161 timestamps, which prove when a signature was made, so it stays valid after its key expires.
+//!
+//! A time-stamping authority (TSA) signs the digest of the signature together with the time. The token is a
+//! CMS SignedData (RFC 5652) holding a TSTInfo. It is only trusted if its signer has a time stamping certificate
+//! that is one of the configured TSA certificates or was issued by one of them.
+use std::io::Read;
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use cms::{
+    cert::CertificateChoices,
+    content_info::ContentInfo,
+    signed_data::{SignedAttributes, SignedData, SignerIdentifier},
+};
+use der::{
+    Decode, Encode, Sequence, Tag, Tagged,
+    asn1::{Any, BitString, ObjectIdentifier, OctetString, Uint},
+    oid::{
+        AssociatedOid,
+        db::{rfc5911, rfc5912},
+    },
+};
+use serde::{Deserialize, Serialize};
+use x509_cert::{
+    Certificate,
+    ext::{
+        Extensions,
+        pkix::{SubjectKeyIdentifier, name::GeneralName},
+    },
+    spki::AlgorithmIdentifierOwned,
+};
+
+use super::{
+    classic::HashAlgorithm,
+    x509::{ensure_valid_at, has_extended_key_usage, verify_chain, verify_signed_message},
+};
+
+/// Content type of the TSTInfo signed by the TSA (RFC 3161, section 2.4.2)
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+const TIMESTAMP_QUERY_CONTENT_TYPE: &str = "application/timestamp-query";
+
+/// Responses are a few kilobytes, with the certificates of the TSA
+const MAX_RESPONSE_LEN: u64 = 1 << 20;
+
+const NONCE_LEN: usize = 16;
+
+/// A request was granted, possibly with modifications (RFC 3161, section 2.4.2)
+const GRANTED_STATUSES: [u8; 2] = [0, 1];
+
+/// DER encoded RFC 3161 timestamp token
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct TimestampToken(#[serde(with = "hex")] Vec<u8>);
+
+#[derive(Sequence)]
+struct MessageImprint {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetString,
+}
+
+#[derive(Sequence)]
+struct TimeStampReq {
+    version: u8,
+    message_imprint: MessageImprint,
+    #[asn1(optional = "true")]
+    req_policy: Option<ObjectIdentifier>,
+    #[asn1(optional = "true")]
+    nonce: Option<Uint>,
+    #[asn1(default = "Default::default")]
+    cert_req: bool,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+#[derive(Sequence)]
+struct TimeStampResp {
+    status: PkiStatusInfo,
+    #[asn1(optional = "true")]
+    time_stamp_token: Option<ContentInfo>,
+}
+
+#[derive(Sequence)]
+struct PkiStatusInfo {
+    status: u8,
+    #[asn1(optional = "true")]
+    status_string: Option<Vec<String>>,
+    #[asn1(optional = "true")]
+    fail_info: Option<BitString>,
+}
+
+#[derive(Sequence)]
+struct TstInfo {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: MessageImprint,
+    serial_number: Uint,
+    /// GeneralizedTime, which may have fractional seconds that the DER types do not support
+    gen_time: Any,
+    #[asn1(optional = "true")]
+    accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    ordering: bool,
+    #[asn1(optional = "true")]
+    nonce: Option<Uint>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    tsa: Option<GeneralName>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+#[derive(Sequence)]
+struct Accuracy {
+    #[asn1(optional = "true")]
+    seconds: Option<u32>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    micros: Option<u16>,
+}
+
+/// The parts of a token that are verified
+struct ParsedToken {
+    signed_data: SignedData,
+    /// DER encoding of the TSTInfo, over which the message digest attribute is computed
+    content: Vec<u8>,
+    tst_info: TstInfo,
+}
+
+impl TimestampToken {
+    /// Requests a timestamp over `signed` from the TSA at `url`.
+    /// Returns the token and the time it states, which is not trusted until [`TimestampToken::verify`] succeeds
+    pub fn request(url: &str, signed: &[u8]) -> Result<(Self, DateTime<Utc>)> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let nonce = Uint::new(&nonce).context("Failed to encode nonce")?;
+        let request = TimeStampReq {
+            version: 1,
+            message_imprint: MessageImprint::new(HashAlgorithm::Sha256, signed)?,
+            req_policy: None,
+            nonce: Some(nonce.clone()),
+            cert_req: true,
+            extensions: None,
+        }
+        .to_der()
+        .context("Failed to encode timestamp request")?;
+
+        let response = ureq::post(url)
+            .set("Content-Type", TIMESTAMP_QUERY_CONTENT_TYPE)
+            .send_bytes(&request)
+            .context(format!("Failed to request a timestamp from {url}"))?;
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_RESPONSE_LEN)
+            .read_to_end(&mut body)
+            .context(format!("Failed to read the timestamp response of {url}"))?;
+
+        let response = TimeStampResp::from_der(&body)
+            .context(format!("{url} did not send an RFC 3161 timestamp response"))?;
+        let status = &response.status;
+        if !GRANTED_STATUSES.contains(&status.status) {
+            match &status.status_string {
+                Some(reason) => bail!(
+                    "{url} rejected the timestamp request with status {}: {}",
+                    status.status,
+                    reason.join(" ")
+                ),
+                None => bail!(
+                    "{url} rejected the timestamp request with status {}",
+                    status.status
+                ),
+            }
+        }
+        let token = response
+            .time_stamp_token
+            .context(format!("{url} granted the request, but sent no token"))?;
+        let token = Self(token.to_der().context("Failed to encode timestamp token")?);
+
+        let tst_info = token.parse()?.tst_info;
+        ensure!(
+            tst_info.nonce.as_ref() == Some(&nonce),
+            "The timestamp of {url} does not answer the request: its nonce differs"
+        );
+        tst_info.message_imprint.ensure_matches(signed)?;
+        Ok((token, tst_info.latest_time()?))
+    }
+
+    /// Checks that the token was made over `signed` by a TSA of the `trusted` certificates.
+    /// Returns the latest time `signed` can have been made: the time of the token plus its accuracy
+    pub fn verify(&self, signed: &[u8], trusted: &[Certificate]) -> Result<DateTime<Utc>> {
+        ensure!(!trusted.is_empty(), "No TSA certificates are configured");
+        let token = self.parse()?;
+        token.tst_info.message_imprint.ensure_matches(signed)?;
+        let time = token.tst_info.latest_time()?;
+
+        let [signer_info] = token.signed_data.signer_infos.0.as_slice() else {
+            bail!("A timestamp token must have exactly one signer");
+        };
+        let signed_attributes = signer_info
+            .signed_attrs
+            .as_ref()
+            .context("The timestamp token has no signed attributes")?;
+        let digest = HashAlgorithm::from_oid(&signer_info.digest_alg.oid)?;
+        let content_type: ObjectIdentifier =
+            attribute(signed_attributes, rfc5911::ID_CONTENT_TYPE)?;
+        ensure!(
+            content_type == ID_CT_TST_INFO,
+            "The content type attribute of the timestamp token is not TSTInfo"
+        );
+        let message_digest: OctetString = attribute(signed_attributes, rfc5911::ID_MESSAGE_DIGEST)?;
+        ensure!(
+            message_digest.as_bytes() == digest.hash(&token.content),
+            "The timestamp token does not match its message digest attribute"
+        );
+
+        let certificates: Vec<Certificate> = token
+            .signed_data
+            .certificates
+            .iter()
+            .flat_map(|certificates| certificates.0.iter())
+            .filter_map(|choice| match choice {
+                CertificateChoices::Certificate(certificate) => Some(certificate.clone()),
+                CertificateChoices::Other(_) => None,
+            })
+            .collect();
+        let signer = certificates
+            .iter()
+            .chain(trusted)
+            .find(|certificate| is_signer(certificate, &signer_info.sid))
+            .context("The certificate of the TSA is neither in the token nor configured")?;
+
+        // RSA signatures may name only the key algorithm, the digest is that of the signer
+        let signature_algorithm = match signer_info.signature_algorithm.oid {
+            rfc5912::RSA_ENCRYPTION => AlgorithmIdentifierOwned {
+                oid: digest.with_rsa_encryption(),
+                parameters: None,
+            },
+            _ => signer_info.signature_algorithm.clone(),
+        };
+        // Decoding sorts the attributes, so this is the signed encoding if the TSA encoded them in DER as required
+        verify_signed_message(
+            &signed_attributes
+                .to_der()
+                .context("Failed to encode signed attributes")?,
+            &signature_algorithm,
+            signer_info.signature.as_bytes(),
+            &signer.tbs_certificate.subject_public_key_info,
+        )
+        .context("The signature of the timestamp token is not valid")?;
+        ensure!(
+            has_extended_key_usage(signer, rfc5912::ID_KP_TIME_STAMPING)?,
+            "The certificate {} may not sign timestamps",
+            signer.tbs_certificate.subject
+        );
+
+        let mut reason = None;
+        for certificate in trusted {
+            let result = if certificate == signer {
+                ensure_valid_at(signer, time)
+            } else {
+                verify_chain(signer, &certificates, certificate, time).map(|_| ())
+            };
+            match result {
+                Ok(()) => return Ok(time),
+                Err(err) => {
+                    reason.get_or_insert(err);
+                }
+            }
+        }
+        Err(reason
+            .unwrap_or_else(|| anyhow!("No TSA certificates are configured"))
+            .context(format!(
+                "The TSA {} is not trusted",
+                signer.tbs_certificate.subject
+            )))
+    }
+
+    fn parse(&self) -> Result<ParsedToken> {
+        let content_info =
+            ContentInfo::from_der(&self.0).context("The timestamp token is not valid DER")?;
+        ensure!(
+            content_info.content_type == rfc5911::ID_SIGNED_DATA,
+            "The timestamp token is not CMS signed data"
+        );
+        let signed_data: SignedData = content_info
+            .content
+            .decode_as()
+            .context("The timestamp token has invalid signed data")?;
+        let encapsulated = &signed_data.encap_content_info;
+        ensure!(
+            encapsulated.econtent_type == ID_CT_TST_INFO,
+            "The timestamp token does not contain a TSTInfo"
+        );
+        let content = encapsulated
+            .econtent
+            .as_ref()
+            .context("The timestamp token has no content")?
+            .decode_as::<OctetString>()
+            .context("The content of the timestamp token is not an octet string")?
+            .into_bytes();
+        let tst_info = TstInfo::from_der(&content).context("The TSTInfo is not valid")?;
+
+        Ok(ParsedToken {
+            signed_data,
+            content,
+            tst_info,
+        })
+    }
+}
+
+impl MessageImprint {
+    fn new(hash: HashAlgorithm, message: &[u8]) -> Result<Self> {
+        Ok(Self {
+            hash_algorithm: AlgorithmIdentifierOwned {
+                oid: hash.oid(),
+                parameters: None,
+            },
+            hashed_message: OctetString::new(hash.hash(message))
+                .context("Failed to encode message digest")?,
+        })
+    }
+
+    fn ensure_matches(&self, message: &[u8]) -> Result<()> {
+        let hash = HashAlgorithm::from_oid(&self.hash_algorithm.oid)?;
+        ensure!(
+            self.hashed_message.as_bytes() == hash.hash(message),
+            "The timestamp was made for other content"
+        );
+        Ok(())
+    }
+}
+
+impl TstInfo {
+    /// Time of the timestamp plus its accuracy, rounded up to whole seconds
+    fn latest_time(&self) -> Result<DateTime<Utc>> {
+        ensure!(
+            self.gen_time.tag() == Tag::GeneralizedTime,
+            "The time of the timestamp is not a GeneralizedTime"
+        );
+        let value = std::str::from_utf8(self.gen_time.value())
+            .ok()
+            .and_then(|value| value.strip_suffix('Z'))
+            .context("The time of the timestamp is not in UTC")?;
+        let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let mut time = NaiveDateTime::parse_from_str(seconds, "%Y%m%d%H%M%S")
+            .context(format!("Invalid time in timestamp: {value}"))?
+            .and_utc();
+        if fraction.bytes().any(|digit| digit != b'0') {
+            time += TimeDelta::seconds(1);
+        }
+
+        if let Some(accuracy) = &self.accuracy {
+            let micros = i64::from(accuracy.seconds.unwrap_or(0)) * 1_000_000
+                + i64::from(accuracy.millis.unwrap_or(0)) * 1_000
+                + i64::from(accuracy.micros.unwrap_or(0));
+            time += TimeDelta::microseconds(micros);
+        }
+        Ok(time)
+    }
+}
+
+/// Returns the only value of the signed attribute `oid`
+fn attribute<'a, T>(attributes: &'a SignedAttributes, oid: ObjectIdentifier) -> Result<T>
+where
+    T: der::Choice<'a> + der::DecodeValue<'a>,
+{
+    let attribute = attributes
+        .iter()
+        .find(|attribute| attribute.oid == oid)
+        .context(format!(
+            "The timestamp token lacks the signed attribute {oid}"
+        ))?;
+    let [value] = attribute.values.as_slice() else {
+        bail!("The signed attribute {oid} must have exactly one value");
+    };
+    value
+        .decode_as()
+        .context(format!("The signed attribute {oid} is not valid"))
+}
+
+fn is_signer(certificate: &Certificate, signer: &SignerIdentifier) -> bool {
+    let tbs = &certificate.tbs_certificate;
+    match signer {
+        SignerIdentifier::IssuerAndSerialNumber(id) => {
+            tbs.issuer == id.issuer && tbs.serial_number == id.serial_number
+        }
+        SignerIdentifier::SubjectKeyIdentifier(id) => tbs
+            .extensions
+            .iter()
+            .flatten()
+            .filter(|extension| extension.extn_id == SubjectKeyIdentifier::OID)
+            .any(|extension| {
+                id.to_der()
+                    .is_ok_and(|encoded| encoded == extension.extn_value.as_bytes())
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use chrono::Duration;
+    use cms::{
+        cert::IssuerAndSerialNumber,
+        content_info::CmsVersion,
+        signed_data::{CertificateSet, EncapsulatedContentInfo, SignerInfo, SignerInfos},
+    };
+    use der::asn1::SetOfVec;
+    use oqs::sig::{Algorithm as SigAlgorithm, SecretKey as SigSecretKey, Sig};
+    use x509_cert::attr::Attribute;
+
+    use super::*;
+    use crate::cryptography::{
+        asn1::signature_algorithm_identifier,
+        x509::{CertificateOptions, X509Signer, issue_certificate, parse_name},
+    };
+
+    /// Stand-in for a time-stamping authority. It answers one timestamp request per connection over HTTP
+    /// on a local port and signs with an ML-DSA key that a test CA certified for time stamping
+    struct TestTsa {
+        url: String,
+        root: Certificate,
+        certificate: Certificate,
+    }
+
+    impl TestTsa {
+        fn start(requests: usize) -> Self {
+            let sig = Sig::new(SigAlgorithm::MlDsa44).unwrap();
+            let (root_public_key, root_secret_key) = sig.keypair().unwrap();
+            let (public_key, secret_key) = sig.keypair().unwrap();
+            let root_signer = X509Signer {
+                signature: &sig,
+                public_key: &root_public_key,
+                secret_key: &root_secret_key,
+            };
+            let options = |subject, public_key_info, ca, extended_key_usage| CertificateOptions {
+                subject: parse_name(subject).unwrap(),
+                public_key_info,
+                not_after: Utc::now() + Duration::days(if ca { 2 } else { 1 }),
+                ca,
+                extended_key_usage,
+            };
+            let root = issue_certificate(
+                options(
+                    "CN=Test TSA Root",
+                    root_signer.public_key_info().unwrap(),
+                    true,
+                    Vec::new(),
+                ),
+                None,
+                &root_signer,
+            )
+            .unwrap();
+            let tsa_signer = X509Signer {
+                signature: &sig,
+                public_key: &public_key,
+                secret_key: &secret_key,
+            };
+            let certificate = issue_certificate(
+                options(
+                    "CN=Test TSA",
+                    tsa_signer.public_key_info().unwrap(),
+                    false,
+                    vec![rfc5912::ID_KP_TIME_STAMPING],
+                ),
+                Some(&root),
+                &root_signer,
+            )
+            .unwrap();
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/tsa", listener.local_addr().unwrap());
+            let tsa_certificate = certificate.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().take(requests) {
+                    let stream = stream.unwrap();
+                    let mut reader = BufReader::new(&stream);
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':')
+                            && name.eq_ignore_ascii_case("content-length")
+                        {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                    let mut request = vec![0; content_length];
+                    reader.read_exact(&mut request).unwrap();
+
+                    let request = TimeStampReq::from_der(&request).unwrap();
+                    let response = respond(&request, &sig, &secret_key, &tsa_certificate);
+                    write!(
+                        &stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/timestamp-reply\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        response.len()
+                    )
+                    .unwrap();
+                    (&stream).write_all(&response).unwrap();
+                }
+            });
+
+            Self {
+                url,
+                root,
+                certificate,
+            }
+        }
+    }
+
+    /// Returns a granted response with a token over the message imprint of `request`.
+    /// The time has fractional seconds and an accuracy, as many TSAs send them
+    fn respond(
+        request: &TimeStampReq,
+        sig: &Sig,
+        secret_key: &SigSecretKey,
+        certificate: &Certificate,
+    ) -> Vec<u8> {
+        let sha256 = AlgorithmIdentifierOwned {
+            oid: rfc5912::ID_SHA_256,
+            parameters: None,
+        };
+        let tst_info = TstInfo {
+            version: 1,
+            policy: ObjectIdentifier::new_unwrap("1.3.6.1.4.1.4146.2.3"),
+            message_imprint: MessageImprint {
+                hash_algorithm: request.message_imprint.hash_algorithm.clone(),
+                hashed_message: request.message_imprint.hashed_message.clone(),
+            },
+            serial_number: Uint::new(&[1]).unwrap(),
+            gen_time: Any::new(
+                Tag::GeneralizedTime,
+                Utc::now()
+                    .format("%Y%m%d%H%M%S.25Z")
+                    .to_string()
+                    .into_bytes(),
+            )
+            .unwrap(),
+            accuracy: Some(Accuracy {
+                seconds: Some(1),
+                millis: None,
+                micros: None,
+            }),
+            ordering: false,
+            nonce: request.nonce.clone(),
+            tsa: None,
+            extensions: None,
+        }
+        .to_der()
+        .unwrap();
+
+        let attribute = |oid, value| Attribute {
+            oid,
+            values: SetOfVec::try_from(vec![value]).unwrap(),
+        };
+        let signed_attributes: SignedAttributes = SetOfVec::try_from(vec![
+            attribute(
+                rfc5911::ID_CONTENT_TYPE,
+                Any::encode_from(&ID_CT_TST_INFO).unwrap(),
+            ),
+            attribute(
+                rfc5911::ID_MESSAGE_DIGEST,
+                Any::encode_from(&OctetString::new(HashAlgorithm::Sha256.hash(&tst_info)).unwrap())
+                    .unwrap(),
+            ),
+        ])
+        .unwrap();
+        let signature = sig
+            .sign(&signed_attributes.to_der().unwrap(), secret_key)
+            .unwrap();
+
+        let signer_info = SignerInfo {
+            version: CmsVersion::V1,
+            sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: certificate.tbs_certificate.issuer.clone(),
+                serial_number: certificate.tbs_certificate.serial_number.clone(),
+            }),
+            digest_alg: sha256.clone(),
+            signed_attrs: Some(signed_attributes),
+            signature_algorithm: signature_algorithm_identifier(sig.algorithm()).unwrap(),
+            signature: OctetString::new(signature.into_vec()).unwrap(),
+            unsigned_attrs: None,
+        };
+        let signed_data = SignedData {
+            version: CmsVersion::V3,
+            digest_algorithms: SetOfVec::try_from(vec![sha256]).unwrap(),
+            encap_content_info: EncapsulatedContentInfo {
+                econtent_type: ID_CT_TST_INFO,
+                econtent: Some(Any::encode_from(&OctetString::new(tst_info).unwrap()).unwrap()),
+            },
+            certificates: Some(CertificateSet(
+                SetOfVec::try_from(vec![CertificateChoices::Certificate(certificate.clone())])
+                    .unwrap(),
+            )),
+            crls: None,
+            signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info]).unwrap()),
+        };
+
+        TimeStampResp {
+            status: PkiStatusInfo {
+                status: 0,
+                status_string: None,
+                fail_info: None,
+            },
+            time_stamp_token: Some(ContentInfo {
+                content_type: rfc5911::ID_SIGNED_DATA,
+                content: Any::encode_from(&signed_data).unwrap(),
+            }),
+        }
+        .to_der()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_timestamp_of_trusted_tsa_is_verified() {
+        let tsa = TestTsa::start(1);
+        let before = Utc::now();
+        let (token, time) = TimestampToken::request(&tsa.url, b"signature").unwrap();
+        // The fraction of the second and the accuracy are added to the time
+        assert!(before + Duration::seconds(1) < time);
+        assert!(time <= Utc::now() + Duration::seconds(2));
+
+        let token: TimestampToken =
+            serde_json::from_str(&serde_json::to_string(&token).unwrap()).unwrap();
+        assert_eq!(
+            token
+                .verify(b"signature", std::slice::from_ref(&tsa.root))
+                .unwrap(),
+            time
+        );
+        assert_eq!(
+            token
+                .verify(b"signature", std::slice::from_ref(&tsa.certificate))
+                .unwrap(),
+            time
+        );
+
+        assert!(
+            token
+                .verify(b"other", std::slice::from_ref(&tsa.root))
+                .is_err()
+        );
+        assert!(token.verify(b"signature", &[]).is_err());
+        assert!(
+            token
+                .verify(b"signature", &[TestTsa::start(0).root])
+                .is_err()
+        );
+        let mut tampered = token.clone();
+        *tampered.0.last_mut().unwrap() ^= 1;
+        assert!(tampered.verify(b"signature", &[tsa.root]).is_err());
+    }
+}
@@ -0,0 +1,616 @@
+//! X.509 certificates and certificate signing requests (CSRs) for post-quantum signature keys.
+//!
+//! ML-DSA keys and signatures follow RFC 9881: the public key is stored as is in the subject public key info
+//! and the signature is pure ML-DSA with an empty context.
+use std::{path::Path, str::FromStr, time::SystemTime};
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use anyhow::{Context, Result, ensure};
+use chrono::{DateTime, Datelike, SubsecRound, Utc};
+use oqs::sig::{PublicKey as SigPublicKey, SecretKey as SigSecretKey, Sig};
+use sha2::{Digest, Sha256};
+use x509_cert::{
+    Certificate, TbsCertificate, Version,
+    der::{
+        Decode, Encode,
+        asn1::{BitString, GeneralizedTime, ObjectIdentifier, OctetString, UtcTime},
+        oid::{AssociatedOid, db::rfc5280},
+    },
+    ext::{
+        AsExtension, Extension,
+        pkix::{
+            AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage, KeyUsages,
+            SubjectKeyIdentifier,
+        },
+    },
+    name::Name,
+    request::{CertReq, CertReqInfo, Version as RequestVersion},
+    serial_number::SerialNumber,
+    spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+    time::{Time, Validity},
+};
+
+use super::{
+    asn1::{read_der, signature_algorithm, signature_algorithm_identifier},
+    classic::{is_classic_signature_algorithm, verify_classic_signature},
+};
+
+/// Serial numbers are random, as recommended by the CA/Browser Forum baseline requirements
+const SERIAL_NUMBER_LEN: usize = 16;
+
+/// An own signature key used to sign requests and certificates
+pub struct X509Signer<'a> {
+    pub signature: &'a Sig,
+    pub public_key: &'a SigPublicKey,
+    pub secret_key: &'a SigSecretKey,
+}
+
+impl X509Signer<'_> {
+    fn algorithm_identifier(&self) -> Result<AlgorithmIdentifierOwned> {
+        signature_algorithm_identifier(self.signature.algorithm())
+    }
+
+    pub fn public_key_info(&self) -> Result<SubjectPublicKeyInfoOwned> {
+        Ok(SubjectPublicKeyInfoOwned {
+            algorithm: self.algorithm_identifier()?,
+            subject_public_key: BitString::from_bytes(self.public_key.as_ref())
+                .context("Failed to encode public key")?,
+        })
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<BitString> {
+        let signature = self
+            .signature
+            .sign(message, self.secret_key)
+            .context("Failed to sign")?;
+        BitString::from_bytes(signature.as_ref()).context("Failed to encode signature")
+    }
+}
+
+/// Validity and type of a certificate to be issued
+pub struct CertificateOptions {
+    pub subject: Name,
+    pub public_key_info: SubjectPublicKeyInfoOwned,
+    pub not_after: DateTime<Utc>,
+    /// Whether the certificate may sign other certificates
+    pub ca: bool,
+    /// Purposes the key is restricted to, e.g. time stamping. The extension is critical if there are any
+    pub extended_key_usage: Vec<ObjectIdentifier>,
+}
+
+/// Parses a distinguished name such as `CN=Alice,O=Example` (RFC 4514)
+pub fn parse_name(value: &str) -> Result<Name> {
+    let name = Name::from_str(value).context(format!("Invalid distinguished name: {value}"))?;
+    ensure!(!name.0.is_empty(), "Distinguished name must not be empty");
+    Ok(name)
+}
+
+/// Creates a certificate signing request for the key of `signer`
+pub fn create_request(subject: Name, signer: &X509Signer) -> Result<CertReq> {
+    let info = CertReqInfo {
+        version: RequestVersion::V1,
+        subject,
+        public_key: signer.public_key_info()?,
+        attributes: Default::default(),
+    };
+    let signature = signer.sign(&info.to_der().context("Failed to encode request")?)?;
+
+    Ok(CertReq {
+        info,
+        algorithm: signer.algorithm_identifier()?,
+        signature,
+    })
+}
+
+/// Checks that a request was signed by the key it contains, which proves possession of the secret key
+pub fn verify_request(request: &CertReq) -> Result<()> {
+    verify_signature(
+        &request.info.to_der().context("Failed to encode request")?,
+        &request.algorithm,
+        &request.signature,
+        &request.info.public_key,
+    )
+    .context("Request signature verification failed")
+}
+
+/// Issues a certificate signed by `signer`.
+/// Without an `issuer` certificate, the certificate is self-signed and `options` must contain the key of `signer`.
+pub fn issue_certificate(
+    options: CertificateOptions,
+    issuer: Option<&Certificate>,
+    signer: &X509Signer,
+) -> Result<Certificate> {
+    let now = Utc::now().trunc_subsecs(0);
+    ensure!(
+        now < options.not_after,
+        "Certificate would expire before it becomes valid"
+    );
+    let signer_key_info = signer.public_key_info()?;
+
+    let (issuer_name, authority_key_id) = match issuer {
+        Some(issuer) => {
+            let tbs = &issuer.tbs_certificate;
+            ensure!(
+                tbs.subject_public_key_info == signer_key_info,
+                "The issuer certificate does not belong to your {} key",
+                signer.signature.algorithm()
+            );
+            ensure!(
+                is_ca(issuer)?,
+                "The issuer certificate {} is not a CA certificate",
+                tbs.subject
+            );
+            ensure_valid_at(issuer, now)?;
+            ensure!(
+                options.not_after <= to_date_time(&tbs.validity.not_after),
+                "Certificate must not be valid longer than its issuer, which expires on {}",
+                tbs.validity.not_after
+            );
+            (
+                tbs.subject.clone(),
+                key_identifier(&tbs.subject_public_key_info),
+            )
+        }
+        None => {
+            ensure!(
+                options.public_key_info == signer_key_info,
+                "A self-signed certificate must contain the signer's key"
+            );
+            (
+                options.subject.clone(),
+                key_identifier(&options.public_key_info),
+            )
+        }
+    };
+
+    let key_usage = if options.ca {
+        KeyUsage(KeyUsages::KeyCertSign | KeyUsages::CRLSign | KeyUsages::DigitalSignature)
+    } else {
+        KeyUsage(KeyUsages::DigitalSignature.into())
+    };
+    let extension_list = [
+        BasicConstraints {
+            ca: options.ca,
+            path_len_constraint: None,
+        }
+        .to_extension(&options.subject, &[]),
+        key_usage.to_extension(&options.subject, &[]),
+        SubjectKeyIdentifier(key_identifier(&options.public_key_info)?)
+            .to_extension(&options.subject, &[]),
+        AuthorityKeyIdentifier {
+            key_identifier: Some(authority_key_id?),
+            authority_cert_issuer: None,
+            authority_cert_serial_number: None,
+        }
+        .to_extension(&options.subject, &[]),
+    ];
+    let mut extensions = extension_list
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to encode certificate extensions")?;
+    if !options.extended_key_usage.is_empty() {
+        let usage = ExtendedKeyUsage(options.extended_key_usage)
+            .to_der()
+            .context("Failed to encode extended key usage")?;
+        extensions.push(Extension {
+            extn_id: ExtendedKeyUsage::OID,
+            critical: true,
+            extn_value: OctetString::new(usage).context("Failed to encode extended key usage")?,
+        });
+    }
+
+    let tbs_certificate = TbsCertificate {
+        version: Version::V3,
+        serial_number: random_serial_number()?,
+        signature: signer.algorithm_identifier()?,
+        issuer: issuer_name,
+        validity: Validity {
+            not_before: to_x509_time(now)?,
+            not_after: to_x509_time(options.not_after)?,
+        },
+        subject: options.subject,
+        subject_public_key_info: options.public_key_info,
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(extensions),
+    };
+    let signature = signer.sign(
+        &tbs_certificate
+            .to_der()
+            .context("Failed to encode certificate")?,
+    )?;
+
+    Ok(Certificate {
+        tbs_certificate,
+        signature_algorithm: signer.algorithm_identifier()?,
+        signature,
+    })
+}
+
+/// Verifies that `certificate` chains up to the trusted `root` through `intermediates` at time `now`.
+/// Returns the chain from `certificate` to `root`.
+pub fn verify_chain<'a>(
+    certificate: &'a Certificate,
+    intermediates: &'a [Certificate],
+    root: &'a Certificate,
+    now: DateTime<Utc>,
+) -> Result<Vec<&'a Certificate>> {
+    ensure_supported_extensions(root)?;
+    ensure!(
+        root.tbs_certificate.issuer == root.tbs_certificate.subject,
+        "The root certificate {} is not self-signed",
+        root.tbs_certificate.subject
+    );
+    verify_issued_by(root, root).context("The root certificate has an invalid self-signature")?;
+    ensure!(
+        is_ca(root)?,
+        "The root certificate {} is not a CA certificate",
+        root.tbs_certificate.subject
+    );
+    ensure_valid_at(root, now)?;
+    if certificate == root {
+        return Ok(vec![root]);
+    }
+
+    let mut chain = vec![certificate];
+    loop {
+        let current = chain[chain.len() - 1];
+        ensure_supported_extensions(current)?;
+        ensure_valid_at(current, now)?;
+
+        // The number of intermediate certificates between the issuer and the end entity
+        let issued_intermediates = (chain.len() - 1) as u8;
+        let issuer = if current.tbs_certificate.issuer == root.tbs_certificate.subject
+            && verify_issued_by(current, root).is_ok()
+        {
+            root
+        } else {
+            let issuer = intermediates
+                .iter()
+                .filter(|candidate| !chain.contains(candidate))
+                .find(|candidate| {
+                    candidate.tbs_certificate.subject == current.tbs_certificate.issuer
+                        && verify_issued_by(current, candidate).is_ok()
+                })
+                .context(format!(
+                    "No trusted issuer found for certificate {} (issued by {})",
+                    current.tbs_certificate.subject, current.tbs_certificate.issuer
+                ))?;
+            ensure!(
+                is_ca(issuer)?,
+                "Certificate {} is not a CA certificate and must not issue certificates",
+                issuer.tbs_certificate.subject
+            );
+            issuer
+        };
+
+        if let Some(path_len) = basic_constraints(issuer)?.and_then(|bc| bc.path_len_constraint) {
+            ensure!(
+                issued_intermediates <= path_len,
+                "Path length constraint of {} is exceeded",
+                issuer.tbs_certificate.subject
+            );
+        }
+
+        chain.push(issuer);
+        if std::ptr::eq(issuer, root) {
+            return Ok(chain);
+        }
+    }
+}
+
+/// Checks that `certificate` was signed by the key of `issuer`
+fn verify_issued_by(certificate: &Certificate, issuer: &Certificate) -> Result<()> {
+    ensure!(
+        certificate.signature_algorithm == certificate.tbs_certificate.signature,
+        "Certificate {} has mismatching signature algorithms",
+        certificate.tbs_certificate.subject
+    );
+    verify_signature(
+        &certificate
+            .tbs_certificate
+            .to_der()
+            .context("Failed to encode certificate")?,
+        &certificate.signature_algorithm,
+        &certificate.signature,
+        &issuer.tbs_certificate.subject_public_key_info,
+    )
+    .context(format!(
+        "Signature of certificate {} verification failed",
+        certificate.tbs_certificate.subject
+    ))
+}
+
+fn verify_signature(
+    message: &[u8],
+    algorithm: &AlgorithmIdentifierOwned,
+    signature: &BitString,
+    public_key_info: &SubjectPublicKeyInfoOwned,
+) -> Result<()> {
+    let signature = signature
+        .as_bytes()
+        .context("Signature is not a whole number of bytes")?;
+    verify_signed_message(message, algorithm, signature, public_key_info)
+}
+
+/// Verifies `signature` over `message` with the key of `public_key_info`.
+/// Besides ML-DSA, the RSA and ECDSA signatures of time-stamping authorities and their CAs are supported
+pub fn verify_signed_message(
+    message: &[u8],
+    algorithm: &AlgorithmIdentifierOwned,
+    signature: &[u8],
+    public_key_info: &SubjectPublicKeyInfoOwned,
+) -> Result<()> {
+    if is_classic_signature_algorithm(&algorithm.oid) {
+        return verify_classic_signature(message, &algorithm.oid, signature, public_key_info);
+    }
+
+    ensure!(
+        algorithm == &public_key_info.algorithm,
+        "Signature algorithm {} does not match the key algorithm {}",
+        algorithm.oid,
+        public_key_info.algorithm.oid
+    );
+    let verifier =
+        Sig::new(signature_algorithm(algorithm)?).context("Signature algorithm is not enabled")?;
+    let public_key = verifier
+        .public_key_from_bytes(
+            public_key_info
+                .subject_public_key
+                .as_bytes()
+                .context("Public key is not a whole number of bytes")?,
+        )
+        .context("Public key is not valid")?;
+    let signature = verifier
+        .signature_from_bytes(signature)
+        .context("Signature is not valid")?;
+
+    verifier
+        .verify(message, signature, public_key)
+        .context("Signature verification failed")
+}
+
+fn find_extension(certificate: &Certificate, oid: ObjectIdentifier) -> Option<&Extension> {
+    certificate
+        .tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .find(|extension| extension.extn_id == oid)
+}
+
+fn basic_constraints(certificate: &Certificate) -> Result<Option<BasicConstraints>> {
+    find_extension(certificate, BasicConstraints::OID)
+        .map(|extension| {
+            BasicConstraints::from_der(extension.extn_value.as_bytes())
+                .context("Invalid basic constraints extension")
+        })
+        .transpose()
+}
+
+/// A CA certificate must have the CA basic constraint and, if its key usage is restricted, may sign certificates
+fn is_ca(certificate: &Certificate) -> Result<bool> {
+    let ca = basic_constraints(certificate)?.is_some_and(|constraints| constraints.ca);
+    let cert_sign = find_extension(certificate, KeyUsage::OID)
+        .map(|extension| {
+            KeyUsage::from_der(extension.extn_value.as_bytes())
+                .context("Invalid key usage extension")
+        })
+        .transpose()?
+        .is_none_or(|usage| usage.key_cert_sign());
+    Ok(ca && cert_sign)
+}
+
+/// Returns true if the extended key usage of `certificate` includes `purpose`
+pub fn has_extended_key_usage(
+    certificate: &Certificate,
+    purpose: ObjectIdentifier,
+) -> Result<bool> {
+    Ok(find_extension(certificate, ExtendedKeyUsage::OID)
+        .map(|extension| {
+            ExtendedKeyUsage::from_der(extension.extn_value.as_bytes())
+                .context("Invalid extended key usage extension")
+        })
+        .transpose()?
+        .is_some_and(|usage| usage.0.contains(&purpose)))
+}
+
+/// Certificates with critical extensions that are not understood must be rejected (RFC 5280, section 4.2).
+/// The extended key usage is checked by the users of the certificate that require a purpose, such as timestamps
+fn ensure_supported_extensions(certificate: &Certificate) -> Result<()> {
+    const SUPPORTED: [ObjectIdentifier; 3] = [
+        rfc5280::ID_CE_BASIC_CONSTRAINTS,
+        rfc5280::ID_CE_KEY_USAGE,
+        rfc5280::ID_CE_EXT_KEY_USAGE,
+    ];
+    for extension in certificate.tbs_certificate.extensions.iter().flatten() {
+        ensure!(
+            !extension.critical || SUPPORTED.contains(&extension.extn_id),
+            "Certificate {} has the unsupported critical extension {}",
+            certificate.tbs_certificate.subject,
+            extension.extn_id
+        );
+    }
+    Ok(())
+}
+
+pub fn ensure_valid_at(certificate: &Certificate, now: DateTime<Utc>) -> Result<()> {
+    let tbs = &certificate.tbs_certificate;
+    ensure!(
+        to_date_time(&tbs.validity.not_before) <= now,
+        "Certificate {} is not valid before {}",
+        tbs.subject,
+        tbs.validity.not_before
+    );
+    ensure!(
+        now <= to_date_time(&tbs.validity.not_after),
+        "Certificate {} expired on {}",
+        tbs.subject,
+        tbs.validity.not_after
+    );
+    Ok(())
+}
+
+/// Key identifier of RFC 7093, section 2, method 1: the leftmost 160 bits of the SHA-256 hash of the public key
+fn key_identifier(public_key_info: &SubjectPublicKeyInfoOwned) -> Result<OctetString> {
+    let hash = Sha256::digest(public_key_info.subject_public_key.raw_bytes());
+    OctetString::new(&hash[..20]).context("Failed to encode key identifier")
+}
+
+fn random_serial_number() -> Result<SerialNumber> {
+    let mut bytes = [0u8; SERIAL_NUMBER_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    // Serial numbers must be positive and should not have leading zeros
+    bytes[0] = (bytes[0] & 0x7f) | 0x40;
+    SerialNumber::new(&bytes).context("Failed to encode serial number")
+}
+
+/// Dates before 2050 must be encoded as UTCTime (RFC 5280, section 4.1.2.5)
+fn to_x509_time(time: DateTime<Utc>) -> Result<Time> {
+    let system_time = SystemTime::from(time);
+    let time = if time.year() < 2050 {
+        Time::UtcTime(UtcTime::from_system_time(system_time)?)
+    } else {
+        Time::GeneralTime(GeneralizedTime::from_system_time(system_time)?)
+    };
+    Ok(time)
+}
+
+fn to_date_time(time: &Time) -> DateTime<Utc> {
+    DateTime::<Utc>::from(time.to_system_time())
+}
+
+/// Reads a certificate in PEM or DER encoding
+pub fn read_certificate(path: &Path) -> Result<Certificate> {
+    Certificate::from_der(&read_der(path, "CERTIFICATE")?)
+        .context(format!("Failed to parse certificate {}", path.display()))
+}
+
+/// Reads a certificate signing request in PEM or DER encoding
+pub fn read_request(path: &Path) -> Result<CertReq> {
+    CertReq::from_der(&read_der(path, "CERTIFICATE REQUEST")?)
+        .context(format!("Failed to parse request {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use oqs::sig::Algorithm as SigAlgorithm;
+
+    use super::*;
+
+    struct Key {
+        signature: Sig,
+        public_key: SigPublicKey,
+        secret_key: SigSecretKey,
+    }
+
+    impl Key {
+        fn new(algorithm: SigAlgorithm) -> Self {
+            let signature = Sig::new(algorithm).unwrap();
+            let (public_key, secret_key) = signature.keypair().unwrap();
+            Self {
+                signature,
+                public_key,
+                secret_key,
+            }
+        }
+
+        fn signer(&self) -> X509Signer<'_> {
+            X509Signer {
+                signature: &self.signature,
+                public_key: &self.public_key,
+                secret_key: &self.secret_key,
+            }
+        }
+    }
+
+    fn self_signed(key: &Key, subject: &str) -> Certificate {
+        let signer = key.signer();
+        let options = CertificateOptions {
+            subject: parse_name(subject).unwrap(),
+            public_key_info: signer.public_key_info().unwrap(),
+            not_after: Utc::now() + Duration::days(30),
+            ca: true,
+            extended_key_usage: Vec::new(),
+        };
+        issue_certificate(options, None, &signer).unwrap()
+    }
+
+    fn issue(
+        key: &Key,
+        issuer: &Certificate,
+        issuer_key: &Key,
+        ca: bool,
+        days: i64,
+    ) -> Certificate {
+        let request = create_request(parse_name("CN=Leaf").unwrap(), &key.signer()).unwrap();
+        verify_request(&request).unwrap();
+        let options = CertificateOptions {
+            subject: request.info.subject,
+            public_key_info: request.info.public_key,
+            not_after: Utc::now() + Duration::days(days),
+            ca,
+            extended_key_usage: Vec::new(),
+        };
+        issue_certificate(options, Some(issuer), &issuer_key.signer()).unwrap()
+    }
+
+    #[test]
+    fn test_chain_verification() {
+        let root_key = Key::new(SigAlgorithm::MlDsa87);
+        let intermediate_key = Key::new(SigAlgorithm::MlDsa65);
+        let leaf_key = Key::new(SigAlgorithm::MlDsa44);
+
+        let root = self_signed(&root_key, "CN=Root,O=Example");
+        let intermediate = issue(&intermediate_key, &root, &root_key, true, 20);
+        let leaf = issue(&leaf_key, &intermediate, &intermediate_key, false, 7);
+
+        let parsed = Certificate::from_der(&leaf.to_der().unwrap()).unwrap();
+        let intermediates = [intermediate];
+        let chain = verify_chain(&parsed, &intermediates, &root, Utc::now()).unwrap();
+        assert_eq!(chain.len(), 3);
+
+        assert!(verify_chain(&parsed, &[], &root, Utc::now()).is_err());
+        assert!(
+            verify_chain(
+                &parsed,
+                &intermediates,
+                &root,
+                Utc::now() + Duration::days(8)
+            )
+            .is_err()
+        );
+
+        let other_root = self_signed(&Key::new(SigAlgorithm::MlDsa87), "CN=Root,O=Example");
+        assert!(verify_chain(&parsed, &intermediates, &other_root, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_end_entity_cannot_issue() {
+        let root_key = Key::new(SigAlgorithm::MlDsa44);
+        let leaf_key = Key::new(SigAlgorithm::MlDsa44);
+        let root = self_signed(&root_key, "CN=Root");
+        let leaf = issue(&leaf_key, &root, &root_key, false, 7);
+
+        let signer = leaf_key.signer();
+        let options = CertificateOptions {
+            subject: parse_name("CN=Other").unwrap(),
+            public_key_info: signer.public_key_info().unwrap(),
+            not_after: Utc::now() + Duration::days(1),
+            ca: false,
+            extended_key_usage: Vec::new(),
+        };
+        assert!(issue_certificate(options, Some(&leaf), &signer).is_err());
+    }
+
+    #[test]
+    fn test_modified_request_is_rejected() {
+        let key = Key::new(SigAlgorithm::MlDsa44);
+        let mut request = create_request(parse_name("CN=Alice").unwrap(), &key.signer()).unwrap();
+        verify_request(&request).unwrap();
+
+        request.info.subject = parse_name("CN=Mallory").unwrap();
+        assert!(verify_request(&request).is_err());
+    }
+}
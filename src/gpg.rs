@@ -0,0 +1,276 @@
+//! The subset of the gpg command line git uses as `gpg.program`, so commits and tags can be signed with rust-seal signature keys.
+//!
+//! git signs with `--status-fd=2 -bsau <KEY>` and verifies with `--keyid-format=long --status-fd=1 --verify <SIGNATURE> -`.
+//! It decides on the `[GNUPG:]` status lines; the other output is shown to the user by `git log --show-signature`.
+use std::io::Write;
+
+use anyhow::{Context, Result, bail};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, Sig};
+
+use crate::{
+    Config,
+    cli::validate_signature_algorithm,
+    config::Contacts,
+    cryptography::{
+        fingerprint::{fingerprint, fingerprints_match},
+        git_signature::GitSignature,
+    },
+    oqs::convert_str_to_sig_alg,
+};
+
+const STATUS_PREFIX: &str = "[GNUPG:]";
+const LOG_PREFIX: &str = "rust-seal:";
+
+/// How far the signer's key is trusted, reported like the gpg trust levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trust {
+    /// The own key
+    Ultimate,
+    /// A contact key, whose fingerprint was checked on import
+    Full,
+}
+
+impl Trust {
+    fn status(self) -> &'static str {
+        match self {
+            Trust::Ultimate => "TRUST_ULTIMATE",
+            Trust::Full => "TRUST_FULLY",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Trust::Ultimate => "ultimate",
+            Trust::Full => "full",
+        }
+    }
+}
+
+/// Signs `payload` with the own key named by `key` and returns the armored detached signature.
+///
+/// `key` is a signature algorithm or the fingerprint or key ID of an own key. git passes the
+/// committer identity if `user.signingkey` is not set, which selects the default signature algorithm.
+pub fn sign(payload: &[u8], key: &str, config: &Config, status: &mut impl Write) -> Result<String> {
+    let algorithm = resolve_signing_key(key, config)?;
+    let signer = Sig::new(algorithm).context("Signature algorithm is not enabled")?;
+    validate_signature_algorithm(&signer, config.policy(), false)?;
+    config.ensure_signature_key_valid(&algorithm)?;
+
+    let (public_key, secret_key) = config.get_signature_keys(&algorithm)?;
+    let signature = GitSignature::sign(payload, &signer, public_key.as_ref(), &secret_key)?;
+
+    let key_fingerprint = signature.statement.fingerprint.to_uppercase();
+    writeln!(status, "{STATUS_PREFIX} KEY_CONSIDERED {key_fingerprint} 0")?;
+    writeln!(
+        status,
+        "{STATUS_PREFIX} SIG_CREATED D 0 0 00 {} {key_fingerprint}",
+        signature.statement.created.timestamp()
+    )?;
+    signature.to_armored()
+}
+
+/// Verifies the armored detached signature of `payload` with the own keys and the contacts.
+/// Returns false if the signature does not match; fails if it cannot be checked at all
+pub fn verify(
+    armored: &str,
+    payload: &[u8],
+    config: &Config,
+    status: &mut impl Write,
+    log: &mut impl Write,
+) -> Result<bool> {
+    let signature = GitSignature::from_armored(armored)?;
+    let statement = &signature.statement;
+    let key_fingerprint = statement.fingerprint.to_uppercase();
+    let key_id = key_id(&statement.fingerprint);
+
+    writeln!(
+        log,
+        "{LOG_PREFIX} Signature made {}",
+        statement.created.format("%Y-%m-%d %H:%M:%S UTC")
+    )?;
+    writeln!(
+        log,
+        "{LOG_PREFIX}                using {} key {key_fingerprint}",
+        statement.algorithm
+    )?;
+    writeln!(status, "{STATUS_PREFIX} NEWSIG")?;
+
+    let Some((signer, public_key, trust)) =
+        signer_key(statement.algorithm, &key_fingerprint, config)?
+    else {
+        writeln!(
+            status,
+            "{STATUS_PREFIX} ERRSIG {key_id} 0 0 00 {} 9 {key_fingerprint}",
+            statement.created.timestamp()
+        )?;
+        writeln!(status, "{STATUS_PREFIX} NO_PUBKEY {key_id}")?;
+        bail!(
+            "Can't check signature: no {} key with fingerprint {key_fingerprint}. Import it with `rust-seal contacts import`",
+            statement.algorithm
+        );
+    };
+
+    if let Err(err) = signature.verify(payload, &public_key) {
+        writeln!(status, "{STATUS_PREFIX} BADSIG {key_id} {signer}")?;
+        writeln!(log, "{LOG_PREFIX} BAD signature from \"{signer}\": {err:#}")?;
+        return Ok(false);
+    }
+
+    writeln!(status, "{STATUS_PREFIX} GOODSIG {key_id} {signer}")?;
+    writeln!(
+        status,
+        "{STATUS_PREFIX} VALIDSIG {key_fingerprint} {} {} 0 4 0 0 0 00 {key_fingerprint}",
+        statement.created.format("%Y-%m-%d"),
+        statement.created.timestamp()
+    )?;
+    writeln!(status, "{STATUS_PREFIX} {} 0 pgp", trust.status())?;
+    writeln!(
+        log,
+        "{LOG_PREFIX} Good signature from \"{signer}\" [{}]",
+        trust.name()
+    )?;
+    Ok(true)
+}
+
+fn resolve_signing_key(key: &str, config: &Config) -> Result<SigAlgorithmVariant> {
+    if let Ok(algorithm) = convert_str_to_sig_alg(key) {
+        return Ok(algorithm);
+    }
+
+    for algorithm in config.signature_key_algorithms() {
+        let (public_key, _) = config.get_signature_keys(&algorithm)?;
+        let own_fingerprint = fingerprint(&algorithm.to_string(), public_key.as_ref());
+        if fingerprints_match(&own_fingerprint, key)
+            || fingerprints_match(&key_id(&own_fingerprint), key)
+        {
+            return Ok(algorithm);
+        }
+    }
+
+    if key.contains('<') {
+        return config.default_signature_algorithm().context(
+            "user.signingkey is not set and no default signature algorithm is configured. Set user.signingkey to a signature algorithm or key fingerprint",
+        );
+    }
+    bail!(
+        "No own signature key matches {key}. Use a signature algorithm or the fingerprint of one of your keys"
+    )
+}
+
+/// Returns the name, public key and trust of the own key or contact key with `key_fingerprint`,
+/// or `None` if neither is known. Fails if the key is revoked or expired
+fn signer_key(
+    algorithm: SigAlgorithmVariant,
+    key_fingerprint: &str,
+    config: &Config,
+) -> Result<Option<(String, Vec<u8>, Trust)>> {
+    if let Ok((public_key, _)) = config.get_signature_keys(&algorithm)
+        && fingerprints_match(
+            &fingerprint(&algorithm.to_string(), public_key.as_ref()),
+            key_fingerprint,
+        )
+    {
+        config.ensure_signature_key_valid(&algorithm)?;
+        return Ok(Some((
+            format!("rust-seal {algorithm} key"),
+            public_key.into_vec(),
+            Trust::Ultimate,
+        )));
+    }
+
+    let contacts = Contacts::load(config.home()).context("Failed to load contacts")?;
+    let Some(name) = contacts.signature_key_owner(algorithm, key_fingerprint) else {
+        return Ok(None);
+    };
+    let public_key = contacts.signature_public_key(name, algorithm)?.to_vec();
+    Ok(Some((name.to_string(), public_key, Trust::Full)))
+}
+
+/// The long key ID: the first 16 hexadecimal digits of the fingerprint
+fn key_id(key_fingerprint: &str) -> String {
+    key_fingerprint[..16].to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Home;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sign_and_verify_like_git() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::load(Home::from_root(dir.path())).unwrap();
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa65).unwrap();
+        config
+            .add_signature_algorithm(&signer, None, false)
+            .unwrap();
+        let (public_key, _) = config
+            .get_signature_keys(&SigAlgorithmVariant::MlDsa65)
+            .unwrap();
+        let key_fingerprint = fingerprint("ML-DSA-65", public_key.as_ref());
+
+        let payload = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n";
+        for key in [
+            "ML-DSA-65",
+            key_fingerprint.as_str(),
+            &key_id(&key_fingerprint),
+        ] {
+            let mut status = Vec::new();
+            let armored = sign(payload, key, &config, &mut status).unwrap();
+            assert!(
+                String::from_utf8(status)
+                    .unwrap()
+                    .contains("[GNUPG:] SIG_CREATED ")
+            );
+
+            let (mut status, mut log) = (Vec::new(), Vec::new());
+            assert!(verify(&armored, payload, &config, &mut status, &mut log).unwrap());
+            let status = String::from_utf8(status).unwrap();
+            assert!(status.contains(&format!(
+                "[GNUPG:] GOODSIG {} rust-seal ML-DSA-65 key",
+                key_id(&key_fingerprint)
+            )));
+            assert!(status.contains("[GNUPG:] TRUST_ULTIMATE"));
+
+            let mut status = Vec::new();
+            assert!(!verify(&armored, b"tree 0\n", &config, &mut status, &mut Vec::new()).unwrap());
+            assert!(
+                String::from_utf8(status)
+                    .unwrap()
+                    .contains("[GNUPG:] BADSIG")
+            );
+        }
+
+        assert!(sign(payload, "0123456789abcdef", &config, &mut Vec::new()).is_err());
+        assert!(
+            sign(
+                payload,
+                "A U Thor <author@example.com>",
+                &config,
+                &mut Vec::new()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unknown_signer_is_reported() {
+        let dir = tempdir().unwrap();
+        let config = Config::load(Home::from_root(dir.path())).unwrap();
+        let signer = Sig::new(SigAlgorithmVariant::MlDsa44).unwrap();
+        let (public_key, secret_key) = signer.keypair().unwrap();
+        let armored = GitSignature::sign(b"payload", &signer, public_key.as_ref(), &secret_key)
+            .unwrap()
+            .to_armored()
+            .unwrap();
+
+        let mut status = Vec::new();
+        assert!(verify(&armored, b"payload", &config, &mut status, &mut Vec::new()).is_err());
+        assert!(
+            String::from_utf8(status)
+                .unwrap()
+                .contains("[GNUPG:] NO_PUBKEY")
+        );
+    }
+}
@@ -1,8 +1,14 @@
+pub mod age;
+pub mod agent;
+pub mod bench;
 pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod cryptography;
+pub mod gpg;
 pub mod oqs;
+pub mod selftest;
+pub mod transparency;
 pub mod util;
 
 pub use config::Config;
@@ -1,14 +1,33 @@
+use std::path::PathBuf;
+
 use anyhow::{Context, Result};
 
-use rust_seal::Config;
+use rust_seal::{
+    Config,
+    cli::{CONFIG_PATH_ID, KEYS_DIR_ID},
+    config::Home,
+};
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
-    let mut config = Config::new().context("Failed to load configuration")?;
+    let matches = rust_seal::cli::create_cli().get_matches();
+
+    let home = Home::resolve(
+        matches
+            .get_one::<PathBuf>(CONFIG_PATH_ID)
+            .map(PathBuf::as_path),
+        matches
+            .get_one::<PathBuf>(KEYS_DIR_ID)
+            .map(PathBuf::as_path),
+    )
+    .context("Failed to determine rust-seal home")?;
+    let mut config = Config::load(home).context("Failed to load configuration")?;
 
-    rust_seal::cli::start(&mut config)?;
-    config.save()?;
+    rust_seal::cli::start(&matches, &mut config)?;
+    if config.is_modified() {
+        config.save()?;
+    }
 
     Ok(())
 }
@@ -1,101 +1,332 @@
+//! Registry of the post-quantum algorithms rust-seal knows about.
+//!
+//! Every algorithm is listed once with its canonical liboqs name, aliases, claimed NIST security level
+//! and standardisation status. Parsing is case-insensitive and ignores `-`, `_` and spaces,
+//! so `mlkem768` and `ml_kem_768` both resolve to `ML-KEM-768`.
+use std::fmt;
+
 use anyhow::{Result, bail};
-/// Temporary module for OQS algorithm conversion
-/// A PR implementing `FromStr` for `oqs::kem::Algorithm` and `oqs::sig::Algorithm` is pending.
-/// Until the PR is merged and a new version is released, we this module is used for conversion.
 use oqs::kem::Algorithm as KemAlgorithm;
 use oqs::sig::Algorithm as SigAlgorithm;
+use serde::Serialize;
 
-pub fn convert_str_to_kem_alg(alg: &str) -> Result<KemAlgorithm> {
-    match alg {
-        "BIKE-L3" => Ok(KemAlgorithm::BikeL3),
-        "BIKE-L5" => Ok(KemAlgorithm::BikeL5),
-        "Classic-McEliece-348864" => Ok(KemAlgorithm::ClassicMcEliece348864),
-        "Classic-McEliece-348864f" => Ok(KemAlgorithm::ClassicMcEliece348864f),
-        "Classic-McEliece-460896" => Ok(KemAlgorithm::ClassicMcEliece460896),
-        "Classic-McEliece-460896f" => Ok(KemAlgorithm::ClassicMcEliece460896f),
-        "Classic-McEliece-6688128" => Ok(KemAlgorithm::ClassicMcEliece6688128),
-        "Classic-McEliece-6688128f" => Ok(KemAlgorithm::ClassicMcEliece6688128f),
-        "Classic-McEliece-6960119" => Ok(KemAlgorithm::ClassicMcEliece6960119),
-        "Classic-McEliece-6960119f" => Ok(KemAlgorithm::ClassicMcEliece6960119f),
-        "Classic-McEliece-8192128" => Ok(KemAlgorithm::ClassicMcEliece8192128),
-        "Classic-McEliece-8192128f" => Ok(KemAlgorithm::ClassicMcEliece8192128f),
-        "HQC-128" => Ok(KemAlgorithm::Hqc128),
-        "HQC-192" => Ok(KemAlgorithm::Hqc192),
-        "HQC-256" => Ok(KemAlgorithm::Hqc256),
-        "Kyber512" => Ok(KemAlgorithm::Kyber512),
-        "Kyber768" => Ok(KemAlgorithm::Kyber768),
-        "Kyber1024" => Ok(KemAlgorithm::Kyber1024),
-        "ML-KEM-512" => Ok(KemAlgorithm::MlKem512),
-        "ML-KEM-768" => Ok(KemAlgorithm::MlKem768),
-        "ML-KEM-1024" => Ok(KemAlgorithm::MlKem1024),
-        "sntrup761" => Ok(KemAlgorithm::NtruPrimeSntrup761),
-        "FrodoKEM-640-AES" => Ok(KemAlgorithm::FrodoKem640Aes),
-        "FrodoKEM-640-SHAKE" => Ok(KemAlgorithm::FrodoKem640Shake),
-        "FrodoKEM-976-AES" => Ok(KemAlgorithm::FrodoKem976Aes),
-        "FrodoKEM-976-SHAKE" => Ok(KemAlgorithm::FrodoKem976Shake),
-        "FrodoKEM-1344-AES" => Ok(KemAlgorithm::FrodoKem1344Aes),
-        "FrodoKEM-1344-SHAKE" => Ok(KemAlgorithm::FrodoKem1344Shake),
-        "BIKE-L1" => Ok(KemAlgorithm::BikeL1),
-        _ => bail!(format!("Unsupported KEM algorithm: {}", alg)),
+/// Standardisation status of an algorithm
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// Published as a NIST FIPS standard
+    Standardized,
+    /// Selected by NIST, the standard is not published yet
+    Selected,
+    /// Part of an ongoing NIST selection process
+    Candidate,
+    /// Evaluated by NIST but not selected for standardisation
+    Alternate,
+    /// Pre-standard version of an algorithm that has since been standardised
+    Superseded,
+    /// Not part of the NIST process
+    Other,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            Status::Standardized => "standardized",
+            Status::Selected => "selected",
+            Status::Candidate => "candidate",
+            Status::Alternate => "alternate",
+            Status::Superseded => "superseded",
+            Status::Other => "other",
+        };
+        f.write_str(status)
+    }
+}
+
+/// Registry entry of an algorithm
+#[derive(Debug)]
+pub struct AlgorithmInfo<A: 'static> {
+    pub algorithm: A,
+    /// Name used by liboqs
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Claimed NIST security level (1-5)
+    pub nist_level: u8,
+    pub status: Status,
+}
+
+pub type KemInfo = AlgorithmInfo<KemAlgorithm>;
+pub type SigInfo = AlgorithmInfo<SigAlgorithm>;
+
+const fn info<A>(
+    algorithm: A,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    nist_level: u8,
+    status: Status,
+) -> AlgorithmInfo<A> {
+    AlgorithmInfo {
+        algorithm,
+        name,
+        aliases,
+        nist_level,
+        status,
+    }
+}
+
+#[rustfmt::skip]
+pub const KEM_ALGORITHMS: &[KemInfo] = &[
+    info(KemAlgorithm::BikeL1, "BIKE-L1", &[], 1, Status::Alternate),
+    info(KemAlgorithm::BikeL3, "BIKE-L3", &[], 3, Status::Alternate),
+    info(KemAlgorithm::BikeL5, "BIKE-L5", &[], 5, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece348864, "Classic-McEliece-348864", &["mceliece348864"], 1, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece348864f, "Classic-McEliece-348864f", &["mceliece348864f"], 1, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece460896, "Classic-McEliece-460896", &["mceliece460896"], 3, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece460896f, "Classic-McEliece-460896f", &["mceliece460896f"], 3, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece6688128, "Classic-McEliece-6688128", &["mceliece6688128"], 5, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece6688128f, "Classic-McEliece-6688128f", &["mceliece6688128f"], 5, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece6960119, "Classic-McEliece-6960119", &["mceliece6960119"], 5, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece6960119f, "Classic-McEliece-6960119f", &["mceliece6960119f"], 5, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece8192128, "Classic-McEliece-8192128", &["mceliece8192128"], 5, Status::Alternate),
+    info(KemAlgorithm::ClassicMcEliece8192128f, "Classic-McEliece-8192128f", &["mceliece8192128f"], 5, Status::Alternate),
+    info(KemAlgorithm::Hqc128, "HQC-128", &[], 1, Status::Selected),
+    info(KemAlgorithm::Hqc192, "HQC-192", &[], 3, Status::Selected),
+    info(KemAlgorithm::Hqc256, "HQC-256", &[], 5, Status::Selected),
+    info(KemAlgorithm::Kyber512, "Kyber512", &[], 1, Status::Superseded),
+    info(KemAlgorithm::Kyber768, "Kyber768", &[], 3, Status::Superseded),
+    info(KemAlgorithm::Kyber1024, "Kyber1024", &[], 5, Status::Superseded),
+    info(KemAlgorithm::MlKem512, "ML-KEM-512", &[], 1, Status::Standardized),
+    info(KemAlgorithm::MlKem768, "ML-KEM-768", &[], 3, Status::Standardized),
+    info(KemAlgorithm::MlKem1024, "ML-KEM-1024", &[], 5, Status::Standardized),
+    info(KemAlgorithm::NtruPrimeSntrup761, "sntrup761", &["NTRU-Prime-sntrup761"], 2, Status::Other),
+    info(KemAlgorithm::FrodoKem640Aes, "FrodoKEM-640-AES", &[], 1, Status::Alternate),
+    info(KemAlgorithm::FrodoKem640Shake, "FrodoKEM-640-SHAKE", &[], 1, Status::Alternate),
+    info(KemAlgorithm::FrodoKem976Aes, "FrodoKEM-976-AES", &[], 3, Status::Alternate),
+    info(KemAlgorithm::FrodoKem976Shake, "FrodoKEM-976-SHAKE", &[], 3, Status::Alternate),
+    info(KemAlgorithm::FrodoKem1344Aes, "FrodoKEM-1344-AES", &[], 5, Status::Alternate),
+    info(KemAlgorithm::FrodoKem1344Shake, "FrodoKEM-1344-SHAKE", &[], 5, Status::Alternate),
+];
+
+#[rustfmt::skip]
+pub const SIG_ALGORITHMS: &[SigInfo] = &[
+    info(SigAlgorithm::CrossRsdp128Balanced, "cross-rsdp-128-balanced", &[], 1, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp128Fast, "cross-rsdp-128-fast", &[], 1, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp128Small, "cross-rsdp-128-small", &[], 1, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp192Balanced, "cross-rsdp-192-balanced", &[], 3, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp192Fast, "cross-rsdp-192-fast", &[], 3, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp192Small, "cross-rsdp-192-small", &[], 3, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp256Balanced, "cross-rsdp-256-balanced", &[], 5, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp256Fast, "cross-rsdp-256-fast", &[], 5, Status::Candidate),
+    info(SigAlgorithm::CrossRsdp256Small, "cross-rsdp-256-small", &[], 5, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg128Balanced, "cross-rsdpg-128-balanced", &[], 1, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg128Fast, "cross-rsdpg-128-fast", &[], 1, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg128Small, "cross-rsdpg-128-small", &[], 1, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg192Balanced, "cross-rsdpg-192-balanced", &[], 3, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg192Fast, "cross-rsdpg-192-fast", &[], 3, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg192Small, "cross-rsdpg-192-small", &[], 3, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg256Balanced, "cross-rsdpg-256-balanced", &[], 5, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg256Fast, "cross-rsdpg-256-fast", &[], 5, Status::Candidate),
+    info(SigAlgorithm::CrossRsdpg256Small, "cross-rsdpg-256-small", &[], 5, Status::Candidate),
+    info(SigAlgorithm::Dilithium2, "Dilithium2", &[], 2, Status::Superseded),
+    info(SigAlgorithm::Dilithium3, "Dilithium3", &[], 3, Status::Superseded),
+    info(SigAlgorithm::Dilithium5, "Dilithium5", &[], 5, Status::Superseded),
+    info(SigAlgorithm::Falcon512, "Falcon-512", &[], 1, Status::Selected),
+    info(SigAlgorithm::Falcon1024, "Falcon-1024", &[], 5, Status::Selected),
+    info(SigAlgorithm::Mayo1, "MAYO-1", &[], 1, Status::Candidate),
+    info(SigAlgorithm::Mayo2, "MAYO-2", &[], 1, Status::Candidate),
+    info(SigAlgorithm::Mayo3, "MAYO-3", &[], 3, Status::Candidate),
+    info(SigAlgorithm::Mayo5, "MAYO-5", &[], 5, Status::Candidate),
+    info(SigAlgorithm::MlDsa44, "ML-DSA-44", &[], 2, Status::Standardized),
+    info(SigAlgorithm::MlDsa65, "ML-DSA-65", &[], 3, Status::Standardized),
+    info(SigAlgorithm::MlDsa87, "ML-DSA-87", &[], 5, Status::Standardized),
+    info(SigAlgorithm::SphincsSha2128fSimple, "SPHINCS+-SHA2-128f-simple", &["SPHINCS+-SHA2-128f"], 1, Status::Superseded),
+    info(SigAlgorithm::SphincsSha2128sSimple, "SPHINCS+-SHA2-128s-simple", &["SPHINCS+-SHA2-128s"], 1, Status::Superseded),
+    info(SigAlgorithm::SphincsSha2192fSimple, "SPHINCS+-SHA2-192f-simple", &["SPHINCS+-SHA2-192f"], 3, Status::Superseded),
+    info(SigAlgorithm::SphincsSha2192sSimple, "SPHINCS+-SHA2-192s-simple", &["SPHINCS+-SHA2-192s"], 3, Status::Superseded),
+    info(SigAlgorithm::SphincsSha2256fSimple, "SPHINCS+-SHA2-256f-simple", &["SPHINCS+-SHA2-256f"], 5, Status::Superseded),
+    info(SigAlgorithm::SphincsSha2256sSimple, "SPHINCS+-SHA2-256s-simple", &["SPHINCS+-SHA2-256s"], 5, Status::Superseded),
+    info(SigAlgorithm::SphincsShake128fSimple, "SPHINCS+-SHAKE-128f-simple", &["SPHINCS+-SHAKE-128f"], 1, Status::Superseded),
+    info(SigAlgorithm::SphincsShake128sSimple, "SPHINCS+-SHAKE-128s-simple", &["SPHINCS+-SHAKE-128s"], 1, Status::Superseded),
+    info(SigAlgorithm::SphincsShake192fSimple, "SPHINCS+-SHAKE-192f-simple", &["SPHINCS+-SHAKE-192f"], 3, Status::Superseded),
+    info(SigAlgorithm::SphincsShake192sSimple, "SPHINCS+-SHAKE-192s-simple", &["SPHINCS+-SHAKE-192s"], 3, Status::Superseded),
+    info(SigAlgorithm::SphincsShake256fSimple, "SPHINCS+-SHAKE-256f-simple", &["SPHINCS+-SHAKE-256f"], 5, Status::Superseded),
+    info(SigAlgorithm::SphincsShake256sSimple, "SPHINCS+-SHAKE-256s-simple", &["SPHINCS+-SHAKE-256s"], 5, Status::Superseded),
+    info(SigAlgorithm::UovOvIs, "OV-Is", &[], 1, Status::Candidate),
+    info(SigAlgorithm::UovOvIp, "OV-Ip", &[], 1, Status::Candidate),
+    info(SigAlgorithm::UovOvIII, "OV-III", &[], 3, Status::Candidate),
+    info(SigAlgorithm::UovOvV, "OV-V", &[], 5, Status::Candidate),
+    info(SigAlgorithm::UovOvIsPkc, "OV-Is-pkc", &[], 1, Status::Candidate),
+    info(SigAlgorithm::UovOvIpPkc, "OV-Ip-pkc", &[], 1, Status::Candidate),
+    info(SigAlgorithm::UovOvIIIPkc, "OV-III-pkc", &[], 3, Status::Candidate),
+    info(SigAlgorithm::UovOvVPkc, "OV-V-pkc", &[], 5, Status::Candidate),
+    info(SigAlgorithm::UovOvIsPkcSkc, "OV-Is-pkc-skc", &[], 1, Status::Candidate),
+    info(SigAlgorithm::UovOvIpPkcSkc, "OV-Ip-pkc-skc", &[], 1, Status::Candidate),
+    info(SigAlgorithm::UovOvIIIPkcSkc, "OV-III-pkc-skc", &[], 3, Status::Candidate),
+    info(SigAlgorithm::UovOvVPkcSkc, "OV-V-pkc-skc", &[], 5, Status::Candidate),
+];
+
+impl<A: Copy + PartialEq> AlgorithmInfo<A> {
+    fn names(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
     }
 }
 
+pub fn kem_info(algorithm: KemAlgorithm) -> &'static KemInfo {
+    find_info(KEM_ALGORITHMS, algorithm)
+}
+
+pub fn sig_info(algorithm: SigAlgorithm) -> &'static SigInfo {
+    find_info(SIG_ALGORITHMS, algorithm)
+}
+
+pub fn convert_str_to_kem_alg(alg: &str) -> Result<KemAlgorithm> {
+    lookup(KEM_ALGORITHMS, alg, "KEM")
+}
+
 pub fn convert_str_to_sig_alg(alg: &str) -> Result<SigAlgorithm> {
-    match alg {
-        "cross-rsdp-128-balanced" => Ok(SigAlgorithm::CrossRsdp128Balanced),
-        "cross-rsdp-128-fast" => Ok(SigAlgorithm::CrossRsdp128Fast),
-        "cross-rsdp-128-small" => Ok(SigAlgorithm::CrossRsdp128Small),
-        "cross-rsdp-192-balanced" => Ok(SigAlgorithm::CrossRsdp192Balanced),
-        "cross-rsdp-192-fast" => Ok(SigAlgorithm::CrossRsdp192Fast),
-        "cross-rsdp-192-small" => Ok(SigAlgorithm::CrossRsdp192Small),
-        "cross-rsdp-256-balanced" => Ok(SigAlgorithm::CrossRsdp256Balanced),
-        "cross-rsdp-256-fast" => Ok(SigAlgorithm::CrossRsdp256Fast),
-        "cross-rsdp-256-small" => Ok(SigAlgorithm::CrossRsdp256Small),
-        "cross-rsdpg-128-balanced" => Ok(SigAlgorithm::CrossRsdpg128Balanced),
-        "cross-rsdpg-128-fast" => Ok(SigAlgorithm::CrossRsdpg128Fast),
-        "cross-rsdpg-128-small" => Ok(SigAlgorithm::CrossRsdpg128Small),
-        "cross-rsdpg-192-balanced" => Ok(SigAlgorithm::CrossRsdpg192Balanced),
-        "cross-rsdpg-192-fast" => Ok(SigAlgorithm::CrossRsdpg192Fast),
-        "cross-rsdpg-192-small" => Ok(SigAlgorithm::CrossRsdpg192Small),
-        "cross-rsdpg-256-balanced" => Ok(SigAlgorithm::CrossRsdpg256Balanced),
-        "cross-rsdpg-256-fast" => Ok(SigAlgorithm::CrossRsdpg256Fast),
-        "cross-rsdpg-256-small" => Ok(SigAlgorithm::CrossRsdpg256Small),
-        "Dilithium2" => Ok(SigAlgorithm::Dilithium2),
-        "Dilithium3" => Ok(SigAlgorithm::Dilithium3),
-        "Dilithium5" => Ok(SigAlgorithm::Dilithium5),
-        "Falcon-512" => Ok(SigAlgorithm::Falcon512),
-        "Falcon-1024" => Ok(SigAlgorithm::Falcon1024),
-        "MAYO-1" => Ok(SigAlgorithm::Mayo1),
-        "MAYO-2" => Ok(SigAlgorithm::Mayo2),
-        "MAYO-3" => Ok(SigAlgorithm::Mayo3),
-        "MAYO-5" => Ok(SigAlgorithm::Mayo5),
-        "ML-DSA-44" => Ok(SigAlgorithm::MlDsa44),
-        "ML-DSA-65" => Ok(SigAlgorithm::MlDsa65),
-        "ML-DSA-87" => Ok(SigAlgorithm::MlDsa87),
-        "SPHINCS+-SHA2-128f-simple" => Ok(SigAlgorithm::SphincsSha2128fSimple),
-        "SPHINCS+-SHA2-128s-simple" => Ok(SigAlgorithm::SphincsSha2128sSimple),
-        "SPHINCS+-SHA2-192f-simple" => Ok(SigAlgorithm::SphincsSha2192fSimple),
-        "SPHINCS+-SHA2-192s-simple" => Ok(SigAlgorithm::SphincsSha2192sSimple),
-        "SPHINCS+-SHA2-256f-simple" => Ok(SigAlgorithm::SphincsSha2256fSimple),
-        "SPHINCS+-SHA2-256s-simple" => Ok(SigAlgorithm::SphincsSha2256sSimple),
-        "SPHINCS+-SHAKE-128f-simple" => Ok(SigAlgorithm::SphincsShake128fSimple),
-        "SPHINCS+-SHAKE-128s-simple" => Ok(SigAlgorithm::SphincsShake128sSimple),
-        "SPHINCS+-SHAKE-192f-simple" => Ok(SigAlgorithm::SphincsShake192fSimple),
-        "SPHINCS+-SHAKE-192s-simple" => Ok(SigAlgorithm::SphincsShake192sSimple),
-        "SPHINCS+-SHAKE-256f-simple" => Ok(SigAlgorithm::SphincsShake256fSimple),
-        "SPHINCS+-SHAKE-256s-simple" => Ok(SigAlgorithm::SphincsShake256sSimple),
-        "OV-Is" => Ok(SigAlgorithm::UovOvIs),
-        "OV-Ip" => Ok(SigAlgorithm::UovOvIp),
-        "OV-III" => Ok(SigAlgorithm::UovOvIII),
-        "OV-V" => Ok(SigAlgorithm::UovOvV),
-        "OV-Is-pkc" => Ok(SigAlgorithm::UovOvIsPkc),
-        "OV-Ip-pkc" => Ok(SigAlgorithm::UovOvIpPkc),
-        "OV-III-pkc" => Ok(SigAlgorithm::UovOvIIIPkc),
-        "OV-V-pkc" => Ok(SigAlgorithm::UovOvVPkc),
-        "OV-Is-pkc-skc" => Ok(SigAlgorithm::UovOvIsPkcSkc),
-        "OV-Ip-pkc-skc" => Ok(SigAlgorithm::UovOvIpPkcSkc),
-        "OV-III-pkc-skc" => Ok(SigAlgorithm::UovOvIIIPkcSkc),
-        "OV-V-pkc-skc" => Ok(SigAlgorithm::UovOvVPkcSkc),
-        _ => bail!(format!("Unsupported signature algorithm: {}", alg)),
+    lookup(SIG_ALGORITHMS, alg, "signature")
+}
+
+fn find_info<A: Copy + PartialEq + fmt::Debug>(
+    table: &'static [AlgorithmInfo<A>],
+    algorithm: A,
+) -> &'static AlgorithmInfo<A> {
+    table
+        .iter()
+        .find(|info| info.algorithm == algorithm)
+        .unwrap_or_else(|| panic!("Algorithm {algorithm:?} is missing from the registry"))
+}
+
+fn lookup<A: Copy + PartialEq>(table: &[AlgorithmInfo<A>], alg: &str, kind: &str) -> Result<A> {
+    let query = normalize(alg);
+
+    if let Some(info) = table
+        .iter()
+        .find(|info| info.names().any(|name| normalize(name) == query))
+    {
+        return Ok(info.algorithm);
+    }
+
+    match suggest(table, &query) {
+        Some(suggestion) => {
+            bail!("Unsupported {kind} algorithm: {alg}. Did you mean {suggestion}?")
+        }
+        None => bail!(
+            "Unsupported {kind} algorithm: {alg}. Run `rust-seal algorithms` to list all supported algorithms"
+        ),
+    }
+}
+
+/// Returns the canonical name of the closest match, if it is close enough to be a likely typo
+fn suggest<A: Copy + PartialEq>(table: &[AlgorithmInfo<A>], query: &str) -> Option<&'static str> {
+    let max_distance = (query.chars().count() / 3).max(2);
+
+    table
+        .iter()
+        .flat_map(|info| {
+            info.names()
+                .map(move |name| (info.name, edit_distance(&normalize(name), query)))
+        })
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(name, _)| name)
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_registry_names_match_liboqs() {
+        for info in KEM_ALGORITHMS {
+            assert_eq!(info.name, info.algorithm.name());
+        }
+        for info in SIG_ALGORITHMS {
+            assert_eq!(info.name, info.algorithm.name());
+        }
+    }
+
+    #[test]
+    fn test_names_are_unambiguous() {
+        let mut seen = HashSet::new();
+        for name in KEM_ALGORITHMS.iter().flat_map(|info| info.names()) {
+            assert!(seen.insert(normalize(name)), "Duplicate KEM name {name}");
+        }
+
+        let mut seen = HashSet::new();
+        for name in SIG_ALGORITHMS.iter().flat_map(|info| info.names()) {
+            assert!(
+                seen.insert(normalize(name)),
+                "Duplicate signature name {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_is_case_insensitive_and_supports_aliases() {
+        assert_eq!(
+            convert_str_to_kem_alg("ml-kem-768").unwrap(),
+            KemAlgorithm::MlKem768
+        );
+        assert_eq!(
+            convert_str_to_kem_alg("MLKEM768").unwrap(),
+            KemAlgorithm::MlKem768
+        );
+        assert_eq!(
+            convert_str_to_kem_alg("mceliece348864f").unwrap(),
+            KemAlgorithm::ClassicMcEliece348864f
+        );
+        assert_eq!(
+            convert_str_to_sig_alg("ml_dsa_65").unwrap(),
+            SigAlgorithm::MlDsa65
+        );
+        assert_eq!(
+            convert_str_to_sig_alg("SPHINCS+-SHA2-128f").unwrap(),
+            SigAlgorithm::SphincsSha2128fSimple
+        );
+    }
+
+    #[test]
+    fn test_unknown_algorithm_suggests_closest_match() {
+        let error = convert_str_to_sig_alg("ML-DSA-66").unwrap_err().to_string();
+        assert!(error.contains("Did you mean ML-DSA-65?"), "{error}");
+
+        let error = convert_str_to_kem_alg("Kyber-786").unwrap_err().to_string();
+        assert!(error.contains("Did you mean Kyber768?"), "{error}");
+
+        let error = convert_str_to_kem_alg("rot13").unwrap_err().to_string();
+        assert!(!error.contains("Did you mean"), "{error}");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
     }
 }
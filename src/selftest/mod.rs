@@ -0,0 +1,458 @@
+//! Known-answer tests and pairwise-consistency checks for the linked liboqs build and the AES-GCM path.
+//!
+//! The ML-KEM and ML-DSA vectors in `vectors.json` are taken from the NIST ACVP test vectors.
+//! liboqs offers no deterministic key generation or signing for the other algorithms,
+//! so they are covered by pairwise-consistency checks only.
+use std::{fmt, sync::OnceLock};
+
+use aes_gcm::Nonce;
+use anyhow::{Context, Result, bail, ensure};
+use oqs::{
+    kem::{Kem, PublicKeyRef as KemPublicKeyRef, SecretKeyRef as KemSecretKeyRef},
+    sig::{PublicKeyRef as SigPublicKeyRef, SecretKeyRef as SigSecretKeyRef, Sig},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cryptography::symmetric::{symmetric_decrypt, symmetric_encrypt_with_nonce},
+    oqs::{KEM_ALGORITHMS, SIG_ALGORITHMS, convert_str_to_kem_alg, convert_str_to_sig_alg},
+};
+
+const VECTORS: &str = include_str!("vectors.json");
+
+const PAIRWISE_MESSAGE: &[u8] = b"rust-seal pairwise consistency check";
+
+/// AES-256-GCM test case 14 of the original GCM specification (McGrew & Viega)
+const AES_GCM_KEY: [u8; 32] = [0; 32];
+const AES_GCM_NONCE: [u8; 12] = [0; 12];
+const AES_GCM_PLAINTEXT: [u8; 16] = [0; 16];
+const AES_GCM_CIPHERTEXT_AND_TAG: &str =
+    "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919";
+
+#[derive(Deserialize)]
+struct Vectors {
+    ml_kem_keygen: Vec<MlKemKeygenVector>,
+    ml_kem_decapsulation: Vec<MlKemDecapsulationVector>,
+    ml_dsa_verify: Vec<MlDsaVerifyVector>,
+}
+
+#[derive(Deserialize)]
+struct MlKemKeygenVector {
+    algorithm: String,
+    tc_id: u32,
+    #[serde(with = "hex")]
+    d: Vec<u8>,
+    #[serde(with = "hex")]
+    z: Vec<u8>,
+    #[serde(with = "hex")]
+    ek: Vec<u8>,
+    #[serde(with = "hex")]
+    dk: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct MlKemDecapsulationVector {
+    algorithm: String,
+    tc_id: u32,
+    #[serde(with = "hex")]
+    dk: Vec<u8>,
+    #[serde(with = "hex")]
+    c: Vec<u8>,
+    #[serde(with = "hex")]
+    k: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct MlDsaVerifyVector {
+    algorithm: String,
+    tc_id: u32,
+    #[serde(with = "hex")]
+    pk: Vec<u8>,
+    #[serde(with = "hex")]
+    message: Vec<u8>,
+    #[serde(with = "hex")]
+    context: Vec<u8>,
+    #[serde(with = "hex")]
+    signature: Vec<u8>,
+    valid: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckKind {
+    KnownAnswer,
+    PairwiseConsistency,
+}
+
+impl fmt::Display for CheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckKind::KnownAnswer => write!(f, "KAT"),
+            CheckKind::PairwiseConsistency => write!(f, "PCT"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", content = "reason", rename_all = "lowercase")]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+    /// The algorithm is not enabled in the linked liboqs build
+    Skipped(String),
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub kind: CheckKind,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+impl CheckResult {
+    fn new(name: String, kind: CheckKind, result: Result<Outcome>) -> Self {
+        let outcome = result.unwrap_or_else(|error| Outcome::Failed(format!("{error:#}")));
+        Self {
+            name,
+            kind,
+            outcome,
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed(_))
+    }
+}
+
+/// Runs every known-answer test and a pairwise-consistency check for every algorithm in the registry
+pub fn run_all() -> Vec<CheckResult> {
+    let mut results = known_answer_tests();
+    results.extend(pairwise_consistency_checks());
+    results
+}
+
+/// Runs the embedded known-answer tests
+pub fn known_answer_tests() -> Vec<CheckResult> {
+    let mut results = vec![CheckResult::new(
+        "AES-256-GCM".to_string(),
+        CheckKind::KnownAnswer,
+        aes_gcm_kat(),
+    )];
+
+    let vectors: Vectors = match serde_json::from_str(VECTORS) {
+        Ok(vectors) => vectors,
+        Err(error) => {
+            results.push(CheckResult::new(
+                "embedded vectors".to_string(),
+                CheckKind::KnownAnswer,
+                Err(error).context("Failed to parse the embedded test vectors"),
+            ));
+            return results;
+        }
+    };
+
+    for vector in &vectors.ml_kem_keygen {
+        results.push(CheckResult::new(
+            format!("{} keygen (tcId {})", vector.algorithm, vector.tc_id),
+            CheckKind::KnownAnswer,
+            ml_kem_keygen_kat(vector),
+        ));
+    }
+    for vector in &vectors.ml_kem_decapsulation {
+        results.push(CheckResult::new(
+            format!("{} decapsulate (tcId {})", vector.algorithm, vector.tc_id),
+            CheckKind::KnownAnswer,
+            ml_kem_decapsulation_kat(vector),
+        ));
+    }
+    for vector in &vectors.ml_dsa_verify {
+        results.push(CheckResult::new(
+            format!("{} verify (tcId {})", vector.algorithm, vector.tc_id),
+            CheckKind::KnownAnswer,
+            ml_dsa_verify_kat(vector),
+        ));
+    }
+
+    results
+}
+
+/// Generates a key pair for every enabled algorithm and checks that it works end to end
+pub fn pairwise_consistency_checks() -> Vec<CheckResult> {
+    let kem_results = KEM_ALGORITHMS.iter().map(|info| {
+        let result = match Kem::new(info.algorithm) {
+            Ok(kem) => kem
+                .keypair()
+                .context("Failed to generate key pair")
+                .and_then(|(public_key, secret_key)| {
+                    check_kem_keypair(&kem, &public_key, &secret_key)
+                })
+                .map(|_| Outcome::Passed),
+            Err(_) => Ok(Outcome::Skipped("not enabled in liboqs".to_string())),
+        };
+        CheckResult::new(
+            info.name.to_string(),
+            CheckKind::PairwiseConsistency,
+            result,
+        )
+    });
+
+    let sig_results = SIG_ALGORITHMS.iter().map(|info| {
+        let result = match Sig::new(info.algorithm) {
+            Ok(signature) => signature
+                .keypair()
+                .context("Failed to generate key pair")
+                .and_then(|(public_key, secret_key)| {
+                    check_signature_keypair(&signature, &public_key, &secret_key)
+                })
+                .map(|_| Outcome::Passed),
+            Err(_) => Ok(Outcome::Skipped("not enabled in liboqs".to_string())),
+        };
+        CheckResult::new(
+            info.name.to_string(),
+            CheckKind::PairwiseConsistency,
+            result,
+        )
+    });
+
+    kem_results.chain(sig_results).collect()
+}
+
+/// Runs the known-answer tests once per process and fails if any of them fails.
+/// Must be called before keys are generated.
+pub fn ensure_known_answer_tests_passed() -> Result<()> {
+    static FAILURES: OnceLock<Vec<String>> = OnceLock::new();
+
+    let failures = FAILURES.get_or_init(|| {
+        known_answer_tests()
+            .into_iter()
+            .filter_map(|result| match result.outcome {
+                Outcome::Failed(reason) => {
+                    Some(format!("{} {}: {reason}", result.kind, result.name))
+                }
+                _ => None,
+            })
+            .collect()
+    });
+
+    ensure!(
+        failures.is_empty(),
+        "Self-test failed, refusing to generate keys:\n{}",
+        failures.join("\n")
+    );
+    Ok(())
+}
+
+/// Checks that a freshly generated KEM key pair encapsulates and decapsulates to the same secret
+pub fn check_kem_keypair<'a>(
+    kem: &Kem,
+    public_key: impl Into<KemPublicKeyRef<'a>>,
+    secret_key: impl Into<KemSecretKeyRef<'a>>,
+) -> Result<()> {
+    let secret_key = secret_key.into();
+    let (ciphertext, shared_secret) = kem
+        .encapsulate(public_key)
+        .context("Failed to encapsulate")?;
+    let decapsulated = kem
+        .decapsulate(secret_key, &ciphertext)
+        .context("Failed to decapsulate")?;
+
+    ensure!(
+        shared_secret == decapsulated,
+        "Pairwise-consistency check failed for {}: shared secrets differ",
+        kem.algorithm()
+    );
+    Ok(())
+}
+
+/// Checks that a freshly generated signature key pair verifies its own signature and rejects a modified message
+pub fn check_signature_keypair<'a>(
+    signature: &Sig,
+    public_key: impl Into<SigPublicKeyRef<'a>>,
+    secret_key: impl Into<SigSecretKeyRef<'a>>,
+) -> Result<()> {
+    let public_key = public_key.into();
+    let signed = signature
+        .sign(PAIRWISE_MESSAGE, secret_key)
+        .context("Failed to sign")?;
+
+    signature
+        .verify(PAIRWISE_MESSAGE, &signed, public_key)
+        .context(format!(
+            "Pairwise-consistency check failed for {}: signature does not verify",
+            signature.algorithm()
+        ))?;
+
+    let mut modified = PAIRWISE_MESSAGE.to_vec();
+    modified[0] ^= 1;
+    ensure!(
+        signature.verify(&modified, &signed, public_key).is_err(),
+        "Pairwise-consistency check failed for {}: signature verifies a modified message",
+        signature.algorithm()
+    );
+    Ok(())
+}
+
+fn aes_gcm_kat() -> Result<Outcome> {
+    let expected = hex::decode(AES_GCM_CIPHERTEXT_AND_TAG).context("Invalid test vector")?;
+
+    let combined = symmetric_encrypt_with_nonce(
+        &AES_GCM_PLAINTEXT,
+        &AES_GCM_KEY,
+        Nonce::from_slice(&AES_GCM_NONCE),
+    )?;
+    // Skip the nonce length and nonce that are prepended to the ciphertext
+    ensure!(
+        combined[1 + AES_GCM_NONCE.len()..] == expected[..],
+        "Ciphertext does not match the known answer"
+    );
+
+    let decrypted = symmetric_decrypt(&combined, &AES_GCM_KEY)?;
+    ensure!(
+        decrypted.as_slice() == AES_GCM_PLAINTEXT,
+        "Decrypted data does not match the plaintext"
+    );
+
+    let mut tampered = combined;
+    *tampered.last_mut().context("Ciphertext is empty")? ^= 1;
+    ensure!(
+        symmetric_decrypt(&tampered, &AES_GCM_KEY).is_err(),
+        "A modified authentication tag was accepted"
+    );
+
+    Ok(Outcome::Passed)
+}
+
+fn ml_kem_keygen_kat(vector: &MlKemKeygenVector) -> Result<Outcome> {
+    let Ok(kem) = Kem::new(convert_str_to_kem_alg(&vector.algorithm)?) else {
+        return Ok(Outcome::Skipped("not enabled in liboqs".to_string()));
+    };
+
+    // liboqs expects the ML-KEM key generation seed as d || z
+    let seed = [vector.d.as_slice(), vector.z.as_slice()].concat();
+    let seed = kem
+        .keypair_seed_from_bytes(&seed)
+        .context("Seed has an unexpected length")?;
+    let (public_key, secret_key) = kem
+        .keypair_derand(seed)
+        .context("Failed to derive key pair")?;
+
+    ensure!(
+        public_key.as_ref() == vector.ek.as_slice(),
+        "Encapsulation key does not match the known answer"
+    );
+    ensure!(
+        secret_key.as_ref() == vector.dk.as_slice(),
+        "Decapsulation key does not match the known answer"
+    );
+    Ok(Outcome::Passed)
+}
+
+fn ml_kem_decapsulation_kat(vector: &MlKemDecapsulationVector) -> Result<Outcome> {
+    let Ok(kem) = Kem::new(convert_str_to_kem_alg(&vector.algorithm)?) else {
+        return Ok(Outcome::Skipped("not enabled in liboqs".to_string()));
+    };
+
+    let secret_key = kem
+        .secret_key_from_bytes(&vector.dk)
+        .context("Decapsulation key has an unexpected length")?;
+    let ciphertext = kem
+        .ciphertext_from_bytes(&vector.c)
+        .context("Ciphertext has an unexpected length")?;
+    let shared_secret = kem
+        .decapsulate(secret_key, ciphertext)
+        .context("Failed to decapsulate")?;
+
+    ensure!(
+        shared_secret.as_ref() == vector.k.as_slice(),
+        "Shared secret does not match the known answer"
+    );
+    Ok(Outcome::Passed)
+}
+
+fn ml_dsa_verify_kat(vector: &MlDsaVerifyVector) -> Result<Outcome> {
+    let Ok(signature) = Sig::new(convert_str_to_sig_alg(&vector.algorithm)?) else {
+        return Ok(Outcome::Skipped("not enabled in liboqs".to_string()));
+    };
+
+    let public_key = signature
+        .public_key_from_bytes(&vector.pk)
+        .context("Public key has an unexpected length")?;
+    let signed = signature
+        .signature_from_bytes(&vector.signature)
+        .context("Signature has an unexpected length")?;
+    let verified = signature
+        .verify_with_ctx_str(&vector.message, signed, &vector.context, public_key)
+        .is_ok();
+
+    match (vector.valid, verified) {
+        (true, false) => bail!("Valid signature was rejected"),
+        (false, true) => bail!("Invalid signature was accepted"),
+        _ => Ok(Outcome::Passed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_answer_tests_pass() {
+        for result in known_answer_tests() {
+            assert!(
+                !result.failed(),
+                "{} failed: {:?}",
+                result.name,
+                result.outcome
+            );
+        }
+    }
+
+    #[test]
+    fn test_vectors_cover_every_ml_kem_and_ml_dsa_parameter_set() {
+        let vectors: Vectors = serde_json::from_str(VECTORS).unwrap();
+
+        for algorithm in ["ML-KEM-512", "ML-KEM-768", "ML-KEM-1024"] {
+            assert!(
+                vectors
+                    .ml_kem_keygen
+                    .iter()
+                    .any(|v| v.algorithm == algorithm)
+            );
+            assert!(
+                vectors
+                    .ml_kem_decapsulation
+                    .iter()
+                    .any(|v| v.algorithm == algorithm)
+            );
+        }
+        for algorithm in ["ML-DSA-44", "ML-DSA-65", "ML-DSA-87"] {
+            let verify = vectors
+                .ml_dsa_verify
+                .iter()
+                .filter(|v| v.algorithm == algorithm);
+            assert!(verify.clone().any(|v| v.valid));
+            assert!(verify.clone().any(|v| !v.valid));
+        }
+    }
+
+    #[test]
+    fn test_pairwise_check_rejects_foreign_key() {
+        let signature = Sig::new(oqs::sig::Algorithm::MlDsa44).unwrap();
+        let (public_key, _) = signature.keypair().unwrap();
+        let (_, other_secret_key) = signature.keypair().unwrap();
+
+        assert!(check_signature_keypair(&signature, &public_key, &other_secret_key).is_err());
+    }
+
+    #[test]
+    fn test_pairwise_checks_pass() {
+        let kem = Kem::new(oqs::kem::Algorithm::MlKem768).unwrap();
+        let (public_key, secret_key) = kem.keypair().unwrap();
+        check_kem_keypair(&kem, &public_key, &secret_key).unwrap();
+
+        let signature = Sig::new(oqs::sig::Algorithm::Falcon512).unwrap();
+        let (public_key, secret_key) = signature.keypair().unwrap();
+        check_signature_keypair(&signature, &public_key, &secret_key).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::de::DeserializeOwned;
+
+use super::{
+    ConsistencyProof, LogEntry, LogReceipt, SignedTreeHead, TransparencyLog,
+    server::{CONSISTENCY_PATH, ENTRIES_PATH, TREE_HEAD_PATH},
+};
+
+/// A transparency log in a local directory, or served by `rust-seal log serve` at a URL
+pub enum LogClient {
+    Local(TransparencyLog),
+    Remote(String),
+}
+
+impl LogClient {
+    /// Opens the log at `location`: an `http://` or `https://` URL, or the directory of a local log
+    pub fn open(location: &str) -> Result<Self> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            Ok(Self::Remote(location.trim_end_matches('/').to_string()))
+        } else {
+            TransparencyLog::open(Path::new(location)).map(Self::Local)
+        }
+    }
+
+    /// Appends `entry` and returns the receipt of the log. The receipt is checked, but not whether the log is trusted
+    pub fn append(&self, entry: &LogEntry) -> Result<LogReceipt> {
+        let receipt = match self {
+            Self::Local(log) => log.append(entry)?,
+            Self::Remote(url) => {
+                let body = serde_json::to_string(entry).context("Failed to serialize log entry")?;
+                call(
+                    ureq::post(&format!("{url}{ENTRIES_PATH}"))
+                        .set("Content-Type", "application/json")
+                        .send_string(&body),
+                    url,
+                )?
+            }
+        };
+        receipt
+            .verify(entry, &[receipt.tree_head.log_fingerprint()])
+            .context("The log sent an invalid receipt")?;
+        Ok(receipt)
+    }
+
+    pub fn tree_head(&self) -> Result<SignedTreeHead> {
+        match self {
+            Self::Local(log) => log.tree_head(),
+            Self::Remote(url) => call(ureq::get(&format!("{url}{TREE_HEAD_PATH}")).call(), url),
+        }
+    }
+
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Result<ConsistencyProof> {
+        match self {
+            Self::Local(log) => log.consistency_proof(old_size, new_size),
+            Self::Remote(url) => call(
+                ureq::get(&format!(
+                    "{url}{CONSISTENCY_PATH}?old={old_size}&new={new_size}"
+                ))
+                .call(),
+                url,
+            ),
+        }
+    }
+}
+
+/// Parses the JSON answer of the log server at `url`, or returns the error message it sent
+fn call<T: DeserializeOwned>(
+    response: Result<ureq::Response, ureq::Error>,
+    url: &str,
+) -> Result<T> {
+    match response {
+        Ok(response) => {
+            let body = response
+                .into_string()
+                .context(format!("Failed to read the answer of {url}"))?;
+            serde_json::from_str(&body).context(format!("{url} sent an invalid answer"))
+        }
+        Err(ureq::Error::Status(status, response)) => bail!(
+            "The log at {url} answered with status {status}: {}",
+            response.into_string().unwrap_or_default()
+        ),
+        Err(err) => Err(err).context(format!("Failed to reach the log at {url}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use oqs::sig::{Algorithm, Sig};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::transparency::serve;
+
+    fn entry(index: u8) -> LogEntry {
+        LogEntry {
+            algorithm: Algorithm::MlDsa44,
+            public_key: vec![index; 4],
+            namespace: Some("release".to_string()),
+            file_digest: [index; 32],
+            signature: vec![index; 8],
+        }
+    }
+
+    /// Appends three entries through `client` and checks the receipts and tree heads it returns
+    fn check_receipts(client: &LogClient, log_fingerprint: &[String]) {
+        let first = client.append(&entry(0)).unwrap();
+        first.verify(&entry(0), log_fingerprint).unwrap();
+        assert!(first.verify(&entry(1), log_fingerprint).is_err());
+        for index in 1..3 {
+            client
+                .append(&entry(index))
+                .unwrap()
+                .verify(&entry(index), log_fingerprint)
+                .unwrap();
+        }
+
+        let head = client.tree_head().unwrap();
+        head.verify_trusted(log_fingerprint).unwrap();
+        assert_eq!(head.tree_size, 3);
+        client
+            .consistency_proof(first.tree_head.tree_size, head.tree_size)
+            .unwrap()
+            .verify(&first.tree_head, &head)
+            .unwrap();
+        assert!(client.consistency_proof(2, 4).is_err());
+    }
+
+    #[test]
+    fn test_local_log_receipts() {
+        let dir = tempdir().unwrap();
+        let sig = Sig::new(Algorithm::MlDsa44).unwrap();
+        let log = TransparencyLog::init(dir.path(), &sig).unwrap();
+
+        let client = LogClient::open(&dir.path().to_string_lossy()).unwrap();
+        assert!(matches!(client, LogClient::Local(_)));
+        check_receipts(&client, &[log.fingerprint()]);
+    }
+
+    #[test]
+    fn test_served_log_receipts() {
+        let dir = tempdir().unwrap();
+        let sig = Sig::new(Algorithm::MlDsa44).unwrap();
+        let log = TransparencyLog::init(dir.path(), &sig).unwrap();
+        let log_fingerprint = [log.fingerprint()];
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || serve(log, listener));
+
+        let client = LogClient::open(&url).unwrap();
+        assert!(matches!(client, LogClient::Remote(_)));
+        check_receipts(&client, &log_fingerprint);
+
+        let head = client.tree_head().unwrap();
+        assert!(head.verify_trusted(&["ab".repeat(32)]).is_err());
+        assert!(
+            LogClient::open(&format!("{url}missing"))
+                .unwrap()
+                .tree_head()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Merkle tree hashing and proofs as specified in RFC 9162, section 2.1.
+//!
+//! Leaves and nodes are hashed with different prefixes, so a leaf can never be passed off as a node.
+use anyhow::{Result, bail, ensure};
+use sha2::{Digest, Sha256};
+
+/// SHA-256 digest of a leaf or node
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    Sha256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(data)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Returns the root of the tree over the leaf hashes `leaves`. The root of the empty tree is the digest of nothing
+pub fn root_hash(leaves: &[Hash]) -> Hash {
+    match leaves {
+        [] => Sha256::digest([]).into(),
+        [leaf] => *leaf,
+        _ => {
+            let (left, right) = leaves.split_at(split(leaves.len()));
+            node_hash(&root_hash(left), &root_hash(right))
+        }
+    }
+}
+
+/// Returns the audit path of the leaf at `index`: the hashes that combine with it into the root of the tree
+pub fn inclusion_proof(index: usize, leaves: &[Hash]) -> Vec<Hash> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+    let k = split(leaves.len());
+    let (left, right) = leaves.split_at(k);
+    let (mut proof, sibling) = if index < k {
+        (inclusion_proof(index, left), root_hash(right))
+    } else {
+        (inclusion_proof(index - k, right), root_hash(left))
+    };
+    proof.push(sibling);
+    proof
+}
+
+/// Returns the hashes that prove the tree of the first `old_size` leaves is a prefix of the tree over `leaves`
+pub fn consistency_proof(old_size: usize, leaves: &[Hash]) -> Vec<Hash> {
+    if old_size == 0 || old_size >= leaves.len() {
+        return Vec::new();
+    }
+    subproof(old_size, leaves, true)
+}
+
+fn subproof(old_size: usize, leaves: &[Hash], complete: bool) -> Vec<Hash> {
+    if old_size == leaves.len() {
+        return if complete {
+            Vec::new()
+        } else {
+            vec![root_hash(leaves)]
+        };
+    }
+    let k = split(leaves.len());
+    let (left, right) = leaves.split_at(k);
+    let (mut proof, sibling) = if old_size <= k {
+        (subproof(old_size, left, complete), root_hash(right))
+    } else {
+        (subproof(old_size - k, right, false), root_hash(left))
+    };
+    proof.push(sibling);
+    proof
+}
+
+/// Checks that `leaf` is the leaf at `index` of the tree with `tree_size` leaves and the root `root`
+pub fn verify_inclusion(
+    index: u64,
+    tree_size: u64,
+    leaf: &Hash,
+    proof: &[Hash],
+    root: &Hash,
+) -> Result<()> {
+    ensure!(
+        index < tree_size,
+        "Entry {index} is not in a tree of {tree_size} entries"
+    );
+    let (mut f, mut s) = (index, tree_size - 1);
+    let mut hash = *leaf;
+    for sibling in proof {
+        ensure!(s != 0, "The inclusion proof is too long");
+        if f & 1 == 1 || f == s {
+            hash = node_hash(sibling, &hash);
+            while f & 1 == 0 && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    ensure!(
+        s == 0 && &hash == root,
+        "The inclusion proof does not lead to the root of the tree"
+    );
+    Ok(())
+}
+
+/// Checks that the tree with `old_size` leaves and the root `old_root` is a prefix of
+/// the tree with `new_size` leaves and the root `new_root`, i.e. that no leaf was changed or removed
+pub fn verify_consistency(
+    old_size: u64,
+    new_size: u64,
+    old_root: &Hash,
+    new_root: &Hash,
+    proof: &[Hash],
+) -> Result<()> {
+    ensure!(
+        old_size <= new_size,
+        "The old tree of {old_size} entries is larger than the new one of {new_size}"
+    );
+    if old_size == new_size {
+        ensure!(
+            proof.is_empty() && old_root == new_root,
+            "Trees of the same size {old_size} have different roots"
+        );
+        return Ok(());
+    }
+    if old_size == 0 {
+        ensure!(proof.is_empty(), "The consistency proof is too long");
+        return Ok(());
+    }
+
+    // A complete old tree is a node of the new one, which the proof leaves out
+    let proof: Vec<Hash> = if old_size.is_power_of_two() {
+        std::iter::once(*old_root)
+            .chain(proof.iter().copied())
+            .collect()
+    } else {
+        proof.to_vec()
+    };
+    let Some((first, rest)) = proof.split_first() else {
+        bail!("The consistency proof is empty");
+    };
+    let (mut f, mut s) = (old_size - 1, new_size - 1);
+    while f & 1 == 1 {
+        f >>= 1;
+        s >>= 1;
+    }
+    let (mut old_hash, mut new_hash) = (*first, *first);
+    for sibling in rest {
+        ensure!(s != 0, "The consistency proof is too long");
+        if f & 1 == 1 || f == s {
+            old_hash = node_hash(sibling, &old_hash);
+            new_hash = node_hash(sibling, &new_hash);
+            while f & 1 == 0 && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            new_hash = node_hash(&new_hash, sibling);
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    ensure!(
+        s == 0 && &old_hash == old_root && &new_hash == new_root,
+        "The consistency proof does not lead to the roots of both trees. The log was changed"
+    );
+    Ok(())
+}
+
+/// Size of the left subtree of a tree with `n` > 1 leaves: the largest power of two smaller than `n`
+fn split(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: usize) -> Vec<Hash> {
+        (0..n).map(|i| leaf_hash(&i.to_be_bytes())).collect()
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        assert_eq!(
+            hex::encode(root_hash(&[])),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        // Test vector of the RFC 6962 reference implementation
+        let vector: Vec<Hash> = [
+            &b""[..],
+            b"\x00",
+            b"\x10",
+            b"\x20\x21",
+            b"\x30\x31",
+            b"\x40\x41\x42\x43",
+            b"\x50\x51\x52\x53\x54\x55\x56\x57",
+            b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+        ]
+        .iter()
+        .map(|data| leaf_hash(data))
+        .collect();
+        assert_eq!(
+            hex::encode(root_hash(&vector)),
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"
+        );
+
+        for n in 1..=17 {
+            let leaves = leaves(n);
+            let root = root_hash(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = inclusion_proof(index, &leaves);
+                let (index, size) = (index as u64, n as u64);
+                verify_inclusion(index, size, leaf, &proof, &root).unwrap();
+                assert!(
+                    verify_inclusion(index, size, &leaf_hash(b"other"), &proof, &root).is_err()
+                );
+                if n > 1 {
+                    assert!(
+                        verify_inclusion((index + 1) % size, size, leaf, &proof, &root).is_err()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        for n in 1..=17 {
+            let leaves = leaves(n);
+            let new_root = root_hash(&leaves);
+            for old_size in 0..=n {
+                let old_root = root_hash(&leaves[..old_size]);
+                let proof = consistency_proof(old_size, &leaves);
+                let (old, new) = (old_size as u64, n as u64);
+                verify_consistency(old, new, &old_root, &new_root, &proof).unwrap();
+                if 0 < old_size && old_size < n {
+                    let forked = root_hash(&[&leaves[..old_size - 1], &[leaf_hash(b"x")]].concat());
+                    assert!(verify_consistency(old, new, &forked, &new_root, &proof).is_err());
+                    assert!(verify_consistency(old, new, &old_root, &old_root, &proof).is_err());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Transparency log of signatures, so a signature made with a stolen key cannot go unnoticed.
+//!
+//! The log is an append-only Merkle tree as in RFC 9162. Every leaf is a [`LogEntry`] describing one signature.
+//! The log signs the root of its tree with its own key as a [`SignedTreeHead`], and answers an appended entry with
+//! a [`LogReceipt`]: the tree head and an inclusion proof of the entry. Consistency proofs show that a later tree head
+//! extends an earlier one, i.e. that the log never removed or changed an entry.
+//!
+//! The log does not check the signatures it records; verifiers do. A log is a directory created with
+//! `rust-seal log init`, optionally shared over HTTP with `rust-seal log serve`.
+mod client;
+mod merkle;
+mod server;
+mod store;
+
+use anyhow::{Context, Result, ensure};
+use chrono::{DateTime, SecondsFormat, Utc};
+use oqs::sig::{Algorithm as SigAlgorithmVariant, Sig};
+use serde::{Deserialize, Serialize};
+
+use crate::cryptography::{
+    fingerprint::{fingerprint, fingerprints_match, format_fingerprint},
+    signature::verify_file_with_signature,
+};
+
+pub use client::LogClient;
+pub use merkle::Hash;
+pub use server::serve;
+pub use store::TransparencyLog;
+
+/// Prefix of the message a tree head signature is made over, so it cannot be mistaken for a signature over a file
+const TREE_HEAD_CONTEXT: &[u8] = b"rust-seal tree head v1\0";
+
+/// One signature in the log. Its JSON encoding is the leaf of the Merkle tree
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct LogEntry {
+    pub algorithm: SigAlgorithmVariant,
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+    /// Namespace the signature was made in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// SHA-256 digest of the signed file, by which auditors find the signatures of a release
+    #[serde(with = "hex")]
+    pub file_digest: Hash,
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+}
+
+impl LogEntry {
+    fn leaf_hash(&self) -> Result<Hash> {
+        let leaf = serde_json::to_vec(self).context("Failed to serialize log entry")?;
+        Ok(merkle::leaf_hash(&leaf))
+    }
+}
+
+/// Root of the log's tree at a size, signed by the log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SignedTreeHead {
+    pub algorithm: SigAlgorithmVariant,
+    /// Key of the log. It is only trusted if its fingerprint is in `transparency.trusted_logs`
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+    pub tree_size: u64,
+    #[serde(with = "hex")]
+    pub root_hash: Hash,
+    pub timestamp: DateTime<Utc>,
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+}
+
+impl SignedTreeHead {
+    /// Returns the message the log signs: the context, the tree size, the root hash and the RFC 3339 timestamp
+    fn message(tree_size: u64, root_hash: &Hash, timestamp: &DateTime<Utc>) -> Vec<u8> {
+        let mut message = TREE_HEAD_CONTEXT.to_vec();
+        message.extend_from_slice(&tree_size.to_be_bytes());
+        message.extend_from_slice(root_hash);
+        message.extend_from_slice(
+            timestamp
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                .as_bytes(),
+        );
+        message
+    }
+
+    /// Fingerprint of the log key
+    pub fn log_fingerprint(&self) -> String {
+        fingerprint(&self.algorithm.to_string(), &self.public_key)
+    }
+
+    /// Checks the signature of the tree head with the log key it names, which is not necessarily trusted
+    pub fn verify(&self) -> Result<()> {
+        let sig = Sig::new(self.algorithm).context(format!(
+            "Failed to create signature algorithm {}. Algorithm might be disabled",
+            self.algorithm
+        ))?;
+        let public_key = sig
+            .public_key_from_bytes(&self.public_key)
+            .context("The log key is not valid")?;
+        verify_file_with_signature(
+            &Self::message(self.tree_size, &self.root_hash, &self.timestamp),
+            &self.signature,
+            &sig,
+            public_key,
+        )
+        .context("The signature of the tree head is not valid")
+    }
+
+    /// Checks the signature of the tree head and that it was made by one of the `trusted_logs` fingerprints
+    pub fn verify_trusted(&self, trusted_logs: &[String]) -> Result<()> {
+        self.verify()?;
+        ensure!(
+            trusted_logs
+                .iter()
+                .any(|trusted| fingerprints_match(trusted, &self.log_fingerprint())),
+            "The log with key {} is not trusted",
+            format_fingerprint(&self.log_fingerprint())
+        );
+        Ok(())
+    }
+}
+
+/// Proof that a log recorded an entry, as stored in a signature bundle next to the signature
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct LogReceipt {
+    /// Position of the entry in the log
+    pub index: u64,
+    #[serde(with = "hex_hashes")]
+    pub inclusion_proof: Vec<Hash>,
+    pub tree_head: SignedTreeHead,
+}
+
+impl LogReceipt {
+    /// Checks that `entry` is in the tree of the tree head and that a log of `trusted_logs` signed the tree head
+    pub fn verify(&self, entry: &LogEntry, trusted_logs: &[String]) -> Result<()> {
+        self.tree_head.verify_trusted(trusted_logs)?;
+        merkle::verify_inclusion(
+            self.index,
+            self.tree_head.tree_size,
+            &entry.leaf_hash()?,
+            &self.inclusion_proof,
+            &self.tree_head.root_hash,
+        )
+        .context("The signature is not in the log")
+    }
+}
+
+/// Hashes that prove the tree of `old_size` entries is a prefix of the tree of `new_size` entries
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ConsistencyProof {
+    pub old_size: u64,
+    pub new_size: u64,
+    #[serde(with = "hex_hashes")]
+    pub proof: Vec<Hash>,
+}
+
+impl ConsistencyProof {
+    /// Checks that the log of both tree heads only appended entries between them
+    pub fn verify(&self, old: &SignedTreeHead, new: &SignedTreeHead) -> Result<()> {
+        ensure!(
+            old.algorithm == new.algorithm && old.public_key == new.public_key,
+            "The tree heads were signed by different logs"
+        );
+        old.verify().context("The old tree head is not valid")?;
+        new.verify().context("The new tree head is not valid")?;
+        ensure!(
+            (self.old_size, self.new_size) == (old.tree_size, new.tree_size),
+            "The consistency proof is for the tree sizes {} and {}, but the tree heads have {} and {}",
+            self.old_size,
+            self.new_size,
+            old.tree_size,
+            new.tree_size
+        );
+        merkle::verify_consistency(
+            old.tree_size,
+            new.tree_size,
+            &old.root_hash,
+            &new.root_hash,
+            &self.proof,
+        )
+    }
+}
+
+/// Serializes a list of hashes as hex strings
+mod hex_hashes {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use super::Hash;
+
+    pub fn serialize<S: Serializer>(hashes: &[Hash], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(hashes.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Hash>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|hash| {
+                let mut decoded = Hash::default();
+                hex::decode_to_slice(hash, &mut decoded).map_err(D::Error::custom)?;
+                Ok(decoded)
+            })
+            .collect()
+    }
+}
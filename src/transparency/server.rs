@@ -0,0 +1,85 @@
+use std::{io::Read, net::TcpListener};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::warn;
+
+use super::{LogEntry, TransparencyLog};
+
+/// `POST` appends the [`LogEntry`] in the body and answers with its [`super::LogReceipt`]
+pub(super) const ENTRIES_PATH: &str = "/entries";
+/// `GET` answers with the current [`super::SignedTreeHead`]
+pub(super) const TREE_HEAD_PATH: &str = "/tree-head";
+/// `GET` with the query `old=<SIZE>&new=<SIZE>` answers with a [`super::ConsistencyProof`]
+pub(super) const CONSISTENCY_PATH: &str = "/consistency";
+
+/// Entries are a few kilobytes, even with the large signatures of SPHINCS+
+const MAX_ENTRY_LEN: u64 = 1 << 20;
+
+/// Answers the requests of log clients on `listener` until the process is stopped.
+/// Requests are handled one at a time; failed requests are logged and answered with an error message
+pub fn serve(log: TransparencyLog, listener: TcpListener) -> Result<()> {
+    let server = Server::from_listener(listener, None)
+        .map_err(|err| anyhow!("Failed to start the log server: {err}"))?;
+    let json = Header::from_bytes("Content-Type", "application/json")
+        .map_err(|()| anyhow!("Invalid content type header"))?;
+    for mut request in server.incoming_requests() {
+        let response = match respond(&log, &mut request) {
+            Ok(Some(body)) => Response::from_string(body).with_header(json.clone()),
+            Ok(None) => Response::from_string("Not found").with_status_code(404),
+            Err(err) => {
+                warn!(
+                    "Failed to answer {} {}: {err:#}",
+                    request.method(),
+                    request.url()
+                );
+                Response::from_string(format!("{err:#}")).with_status_code(400)
+            }
+        };
+        if let Err(err) = request.respond(response) {
+            warn!("Failed to send a response: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// Returns the JSON answer to `request`, or None if it is not a request of the log
+fn respond(log: &TransparencyLog, request: &mut Request) -> Result<Option<String>> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    match (request.method(), path) {
+        (Method::Post, ENTRIES_PATH) => {
+            let mut body = Vec::new();
+            request
+                .as_reader()
+                .take(MAX_ENTRY_LEN)
+                .read_to_end(&mut body)
+                .context("Failed to read the request")?;
+            let entry: LogEntry = serde_json::from_slice(&body).context("Invalid log entry")?;
+            to_json(&log.append(&entry)?)
+        }
+        (Method::Get, TREE_HEAD_PATH) => to_json(&log.tree_head()?),
+        (Method::Get, CONSISTENCY_PATH) => {
+            let size = |name: &str| -> Result<u64> {
+                let Some(value) = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                else {
+                    bail!("Missing query parameter {name}");
+                };
+                value
+                    .parse()
+                    .context(format!("Invalid query parameter {name}"))
+            };
+            to_json(&log.consistency_proof(size("old")?, size("new")?)?)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn to_json(value: &impl Serialize) -> Result<Option<String>> {
+    serde_json::to_string(value)
+        .map(Some)
+        .context("Failed to serialize the response")
+}
@@ -0,0 +1,268 @@
+use std::{
+    fs::{File, OpenOptions, create_dir_all, read, read_to_string},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, ensure};
+use chrono::Utc;
+use oqs::sig::{Algorithm as SigAlgorithmVariant, Sig};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::{
+    ConsistencyProof, LogEntry, LogReceipt, SignedTreeHead,
+    merkle::{self, Hash},
+};
+use crate::{
+    cryptography::{fingerprint::fingerprint, secret::Secret},
+    util::{write_file, write_secret_file},
+};
+
+const LOG_VERSION: u64 = 1;
+
+const INFO_FILE_NAME: &str = "log.json";
+const SECRET_KEY_FILE_NAME: &str = "log.key";
+const ENTRIES_FILE_NAME: &str = "entries.jsonl";
+
+/// Key of the log, stored in `log.json`
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct LogInfo {
+    version: u64,
+    algorithm: SigAlgorithmVariant,
+    #[serde(with = "hex")]
+    public_key: Vec<u8>,
+}
+
+/// A transparency log in a directory. `entries.jsonl` holds one entry per line in the order they were appended,
+/// so auditors can read it; `log.key` is the secret key that signs the tree heads.
+/// Appending locks the entries file, so several processes can share a log
+pub struct TransparencyLog {
+    dir: PathBuf,
+    info: LogInfo,
+}
+
+impl TransparencyLog {
+    /// Creates a log with a new key of `signature` in `dir`, which must not hold a log yet
+    pub fn init(dir: &Path, signature: &Sig) -> Result<Self> {
+        let info_path = dir.join(INFO_FILE_NAME);
+        ensure!(
+            !info_path.exists(),
+            "{} already holds a transparency log",
+            dir.display()
+        );
+        create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+
+        let (public_key, secret_key) = signature
+            .keypair()
+            .context("Failed to generate the log key")?;
+        let secret_key = Secret::new(secret_key);
+        write_secret_file(&dir.join(SECRET_KEY_FILE_NAME), &secret_key, false)
+            .context("Failed to save the log key")?;
+        let info = LogInfo {
+            version: LOG_VERSION,
+            algorithm: signature.algorithm(),
+            public_key: public_key.into_vec(),
+        };
+        write_file(&dir.join(ENTRIES_FILE_NAME), [], false)
+            .context("Failed to create the log entries")?;
+        write_file(
+            &info_path,
+            serde_json::to_string_pretty(&info).context("Failed to serialize the log")?,
+            false,
+        )
+        .context("Failed to save the log")?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            info,
+        })
+    }
+
+    pub fn open(dir: &Path) -> Result<Self> {
+        let info_path = dir.join(INFO_FILE_NAME);
+        let info: LogInfo = serde_json::from_str(&read_to_string(&info_path).context(format!(
+            "{} holds no transparency log. Create one with `rust-seal log init`",
+            dir.display()
+        ))?)
+        .context(format!("Failed to parse {}", info_path.display()))?;
+        ensure!(
+            info.version <= LOG_VERSION,
+            "The transparency log has version {}, but this rust-seal only supports versions up to {LOG_VERSION}",
+            info.version
+        );
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            info,
+        })
+    }
+
+    /// Fingerprint of the key that signs the tree heads
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.info.algorithm.to_string(), &self.info.public_key)
+    }
+
+    /// Appends `entry` and returns the proof that the new tree head includes it
+    pub fn append(&self, entry: &LogEntry) -> Result<LogReceipt> {
+        let mut file = self.open_entries(true)?;
+        file.lock().context("Failed to lock the log entries")?;
+        let mut leaves = read_leaves(&mut file)?;
+
+        let mut line = serde_json::to_vec(entry).context("Failed to serialize log entry")?;
+        leaves.push(merkle::leaf_hash(&line));
+        line.push(b'\n');
+        file.write_all(&line)
+            .and_then(|()| file.sync_all())
+            .context("Failed to append to the log entries")?;
+
+        let index = leaves.len() - 1;
+        Ok(LogReceipt {
+            index: index as u64,
+            inclusion_proof: merkle::inclusion_proof(index, &leaves),
+            tree_head: self.sign_tree_head(&leaves)?,
+        })
+    }
+
+    /// Signs the current root of the tree
+    pub fn tree_head(&self) -> Result<SignedTreeHead> {
+        let leaves = self.leaves()?;
+        self.sign_tree_head(&leaves)
+    }
+
+    /// Returns the proof that the tree of `old_size` entries is a prefix of the tree of `new_size` entries
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Result<ConsistencyProof> {
+        let leaves = self.leaves()?;
+        ensure!(
+            old_size <= new_size && new_size <= leaves.len() as u64,
+            "The log has {} entries, so it cannot prove the tree of {old_size} entries is a prefix of the tree of {new_size}",
+            leaves.len()
+        );
+        Ok(ConsistencyProof {
+            old_size,
+            new_size,
+            proof: merkle::consistency_proof(old_size as usize, &leaves[..new_size as usize]),
+        })
+    }
+
+    fn leaves(&self) -> Result<Vec<Hash>> {
+        let mut file = self.open_entries(false)?;
+        file.lock_shared()
+            .context("Failed to lock the log entries")?;
+        read_leaves(&mut file)
+    }
+
+    fn open_entries(&self, append: bool) -> Result<File> {
+        let path = self.dir.join(ENTRIES_FILE_NAME);
+        OpenOptions::new()
+            .read(true)
+            .append(append)
+            .open(&path)
+            .context(format!("Failed to open {}", path.display()))
+    }
+
+    fn sign_tree_head(&self, leaves: &[Hash]) -> Result<SignedTreeHead> {
+        let algorithm = self.info.algorithm;
+        let sig = Sig::new(algorithm).context(format!(
+            "Failed to create signature algorithm {algorithm}. Algorithm might be disabled"
+        ))?;
+        let secret_key = Zeroizing::new(
+            read(self.dir.join(SECRET_KEY_FILE_NAME)).context("Failed to read the log key")?,
+        );
+        let secret_key = Secret::new(
+            sig.secret_key_from_bytes(&secret_key)
+                .context(format!("The log key is not a valid key for {algorithm}"))?
+                .to_owned(),
+        );
+
+        let tree_size = leaves.len() as u64;
+        let root_hash = merkle::root_hash(leaves);
+        let timestamp = Utc::now();
+        let signature = sig
+            .sign(
+                &SignedTreeHead::message(tree_size, &root_hash, &timestamp),
+                &secret_key,
+            )
+            .context("Failed to sign the tree head")?;
+        Ok(SignedTreeHead {
+            algorithm,
+            public_key: self.info.public_key.clone(),
+            tree_size,
+            root_hash,
+            timestamp,
+            signature: signature.into_vec(),
+        })
+    }
+}
+
+/// Returns the leaf hashes of the entries, one per line
+fn read_leaves(file: &mut File) -> Result<Vec<Hash>> {
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)
+        .context("Failed to read the log entries")?;
+    ensure!(
+        content.is_empty() || content.ends_with(b"\n"),
+        "The last log entry is incomplete. The log might have been interrupted while appending"
+    );
+    Ok(content
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(merkle::leaf_hash)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oqs::sig::Algorithm;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_appended_entries_are_included_and_consistent() {
+        let dir = tempdir().unwrap();
+        let sig = Sig::new(Algorithm::MlDsa44).unwrap();
+        let log = TransparencyLog::init(&dir.path().join("log"), &sig).unwrap();
+        assert!(TransparencyLog::init(&dir.path().join("log"), &sig).is_err());
+        let log_fingerprint = [log.fingerprint()];
+
+        let entry = |index: u8| LogEntry {
+            algorithm: Algorithm::MlDsa44,
+            public_key: vec![index; 4],
+            namespace: None,
+            file_digest: [index; 32],
+            signature: vec![index; 8],
+        };
+        let mut receipts = Vec::new();
+        for index in 0..5 {
+            receipts.push(log.append(&entry(index)).unwrap());
+        }
+        let log = TransparencyLog::open(&dir.path().join("log")).unwrap();
+        let head = log.tree_head().unwrap();
+        assert_eq!(head.tree_size, 5);
+
+        for (index, receipt) in receipts.iter().enumerate() {
+            let receipt: LogReceipt =
+                serde_json::from_str(&serde_json::to_string(receipt).unwrap()).unwrap();
+            receipt
+                .verify(&entry(index as u8), &log_fingerprint)
+                .unwrap();
+            assert!(receipt.verify(&entry(9), &log_fingerprint).is_err());
+            assert!(
+                receipt
+                    .verify(&entry(index as u8), &["ab".repeat(32)])
+                    .is_err()
+            );
+
+            let old = &receipt.tree_head;
+            let proof = log
+                .consistency_proof(old.tree_size, head.tree_size)
+                .unwrap();
+            proof.verify(old, &head).unwrap();
+        }
+
+        let mut forged = head.clone();
+        forged.root_hash = receipts[2].tree_head.root_hash;
+        assert!(forged.verify().is_err());
+        assert!(log.consistency_proof(2, 6).is_err());
+    }
+}
@@ -1,11 +1,24 @@
-use std::path::PathBuf;
+use std::{
+    fs::{OpenOptions, hard_link, remove_file, rename},
+    io::{ErrorKind, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result, ensure};
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, Duration, NaiveDate, SubsecRound, Utc};
 use clap::ArgMatches;
 use oqs::kem::{Algorithm as KEMAlgorithm, Kem};
 use oqs::sig::{Algorithm as SigAlgorithm, Sig};
 
-use crate::cli::{KEM_ALGORITHM_ID, SIGNATURE_ALGORITHM_ID};
+use crate::{
+    Config,
+    cli::{
+        IGNORE_POLICY_ID, KEM_ALGORITHM_ID, SIGNATURE_ALGORITHM_ID, validate_kem_algorithm,
+        validate_signature_algorithm,
+    },
+    config::Setting,
+};
 
 pub fn parse_path_arg(matches: &ArgMatches, id: &str) -> Result<PathBuf> {
     matches
@@ -20,18 +33,264 @@ pub fn parse_path_arg(matches: &ArgMatches, id: &str) -> Result<PathBuf> {
         })
 }
 
-pub fn parse_signature_algorithm_arg(matches: &ArgMatches) -> Result<Sig> {
+/// Returns the signature algorithm given on the command line or the configured default.
+/// Fails if the algorithm violates the algorithm policy and `--ignore-policy` is not given.
+pub fn parse_signature_algorithm_arg(matches: &ArgMatches, config: &Config) -> Result<Sig> {
     matches
         .get_one::<SigAlgorithm>(SIGNATURE_ALGORITHM_ID)
-        .context("Signature algorithm argument is invalid")
+        .copied()
+        .or_else(|| config.default_signature_algorithm())
+        .context(format!(
+            "No signature algorithm specified. Pass --signature-algorithm or set a default with `rust-seal config set {} <ALGORITHM>`",
+            Setting::DefaultSignatureAlgorithm.key()
+        ))
         .and_then(|algorithm| {
-            Sig::new(*algorithm).context("Signature algorithm argument is invalid")
+            Sig::new(algorithm).context("Signature algorithm argument is invalid")
+        })
+        .and_then(|signature| {
+            validate_signature_algorithm(
+                &signature,
+                config.policy(),
+                matches.get_flag(IGNORE_POLICY_ID),
+            )?;
+            Ok(signature)
         })
 }
 
-pub fn parse_kem_algorithm_arg(matches: &ArgMatches) -> Result<Kem> {
+/// Returns the KEM algorithm given on the command line or the configured default.
+/// Fails if the algorithm violates the algorithm policy and `--ignore-policy` is not given.
+pub fn parse_kem_algorithm_arg(matches: &ArgMatches, config: &Config) -> Result<Kem> {
     matches
         .get_one::<KEMAlgorithm>(KEM_ALGORITHM_ID)
-        .context("KEM algorithm argument is invalid")
-        .and_then(|algorithm| Kem::new(*algorithm).context("KEM algorithm argument is invalid"))
+        .copied()
+        .or_else(|| config.default_kem_algorithm())
+        .context(format!(
+            "No KEM algorithm specified. Pass --kem-algorithm or set a default with `rust-seal config set {} <ALGORITHM>`",
+            Setting::DefaultKemAlgorithm.key()
+        ))
+        .and_then(|algorithm| Kem::new(algorithm).context("KEM algorithm argument is invalid"))
+        .and_then(|kem| {
+            validate_kem_algorithm(&kem, config.policy(), matches.get_flag(IGNORE_POLICY_ID))?;
+            Ok(kem)
+        })
+}
+
+/// Prints `rows` as a left-aligned table below `header`
+pub fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|cell| cell.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: &mut dyn Iterator<Item = &str>| {
+        let line: Vec<String> = cells
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(&mut header.iter().copied());
+    for row in rows {
+        print_row(&mut row.iter().map(String::as_str));
+    }
+}
+
+/// Parses an expiry date given as `YYYY-MM-DD`, an RFC 3339 timestamp or a duration from now such as `90d`, `12w`, `6m` or `2y`.
+/// Months count as 30 days and years as 365 days. Fails if the expiry is not in the future.
+pub fn parse_expiry(value: &str) -> Result<DateTime<Utc>> {
+    let expires = if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0)
+            .context("Midnight is a valid time")?
+            .and_utc()
+    } else if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        timestamp.with_timezone(&Utc)
+    } else {
+        return parse_expiry_duration(value);
+    };
+    ensure!(
+        expires > Utc::now(),
+        "Expiry {} is not in the future",
+        expires.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    Ok(expires)
+}
+
+fn parse_expiry_duration(value: &str) -> Result<DateTime<Utc>> {
+    let Some(unit) = value.chars().last() else {
+        bail!("Expiry must not be empty");
+    };
+    let amount: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .context(format!(
+            "Invalid expiry: {value}. Use a date like 2030-01-31 or a duration like 90d, 12w, 6m or 2y"
+        ))?;
+    let days = match unit {
+        'd' => amount,
+        'w' => amount * 7,
+        'm' => amount * 30,
+        'y' => amount * 365,
+        _ => bail!("Invalid expiry unit '{unit}' in {value}. Expected one of: d, w, m, y"),
+    };
+    ensure!(days > 0, "Expiry must be in the future");
+
+    Ok(Utc::now().trunc_subsecs(0) + Duration::days(days))
+}
+
+/// Parses a key lifetime in seconds, e.g. 3600, or with a unit: 30s, 15m, 8h or 1d
+pub fn parse_lifetime(value: &str) -> Result<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().context(format!(
+        "Invalid lifetime: {value}. Use seconds or a duration like 30s, 15m, 8h or 1d"
+    ))?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("Invalid lifetime unit '{unit}' in {value}. Expected one of: s, m, h, d"),
+    };
+    ensure!(amount > 0, "Lifetime must be positive");
+
+    amount
+        .checked_mul(multiplier)
+        .context(format!("Lifetime is too long: {value}"))
+}
+
+/// Permissions of files with secret keys or decrypted content
+const SECRET_FILE_MODE: u32 = 0o600;
+
+/// Permissions of other files, which are restricted further by the umask
+const FILE_MODE: u32 = 0o666;
+
+/// Writes `contents` to `path` atomically: they are written to a temporary file next to `path`,
+/// which is renamed to `path` once it is complete, so `path` is never left partially written.
+/// Fails if `path` exists, unless `force` is set. Without `force`, the temporary file is hard linked to `path`
+/// instead, which fails if a file was created at `path` in the meantime
+pub fn write_file(path: &Path, contents: impl AsRef<[u8]>, force: bool) -> Result<()> {
+    write_atomic(path, contents.as_ref(), force, FILE_MODE)
+}
+
+/// Like [`write_file`], but the file is only readable and writable by its owner.
+/// Used for secret keys and decrypted content
+pub fn write_secret_file(path: &Path, contents: impl AsRef<[u8]>, force: bool) -> Result<()> {
+    write_atomic(path, contents.as_ref(), force, SECRET_FILE_MODE)
+}
+
+/// Fails if `path` exists and `force` is not set. Used to check every file before the first one is written
+pub fn ensure_overwritable(path: &Path, force: bool) -> Result<()> {
+    ensure!(
+        force || !path.exists(),
+        "{} already exists. Pass --force to overwrite it",
+        path.display()
+    );
+    Ok(())
+}
+
+fn write_atomic(path: &Path, contents: &[u8], force: bool, mode: u32) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .context(format!("{} is not a file path", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| {
+            if force {
+                rename(&temp_path, path)
+            } else {
+                hard_link(&temp_path, path).and_then(|()| remove_file(&temp_path))
+            }
+        });
+    if let Err(err) = written {
+        let _ = remove_file(&temp_path);
+        if err.kind() == ErrorKind::AlreadyExists {
+            bail!(
+                "{} already exists. Pass --force to overwrite it",
+                path.display()
+            );
+        }
+        return Err(err).context(format!("Failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::read, os::unix::fs::PermissionsExt};
+
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_expiry() {
+        assert_eq!(
+            parse_expiry("2030-01-31").unwrap().to_rfc3339(),
+            "2030-01-31T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_expiry("2030-01-31T12:00:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2030-01-31T10:00:00+00:00"
+        );
+
+        let in_90_days = parse_expiry("90d").unwrap() - Utc::now();
+        assert!(in_90_days <= Duration::days(90) && in_90_days > Duration::days(89));
+        assert!(parse_expiry("2y").unwrap() > parse_expiry("12m").unwrap());
+
+        assert!(parse_expiry("0d").is_err());
+        assert!(parse_expiry("2020-01-31").is_err());
+        assert!(parse_expiry(&Utc::now().to_rfc3339()).is_err());
+        assert!(parse_expiry("5x").is_err());
+        assert!(parse_expiry("soon").is_err());
+        assert!(parse_expiry("").is_err());
+    }
+
+    #[test]
+    fn test_parse_lifetime() {
+        assert_eq!(parse_lifetime("3600").unwrap(), 3600);
+        assert_eq!(parse_lifetime("30s").unwrap(), 30);
+        assert_eq!(parse_lifetime("15m").unwrap(), 900);
+        assert_eq!(parse_lifetime("8h").unwrap(), 28800);
+        assert_eq!(parse_lifetime("1d").unwrap(), 86400);
+
+        assert!(parse_lifetime("0").is_err());
+        assert!(parse_lifetime("5w").is_err());
+        assert!(parse_lifetime("h").is_err());
+        assert!(parse_lifetime("").is_err());
+    }
+
+    #[test]
+    fn test_write_file_refuses_to_overwrite_without_force() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("key.sec");
+
+        write_secret_file(&path, b"first", false).unwrap();
+        assert_eq!(path.metadata().unwrap().permissions().mode() & 0o777, 0o600);
+
+        let err = write_secret_file(&path, b"second", false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        assert_eq!(read(&path).unwrap(), b"first");
+
+        write_file(&path, b"second", true).unwrap();
+        assert_eq!(read(&path).unwrap(), b"second");
+        assert_eq!(dir.path().read_dir().unwrap().count(), 1);
+    }
 }